@@ -0,0 +1,451 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use reqwest::{Body, Client, Response, StatusCode};
+use tokio::io::AsyncWriteExt;
+use tokio_stream::StreamExt;
+use tokio_util::io::ReaderStream;
+
+use crate::{
+    cloud::{self, CloudAccount, CloudEntry, CloudError, CloudKind, CloudListing},
+    msg::TransferSessionID,
+    PitouFilePath,
+};
+
+use super::{
+    transfer::TransferProgress,
+    vfs::{self, RemoteFs},
+};
+
+mod s3;
+mod sftp;
+mod ssh_config;
+mod webdav;
+
+/// A storage reached over the network, which pitou browses like a folder. Paths are those of
+/// [`CloudEntry`], relative to where the account opens.
+#[async_trait]
+pub trait CloudProvider: Send + Sync {
+    /// The files and folders right inside `dir`.
+    async fn list(&self, dir: &str) -> Result<Vec<CloudEntry>, CloudError>;
+
+    /// Makes the folder `path`, failing with [`CloudError::Exists`] if it is already there.
+    async fn create_dir(&self, path: &str) -> Result<(), CloudError>;
+
+    /// Puts the local file at `path`, counting the bytes sent in `progress`.
+    async fn upload(
+        &self,
+        local: &Path,
+        path: &str,
+        progress: &TransferProgress,
+    ) -> Result<(), CloudError>;
+
+    /// Saves the file at `path` as `local`, counting the bytes received in `progress`.
+    async fn download(
+        &self,
+        path: &str,
+        local: &Path,
+        progress: &TransferProgress,
+    ) -> Result<(), CloudError>;
+
+    /// Moves the entry, along with its contents if it is a folder, to `to`.
+    async fn rename(&self, entry: &CloudEntry, to: &str) -> Result<(), CloudError>;
+
+    /// Removes the entry, along with its contents if it is a folder.
+    async fn delete(&self, entry: &CloudEntry) -> Result<(), CloudError>;
+}
+
+impl From<reqwest::Error> for CloudError {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_builder() {
+            Self::BadEndpoint
+        } else if e.is_decode() {
+            Self::BadResponse
+        } else {
+            Self::Unreachable
+        }
+    }
+}
+
+impl From<std::io::Error> for CloudError {
+    fn from(_: std::io::Error) -> Self {
+        Self::Io
+    }
+}
+
+impl From<CloudError> for std::io::Error {
+    fn from(e: CloudError) -> Self {
+        let kind = match e {
+            CloudError::NotFound => std::io::ErrorKind::NotFound,
+            CloudError::Exists => std::io::ErrorKind::AlreadyExists,
+            CloudError::Unauthorized | CloudError::Denied => std::io::ErrorKind::PermissionDenied,
+            _ => std::io::ErrorKind::Other,
+        };
+        std::io::Error::new(kind, e.message())
+    }
+}
+
+fn client() -> Client {
+    Client::builder()
+        .connect_timeout(Duration::from_secs(15))
+        .build()
+        .unwrap_or_default()
+}
+
+/// The response if it succeeded, otherwise the error its status stands for.
+fn checked(response: Response) -> Result<Response, CloudError> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    Err(match status {
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => CloudError::Unauthorized,
+        StatusCode::NOT_FOUND => CloudError::NotFound,
+        StatusCode::METHOD_NOT_ALLOWED | StatusCode::PRECONDITION_FAILED => CloudError::Exists,
+        status => CloudError::Rejected(status.as_u16()),
+    })
+}
+
+/// The contents of a local file as a request body, counted in `progress` as they are read.
+async fn file_body(local: &Path, progress: &TransferProgress) -> Result<(Body, u64), CloudError> {
+    let file = tokio::fs::File::open(local).await?;
+    let len = file.metadata().await?.len();
+    let progress = progress.clone();
+    let stream = ReaderStream::new(file).map(move |chunk| {
+        if let Ok(chunk) = &chunk {
+            progress.advance(chunk.len() as u64)
+        }
+        chunk
+    });
+    Ok((Body::wrap_stream(stream), len))
+}
+
+/// Writes the body of `response` to `local`, under a hidden name until it is complete like
+/// pasted files are.
+async fn save_body(
+    mut response: Response,
+    local: &Path,
+    progress: &TransferProgress,
+) -> Result<(), CloudError> {
+    let name = local.file_name().ok_or(CloudError::Io)?;
+    let temp = local.with_file_name(format!(".{}", name.to_string_lossy()));
+    let mut file = tokio::fs::File::create(&temp).await?;
+    let written = async {
+        while let Some(chunk) = response.chunk().await? {
+            file.write_all(&chunk).await?;
+            progress.advance(chunk.len() as u64)
+        }
+        file.flush().await?;
+        Ok::<_, CloudError>(())
+    }
+    .await;
+    drop(file);
+    match written {
+        Ok(()) => Ok(tokio::fs::rename(&temp, local).await?),
+        Err(e) => {
+            tokio::fs::remove_file(&temp).await.ok();
+            Err(e)
+        }
+    }
+}
+
+fn store_path() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("pitou").join("cloud.json"))
+}
+
+/// Held across every read-modify-write of the store, so that concurrent changes aren't lost.
+static STORE_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+/// The saved accounts. A missing store reads as none, while one that can't be read or parsed is
+/// an error rather than being taken for empty and overwritten, losing every account.
+async fn stored() -> std::io::Result<Vec<CloudAccount>> {
+    let path = store_path().ok_or(std::io::ErrorKind::NotFound)?;
+    match tokio::fs::read(path).await {
+        Ok(contents) => Ok(serde_json::from_slice(&contents)?),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Writes the accounts, secrets included, to a file only the user can read.
+async fn store(accounts: &[CloudAccount]) -> std::io::Result<()> {
+    let path = store_path().ok_or(std::io::ErrorKind::NotFound)?;
+    let contents = serde_json::to_vec(accounts)?;
+    super::store::write_private(&path, &contents).await
+}
+
+fn provider_of(account: &CloudAccount) -> Result<Arc<dyn CloudProvider>, CloudError> {
+    Ok(match account.kind {
+        CloudKind::WebDav => Arc::new(webdav::WebDav::new(account)?),
+        CloudKind::S3 => Arc::new(s3::S3::new(account)?),
+        CloudKind::Sftp => Arc::new(sftp::Sftp::new(account)?),
+    })
+}
+
+async fn provider(id: &str) -> Result<Arc<dyn CloudProvider>, CloudError> {
+    let accounts = stored().await?;
+    let account = accounts
+        .iter()
+        .find(|account| account.id == id)
+        .ok_or(CloudError::UnknownAccount)?;
+    provider_of(account)
+}
+
+/// Mounts the account under [`CloudAccount::mount`], replacing what was mounted there, so that
+/// its files are reached like those of any location. Must be called within the runtime the
+/// mounted storage's calls are to be run on.
+fn mount(account: &CloudAccount) -> Result<(), CloudError> {
+    let provider = provider_of(account)?;
+    let fs = RemoteFs::new(provider, tokio::runtime::Handle::current());
+    vfs::unmount(&account.mount());
+    vfs::mount(&account.mount(), Arc::new(fs));
+    Ok(())
+}
+
+/// Mounts every saved account, leaving out those whose details no longer make sense. An
+/// unreadable store mounts nothing.
+pub async fn mount_accounts() {
+    for account in stored().await.unwrap_or_default() {
+        mount(&account).ok();
+    }
+}
+
+/// The saved accounts, with their secrets left out. An unreadable store lists nothing.
+pub async fn accounts() -> Vec<CloudAccount> {
+    let mut accounts = stored().await.unwrap_or_default();
+    accounts
+        .iter_mut()
+        .for_each(|account| account.secret.clear());
+    accounts
+}
+
+/// Saves a new account, or changes the one with the same id, and gives back its id. An empty
+/// secret keeps the one saved before. An SFTP server whose key isn't known yet is turned down
+/// with its fingerprint, to be trusted with [`trust_host_key`] first.
+pub async fn save_account(mut account: CloudAccount) -> Result<String, CloudError> {
+    account.endpoint = account.endpoint.trim().to_owned();
+    provider_of(&account)?;
+    if account.kind == CloudKind::Sftp {
+        sftp::check_host(&account).await?;
+    }
+    let _guard = STORE_LOCK.lock().await;
+    let mut accounts = stored().await?;
+    match accounts.iter_mut().find(|saved| saved.id == account.id) {
+        Some(saved) if !account.id.is_empty() => {
+            if account.secret.is_empty() {
+                account.secret = std::mem::take(&mut saved.secret);
+            }
+            *saved = account.clone();
+        }
+        _ => {
+            let millis = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis();
+            account.id = format!("{millis:x}");
+            accounts.push(account.clone());
+        }
+    }
+    store(&accounts).await?;
+    mount(&account)?;
+    Ok(account.id)
+}
+
+/// Trusts the key of an SFTP account's server, once the user accepted its fingerprint.
+pub async fn trust_host_key(account: CloudAccount, fingerprint: String) -> Result<(), CloudError> {
+    sftp::trust_host(&account, fingerprint).await
+}
+
+/// The hosts named in `~/.ssh/config`, offered when adding an SFTP account.
+pub async fn ssh_hosts() -> Vec<String> {
+    let Some(path) = ssh_config::path() else {
+        return Vec::new();
+    };
+    match tokio::fs::read_to_string(path).await {
+        Ok(contents) => ssh_config::aliases(&contents),
+        Err(_) => Vec::new(),
+    }
+}
+
+pub async fn remove_account(id: String) -> std::io::Result<()> {
+    let _guard = STORE_LOCK.lock().await;
+    let mut accounts = stored().await?;
+    if let Some(account) = accounts.iter().find(|account| account.id == id) {
+        vfs::unmount(&account.mount());
+    }
+    accounts.retain(|account| account.id != id);
+    sftp::disconnect(&id);
+    store(&accounts).await
+}
+
+/// The contents of `dir`, folders first, each sorted by name.
+pub async fn list(id: String, dir: String) -> CloudListing {
+    let listed = match provider(&id).await {
+        Ok(provider) => provider.list(&cloud::normalize(&dir)).await,
+        Err(e) => Err(e),
+    };
+    match listed {
+        Ok(mut entries) => {
+            entries.sort_by(|a, b| {
+                (!a.is_dir, a.name().to_lowercase()).cmp(&(!b.is_dir, b.name().to_lowercase()))
+            });
+            CloudListing {
+                entries,
+                error: None,
+            }
+        }
+        Err(e) => CloudListing {
+            entries: Vec::new(),
+            error: Some(e),
+        },
+    }
+}
+
+pub async fn create_dir(id: String, dir: String, name: String) -> Result<(), CloudError> {
+    let path = cloud::join(&cloud::normalize(&dir), name.trim());
+    provider(&id).await?.create_dir(&path).await
+}
+
+/// Renames the entry within the folder it is in.
+pub async fn rename(id: String, entry: CloudEntry, name: String) -> Result<(), CloudError> {
+    let to = cloud::join(cloud::parent(&entry.path), name.trim());
+    provider(&id).await?.rename(&entry, &to).await
+}
+
+/// Deletes every entry it can, failing with the first error met.
+pub async fn delete(id: String, entries: Vec<CloudEntry>) -> Result<(), CloudError> {
+    let provider = provider(&id).await?;
+    let mut res = Ok(());
+    for entry in entries {
+        if let Err(e) = provider.delete(&entry).await {
+            res = res.and(Err(e));
+        }
+    }
+    res
+}
+
+/// Starts uploading the local items, folders with their contents, into the cloud folder `dir`.
+/// The progress is followed like that of a paste.
+pub async fn upload(
+    id: String,
+    items: Vec<PitouFilePath>,
+    dir: String,
+) -> Result<TransferSessionID, CloudError> {
+    let provider = provider(&id).await?;
+    let progress = TransferProgress::upload();
+    let session = progress.id();
+    let dir = cloud::normalize(&dir);
+    let items = items.into_iter().map(|item| item.path).collect();
+    tokio::spawn(upload_all(provider, items, dir, progress));
+    Ok(session)
+}
+
+/// A folder counts as a byte so that a transfer of only folders still shows some progress.
+const FOLDER_SIZE: u64 = 1;
+
+async fn upload_all(
+    provider: Arc<dyn CloudProvider>,
+    items: Vec<PathBuf>,
+    dir: String,
+    progress: TransferProgress,
+) {
+    // sized up first, folders before what they hold
+    let mut planned = Vec::new();
+    let mut pending = items
+        .into_iter()
+        .map(|item| (item, dir.clone()))
+        .collect::<Vec<_>>();
+    while let Some((local, dir)) = pending.pop() {
+        let Ok(metadata) = tokio::fs::metadata(&local).await else {
+            continue;
+        };
+        let Some(name) = local.file_name() else {
+            continue;
+        };
+        let path = cloud::join(&dir, &name.to_string_lossy());
+        if metadata.is_dir() {
+            progress.add_total(FOLDER_SIZE);
+            if let Ok(mut children) = tokio::fs::read_dir(&local).await {
+                while let Ok(Some(child)) = children.next_entry().await {
+                    pending.push((child.path(), path.clone()));
+                }
+            }
+            planned.push((local, path, true));
+        } else {
+            progress.add_total(metadata.len());
+            planned.push((local, path, false));
+        }
+    }
+    planned.sort_by(|a, b| a.1.cmp(&b.1));
+
+    progress.start();
+    for (local, path, is_dir) in planned {
+        if is_dir {
+            // uploading into a folder that is already there adds to it
+            provider.create_dir(&path).await.ok();
+            progress.advance(FOLDER_SIZE);
+        } else {
+            provider.upload(&local, &path, &progress).await.ok();
+        }
+    }
+    progress.finish();
+}
+
+/// Starts downloading the entries, folders with their contents, into the local folder `dst`.
+/// Names already taken there get a number added. The progress is followed like that of a paste.
+pub async fn download(
+    id: String,
+    entries: Vec<CloudEntry>,
+    dst: PitouFilePath,
+) -> Result<TransferSessionID, CloudError> {
+    let provider = provider(&id).await?;
+    let progress = TransferProgress::download();
+    let session = progress.id();
+    tokio::spawn(download_all(provider, entries, dst.path, progress));
+    Ok(session)
+}
+
+async fn download_all(
+    provider: Arc<dyn CloudProvider>,
+    entries: Vec<CloudEntry>,
+    dst: PathBuf,
+    progress: TransferProgress,
+) {
+    let mut pending = entries
+        .into_iter()
+        .map(|entry| (entry, dst.clone()))
+        .collect::<Vec<_>>();
+    let mut planned = Vec::new();
+    while let Some((entry, dir)) = pending.pop() {
+        let name = std::ffi::OsStr::new(entry.name());
+        let local = match dir.join(name).symlink_metadata() {
+            Ok(_) => dir.join(super::trash::unique_name(&dir, name, entry.is_dir)),
+            Err(_) => dir.join(name),
+        };
+        if entry.is_dir {
+            progress.add_total(FOLDER_SIZE);
+            if let Ok(children) = provider.list(&entry.path).await {
+                pending.extend(children.into_iter().map(|child| (child, local.clone())));
+            }
+        } else {
+            progress.add_total(entry.size);
+        }
+        planned.push((entry, local));
+    }
+    planned.sort_by(|a, b| a.1.cmp(&b.1));
+
+    progress.start();
+    for (entry, local) in planned {
+        if entry.is_dir {
+            tokio::fs::create_dir_all(&local).await.ok();
+            progress.advance(FOLDER_SIZE);
+        } else {
+            provider.download(&entry.path, &local, &progress).await.ok();
+        }
+    }
+    progress.finish();
+}
@@ -0,0 +1,546 @@
+use std::{
+    io::{Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use base64::Engine;
+use chrono::DateTime;
+use sha2::{Digest, Sha256};
+use ssh2::{CheckResult, ErrorCode, HostKeyType, KnownHostFileKind, RenameFlags, Session};
+
+use crate::{
+    cloud::{self, CloudAccount, CloudEntry, CloudError},
+    PitouDateTime,
+};
+
+use super::{ssh_config, CloudProvider, TransferProgress};
+
+// codes libssh2 and the SFTP protocol give to the failures told apart
+const AUTHENTICATION_FAILED: i32 = -18;
+const PUBLICKEY_UNVERIFIED: i32 = -19;
+const NO_SUCH_FILE: i32 = 2;
+const PERMISSION_DENIED: i32 = 3;
+const NO_SUCH_PATH: i32 = 10;
+const FILE_ALREADY_EXISTS: i32 = 11;
+
+impl From<ssh2::Error> for CloudError {
+    fn from(e: ssh2::Error) -> Self {
+        match e.code() {
+            ErrorCode::Session(AUTHENTICATION_FAILED | PUBLICKEY_UNVERIFIED) => Self::Unauthorized,
+            ErrorCode::Session(_) => Self::Unreachable,
+            ErrorCode::SFTP(NO_SUCH_FILE | NO_SUCH_PATH) => Self::NotFound,
+            ErrorCode::SFTP(PERMISSION_DENIED) => Self::Denied,
+            ErrorCode::SFTP(FILE_ALREADY_EXISTS) => Self::Exists,
+            ErrorCode::SFTP(status) => Self::Rejected(status as u16),
+        }
+    }
+}
+
+/// Where an account connects to, once its endpoint is read along with `~/.ssh/config`.
+#[derive(Clone)]
+struct Target {
+    host: String,
+    port: u16,
+    user: String,
+    /// the folder the account opens at, empty for the user's home
+    folder: String,
+    /// the keys to try when the account doesn't name one
+    identities: Vec<PathBuf>,
+}
+
+impl Target {
+    fn new(account: &CloudAccount) -> Result<Self, CloudError> {
+        let endpoint = account.endpoint.trim();
+        let endpoint = endpoint.strip_prefix("sftp://").unwrap_or(endpoint);
+        let (authority, folder) = match endpoint.find('/') {
+            Some(idx) => endpoint.split_at(idx),
+            None => (endpoint, ""),
+        };
+        let (user, address) = match authority.rsplit_once('@') {
+            Some((user, address)) => (Some(user), address),
+            None => (None, authority),
+        };
+        let (alias, port) = split_port(address)?;
+        if alias.is_empty() {
+            return Err(CloudError::BadEndpoint);
+        }
+
+        let home = dirs::home_dir().unwrap_or_default();
+        let config = ssh_config::path()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .map(|contents| ssh_config::resolve(&contents, alias))
+            .unwrap_or_default();
+        let host = config.host_name.unwrap_or_else(|| alias.to_owned());
+        let user = user
+            .map(str::to_owned)
+            .or_else(|| Some(account.username.trim().to_owned()).filter(|u| !u.is_empty()))
+            .or(config.user)
+            .or_else(|| std::env::var("USER").ok())
+            .or_else(|| std::env::var("USERNAME").ok())
+            .ok_or(CloudError::BadEndpoint)?;
+        let identities = match config.identity_files.is_empty() {
+            false => config
+                .identity_files
+                .iter()
+                .map(|identity| ssh_config::expand(identity, &home, &host, &user))
+                .collect(),
+            true => ["id_ed25519", "id_ecdsa", "id_rsa"]
+                .iter()
+                .map(|name| home.join(".ssh").join(name))
+                .collect(),
+        };
+        Ok(Self {
+            host,
+            port: port.or(config.port).unwrap_or(22),
+            user,
+            folder: folder.trim_end_matches('/').to_owned(),
+            identities,
+        })
+    }
+
+    /// The name the host's key is known by in `known_hosts`.
+    fn known_name(&self) -> String {
+        match self.port {
+            22 => self.host.clone(),
+            port => format!("[{}]:{port}", self.host),
+        }
+    }
+}
+
+/// Splits `host:port`, where the host may be an IPv6 address in brackets.
+fn split_port(address: &str) -> Result<(&str, Option<u16>), CloudError> {
+    let (host, port) = match address.strip_prefix('[') {
+        Some(rest) => {
+            let (host, rest) = rest.split_once(']').ok_or(CloudError::BadEndpoint)?;
+            (host, rest.strip_prefix(':'))
+        }
+        None => match address.split_once(':') {
+            Some((host, port)) => (host, Some(port)),
+            None => (address, None),
+        },
+    };
+    match port {
+        Some(port) => Ok((
+            host,
+            Some(port.parse().map_err(|_| CloudError::BadEndpoint)?),
+        )),
+        None => Ok((host, None)),
+    }
+}
+
+struct Connection {
+    // the session has to outlive its sftp channel
+    sftp: ssh2::Sftp,
+    _session: Session,
+}
+
+/// Connections kept open between operations, along with the account each was made for so
+/// that changing an account makes a new one.
+static CONNECTIONS: Mutex<Vec<(CloudAccount, Arc<Connection>)>> = Mutex::new(Vec::new());
+
+fn connection(account: &CloudAccount, target: &Target) -> Result<Arc<Connection>, CloudError> {
+    let kept = CONNECTIONS
+        .lock()
+        .unwrap()
+        .iter()
+        .find(|(made, _)| made == account)
+        .map(|(_, connection)| connection.clone());
+    if let Some(connection) = kept {
+        return Ok(connection);
+    }
+    // connecting can take a while, so other accounts aren't kept waiting on the lock
+    let connection = Arc::new(connect(account, target)?);
+    let mut connections = CONNECTIONS.lock().unwrap();
+    connections.retain(|(made, _)| made.id != account.id);
+    connections.push((account.clone(), connection.clone()));
+    Ok(connection)
+}
+
+/// Drops the kept connection of an account once it has stopped working.
+fn forget(account: &CloudAccount) {
+    CONNECTIONS
+        .lock()
+        .unwrap()
+        .retain(|(made, _)| made != account)
+}
+
+/// Closes the connection kept for the account with the given id, if any.
+pub(super) fn disconnect(id: &str) {
+    CONNECTIONS
+        .lock()
+        .unwrap()
+        .retain(|(made, _)| made.id != id)
+}
+
+fn connect(account: &CloudAccount, target: &Target) -> Result<Connection, CloudError> {
+    let session = handshake(target)?;
+    check_host_key(&session, target)?;
+    authenticate(&session, account, target)?;
+    Ok(Connection {
+        sftp: session.sftp()?,
+        _session: session,
+    })
+}
+
+/// Opens a session with the server, before its key is checked or the user signs in.
+fn handshake(target: &Target) -> Result<Session, CloudError> {
+    let address = (target.host.as_str(), target.port)
+        .to_socket_addrs()
+        .map_err(|_| CloudError::Unreachable)?
+        .next()
+        .ok_or(CloudError::Unreachable)?;
+    let stream = TcpStream::connect_timeout(&address, Duration::from_secs(15))
+        .map_err(|_| CloudError::Unreachable)?;
+    let mut session = Session::new()?;
+    session.set_timeout(30_000);
+    session.set_tcp_stream(stream);
+    session.handshake()?;
+    Ok(session)
+}
+
+/// The `known_hosts` the keys the user trusted from pitou are kept in, apart from ssh's own.
+fn known_hosts_path() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("pitou").join("known_hosts"))
+}
+
+/// The fingerprint of a key as ssh shows it.
+fn fingerprint(key: &[u8]) -> String {
+    let digest = Sha256::digest(key);
+    format!(
+        "SHA256:{}",
+        base64::engine::general_purpose::STANDARD_NO_PAD.encode(digest)
+    )
+}
+
+/// Checks the server's key against pitou's `known_hosts` and `~/.ssh/known_hosts`, turning the
+/// server down if its key changed. A key found in neither is left for the user to trust.
+fn check_host_key(session: &Session, target: &Target) -> Result<(), CloudError> {
+    let (key, _) = session.host_key().ok_or(CloudError::BadResponse)?;
+    let mut known = session.known_hosts()?;
+    let files = [
+        known_hosts_path(),
+        dirs::home_dir().map(|home| home.join(".ssh").join("known_hosts")),
+    ];
+    for file in files.iter().flatten() {
+        known.read_file(file, KnownHostFileKind::OpenSSH).ok();
+    }
+    match known.check_port(&target.host, target.port, key) {
+        CheckResult::Match => Ok(()),
+        CheckResult::Mismatch => Err(CloudError::HostKeyChanged),
+        CheckResult::Failure => Err(CloudError::BadResponse),
+        CheckResult::NotFound => Err(CloudError::UnknownHostKey {
+            fingerprint: fingerprint(key),
+        }),
+    }
+}
+
+/// Checks the key of the account's server without signing in, so that a key not known yet is
+/// told before the account is saved. A server that can't be reached is let through.
+pub(super) async fn check_host(account: &CloudAccount) -> Result<(), CloudError> {
+    let target = Target::new(account)?;
+    tokio::task::spawn_blocking(move || match handshake(&target) {
+        Ok(session) => check_host_key(&session, &target),
+        Err(_) => Ok(()),
+    })
+    .await
+    .unwrap_or(Ok(()))
+}
+
+/// Adds the key of the account's server to pitou's `known_hosts`, as long as it is still the
+/// one with the fingerprint the user accepted.
+pub(super) async fn trust_host(account: &CloudAccount, accepted: String) -> Result<(), CloudError> {
+    let target = Target::new(account)?;
+    tokio::task::spawn_blocking(move || {
+        let session = handshake(&target)?;
+        let (key, kind) = session.host_key().ok_or(CloudError::BadResponse)?;
+        if fingerprint(key) != accepted {
+            return Err(CloudError::HostKeyChanged);
+        }
+        let kind = match kind {
+            HostKeyType::Rsa => "ssh-rsa",
+            HostKeyType::Dss => "ssh-dss",
+            HostKeyType::Ecdsa256 => "ecdsa-sha2-nistp256",
+            HostKeyType::Ecdsa384 => "ecdsa-sha2-nistp384",
+            HostKeyType::Ecdsa521 => "ecdsa-sha2-nistp521",
+            HostKeyType::Ed25519 => "ssh-ed25519",
+            HostKeyType::Unknown => return Err(CloudError::BadResponse),
+        };
+        // appended rather than rewritten so that the entries already there stay as they are
+        let line = format!(
+            "{} {kind} {}\n",
+            target.known_name(),
+            base64::engine::general_purpose::STANDARD.encode(key)
+        );
+        let file = known_hosts_path().ok_or(CloudError::Io)?;
+        if let Some(dir) = file.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let mut options = std::fs::OpenOptions::new();
+        options.append(true).create(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        options.open(&file)?.write_all(line.as_bytes())?;
+        Ok(())
+    })
+    .await
+    .unwrap_or(Err(CloudError::Unreachable))
+}
+
+/// Signs in with the account's key when it names one. Otherwise the ssh agent, the keys from
+/// `~/.ssh/config` or the usual ones, then the password are tried in turn. The secret is the
+/// passphrase of a key as well as the password.
+fn authenticate(
+    session: &Session,
+    account: &CloudAccount,
+    target: &Target,
+) -> Result<(), CloudError> {
+    let user = target.user.as_str();
+    let secret = Some(account.secret.as_str()).filter(|secret| !secret.is_empty());
+    let identity = account.identity.trim();
+    if !identity.is_empty() {
+        let home = dirs::home_dir().unwrap_or_default();
+        let identity = ssh_config::expand(identity, &home, &target.host, user);
+        session.userauth_pubkey_file(user, None, &identity, secret)?;
+    } else {
+        session.userauth_agent(user).ok();
+        for identity in target
+            .identities
+            .iter()
+            .filter(|identity| identity.is_file())
+        {
+            if session.authenticated() {
+                break;
+            }
+            session
+                .userauth_pubkey_file(user, None, identity, secret)
+                .ok();
+        }
+        if let (false, Some(password)) = (session.authenticated(), secret) {
+            session.userauth_password(user, password).ok();
+        }
+    }
+    match session.authenticated() {
+        true => Ok(()),
+        false => Err(CloudError::Unauthorized),
+    }
+}
+
+/// A server reached over SSH, browsed from the user's home or the folder the endpoint names.
+pub(super) struct Sftp {
+    account: CloudAccount,
+    target: Target,
+}
+
+impl Sftp {
+    pub(super) fn new(account: &CloudAccount) -> Result<Self, CloudError> {
+        Ok(Self {
+            account: account.clone(),
+            target: Target::new(account)?,
+        })
+    }
+
+    /// The path on the server of an entry's path. Relative paths start at the user's home.
+    fn remote(&self, path: &str) -> PathBuf {
+        match (self.target.folder.is_empty(), path.is_empty()) {
+            (true, true) => PathBuf::from("."),
+            (true, false) => PathBuf::from(path),
+            (false, _) => Path::new(&self.target.folder).join(path),
+        }
+    }
+
+    /// Runs `op` away from the async workers since libssh2 blocks, connecting first if need be.
+    async fn run<T, F>(&self, op: F) -> Result<T, CloudError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&ssh2::Sftp) -> Result<T, CloudError> + Send + 'static,
+    {
+        let account = self.account.clone();
+        let target = self.target.clone();
+        tokio::task::spawn_blocking(move || {
+            let connection = connection(&account, &target)?;
+            let res = op(&connection.sftp);
+            if let Err(CloudError::Unreachable) = res {
+                forget(&account)
+            }
+            res
+        })
+        .await
+        .unwrap_or(Err(CloudError::Unreachable))
+    }
+}
+
+#[async_trait]
+impl CloudProvider for Sftp {
+    async fn list(&self, dir: &str) -> Result<Vec<CloudEntry>, CloudError> {
+        let remote = self.remote(dir);
+        let dir = dir.to_owned();
+        self.run(move |sftp| {
+            let entries = sftp
+                .readdir(&remote)?
+                .into_iter()
+                .filter_map(|(path, mut stat)| {
+                    let name = path.file_name()?.to_string_lossy().into_owned();
+                    // links are listed as what they point to, when that can be reached
+                    if stat.file_type().is_symlink() {
+                        stat = sftp.stat(&path).unwrap_or(stat);
+                    }
+                    let modified = stat
+                        .mtime
+                        .and_then(|secs| DateTime::from_timestamp(secs as i64, 0))
+                        .map(|modified| PitouDateTime {
+                            datetime: modified.naive_utc(),
+                        });
+                    Some(CloudEntry {
+                        path: cloud::join(&dir, &name),
+                        is_dir: stat.is_dir(),
+                        size: stat.size.unwrap_or_default(),
+                        modified,
+                    })
+                })
+                .collect();
+            Ok(entries)
+        })
+        .await
+    }
+
+    async fn create_dir(&self, path: &str) -> Result<(), CloudError> {
+        let remote = self.remote(path);
+        self.run(move |sftp| {
+            // servers don't agree on how to report a folder that is already there
+            if sftp.lstat(&remote).is_ok() {
+                return Err(CloudError::Exists);
+            }
+            Ok(sftp.mkdir(&remote, 0o755)?)
+        })
+        .await
+    }
+
+    async fn upload(
+        &self,
+        local: &Path,
+        path: &str,
+        progress: &TransferProgress,
+    ) -> Result<(), CloudError> {
+        let local = local.to_owned();
+        let remote = self.remote(path);
+        let progress = progress.clone();
+        self.run(move |sftp| {
+            let mut src = std::fs::File::open(&local)?;
+            let mut dst = sftp.create(&remote)?;
+            let mut buf = vec![0; 64 * 1024];
+            loop {
+                let read = src.read(&mut buf)?;
+                if read == 0 {
+                    break;
+                }
+                dst.write_all(&buf[..read])
+                    .map_err(|_| CloudError::Unreachable)?;
+                progress.advance(read as u64)
+            }
+            Ok(())
+        })
+        .await
+    }
+
+    async fn download(
+        &self,
+        path: &str,
+        local: &Path,
+        progress: &TransferProgress,
+    ) -> Result<(), CloudError> {
+        let local = local.to_owned();
+        let remote = self.remote(path);
+        let progress = progress.clone();
+        self.run(move |sftp| {
+            let mut src = sftp.open(&remote)?;
+            // under a hidden name until it is complete, like pasted files are
+            let name = local.file_name().ok_or(CloudError::Io)?;
+            let temp = local.with_file_name(format!(".{}", name.to_string_lossy()));
+            let mut dst = std::fs::File::create(&temp)?;
+            let written = (|| {
+                let mut buf = vec![0; 64 * 1024];
+                loop {
+                    let read = src.read(&mut buf).map_err(|_| CloudError::Unreachable)?;
+                    if read == 0 {
+                        break;
+                    }
+                    dst.write_all(&buf[..read])?;
+                    progress.advance(read as u64)
+                }
+                Ok::<_, CloudError>(dst.flush()?)
+            })();
+            drop(dst);
+            match written {
+                Ok(()) => Ok(std::fs::rename(&temp, &local)?),
+                Err(e) => {
+                    std::fs::remove_file(&temp).ok();
+                    Err(e)
+                }
+            }
+        })
+        .await
+    }
+
+    async fn rename(&self, entry: &CloudEntry, to: &str) -> Result<(), CloudError> {
+        let from = self.remote(&entry.path);
+        let to = self.remote(to);
+        self.run(move |sftp| {
+            if sftp.lstat(&to).is_ok() {
+                return Err(CloudError::Exists);
+            }
+            let flags = RenameFlags::ATOMIC | RenameFlags::NATIVE;
+            Ok(sftp.rename(&from, &to, Some(flags))?)
+        })
+        .await
+    }
+
+    async fn delete(&self, entry: &CloudEntry) -> Result<(), CloudError> {
+        let remote = self.remote(&entry.path);
+        self.run(move |sftp| remove_all(sftp, &remote)).await
+    }
+}
+
+/// Removes `path` along with what it holds, removing links rather than what they point to.
+fn remove_all(sftp: &ssh2::Sftp, path: &Path) -> Result<(), CloudError> {
+    if !sftp.lstat(path)?.is_dir() {
+        return Ok(sftp.unlink(path)?);
+    }
+    for (child, stat) in sftp.readdir(path)? {
+        match stat.is_dir() {
+            true => remove_all(sftp, &child)?,
+            false => sftp.unlink(&child)?,
+        }
+    }
+    Ok(sftp.rmdir(path)?)
+}
+
+#[cfg(test)]
+mod test_mod {
+    use crate::cloud::CloudError;
+
+    #[test]
+    fn test_split_port() {
+        assert_eq!(super::split_port("example.com"), Ok(("example.com", None)));
+        assert_eq!(
+            super::split_port("example.com:2222"),
+            Ok(("example.com", Some(2222)))
+        );
+        assert_eq!(super::split_port("[::1]:22"), Ok(("::1", Some(22))));
+        assert_eq!(super::split_port("[::1]"), Ok(("::1", None)));
+        assert_eq!(
+            super::split_port("example.com:ssh"),
+            Err(CloudError::BadEndpoint)
+        );
+    }
+    #[test]
+    fn test_fingerprint() {
+        assert_eq!(
+            super::fingerprint(b""),
+            "SHA256:47DEQpj8HBSa+/TImW+5JCeuQeRkm5NMpJWZG3hSuFU"
+        );
+    }
+}
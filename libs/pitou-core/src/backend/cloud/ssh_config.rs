@@ -0,0 +1,210 @@
+use std::path::{Path, PathBuf};
+
+/// What `~/.ssh/config` says about reaching a host, with the first value given for each
+/// setting kept as ssh does.
+#[derive(Default)]
+pub(super) struct HostConfig {
+    pub(super) host_name: Option<String>,
+    pub(super) user: Option<String>,
+    pub(super) port: Option<u16>,
+    pub(super) identity_files: Vec<String>,
+}
+
+pub(super) fn path() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".ssh").join("config"))
+}
+
+/// The settings of the `Host` sections matching `alias`, along with those given before any.
+/// `Match` sections can't be evaluated here, so they are passed over.
+pub(super) fn resolve(contents: &str, alias: &str) -> HostConfig {
+    let mut config = HostConfig::default();
+    let mut applies = true;
+    for (keyword, args) in lines(contents) {
+        match keyword.as_str() {
+            "host" => applies = matches(&args, alias),
+            "match" => applies = false,
+            _ if !applies => (),
+            "hostname" if config.host_name.is_none() => config.host_name = args.first().cloned(),
+            "user" if config.user.is_none() => config.user = args.first().cloned(),
+            "port" if config.port.is_none() => {
+                config.port = args.first().and_then(|port| port.parse().ok())
+            }
+            "identityfile" => config.identity_files.extend(args),
+            _ => (),
+        }
+    }
+    config
+}
+
+/// The hosts named in `Host` sections, leaving out patterns since they don't name one.
+pub(super) fn aliases(contents: &str) -> Vec<String> {
+    let mut aliases = Vec::new();
+    for (keyword, args) in lines(contents) {
+        if keyword != "host" {
+            continue;
+        }
+        for arg in args {
+            if !arg.contains(['*', '?', '!']) && !aliases.contains(&arg) {
+                aliases.push(arg)
+            }
+        }
+    }
+    aliases
+}
+
+/// Fills in the `~` and the `%` tokens ssh allows in the path of a key.
+pub(super) fn expand(identity: &str, home: &Path, host: &str, user: &str) -> PathBuf {
+    let identity = match identity.strip_prefix("~/") {
+        Some(rest) => format!("{}/{rest}", home.display()),
+        None => identity.to_owned(),
+    };
+    let mut expanded = String::new();
+    let mut chars = identity.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            expanded.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('d') => expanded.push_str(&home.to_string_lossy()),
+            Some('h') => expanded.push_str(host),
+            Some('r') => expanded.push_str(user),
+            Some('%') => expanded.push('%'),
+            Some(other) => {
+                expanded.push('%');
+                expanded.push(other)
+            }
+            None => expanded.push('%'),
+        }
+    }
+    PathBuf::from(expanded)
+}
+
+/// The keyword of each setting, lowercased, with its arguments unquoted.
+fn lines(contents: &str) -> impl Iterator<Item = (String, Vec<String>)> + '_ {
+    contents.lines().filter_map(|line| {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+        let split = line.find(|c: char| c.is_whitespace() || c == '=')?;
+        let keyword = line[..split].to_lowercase();
+        let rest = line[split..].trim_start_matches(|c: char| c.is_whitespace() || c == '=');
+        Some((keyword, arguments(rest)))
+    })
+}
+
+fn arguments(rest: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    for c in rest.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if !current.is_empty() {
+                    args.push(std::mem::take(&mut current))
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        args.push(current)
+    }
+    args
+}
+
+/// Whether a host matches the patterns of a `Host` line, where any negated pattern that
+/// matches rules it out.
+fn matches(patterns: &[String], host: &str) -> bool {
+    let mut matched = false;
+    for pattern in patterns {
+        match pattern.strip_prefix('!') {
+            Some(negated) if glob(negated, host) => return false,
+            Some(_) => (),
+            None => matched |= glob(pattern, host),
+        }
+    }
+    matched
+}
+
+fn glob(pattern: &str, text: &str) -> bool {
+    let pattern = pattern.to_lowercase().chars().collect::<Vec<_>>();
+    let text = text.to_lowercase().chars().collect::<Vec<_>>();
+    // the last `*` seen and the position in the text it was tried at
+    let (mut p, mut t, mut star) = (0, 0, None);
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, t));
+                p += 1
+            }
+            Some('?') => (p, t) = (p + 1, t + 1),
+            Some(c) if *c == text[t] => (p, t) = (p + 1, t + 1),
+            _ => match star {
+                Some((sp, st)) => {
+                    (p, t) = (sp + 1, st + 1);
+                    star = Some((sp, st + 1))
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+#[cfg(test)]
+mod test_mod {
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn test_ssh_config() {
+        let contents = r#"
+# shared settings
+IdentityFile ~/.ssh/id_work
+
+Host build *.internal !secret.internal
+    HostName 10.0.0.4
+    User deploy
+    Port=2222
+
+Host "files"
+    HostName files.example.com
+    IdentityFile "%d/keys/%r@%h"
+
+Match user root
+    User nobody
+
+Host *
+    User fallback
+    Port 22
+"#;
+        let build = super::resolve(contents, "build");
+        assert_eq!(build.host_name.as_deref(), Some("10.0.0.4"));
+        assert_eq!(build.user.as_deref(), Some("deploy"));
+        assert_eq!(build.port, Some(2222));
+        assert_eq!(build.identity_files, ["~/.ssh/id_work"]);
+
+        let secret = super::resolve(contents, "secret.internal");
+        assert_eq!(secret.host_name, None);
+        assert_eq!(secret.user.as_deref(), Some("fallback"));
+
+        let files = super::resolve(contents, "files");
+        assert_eq!(files.host_name.as_deref(), Some("files.example.com"));
+        assert_eq!(files.user.as_deref(), Some("fallback"));
+        assert_eq!(files.port, Some(22));
+        let identity = super::expand(
+            &files.identity_files[1],
+            Path::new("/home/me"),
+            "files.example.com",
+            "fallback",
+        );
+        assert_eq!(
+            identity,
+            PathBuf::from("/home/me/keys/fallback@files.example.com")
+        );
+
+        assert_eq!(super::aliases(contents), ["build", "files"]);
+    }
+}
@@ -0,0 +1,241 @@
+use std::path::Path;
+
+use async_trait::async_trait;
+use chrono::DateTime;
+use reqwest::{
+    header::{CONTENT_LENGTH, CONTENT_TYPE},
+    Client, Method, RequestBuilder, Url,
+};
+
+use crate::{
+    cloud::{self, CloudAccount, CloudEntry, CloudError},
+    PitouDateTime,
+};
+
+use super::{checked, client, file_body, save_body, CloudProvider, TransferProgress};
+
+const PROPFIND_BODY: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<d:propfind xmlns:d="DAV:"><d:prop><d:resourcetype/><d:getcontentlength/><d:getlastmodified/></d:prop></d:propfind>"#;
+
+/// A WebDAV server, such as Nextcloud's at `https://host/remote.php/dav/files/<user>/`.
+pub(super) struct WebDav {
+    client: Client,
+    /// the folder the account opens at, always ending in `/`
+    base: Url,
+    username: String,
+    password: String,
+}
+
+impl WebDav {
+    pub(super) fn new(account: &CloudAccount) -> Result<Self, CloudError> {
+        let mut base = Url::parse(&account.endpoint).map_err(|_| CloudError::BadEndpoint)?;
+        if base.cannot_be_a_base() || !matches!(base.scheme(), "http" | "https") {
+            return Err(CloudError::BadEndpoint);
+        }
+        if !base.path().ends_with('/') {
+            base.set_path(&format!("{}/", base.path()));
+        }
+        Ok(Self {
+            client: client(),
+            base,
+            username: account.username.clone(),
+            password: account.secret.clone(),
+        })
+    }
+
+    /// The url of `path`, ending in `/` for a folder as servers expect.
+    fn url(&self, path: &str, is_dir: bool) -> Url {
+        let mut url = self.base.clone();
+        if let Ok(mut segments) = url.path_segments_mut() {
+            segments.pop_if_empty();
+            segments.extend(path.split('/').filter(|part| !part.is_empty()));
+            if is_dir {
+                segments.push("");
+            }
+        }
+        url
+    }
+
+    fn request(&self, method: Method, url: Url) -> RequestBuilder {
+        self.client
+            .request(method, url)
+            .basic_auth(&self.username, Some(&self.password))
+    }
+}
+
+fn method(name: &'static str) -> Method {
+    Method::from_bytes(name.as_bytes()).unwrap()
+}
+
+#[async_trait]
+impl CloudProvider for WebDav {
+    async fn list(&self, dir: &str) -> Result<Vec<CloudEntry>, CloudError> {
+        let response = self
+            .request(method("PROPFIND"), self.url(dir, true))
+            .header("Depth", "1")
+            .header(CONTENT_TYPE, "application/xml; charset=utf-8")
+            .body(PROPFIND_BODY)
+            .send()
+            .await?;
+        let contents = checked(response)?.text().await?;
+        let base =
+            super::super::uri::percent_decode(self.base.path()).ok_or(CloudError::BadResponse)?;
+        let mut entries = parse_multistatus(&contents, &base)?;
+        entries.retain(|entry| !entry.path.is_empty() && entry.path != dir);
+        Ok(entries)
+    }
+
+    async fn create_dir(&self, path: &str) -> Result<(), CloudError> {
+        let response = self
+            .request(method("MKCOL"), self.url(path, true))
+            .send()
+            .await?;
+        checked(response).map(|_| ())
+    }
+
+    async fn upload(
+        &self,
+        local: &Path,
+        path: &str,
+        progress: &TransferProgress,
+    ) -> Result<(), CloudError> {
+        let (body, len) = file_body(local, progress).await?;
+        let response = self
+            .request(Method::PUT, self.url(path, false))
+            .header(CONTENT_LENGTH, len)
+            .body(body)
+            .send()
+            .await?;
+        checked(response).map(|_| ())
+    }
+
+    async fn download(
+        &self,
+        path: &str,
+        local: &Path,
+        progress: &TransferProgress,
+    ) -> Result<(), CloudError> {
+        let response = self
+            .request(Method::GET, self.url(path, false))
+            .send()
+            .await?;
+        save_body(checked(response)?, local, progress).await
+    }
+
+    async fn rename(&self, entry: &CloudEntry, to: &str) -> Result<(), CloudError> {
+        let response = self
+            .request(method("MOVE"), self.url(&entry.path, entry.is_dir))
+            .header("Destination", self.url(to, entry.is_dir).as_str())
+            .header("Overwrite", "F")
+            .send()
+            .await?;
+        checked(response).map(|_| ())
+    }
+
+    async fn delete(&self, entry: &CloudEntry) -> Result<(), CloudError> {
+        let response = self
+            .request(Method::DELETE, self.url(&entry.path, entry.is_dir))
+            .send()
+            .await?;
+        checked(response).map(|_| ())
+    }
+}
+
+/// Reads the answer to a `PROPFIND`, giving the path of each item relative to `base`, the
+/// decoded path of the folder the account opens at. The listed folder itself is among them.
+fn parse_multistatus(contents: &str, base: &str) -> Result<Vec<CloudEntry>, CloudError> {
+    let document = roxmltree::Document::parse(contents).map_err(|_| CloudError::BadResponse)?;
+    let entries = document
+        .descendants()
+        .filter(|node| node.has_tag_name(("DAV:", "response")))
+        .filter_map(|response| {
+            let property = |name: &str| {
+                response
+                    .descendants()
+                    .find(|node| node.has_tag_name(("DAV:", name)))
+            };
+            let text = |name: &str| {
+                property(name)
+                    .and_then(|node| node.text())
+                    .map(str::trim)
+                    .filter(|text| !text.is_empty())
+            };
+            // hrefs are either absolute paths or full urls
+            let href = text("href")?;
+            let href = match href.find("://") {
+                Some(idx) => &href[href[idx + 3..].find('/').map(|at| idx + 3 + at)?..],
+                None => href,
+            };
+            let decoded = super::super::uri::percent_decode(href)?;
+            let path = cloud::normalize(decoded.strip_prefix(base.trim_end_matches('/'))?);
+            let is_dir = property("resourcetype").is_some_and(|node| {
+                node.children()
+                    .any(|child| child.has_tag_name(("DAV:", "collection")))
+            });
+            let size = text("getcontentlength")
+                .and_then(|len| len.parse().ok())
+                .unwrap_or_default();
+            let modified = text("getlastmodified")
+                .and_then(|modified| DateTime::parse_from_rfc2822(modified).ok())
+                .map(|modified| PitouDateTime {
+                    datetime: modified.naive_utc(),
+                });
+            Some(CloudEntry {
+                path,
+                is_dir,
+                size,
+                modified,
+            })
+        })
+        .collect();
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod test_mod {
+    #[test]
+    fn test_parse_multistatus() {
+        let contents = r#"<?xml version="1.0"?>
+<d:multistatus xmlns:d="DAV:" xmlns:s="http://sabredav.org/ns" xmlns:oc="http://owncloud.org/ns">
+  <d:response>
+    <d:href>/remote.php/dav/files/user/Documents/</d:href>
+    <d:propstat>
+      <d:prop><d:resourcetype><d:collection/></d:resourcetype><d:getlastmodified>Mon, 04 Mar 2024 09:15:00 GMT</d:getlastmodified></d:prop>
+      <d:status>HTTP/1.1 200 OK</d:status>
+    </d:propstat>
+    <d:propstat>
+      <d:prop><d:getcontentlength/></d:prop>
+      <d:status>HTTP/1.1 404 Not Found</d:status>
+    </d:propstat>
+  </d:response>
+  <d:response>
+    <d:href>/remote.php/dav/files/user/Documents/Q1%20report.pdf</d:href>
+    <d:propstat>
+      <d:prop><d:resourcetype/><d:getcontentlength>5120</d:getcontentlength><d:getlastmodified>Tue, 05 Mar 2024 10:00:00 GMT</d:getlastmodified></d:prop>
+      <d:status>HTTP/1.1 200 OK</d:status>
+    </d:propstat>
+  </d:response>
+  <D:response xmlns:D="DAV:">
+    <D:href>https://cloud.example.com/remote.php/dav/files/user/Documents/Old/</D:href>
+    <D:propstat>
+      <D:prop><D:resourcetype><D:collection/></D:resourcetype></D:prop>
+      <D:status>HTTP/1.1 200 OK</D:status>
+    </D:propstat>
+  </D:response>
+</d:multistatus>"#;
+        let entries = super::parse_multistatus(contents, "/remote.php/dav/files/user/").unwrap();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].path, "Documents");
+        assert!(entries[0].is_dir);
+        assert_eq!(entries[1].path, "Documents/Q1 report.pdf");
+        assert!(!entries[1].is_dir);
+        assert_eq!(entries[1].size, 5120);
+        assert_eq!(
+            entries[1].modified.unwrap().datetime.to_string(),
+            "2024-03-05 10:00:00"
+        );
+        assert_eq!(entries[2].path, "Documents/Old");
+        assert!(entries[2].is_dir);
+        assert!(entries[2].modified.is_none());
+    }
+}
@@ -0,0 +1,358 @@
+use std::{
+    cmp::Reverse,
+    collections::{HashMap, HashSet, LinkedList},
+    fs::File,
+    io::Read,
+    path::PathBuf,
+    sync::atomic::Ordering,
+};
+
+use session::DuplicateSession;
+
+use crate::{
+    duplicates::{DuplicateGroup, DuplicateOptions},
+    msg::DuplicateMsg,
+    PitouFile,
+};
+
+/// Bytes read from the start of every candidate before committing to a full hash.
+const PARTIAL_LEN: u64 = 16 * 1024;
+
+mod session {
+    use std::{
+        collections::LinkedList,
+        sync::{
+            atomic::{AtomicBool, AtomicU64, Ordering},
+            Arc, Mutex, OnceLock,
+        },
+    };
+
+    use crate::{
+        duplicates::DuplicateGroup,
+        msg::{DuplicateMsg, DuplicateProgress},
+    };
+
+    /// A single run of the duplicate finder. Like search streams, every run gets its own session
+    /// so that a cancelled run can never report into the one that replaced it.
+    #[derive(Default)]
+    pub(super) struct DuplicateSession {
+        terminated: AtomicBool,
+        pub(super) scanned: AtomicU64,
+        pub(super) candidates: AtomicU64,
+        pub(super) hashed: AtomicU64,
+        queue: Mutex<LinkedList<DuplicateGroup>>,
+    }
+
+    impl DuplicateSession {
+        pub(super) fn is_terminated(&self) -> bool {
+            self.terminated.load(Ordering::Acquire)
+        }
+
+        pub(super) fn terminate(&self) {
+            self.terminated.store(true, Ordering::Release)
+        }
+
+        pub(super) fn write(&self, group: DuplicateGroup) {
+            if let Ok(mut queue) = self.queue.lock() {
+                queue.push_back(group);
+            }
+        }
+
+        fn progress(&self) -> DuplicateProgress {
+            DuplicateProgress {
+                scanned: self.scanned.load(Ordering::Acquire),
+                candidates: self.candidates.load(Ordering::Acquire),
+                hashed: self.hashed.load(Ordering::Acquire),
+            }
+        }
+
+        pub(super) fn read(&self) -> DuplicateMsg {
+            let terminated = self.is_terminated();
+            let progress = self.progress();
+            let groups = self
+                .queue
+                .lock()
+                .map(|mut queue| queue.split_off(0))
+                .unwrap_or_default();
+            if terminated {
+                DuplicateMsg::Terminated(progress, groups)
+            } else {
+                DuplicateMsg::Active(progress, groups)
+            }
+        }
+    }
+
+    static SESSION: OnceLock<Mutex<Option<Arc<DuplicateSession>>>> = OnceLock::new();
+
+    pub(super) fn get_session() -> Option<Arc<DuplicateSession>> {
+        SESSION
+            .get_or_init(|| Mutex::new(None))
+            .lock()
+            .ok()
+            .and_then(|current| current.clone())
+    }
+
+    /// Replaces the current session with a fresh one, terminating the previous run if it is still going.
+    pub(super) fn configure_session() -> Arc<DuplicateSession> {
+        let session = Arc::new(DuplicateSession::default());
+        if let Ok(mut current) = SESSION.get_or_init(|| Mutex::new(None)).lock() {
+            if let Some(previous) = current.replace(session.clone()) {
+                previous.terminate();
+            }
+        }
+        session
+    }
+}
+
+#[cfg(unix)]
+fn file_id(metadata: &std::fs::Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    Some((metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(unix))]
+fn file_id(_metadata: &std::fs::Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+/// The roots that don't lie within another, each once, so that no file is reached twice through
+/// overlapping roots where [`file_id`] can't tell. Roots that can't be resolved are left out.
+fn distinct_roots(roots: Vec<PathBuf>) -> Vec<PathBuf> {
+    let mut resolved = roots
+        .into_iter()
+        .filter_map(|root| Some((root.canonicalize().ok()?, root)))
+        .collect::<Vec<_>>();
+    // a folder sorts before everything within it
+    resolved.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+    let mut kept = Vec::<(PathBuf, PathBuf)>::new();
+    for (canonical, root) in resolved {
+        if !kept.iter().any(|(outer, _)| canonical.starts_with(outer)) {
+            kept.push((canonical, root));
+        }
+    }
+    kept.into_iter().map(|(_, root)| root).collect()
+}
+
+/// Walks the roots without following symlinks, bucketing every regular file by its size.
+/// Hard links to an already seen file are skipped, deleting them would not free any space.
+fn files_by_size(
+    roots: &[PathBuf],
+    min_size: u64,
+    session: &DuplicateSession,
+) -> HashMap<u64, Vec<PathBuf>> {
+    let mut buckets = HashMap::<u64, Vec<PathBuf>>::new();
+    let mut seen = HashSet::new();
+    let mut directories = roots.to_vec();
+    while let Some(directory) = directories.pop() {
+        if session.is_terminated() {
+            break;
+        }
+        let Ok(entries) = std::fs::read_dir(&directory) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            let unseen = file_id(&metadata).map(|id| seen.insert(id)).unwrap_or(true);
+            if metadata.is_dir() && unseen {
+                directories.push(entry.path());
+            } else if metadata.is_file() {
+                session.scanned.fetch_add(1, Ordering::Relaxed);
+                let size = metadata.len();
+                if size >= min_size.max(1) && unseen {
+                    buckets.entry(size).or_default().push(entry.path());
+                }
+            }
+        }
+    }
+    // the same path listed twice would have both copies selected and deleted
+    for files in buckets.values_mut() {
+        files.sort_unstable();
+        files.dedup();
+    }
+    buckets.retain(|_, files| files.len() > 1);
+    buckets
+}
+
+/// Hashes at most `limit` bytes of the file, `None` if it can't be read or the session ends midway.
+fn hash_file(path: &PathBuf, limit: u64, session: &DuplicateSession) -> Option<blake3::Hash> {
+    let mut reader = File::open(path).ok()?.take(limit);
+    let mut hasher = blake3::Hasher::new();
+    let mut buffer = vec![0; 64 * 1024];
+    loop {
+        if session.is_terminated() {
+            return None;
+        }
+        match reader.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(n) => {
+                hasher.update(&buffer[..n]);
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(_) => return None,
+        }
+    }
+    Some(hasher.finalize())
+}
+
+fn group_by_hash(
+    files: Vec<PathBuf>,
+    limit: u64,
+    session: &DuplicateSession,
+) -> impl Iterator<Item = (blake3::Hash, Vec<PathBuf>)> {
+    let mut groups = HashMap::<blake3::Hash, Vec<PathBuf>>::new();
+    for file in files {
+        if let Some(hash) = hash_file(&file, limit, session) {
+            groups.entry(hash).or_default().push(file);
+        }
+    }
+    groups.into_iter().filter(|(_, files)| files.len() > 1)
+}
+
+/// Narrows a bucket of same-sized files down to groups of identical files, first by the hash
+/// of their leading bytes and then by the hash of their whole contents.
+fn duplicates_of_size(size: u64, files: Vec<PathBuf>, session: &DuplicateSession) {
+    let count = files.len() as u64;
+    let partial_groups = group_by_hash(files, PARTIAL_LEN, session).collect::<Vec<_>>();
+    for (partial, files) in partial_groups {
+        let groups = if size <= PARTIAL_LEN {
+            vec![(partial, files)]
+        } else {
+            group_by_hash(files, u64::MAX, session).collect()
+        };
+        for (hash, files) in groups {
+            if session.is_terminated() {
+                return;
+            }
+            session.write(DuplicateGroup {
+                size,
+                hash: hash.to_hex().to_string(),
+                files: files.into_iter().map(PitouFile::from_pathbuf).collect(),
+            });
+        }
+    }
+    session.hashed.fetch_add(count, Ordering::Relaxed);
+}
+
+fn find(options: DuplicateOptions, session: &DuplicateSession) {
+    let roots = distinct_roots(options.roots.into_iter().map(|root| root.path).collect());
+    let mut buckets = files_by_size(&roots, options.min_size, session)
+        .into_iter()
+        .collect::<Vec<_>>();
+    // the largest files first, they free the most space
+    buckets.sort_unstable_by_key(|(size, _)| Reverse(*size));
+    let candidates = buckets.iter().map(|(_, files)| files.len() as u64).sum();
+    session.candidates.store(candidates, Ordering::Release);
+
+    let threads = if options.hardware_accelerate {
+        std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1)
+    } else {
+        1
+    };
+    let buckets = std::sync::Mutex::new(buckets.into_iter());
+    let next = || buckets.lock().ok().and_then(|mut b| b.next());
+    let work = || {
+        while let Some((size, files)) = next() {
+            if session.is_terminated() {
+                break;
+            }
+            duplicates_of_size(size, files, session);
+        }
+    };
+    std::thread::scope(|scope| {
+        for _ in 1..threads {
+            let _ = std::thread::Builder::new().spawn_scoped(scope, work);
+        }
+        work();
+    });
+}
+
+/// Starts looking for duplicates in the background, the groups are collected through [`read_duplicates`].
+pub async fn find_duplicates(options: DuplicateOptions) {
+    let session = session::configure_session();
+    tokio::task::spawn_blocking(move || {
+        find(options, &session);
+        session.terminate();
+    });
+}
+
+pub async fn read_duplicates() -> DuplicateMsg {
+    match session::get_session() {
+        Some(session) => session.read(),
+        None => DuplicateMsg::Terminated(Default::default(), LinkedList::new()),
+    }
+}
+
+pub async fn terminate_duplicates() {
+    if let Some(session) = session::get_session() {
+        session.terminate()
+    }
+}
+
+#[cfg(test)]
+mod test_mod {
+    use super::{find, session::DuplicateSession};
+    use crate::{duplicates::DuplicateOptions, msg::DuplicateMsg, PitouFilePath};
+
+    #[test]
+    fn test_find() {
+        let root = std::env::temp_dir().join("pitou-duplicates-test");
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("nested")).unwrap();
+        let large = vec![7u8; 3 * super::PARTIAL_LEN as usize];
+        let mut differs_late = large.clone();
+        *differs_late.last_mut().unwrap() = 8;
+        std::fs::write(root.join("a.txt"), "same").unwrap();
+        std::fs::write(root.join("nested/b.txt"), "same").unwrap();
+        std::fs::write(root.join("c.txt"), "diff").unwrap();
+        std::fs::write(root.join("large.bin"), &large).unwrap();
+        std::fs::write(root.join("nested/large.bin"), &large).unwrap();
+        std::fs::write(root.join("late.bin"), &differs_late).unwrap();
+        std::fs::write(root.join("empty1"), "").unwrap();
+        std::fs::write(root.join("empty2"), "").unwrap();
+
+        let session = DuplicateSession::default();
+        let options = DuplicateOptions {
+            roots: vec![PitouFilePath::from_pathbuf(root.clone())],
+            min_size: 0,
+            hardware_accelerate: true,
+        };
+        find(options, &session);
+        session.terminate();
+        let DuplicateMsg::Terminated(progress, groups) = session.read() else {
+            panic!("the session was terminated");
+        };
+        let mut groups = groups
+            .into_iter()
+            .map(|g| (g.size, g.files.len()))
+            .collect::<Vec<_>>();
+        groups.sort();
+        assert_eq!(groups, vec![(4, 2), (large.len() as u64, 2)]);
+        assert_eq!(progress.scanned, 8);
+        assert_eq!(progress.candidates, 6);
+        assert_eq!(progress.hashed, 6);
+
+        // overlapping roots still list every file once
+        let session = DuplicateSession::default();
+        let options = DuplicateOptions {
+            roots: [root.clone(), root.join("nested"), root.join("./nested/..")]
+                .into_iter()
+                .map(PitouFilePath::from_pathbuf)
+                .collect(),
+            min_size: 0,
+            hardware_accelerate: false,
+        };
+        find(options, &session);
+        session.terminate();
+        let DuplicateMsg::Terminated(progress, groups) = session.read() else {
+            panic!("the session was terminated");
+        };
+        assert_eq!(groups.len(), 2);
+        assert!(groups.iter().all(|g| g.files.len() == 2));
+        assert_eq!(progress.scanned, 8);
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
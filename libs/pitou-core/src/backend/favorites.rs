@@ -0,0 +1,137 @@
+use std::path::PathBuf;
+
+use tokio::sync::Mutex;
+
+use crate::{
+    favorites::{self, Favorite, FavoriteItem},
+    PitouFile, PitouFilePath,
+};
+
+fn store_path() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("pitou").join("favorites.json"))
+}
+
+/// Held across every read-modify-write of the store, so that concurrent changes aren't lost.
+static STORE_LOCK: Mutex<()> = Mutex::const_new(());
+
+/// The favorites in the order they are listed. A missing store reads as empty, while one that
+/// can't be read or parsed is an error rather than being taken for empty and overwritten.
+async fn stored() -> std::io::Result<Vec<Favorite>> {
+    let path = store_path().ok_or(std::io::ErrorKind::NotFound)?;
+    match tokio::fs::read(path).await {
+        Ok(contents) => Ok(serde_json::from_slice(&contents)?),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e),
+    }
+}
+
+async fn store(favorites: &[Favorite]) -> std::io::Result<()> {
+    let path = store_path().ok_or(std::io::ErrorKind::NotFound)?;
+    let contents = serde_json::to_vec_pretty(favorites)?;
+    super::store::write_atomic(&path, &contents).await
+}
+
+/// Applies `change` to the stored favorites and saves them.
+async fn update<T>(change: impl FnOnce(&mut Vec<Favorite>) -> T) -> std::io::Result<T> {
+    let _guard = STORE_LOCK.lock().await;
+    let mut favorites = stored().await?;
+    let res = change(&mut favorites);
+    store(&favorites).await?;
+    Ok(res)
+}
+
+/// Every favorite with the file it leads to, which is `None` for a favorite whose target is gone.
+/// An unreadable store lists nothing.
+pub async fn favorites() -> Vec<FavoriteItem> {
+    stored()
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .map(|favorite| {
+            let path = &favorite.path.path;
+            let target = path
+                .symlink_metadata()
+                .ok()
+                .map(|_| PitouFile::from_pathbuf(path.clone()));
+            FavoriteItem { favorite, target }
+        })
+        .collect()
+}
+
+pub async fn add(items: Vec<PitouFilePath>) -> std::io::Result<usize> {
+    update(|favorites| favorites::add(favorites, items.into_iter().map(Favorite::new))).await
+}
+
+pub async fn remove(items: Vec<PitouFilePath>) -> std::io::Result<()> {
+    update(|favorites| favorites.retain(|favorite| !items.contains(&favorite.path))).await
+}
+
+pub async fn rename(path: PitouFilePath, label: String) -> std::io::Result<()> {
+    update(|favorites| favorites::rename(favorites, &path, &label)).await
+}
+
+pub async fn reorder(path: PitouFilePath, position: usize) -> std::io::Result<()> {
+    update(|favorites| favorites::reorder(favorites, &path, position)).await
+}
+
+/// Removes the favorites whose targets are gone, returning how many were removed.
+pub async fn forget_missing() -> std::io::Result<usize> {
+    update(|favorites| {
+        let before = favorites.len();
+        favorites.retain(|favorite| favorite.path.path.symlink_metadata().is_ok());
+        before - favorites.len()
+    })
+    .await
+}
+
+/// Adds the local folders bookmarked in GTK file choosers and file managers that aren't
+/// favorites yet, returning how many were added.
+pub async fn import_gtk_bookmarks() -> std::io::Result<usize> {
+    let candidates = [
+        dirs::config_dir().map(|dir| dir.join("gtk-3.0").join("bookmarks")),
+        dirs::home_dir().map(|dir| dir.join(".gtk-bookmarks")),
+    ];
+    for path in candidates.into_iter().flatten() {
+        if let Ok(contents) = tokio::fs::read_to_string(path).await {
+            let bookmarks = parse_gtk_bookmarks(&contents);
+            return update(|favorites| favorites::add(favorites, bookmarks)).await;
+        }
+    }
+    Err(std::io::ErrorKind::NotFound.into())
+}
+
+/// Reads lines of the form `file:///some/folder Label`. Bookmarks of remote locations are skipped.
+fn parse_gtk_bookmarks(contents: &str) -> Vec<Favorite> {
+    contents
+        .lines()
+        .filter_map(|line| {
+            let (uri, label) = line.trim().split_once(' ').unwrap_or((line.trim(), ""));
+            let path = super::uri::percent_decode(uri.strip_prefix("file://")?)?;
+            let label = label.trim();
+            Some(Favorite {
+                path: PitouFilePath::from_pathbuf(PathBuf::from(path)),
+                label: (!label.is_empty()).then(|| label.to_owned()),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test_mod {
+    use std::path::Path;
+
+    #[test]
+    fn test_parse_gtk_bookmarks() {
+        let contents = "file:///home/user/My%20Music Tunes\n\
+                        sftp://host/srv/share Server\n\
+                        \n\
+                        file:///home/user/caf%C3%A9\n\
+                        file:///bad%2";
+        let bookmarks = super::parse_gtk_bookmarks(contents);
+        assert_eq!(bookmarks.len(), 2);
+        assert_eq!(bookmarks[0].path.path, Path::new("/home/user/My Music"));
+        assert_eq!(bookmarks[0].label(), "Tunes");
+        assert_eq!(bookmarks[1].path.path, Path::new("/home/user/café"));
+        assert!(bookmarks[1].label.is_none());
+    }
+}
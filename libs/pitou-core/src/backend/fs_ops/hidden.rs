@@ -0,0 +1,53 @@
+use std::{collections::HashSet, ffi::OsString, fs::Metadata, path::Path};
+
+/// The names listed one per line in the `.hidden` file of a folder, which file managers
+/// on Linux hide along with dot files.
+#[derive(Default)]
+pub(crate) struct HiddenList {
+    names: HashSet<OsString>,
+}
+
+impl HiddenList {
+    pub(crate) fn read(dir: &Path) -> Self {
+        let Ok(contents) = std::fs::read_to_string(dir.join(".hidden")) else {
+            return Self::default();
+        };
+        let names = contents
+            .lines()
+            .map(|line| line.trim_end_matches('\r'))
+            .filter(|line| !line.is_empty())
+            .map(OsString::from)
+            .collect();
+        Self { names }
+    }
+
+    /// The list of the folder holding `path`.
+    pub(crate) fn of_parent(path: &Path) -> Self {
+        match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => Self::read(dir),
+            _ => Self::default(),
+        }
+    }
+
+    /// Whether the item at `path`, which must be in the folder this list was read from, is hidden.
+    pub(crate) fn hides(&self, path: &Path, metadata: &Metadata) -> bool {
+        let Some(name) = path.file_name() else {
+            return false;
+        };
+        name.as_encoded_bytes().starts_with(b".")
+            || self.names.contains(name)
+            || has_hidden_attribute(metadata)
+    }
+}
+
+#[cfg(windows)]
+fn has_hidden_attribute(metadata: &Metadata) -> bool {
+    use std::os::windows::fs::MetadataExt;
+    const FILE_ATTRIBUTE_HIDDEN: u32 = 2;
+    metadata.file_attributes() & FILE_ATTRIBUTE_HIDDEN != 0
+}
+
+#[cfg(not(windows))]
+fn has_hidden_attribute(_: &Metadata) -> bool {
+    false
+}
@@ -1,22 +1,23 @@
 use std::{
     fs::{FileType, Metadata},
-    path::PathBuf,
-    str::FromStr,
+    path::{Path, PathBuf},
     time::SystemTime,
 };
 
 #[cfg(target_os = "windows")]
 use std::os::windows::fs::MetadataExt;
 
+use super::vfs;
 use crate::{
-    GeneralFolder, PitouDateTime, PitouDrive, PitouFile, PitouFileFilter, PitouFileKind,
-    PitouFileMetadata, PitouFilePath, PitouFileSize, PitouFileSort, PitouTrashItem,
-    PitouTrashItemMetadata,
+    recents::RecentKind, GeneralFolder, PitouDateTime, PitouDrive, PitouFile, PitouFileFilter,
+    PitouFileKind, PitouFileMetadata, PitouFilePath, PitouFileSort, PitouLink,
 };
-use chrono::DateTime;
-use trash::TrashItem;
+use chrono::NaiveDateTime;
 
 pub mod drive;
+mod hidden;
+
+pub(crate) use hidden::HiddenList;
 
 pub mod clipboard {
     use std::sync::{Arc, OnceLock};
@@ -85,9 +86,18 @@ pub fn drives() -> Vec<PitouDrive> {
     drives
 }
 
+/// Sends local items to the trash. Items of a mounted filesystem, which has no trash, are
+/// removed for good.
 pub fn delete(items: Vec<PitouFile>) {
     for item in items {
-        tokio::spawn(async move { trash::delete(&item.path.path) });
+        tokio::spawn(async move {
+            match vfs::route(&item.path.path) {
+                Ok(route) if !route.is_local() => {
+                    blocking(move || route.fs.remove(&route.path)).await.ok()
+                }
+                _ => trash::delete(&item.path.path).ok(),
+            }
+        });
     }
 }
 
@@ -99,36 +109,53 @@ pub async fn cut(items: Vec<PitouFile>) {
     clipboard::cut(items).await
 }
 
-pub fn open(file: PitouFilePath) -> std::io::Result<()> {
-    open::that_detached(&file.path)
+/// Opens `file` with its default application and lists it with the recents.
+pub async fn open(file: PitouFilePath) -> std::io::Result<()> {
+    open::that_detached(&file.path)?;
+    super::recents::record(file, RecentKind::File).await.ok();
+    Ok(())
 }
 
-pub fn open_with(file: PitouFilePath) -> Result<(), ()> {
-    open_with::open_with(file.path).map_err(|_| ())
+pub async fn open_with(file: PitouFilePath) -> Result<(), ()> {
+    open_with::open_with(file.path.clone()).map_err(|_| ())?;
+    super::recents::record(file, RecentKind::File).await.ok();
+    Ok(())
 }
 
 pub fn share(_file: PitouFilePath) -> std::io::Result<()> {
     todo!()
 }
 
-pub async fn rename(file: PitouFilePath, newname: String) {
-    let newpath = file.path.parent().unwrap_or(&PathBuf::new()).join(newname);
-    tokio::fs::rename(&file.path, newpath).await.unwrap();
+pub async fn rename(file: PitouFilePath, newname: String) -> std::io::Result<()> {
+    let route = vfs::route(&file.path)?;
+    let newpath = route.path.parent().unwrap_or(&PathBuf::new()).join(newname);
+    blocking(move || route.fs.rename(&route.path, &newpath)).await
 }
 
-pub async fn create_file(file: PitouFilePath) {
-    tokio::fs::File::create(&file.path)
-        .await
-        .expect("couldn't create file");
+pub async fn create_file(file: PitouFilePath) -> std::io::Result<()> {
+    let route = vfs::route(&file.path)?;
+    blocking(move || route.fs.create_file(&route.path)).await
 }
 
-pub async fn create_dir(dir: PitouFilePath) {
-    tokio::fs::create_dir(&dir.path)
+pub async fn create_dir(dir: PitouFilePath) -> std::io::Result<()> {
+    let route = vfs::route(&dir.path)?;
+    blocking(move || route.fs.create_dir(&route.path)).await
+}
+
+/// Runs a call to a filesystem off the async workers, as its calls block.
+async fn blocking<T: Send + 'static>(
+    op: impl FnOnce() -> std::io::Result<T> + Send + 'static,
+) -> std::io::Result<T> {
+    tokio::task::spawn_blocking(op)
         .await
-        .expect("couldn't create dir");
+        .unwrap_or_else(|e| Err(std::io::Error::other(e)))
 }
 
+/// Where a local link points to. Mounted filesystems have no links.
 pub async fn read_link(link: PitouFilePath) -> Option<crate::PitouFile> {
+    if link.is_mounted() {
+        return None;
+    }
     tokio::fs::read_link(&link.path)
         .await
         .map(|path| PitouFile::from_pathbuf(path))
@@ -146,30 +173,48 @@ pub async fn children(
             .filter_map(|drive| filter.map(PitouFile::from_pathbuf(drive.mount_point.path)))
             .collect::<Vec<_>>();
         return if let Some(sort) = sort {
-            Ok(sort.sorted(items))
+            Ok(sorted(sort, items))
         } else {
             Ok(items)
         };
     }
 
-    let mut read_dir = tokio::fs::read_dir(&dir.path).await?;
-    let mut res = Vec::new();
-    while let Some(entry) = read_dir.next_entry().await? {
-        let file = PitouFile::from_pathbuf(entry.path());
-        if let Some(file) = filter.map(file) {
-            res.push(file);
+    let route = vfs::route(&dir.path)?;
+    let res = blocking(move || {
+        let mut res = Vec::new();
+        for mut file in route.fs.read_dir(&route.path)? {
+            if let Some(metadata) = &mut file.metadata {
+                if metadata.is_dir() {
+                    metadata.size = route.fs.count(&file.path.path).unwrap_or_default().into();
+                }
+            }
+            if let Some(file) = filter.map(route.rebase(file)) {
+                res.push(file);
+            }
         }
-    }
+        Ok(res)
+    })
+    .await?;
     return if let Some(sort) = sort {
-        Ok(sort.sorted(res))
+        Ok(sorted(sort, res))
     } else {
         Ok(res)
     };
 }
 
+fn sorted(sort: PitouFileSort, items: Vec<PitouFile>) -> Vec<PitouFile> {
+    sort.sorted_with(items, super::properties::guessed_mime_type)
+}
+
 impl PitouFile {
     pub fn from_pathbuf(path: PathBuf) -> Self {
-        let mut metadata = PitouFileMetadata::attempt(&path);
+        let hidden = HiddenList::of_parent(&path);
+        Self::from_pathbuf_in(path, &hidden)
+    }
+
+    /// Same as [`Self::from_pathbuf`], with the `.hidden` list of the folder holding `path`.
+    pub(crate) fn from_pathbuf_in(path: PathBuf, hidden: &HiddenList) -> Self {
+        let mut metadata = PitouFileMetadata::attempt(&path, hidden);
         if let Some(metadata) = &mut metadata {
             if metadata.is_dir() {
                 metadata.size = Self::attempt_count(&path).into();
@@ -193,8 +238,32 @@ impl PitouFile {
 }
 
 impl PitouFileMetadata {
-    fn attempt(path: &PathBuf) -> Option<Self> {
-        std::fs::metadata(path).map(|v| v.into()).ok()
+    /// Reads the metadata of the item at `path` itself, without following symlinks.
+    fn attempt(path: &PathBuf, hidden: &HiddenList) -> Option<Self> {
+        std::fs::symlink_metadata(path)
+            .map(|v| Self::from_metadata(v, path, hidden))
+            .ok()
+    }
+
+    fn from_metadata(metadata: Metadata, path: &Path, hidden: &HiddenList) -> Self {
+        let is_hidden = hidden.hides(path, &metadata);
+        let mut res: Self = metadata.into();
+        res.hidden = is_hidden;
+        if matches!(res.kind, PitouFileKind::Link) {
+            res.link = PitouLink::attempt(path);
+        }
+        res
+    }
+}
+
+impl PitouLink {
+    fn attempt(path: &Path) -> Option<Self> {
+        let target = std::fs::read_link(path).ok()?;
+        let target_kind = std::fs::metadata(path).map(|v| v.file_type().into()).ok();
+        Some(Self {
+            target: target.into(),
+            target_kind,
+        })
     }
 }
 
@@ -250,131 +319,218 @@ pub fn general_folders() -> Vec<GeneralFolder> {
     ]
 }
 
-pub fn trash_items() -> Option<Vec<PitouTrashItem>> {
-    trash::os_limited::list()
-        .map(|v| v.into_iter().filter_map(|u| u.try_into().ok()).collect())
-        .ok()
-}
-
-pub fn restore_trash(items: impl Iterator<Item = PitouTrashItem>) {
-    trash::os_limited::restore_all(items.map(|v| <PitouTrashItem as Into<TrashItem>>::into(v)))
-        .ok();
-}
-
-pub fn purge_trash(items: impl Iterator<Item = PitouTrashItem>) {
-    trash::os_limited::purge_all(items.map(|v| <PitouTrashItem as Into<TrashItem>>::into(v))).ok();
-}
-
-impl From<PitouTrashItem> for TrashItem {
-    fn from(
-        PitouTrashItem {
-            mut original_path,
-            metadata:
-                PitouTrashItemMetadata {
-                    id,
-                    deleted,
-                    size: _,
-                    is_dir: _,
-                },
-        }: PitouTrashItem,
-    ) -> Self {
-        let time_deleted = deleted.datetime.and_utc().timestamp();
-        let name = original_path.name().to_owned();
-        original_path.path.pop();
-        let original_parent = original_path.path;
-
-        Self {
-            id: std::ffi::OsString::from_str(&id).unwrap(),
-            name,
-            time_deleted,
-            original_parent,
-        }
-    }
-}
-
-impl TryFrom<TrashItem> for PitouTrashItem {
-    type Error = trash::Error;
-    fn try_from(item: TrashItem) -> Result<Self, Self::Error> {
-        let (size, is_dir) = match trash::os_limited::metadata(&item)?.size {
-            trash::TrashItemSize::Bytes(val) => (val, false),
-            trash::TrashItemSize::Entries(val) => (val as u64, true),
-        };
-
-        let TrashItem {
-            id,
-            name,
-            mut original_parent,
-            time_deleted,
-        } = item;
-
-        original_parent.push(name);
-
-        let metadata = PitouTrashItemMetadata {
-            id: id.into_string().unwrap(),
-            deleted: PitouDateTime {
-                datetime: DateTime::from_timestamp_millis(1000 * time_deleted)
-                    .unwrap()
-                    .naive_utc(),
-            },
-            is_dir,
-            size: PitouFileSize::new(size),
-        };
-
-        Ok(PitouTrashItem {
-            original_path: PitouFilePath::from_pathbuf(original_parent),
-            metadata,
-        })
-    }
-}
-
 impl From<FileType> for PitouFileKind {
     fn from(value: FileType) -> Self {
-        if value.is_dir() {
+        if value.is_symlink() {
+            Self::Link
+        } else if value.is_dir() {
             Self::Directory
         } else if value.is_file() {
             Self::File
         } else {
-            Self::Link
+            Self::special(value)
         }
     }
 }
 
+impl PitouFileKind {
+    #[cfg(unix)]
+    fn special(value: FileType) -> Self {
+        use std::os::unix::fs::FileTypeExt;
+        if value.is_socket() {
+            Self::Socket
+        } else if value.is_fifo() {
+            Self::Fifo
+        } else if value.is_block_device() {
+            Self::BlockDevice
+        } else if value.is_char_device() {
+            Self::CharDevice
+        } else {
+            Self::File
+        }
+    }
+
+    #[cfg(not(unix))]
+    fn special(_: FileType) -> Self {
+        Self::File
+    }
+}
+
 impl PitouFile {
-    pub fn new(path: PathBuf, metadata: Metadata) -> Self {
-        let path = path.into();
-        let metadata = metadata.try_into().ok();
-        Self { path, metadata }
+    pub(crate) fn new(path: PathBuf, metadata: Metadata, hidden: &HiddenList) -> Self {
+        let metadata = Some(PitouFileMetadata::from_metadata(metadata, &path, hidden));
+        Self {
+            path: path.into(),
+            metadata,
+        }
     }
 }
 
 impl From<SystemTime> for PitouDateTime {
+    /// Times before 1970 are kept, those beyond what chrono can represent are clamped.
     fn from(value: SystemTime) -> Self {
-        let millis_epoch = value
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_millis() as i64;
-        Self {
-            datetime: DateTime::from_timestamp_millis(millis_epoch)
-                .unwrap()
-                .naive_utc(),
-        }
+        let millis = match value.duration_since(std::time::UNIX_EPOCH) {
+            Ok(after) => i64::try_from(after.as_millis()).unwrap_or(i64::MAX),
+            Err(e) => i64::try_from(e.duration().as_millis()).map_or(i64::MIN, |v| -v),
+        };
+        Self::from_timestamp_millis(millis).unwrap_or_else(|| {
+            let datetime = if millis < 0 {
+                NaiveDateTime::MIN
+            } else {
+                NaiveDateTime::MAX
+            };
+            Self { datetime }
+        })
     }
 }
 
 impl From<Metadata> for PitouFileMetadata {
+    /// Timestamps the filesystem doesn't provide are left out. On Linux, std reads the
+    /// birth time through `statx` where the kernel and the filesystem support it.
     fn from(value: Metadata) -> Self {
         #[cfg(target_os = "windows")]
         let attribute = value.file_attributes();
         #[cfg(not(target_os = "windows"))]
         let attribute = 0u32; // Default value for non-Windows platforms
-        
+
         Self {
-            modified: value.modified().unwrap().into(),
-            accessed: value.accessed().unwrap().into(),
-            created: value.created().unwrap().into(),
+            modified: value.modified().ok().map(Into::into),
+            accessed: value.accessed().ok().map(Into::into),
+            created: value.created().ok().map(Into::into),
             size: value.len().into(),
             kind: value.file_type().into(),
             attribute,
+            hidden: false,
+            link: None,
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod test_mod {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use crate::{
+        PitouDateTime, PitouFile, PitouFileFilter, PitouFileKind, PitouFilePath, PitouFileSort,
+        PitouFileSortKey, PitouFileSortOrder,
+    };
+
+    #[test]
+    fn test_system_time() {
+        let before = PitouDateTime::from(UNIX_EPOCH - Duration::from_millis(86_400_500));
+        assert_eq!(before.timestamp_millis(), -86_400_500);
+        assert_eq!(
+            before.datetime.format("%Y-%m-%d %H:%M:%S").to_string(),
+            "1969-12-30 23:59:59"
+        );
+        let after = PitouDateTime::from(UNIX_EPOCH + Duration::from_secs(1));
+        assert_eq!(after.timestamp_millis(), 1000);
+    }
+
+    #[test]
+    fn test_file_kinds() {
+        let dir = std::env::temp_dir().join("pitou-kinds-test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("folder")).unwrap();
+        std::os::unix::fs::symlink("folder", dir.join("to_folder")).unwrap();
+        std::os::unix::fs::symlink("missing", dir.join("broken")).unwrap();
+        let _socket = std::os::unix::net::UnixListener::bind(dir.join("socket")).unwrap();
+
+        let link = PitouFile::from_pathbuf(dir.join("to_folder"));
+        assert!(link.is_link());
+        assert!(link.opens_as_dir());
+        assert_eq!(link.link_target().unwrap().path.to_str(), Some("folder"));
+        assert!(!link.is_broken_link());
+
+        let broken = PitouFile::from_pathbuf(dir.join("broken"));
+        assert!(broken.is_link());
+        assert!(broken.is_broken_link());
+        assert!(broken.resolved_kind().is_none());
+
+        let socket = PitouFile::from_pathbuf(dir.join("socket"));
+        assert!(socket.kind() == Some(PitouFileKind::Socket));
+        assert!(socket.is_special());
+
+        let dev = PitouFile::from_pathbuf("/dev/null".into());
+        assert!(dev.kind() == Some(PitouFileKind::CharDevice));
+        assert!(dev.is_sys_item());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_sort() {
+        let dir = std::env::temp_dir().join("pitou-sort-test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("zeta")).unwrap();
+        for (name, len) in [("file10.txt", 3), ("File2.png", 1), ("alpha.txt", 2)] {
+            std::fs::write(dir.join(name), vec![0; len]).unwrap();
         }
+        let names = |sort: PitouFileSort| {
+            let dir = PitouFilePath::from_pathbuf(dir.clone());
+            tokio::runtime::Runtime::new()
+                .unwrap()
+                .block_on(super::children(dir, PitouFileFilter::new(), Some(sort)))
+                .unwrap()
+                .iter()
+                .map(|f| f.name().to_owned())
+                .collect::<Vec<_>>()
+        };
+
+        let sort = PitouFileSort::new(PitouFileSortKey::Name, PitouFileSortOrder::Increasing);
+        assert_eq!(
+            names(sort),
+            ["zeta", "alpha.txt", "File2.png", "file10.txt"]
+        );
+
+        let sort = PitouFileSort {
+            folders_first: false,
+            ..PitouFileSort::new(PitouFileSortKey::Mime, PitouFileSortOrder::Decreasing)
+        };
+        assert_eq!(
+            names(sort),
+            ["alpha.txt", "file10.txt", "zeta", "File2.png"]
+        );
+
+        let sort = PitouFileSort::new(PitouFileSortKey::Size, PitouFileSortOrder::Decreasing);
+        assert_eq!(names(sort)[1..], ["file10.txt", "alpha.txt", "File2.png"]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_hidden() {
+        let dir = std::env::temp_dir().join("pitou-hidden-test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join(".config")).unwrap();
+        std::fs::write(dir.join("listed.txt"), "").unwrap();
+        std::fs::write(dir.join("shown.txt"), "").unwrap();
+        std::fs::write(dir.join(".hidden"), "listed.txt\nmissing\n").unwrap();
+
+        assert!(PitouFile::from_pathbuf(dir.join(".config")).is_hidden());
+        assert!(PitouFile::from_pathbuf(dir.join("listed.txt")).is_hidden());
+        assert!(!PitouFile::from_pathbuf(dir.join("shown.txt")).is_hidden());
+
+        let names = |filter: PitouFileFilter| {
+            let dir = PitouFilePath::from_pathbuf(dir.clone());
+            let children = tokio::runtime::Runtime::new()
+                .unwrap()
+                .block_on(super::children(dir, filter, None))
+                .unwrap();
+            let mut names = children
+                .iter()
+                .map(|f| f.name().to_owned())
+                .collect::<Vec<_>>();
+            names.sort();
+            names
+        };
+        let filter = PitouFileFilter {
+            hidden: false,
+            ..PitouFileFilter::new()
+        };
+        assert_eq!(names(filter), ["shown.txt"]);
+        assert_eq!(names(PitouFileFilter::new()).len(), 4);
+
+        std::fs::remove_dir_all(&dir).unwrap();
     }
 }
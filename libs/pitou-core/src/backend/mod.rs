@@ -1,7 +1,24 @@
 mod fs_ops;
 mod ser_de;
+mod store;
+mod uri;
 
+pub mod cloud;
+pub mod duplicates;
+pub mod favorites;
+pub mod preview;
+pub mod properties;
+pub mod recents;
+pub mod saved_searches;
 pub mod search;
+pub mod session;
+pub mod settings;
+pub mod themes;
+pub mod thumbnails;
 pub mod transfer;
+pub mod trash;
+pub mod usage;
+pub mod vault;
+pub mod vfs;
 
 pub use fs_ops::*;
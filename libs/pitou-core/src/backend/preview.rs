@@ -0,0 +1,351 @@
+use std::{
+    fs::File,
+    io::{Cursor, Read},
+    path::Path,
+    sync::OnceLock,
+};
+
+use base64::Engine;
+use image::{DynamicImage, ImageDecoder, ImageFormat, ImageReader};
+use pulldown_cmark::{CodeBlockKind, Event, Options, Parser, Tag, TagEnd};
+use syntect::{
+    highlighting::{Theme, ThemeSet},
+    html::highlighted_html_for_string,
+    parsing::{SyntaxReference, SyntaxSet},
+};
+
+use crate::{
+    preview::{DirectoryEntry, Preview, MAX_ENTRIES, MAX_HEX_LEN, MAX_IMAGE_LEN, MAX_TEXT_LEN},
+    thumbnails::has_thumbnail,
+    PitouFileKind, PitouFilePath,
+};
+
+/// The longest side of an image preview, larger images are scaled down to it.
+const MAX_IMAGE_SIDE: u32 = 1024;
+/// Images already small enough are sent untouched if they are at most this large.
+const MAX_RAW_IMAGE_LEN: u64 = 2 * 1024 * 1024;
+
+fn syntaxes() -> &'static SyntaxSet {
+    static SYNTAXES: OnceLock<SyntaxSet> = OnceLock::new();
+    SYNTAXES.get_or_init(SyntaxSet::load_defaults_newlines)
+}
+
+fn theme(dark: bool) -> &'static Theme {
+    static THEMES: OnceLock<ThemeSet> = OnceLock::new();
+    let themes = THEMES.get_or_init(ThemeSet::load_defaults);
+    let name = if dark {
+        "base16-ocean.dark"
+    } else {
+        "InspiredGitHub"
+    };
+    &themes.themes[name]
+}
+
+fn syntax_for<'a>(path: &Path, first_line: &str) -> &'a SyntaxReference {
+    let syntaxes = syntaxes();
+    let extension = path.extension().and_then(|e| e.to_str());
+    // files like `Makefile` are known by their whole name
+    let name = path.file_name().and_then(|n| n.to_str());
+    extension
+        .into_iter()
+        .chain(name)
+        .find_map(|key| syntaxes.find_syntax_by_extension(key))
+        .or_else(|| syntaxes.find_syntax_by_first_line(first_line))
+        .unwrap_or_else(|| syntaxes.find_syntax_plain_text())
+}
+
+fn highlight(text: &str, syntax: &SyntaxReference, dark: bool) -> String {
+    highlighted_html_for_string(text, syntaxes(), syntax, theme(dark)).unwrap_or_else(|_| {
+        let plain = [
+            Event::Start(Tag::CodeBlock(CodeBlockKind::Indented)),
+            Event::Text(text.into()),
+            Event::End(TagEnd::CodeBlock),
+        ];
+        let mut html = String::new();
+        pulldown_cmark::html::push_html(&mut html, plain.into_iter());
+        html
+    })
+}
+
+/// Renders Markdown without raw HTML, images or links: embedded HTML shows as text, and images
+/// and links as their description, since following a link would take the app's own view away
+/// from it. Fenced code is highlighted.
+fn render_markdown(text: &str, dark: bool) -> String {
+    let options = Options::ENABLE_TABLES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TASKLISTS
+        | Options::ENABLE_FOOTNOTES;
+    let mut events = Vec::new();
+    let mut code: Option<(String, String)> = None;
+    for event in Parser::new_ext(text, options) {
+        match event {
+            Event::Start(Tag::CodeBlock(kind)) => {
+                let language = match kind {
+                    CodeBlockKind::Fenced(language) => language.to_string(),
+                    CodeBlockKind::Indented => String::new(),
+                };
+                code = Some((language, String::new()));
+            }
+            Event::End(TagEnd::CodeBlock) => {
+                if let Some((language, text)) = code.take() {
+                    let syntax = syntaxes()
+                        .find_syntax_by_token(&language)
+                        .unwrap_or_else(|| syntaxes().find_syntax_plain_text());
+                    events.push(Event::Html(highlight(&text, syntax, dark).into()));
+                }
+            }
+            Event::Text(text) if code.is_some() => {
+                if let Some((_, code)) = &mut code {
+                    code.push_str(&text);
+                }
+            }
+            Event::Html(html) | Event::InlineHtml(html) => events.push(Event::Text(html)),
+            Event::Start(Tag::Image { .. } | Tag::Link { .. })
+            | Event::End(TagEnd::Image | TagEnd::Link) => (),
+            event => events.push(event),
+        }
+    }
+    let mut html = String::new();
+    pulldown_cmark::html::push_html(&mut html, events.into_iter());
+    html
+}
+
+/// The text in `bytes`, if they are text. A character cut by the end of the buffer is dropped.
+fn as_text(bytes: &[u8]) -> Option<&str> {
+    if bytes.contains(&0) {
+        return None;
+    }
+    match std::str::from_utf8(bytes) {
+        Ok(text) => Some(text),
+        Err(e) if e.error_len().is_none() => std::str::from_utf8(&bytes[..e.valid_up_to()]).ok(),
+        Err(_) => None,
+    }
+}
+
+fn read_head(path: &Path, limit: u64) -> std::io::Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    File::open(path)?.take(limit).read_to_end(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn data_url(mime: &str, bytes: &[u8]) -> String {
+    let encoded = base64::engine::general_purpose::STANDARD.encode(bytes);
+    format!("data:{mime};base64,{encoded}")
+}
+
+fn preview_image(path: &Path, len: u64) -> Preview {
+    if len > MAX_IMAGE_LEN {
+        return Preview::Unavailable(String::from("This image is too large to preview"));
+    }
+    let Ok(reader) = ImageReader::open(path).and_then(|r| r.with_guessed_format()) else {
+        return Preview::Unavailable(String::from("This image can't be read"));
+    };
+    let format = reader.format();
+    let Ok(mut decoder) = reader.into_decoder() else {
+        return Preview::Unavailable(String::from("This image can't be decoded"));
+    };
+    let (width, height) = decoder.dimensions();
+    let orientation = decoder.orientation().ok();
+    if len <= MAX_RAW_IMAGE_LEN && width.max(height) <= MAX_IMAGE_SIDE {
+        if let (Some(format), Ok(bytes)) = (format, std::fs::read(path)) {
+            // the webview applies the orientation of images it decodes itself
+            return Preview::Image {
+                src: data_url(format.to_mime_type(), &bytes),
+                width,
+                height,
+            };
+        }
+    }
+    let Ok(mut image) = DynamicImage::from_decoder(decoder) else {
+        return Preview::Unavailable(String::from("This image can't be decoded"));
+    };
+    if let Some(orientation) = orientation {
+        image.apply_orientation(orientation);
+    }
+    if image.width().max(image.height()) > MAX_IMAGE_SIDE {
+        image = image.thumbnail(MAX_IMAGE_SIDE, MAX_IMAGE_SIDE);
+    }
+    // photos compress far better as JPEG, anything with transparency needs PNG
+    let (format, image) = if image.color().has_alpha() {
+        (ImageFormat::Png, image)
+    } else {
+        (ImageFormat::Jpeg, DynamicImage::ImageRgb8(image.to_rgb8()))
+    };
+    let mut bytes = Cursor::new(Vec::new());
+    if image.write_to(&mut bytes, format).is_err() {
+        return Preview::Unavailable(String::from("This image can't be decoded"));
+    }
+    Preview::Image {
+        src: data_url(format.to_mime_type(), bytes.get_ref()),
+        width: image.width(),
+        height: image.height(),
+    }
+}
+
+fn preview_directory(path: &Path) -> Preview {
+    let Ok(read_dir) = std::fs::read_dir(path) else {
+        return Preview::Unavailable(String::from("This folder can't be read"));
+    };
+    let (mut dirs, mut files, mut size) = (0, 0, 0);
+    let mut entries = Vec::new();
+    for entry in read_dir.flatten() {
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        let is_dir = metadata.is_dir();
+        if is_dir {
+            dirs += 1;
+        } else {
+            files += 1;
+            size += metadata.len();
+        }
+        entries.push(DirectoryEntry {
+            name: entry.file_name().to_string_lossy().into_owned(),
+            is_dir,
+        });
+    }
+    entries.sort_unstable_by(|a, b| {
+        b.is_dir
+            .cmp(&a.is_dir)
+            .then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+    });
+    entries.truncate(MAX_ENTRIES);
+    Preview::Directory {
+        dirs,
+        files,
+        size,
+        entries,
+    }
+}
+
+fn preview_file(path: &Path, len: u64, dark: bool) -> Preview {
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default();
+    if has_thumbnail(extension) {
+        return preview_image(path, len);
+    }
+    if extension.eq_ignore_ascii_case("svg") && len <= MAX_TEXT_LEN {
+        // scripts in SVG never run when it is shown as an image
+        if let Ok(bytes) = std::fs::read(path) {
+            return Preview::Image {
+                src: data_url("image/svg+xml", &bytes),
+                width: 0,
+                height: 0,
+            };
+        }
+    }
+    let Ok(head) = read_head(path, MAX_TEXT_LEN) else {
+        return Preview::Unavailable(String::from("This file can't be read"));
+    };
+    let truncated = len > head.len() as u64;
+    match as_text(&head) {
+        Some(text)
+            if extension.eq_ignore_ascii_case("md")
+                || extension.eq_ignore_ascii_case("markdown") =>
+        {
+            Preview::Markdown {
+                html: render_markdown(text, dark),
+                truncated,
+            }
+        }
+        Some(text) => {
+            let syntax = syntax_for(path, text.lines().next().unwrap_or_default());
+            Preview::Code {
+                language: syntax.name.clone(),
+                html: highlight(text, syntax, dark),
+                truncated,
+            }
+        }
+        None => {
+            let mut bytes = head;
+            bytes.truncate(MAX_HEX_LEN as usize);
+            Preview::Hex { bytes, len }
+        }
+    }
+}
+
+fn preview_path(path: &Path, dark: bool) -> Preview {
+    match std::fs::metadata(path) {
+        Ok(metadata) if metadata.is_dir() => preview_directory(path),
+        Ok(metadata) if !metadata.is_file() => {
+            let kind = PitouFileKind::from(metadata.file_type());
+            Preview::Unavailable(format!("{}s can't be previewed", kind.description()))
+        }
+        Ok(metadata) if metadata.len() == 0 => Preview::Empty,
+        Ok(metadata) => preview_file(path, metadata.len(), dark),
+        Err(e) => Preview::Unavailable(e.to_string()),
+    }
+}
+
+/// A preview of the file or directory at `file`, colored to suit a dark or a light background.
+/// Previews are bounded by the limits in [`crate::preview`] so they stay quick whatever the file.
+pub async fn preview(file: PitouFilePath, dark: bool) -> Preview {
+    tokio::task::spawn_blocking(move || preview_path(&file.path, dark))
+        .await
+        .unwrap_or_else(|e| Preview::Unavailable(e.to_string()))
+}
+
+#[cfg(test)]
+mod test_mod {
+    use super::{as_text, preview_path, render_markdown};
+    use crate::preview::Preview;
+
+    #[test]
+    fn test_preview() {
+        assert_eq!(as_text("é".as_bytes()), Some("é"));
+        assert_eq!(as_text(&"é".as_bytes()[..1]), Some(""));
+        assert_eq!(as_text(&[0xff, b'a']), None);
+        assert_eq!(as_text(b"a\0b"), None);
+
+        let html = render_markdown(
+            "# Title\n<script>alert(1)</script>\n\n![pic](http://x/y.png)\n\n```rust\nfn main() {}\n```\n",
+            true,
+        );
+        assert!(html.contains("<h1>Title</h1>"));
+        assert!(!html.contains("<script>"));
+        assert!(!html.contains("<img"));
+        assert!(html.contains("main"));
+
+        let html = render_markdown(
+            "[run](javascript:alert(1)) [away](https://example.com) <https://example.com/auto>",
+            true,
+        );
+        assert!(!html.contains("<a"));
+        assert!(!html.contains("javascript:"));
+        assert!(html.contains("run") && html.contains("away"));
+
+        let dir = std::env::temp_dir().join("pitou-preview-test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("sub")).unwrap();
+        std::fs::write(dir.join("main.rs"), "fn main() {}\n").unwrap();
+        std::fs::write(dir.join("blob.bin"), [0u8, 1, 2, 3]).unwrap();
+        std::fs::write(dir.join("empty.txt"), "").unwrap();
+
+        let Preview::Code { language, .. } = preview_path(&dir.join("main.rs"), false) else {
+            panic!("source files are previewed as code");
+        };
+        assert_eq!(language, "Rust");
+        assert!(matches!(
+            preview_path(&dir.join("blob.bin"), false),
+            Preview::Hex { len: 4, .. }
+        ));
+        assert!(matches!(
+            preview_path(&dir.join("empty.txt"), false),
+            Preview::Empty
+        ));
+        let Preview::Directory {
+            dirs,
+            files,
+            entries,
+            ..
+        } = preview_path(&dir, false)
+        else {
+            panic!("directories are previewed as summaries");
+        };
+        assert_eq!((dirs, files), (1, 3));
+        assert!(entries[0].is_dir);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
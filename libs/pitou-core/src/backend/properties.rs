@@ -0,0 +1,619 @@
+use std::{
+    fs::{File, Metadata},
+    io::Read,
+    path::{Path, PathBuf},
+    sync::atomic::Ordering,
+};
+
+use session::TotalsSession;
+
+use crate::{
+    msg::TotalsMsg,
+    properties::{AttributeChanges, ChangeError, ChecksumKind, ItemProperties, Owner},
+    PitouDateTime, PitouFile, PitouFileKind, PitouFilePath,
+};
+
+mod session {
+    use std::sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex, OnceLock,
+    };
+
+    use crate::msg::{ItemTotals, TotalsMsg};
+
+    #[derive(Default)]
+    pub(super) struct TotalsSession {
+        terminated: AtomicBool,
+        pub(super) files: AtomicU64,
+        pub(super) dirs: AtomicU64,
+        pub(super) bytes: AtomicU64,
+        pub(super) unreadable: AtomicU64,
+    }
+
+    impl TotalsSession {
+        pub(super) fn is_terminated(&self) -> bool {
+            self.terminated.load(Ordering::Acquire)
+        }
+
+        pub(super) fn terminate(&self) {
+            self.terminated.store(true, Ordering::Release)
+        }
+
+        pub(super) fn read(&self) -> TotalsMsg {
+            let totals = ItemTotals {
+                files: self.files.load(Ordering::Acquire),
+                dirs: self.dirs.load(Ordering::Acquire),
+                bytes: self.bytes.load(Ordering::Acquire),
+                unreadable: self.unreadable.load(Ordering::Acquire),
+            };
+            if self.is_terminated() {
+                TotalsMsg::Terminated(totals)
+            } else {
+                TotalsMsg::Active(totals)
+            }
+        }
+    }
+
+    static SESSION: OnceLock<Mutex<Option<Arc<TotalsSession>>>> = OnceLock::new();
+
+    pub(super) fn get_session() -> Option<Arc<TotalsSession>> {
+        SESSION
+            .get_or_init(|| Mutex::new(None))
+            .lock()
+            .ok()
+            .and_then(|current| current.clone())
+    }
+
+    /// Replaces the current session with a fresh one, terminating the previous count if it is still running.
+    pub(super) fn configure_session() -> Arc<TotalsSession> {
+        let session = Arc::new(TotalsSession::default());
+        if let Ok(mut current) = SESSION.get_or_init(|| Mutex::new(None)).lock() {
+            if let Some(previous) = current.replace(session.clone()) {
+                previous.terminate();
+            }
+        }
+        session
+    }
+}
+
+/// The fields of [`ItemProperties`] only found on Unix.
+#[derive(Default)]
+struct UnixDetails {
+    mode: Option<u32>,
+    owner: Option<Owner>,
+    group: Option<Owner>,
+    inode: Option<u64>,
+    links: Option<u64>,
+}
+
+#[cfg(unix)]
+fn unix_details(metadata: &Metadata) -> UnixDetails {
+    use std::os::unix::fs::MetadataExt;
+    let owner = Owner {
+        id: metadata.uid(),
+        name: uzers::get_user_by_uid(metadata.uid())
+            .map(|user| user.name().to_string_lossy().into_owned()),
+    };
+    let group = Owner {
+        id: metadata.gid(),
+        name: uzers::get_group_by_gid(metadata.gid())
+            .map(|group| group.name().to_string_lossy().into_owned()),
+    };
+    UnixDetails {
+        mode: Some(metadata.mode()),
+        owner: Some(owner),
+        group: Some(group),
+        inode: Some(metadata.ino()),
+        links: Some(metadata.nlink()),
+    }
+}
+
+#[cfg(not(unix))]
+fn unix_details(_metadata: &Metadata) -> UnixDetails {
+    UnixDetails::default()
+}
+
+/// The MIME type of the item, using the freedesktop `inode/*` types for anything that isn't a regular file.
+/// Files are recognized by their contents first and their extension second.
+fn mime_type(path: &Path, metadata: &Metadata) -> String {
+    let file_type = metadata.file_type();
+    if file_type.is_dir() {
+        return String::from("inode/directory");
+    }
+    if file_type.is_symlink() {
+        return String::from("inode/symlink");
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::FileTypeExt;
+        let special = if file_type.is_socket() {
+            Some("inode/socket")
+        } else if file_type.is_fifo() {
+            Some("inode/fifo")
+        } else if file_type.is_block_device() {
+            Some("inode/blockdevice")
+        } else if file_type.is_char_device() {
+            Some("inode/chardevice")
+        } else {
+            None
+        };
+        if let Some(special) = special {
+            return String::from(special);
+        }
+    }
+    if metadata.len() == 0 {
+        return String::from("application/x-zerosize");
+    }
+    if let Ok(Some(kind)) = infer::get_from_path(path) {
+        return kind.mime_type().to_owned();
+    }
+    if let Some(guess) = mime_guess::from_path(path).first_raw() {
+        return guess.to_owned();
+    }
+    let mut head = Vec::with_capacity(1024);
+    let is_text = File::open(path)
+        .and_then(|file| file.take(1024).read_to_end(&mut head))
+        .map(|_| match std::str::from_utf8(&head) {
+            Ok(_) => true,
+            // a character cut in half by the end of the buffer
+            Err(e) => e.error_len().is_none(),
+        })
+        .unwrap_or(false);
+    if is_text {
+        String::from("text/plain")
+    } else {
+        String::from("application/octet-stream")
+    }
+}
+
+/// A MIME type for `file` from its kind and its name alone, cheap enough for sorting whole folders.
+pub(crate) fn guessed_mime_type(file: &PitouFile) -> &'static str {
+    match file.kind() {
+        None => "application/octet-stream",
+        Some(PitouFileKind::Directory) => "inode/directory",
+        Some(PitouFileKind::Link) => "inode/symlink",
+        Some(PitouFileKind::Socket) => "inode/socket",
+        Some(PitouFileKind::Fifo) => "inode/fifo",
+        Some(PitouFileKind::BlockDevice) => "inode/blockdevice",
+        Some(PitouFileKind::CharDevice) => "inode/chardevice",
+        Some(PitouFileKind::File) => mime_guess::from_path(&file.path.path)
+            .first_raw()
+            .unwrap_or("application/octet-stream"),
+    }
+}
+
+fn item_properties(path: PitouFilePath) -> ItemProperties {
+    let Ok(metadata) = std::fs::symlink_metadata(&path.path) else {
+        return ItemProperties {
+            path,
+            kind: None,
+            size: 0,
+            modified: None,
+            accessed: None,
+            created: None,
+            readonly: false,
+            mode: None,
+            owner: None,
+            group: None,
+            inode: None,
+            links: None,
+            mime: String::new(),
+            link_target: None,
+        };
+    };
+    let UnixDetails {
+        mode,
+        owner,
+        group,
+        inode,
+        links,
+    } = unix_details(&metadata);
+    let link_target = metadata
+        .file_type()
+        .is_symlink()
+        .then(|| std::fs::read_link(&path.path).ok())
+        .flatten()
+        .map(PitouFilePath::from_pathbuf);
+    ItemProperties {
+        mime: mime_type(&path.path, &metadata),
+        kind: Some(metadata.file_type().into()),
+        size: metadata.len(),
+        modified: metadata.modified().ok().map(Into::into),
+        accessed: metadata.accessed().ok().map(Into::into),
+        created: metadata.created().ok().map(Into::into),
+        readonly: metadata.permissions().readonly(),
+        mode,
+        owner,
+        group,
+        inode,
+        links,
+        link_target,
+        path,
+    }
+}
+
+/// The properties of every item at `paths`. Items that can't be read come without a kind.
+pub async fn properties(paths: Vec<PitouFilePath>) -> Vec<ItemProperties> {
+    tokio::task::spawn_blocking(move || paths.into_iter().map(item_properties).collect())
+        .await
+        .unwrap_or_default()
+}
+
+/// Walks every item at `roots` without following symlinks, adding what it finds to the session as it goes.
+fn count(roots: Vec<PathBuf>, session: &TotalsSession) {
+    let mut pending = Vec::new();
+    for root in roots {
+        match std::fs::symlink_metadata(&root) {
+            Ok(metadata) if metadata.is_dir() => pending.push(root),
+            Ok(metadata) => {
+                session.files.fetch_add(1, Ordering::AcqRel);
+                session.bytes.fetch_add(metadata.len(), Ordering::AcqRel);
+            }
+            Err(_) => (),
+        }
+    }
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            session.unreadable.fetch_add(1, Ordering::AcqRel);
+            continue;
+        };
+        for entry in entries.flatten() {
+            if session.is_terminated() {
+                return;
+            }
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            if metadata.is_dir() {
+                session.dirs.fetch_add(1, Ordering::AcqRel);
+                pending.push(entry.path());
+            } else {
+                session.files.fetch_add(1, Ordering::AcqRel);
+                session.bytes.fetch_add(metadata.len(), Ordering::AcqRel);
+            }
+        }
+    }
+}
+
+/// Starts counting the recursive size, files and folders of `paths` in the background,
+/// ending any count that is still running.
+pub async fn count_totals(paths: Vec<PitouFilePath>) {
+    let session = session::configure_session();
+    tokio::task::spawn_blocking(move || {
+        count(paths.into_iter().map(|p| p.path).collect(), &session);
+        session.terminate();
+    });
+}
+
+pub async fn read_totals() -> TotalsMsg {
+    match session::get_session() {
+        Some(session) => session.read(),
+        None => TotalsMsg::Terminated(Default::default()),
+    }
+}
+
+pub async fn terminate_totals() {
+    if let Some(session) = session::get_session() {
+        session.terminate()
+    }
+}
+
+fn hash_with<H: sha2::Digest>(mut file: File, mut hasher: H) -> std::io::Result<Vec<u8>> {
+    let mut buffer = vec![0; 64 * 1024];
+    loop {
+        match file.read(&mut buffer)? {
+            0 => break,
+            n => hasher.update(&buffer[..n]),
+        }
+    }
+    Ok(hasher.finalize().to_vec())
+}
+
+fn checksum_of(path: &Path, kind: ChecksumKind) -> std::io::Result<String> {
+    let mut file = File::open(path)?;
+    let digest = match kind {
+        ChecksumKind::Md5 => hash_with(file, md5::Md5::default())?,
+        ChecksumKind::Sha256 => hash_with(file, sha2::Sha256::default())?,
+        ChecksumKind::Blake3 => {
+            let mut hasher = blake3::Hasher::new();
+            std::io::copy(&mut file, &mut hasher)?;
+            hasher.finalize().as_bytes().to_vec()
+        }
+    };
+    Ok(digest.iter().map(|b| format!("{b:02x}")).collect())
+}
+
+/// The lowercase hex checksum of the file at `path`, `None` if it isn't a file that can be read.
+pub async fn checksum(path: PitouFilePath, kind: ChecksumKind) -> Option<String> {
+    tokio::task::spawn_blocking(move || {
+        if !path.path.is_file() {
+            return None;
+        }
+        checksum_of(&path.path, kind).ok()
+    })
+    .await
+    .ok()
+    .flatten()
+}
+
+/// Resolves a user or group given by name or numeric id.
+#[cfg(unix)]
+fn resolve_id(value: &str, group: bool) -> Result<u32, String> {
+    if let Ok(id) = value.trim().parse() {
+        return Ok(id);
+    }
+    let id = if group {
+        uzers::get_group_by_name(value.trim()).map(|g| g.gid())
+    } else {
+        uzers::get_user_by_name(value.trim()).map(|u| u.uid())
+    };
+    let kind = if group { "group" } else { "user" };
+    id.ok_or_else(|| format!("There is no {kind} named '{value}'"))
+}
+
+#[cfg(unix)]
+fn change_mode(path: &Path, metadata: &Metadata, changes: &AttributeChanges) -> Result<(), String> {
+    use std::os::unix::fs::{MetadataExt, PermissionsExt};
+    let Some(mut change) = changes.mode else {
+        return Ok(());
+    };
+    // symlinks have no permissions of their own
+    if metadata.file_type().is_symlink() {
+        return Ok(());
+    }
+    // a recursive change never makes folders impossible to enter
+    if changes.recursive && metadata.is_dir() {
+        change.clear &= !0o111;
+    }
+    let mode = change.apply(metadata.mode() & 0o7777);
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))
+        .map_err(|e| format!("Couldn't change the permissions: {e}"))
+}
+
+#[cfg(unix)]
+fn change_owner(path: &Path, owner: Option<u32>, group: Option<u32>) -> Result<(), String> {
+    if owner.is_none() && group.is_none() {
+        return Ok(());
+    }
+    std::os::unix::fs::lchown(path, owner, group)
+        .map_err(|e| format!("Couldn't change the owner: {e}"))
+}
+
+fn file_time(time: PitouDateTime) -> filetime::FileTime {
+    let time = time.datetime.and_utc();
+    filetime::FileTime::from_unix_time(time.timestamp(), time.timestamp_subsec_nanos())
+}
+
+fn change_times(
+    path: &Path,
+    metadata: &Metadata,
+    changes: &AttributeChanges,
+) -> Result<(), String> {
+    if changes.modified.is_none() && changes.accessed.is_none() {
+        return Ok(());
+    }
+    let modified = changes
+        .modified
+        .map(file_time)
+        .unwrap_or_else(|| filetime::FileTime::from_last_modification_time(metadata));
+    let accessed = changes
+        .accessed
+        .map(file_time)
+        .unwrap_or_else(|| filetime::FileTime::from_last_access_time(metadata));
+    filetime::set_symlink_file_times(path, accessed, modified)
+        .map_err(|e| format!("Couldn't change the times: {e}"))
+}
+
+/// Applies `changes` to the items at `paths`, and to everything under them if the changes are recursive.
+/// Symlinks are changed themselves and never followed. Every item that couldn't be changed is reported
+/// along with the reason, the others are changed regardless.
+fn apply_changes(paths: Vec<PathBuf>, changes: &AttributeChanges) -> Vec<ChangeError> {
+    let mut errors = Vec::new();
+    let report = |path: &Path, message: String, errors: &mut Vec<ChangeError>| {
+        errors.push(ChangeError {
+            path: PitouFilePath::from_pathbuf(path.to_path_buf()),
+            message,
+        })
+    };
+
+    #[cfg(unix)]
+    let ids = {
+        let owner = changes
+            .owner
+            .as_deref()
+            .map(|v| resolve_id(v, false))
+            .transpose();
+        let group = changes
+            .group
+            .as_deref()
+            .map(|v| resolve_id(v, true))
+            .transpose();
+        match (owner, group) {
+            (Ok(owner), Ok(group)) => (owner, group),
+            (Err(e), _) | (_, Err(e)) => {
+                for path in &paths {
+                    report(path, e.clone(), &mut errors);
+                }
+                return errors;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    if changes.mode.is_some() || changes.owner.is_some() || changes.group.is_some() {
+        for path in &paths {
+            let message = String::from("Permissions and owners can only be changed on Unix");
+            report(path, message, &mut errors);
+        }
+        return errors;
+    }
+
+    let mut pending = paths;
+    while let Some(path) = pending.pop() {
+        let metadata = match std::fs::symlink_metadata(&path) {
+            Ok(metadata) => metadata,
+            Err(e) => {
+                report(&path, e.to_string(), &mut errors);
+                continue;
+            }
+        };
+        // permissions are changed before the contents are listed, so folders that are being opened up can be walked
+        #[cfg(unix)]
+        if let Err(e) = change_mode(&path, &metadata, changes) {
+            report(&path, e, &mut errors);
+        }
+        if changes.recursive && metadata.is_dir() {
+            match std::fs::read_dir(&path) {
+                Ok(entries) => pending.extend(entries.flatten().map(|entry| entry.path())),
+                Err(e) => report(&path, format!("Couldn't read the folder: {e}"), &mut errors),
+            }
+        }
+        #[cfg(unix)]
+        if let Err(e) = change_owner(&path, ids.0, ids.1) {
+            report(&path, e, &mut errors);
+        }
+        if let Err(e) = change_times(&path, &metadata, changes) {
+            report(&path, e, &mut errors);
+        }
+    }
+    errors
+}
+
+/// Changes the permissions, owners or times of the items at `paths`, returning the items that couldn't be changed.
+pub async fn change_attributes(
+    paths: Vec<PitouFilePath>,
+    changes: AttributeChanges,
+) -> Vec<ChangeError> {
+    tokio::task::spawn_blocking(move || {
+        apply_changes(paths.into_iter().map(|p| p.path).collect(), &changes)
+    })
+    .await
+    .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod test_mod {
+    use super::{checksum_of, count, item_properties, session::TotalsSession};
+    use crate::{properties::ChecksumKind, PitouFilePath};
+    use std::sync::atomic::Ordering;
+
+    #[test]
+    fn test_properties() {
+        let dir = std::env::temp_dir().join("pitou-properties-test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("inner")).unwrap();
+        let file = dir.join("abc.txt");
+        std::fs::write(&file, "abc").unwrap();
+        std::fs::write(dir.join("inner").join("data"), [0u8, 159, 146, 150]).unwrap();
+
+        assert_eq!(
+            checksum_of(&file, ChecksumKind::Md5).unwrap(),
+            "900150983cd24fb0d6963f7d28e17f72"
+        );
+        assert_eq!(
+            checksum_of(&file, ChecksumKind::Sha256).unwrap(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+
+        let props = item_properties(PitouFilePath::from_pathbuf(file.clone()));
+        assert_eq!(props.size, 3);
+        assert_eq!(props.mime, "text/plain");
+        let data = item_properties(PitouFilePath::from_pathbuf(dir.join("inner").join("data")));
+        assert_eq!(data.mime, "application/octet-stream");
+        assert!(
+            item_properties(PitouFilePath::from_pathbuf(dir.join("missing")))
+                .kind
+                .is_none()
+        );
+
+        let session = TotalsSession::default();
+        count(vec![dir.clone(), file], &session);
+        assert_eq!(session.dirs.load(Ordering::Acquire), 1);
+        assert_eq!(session.files.load(Ordering::Acquire), 3);
+        assert_eq!(session.bytes.load(Ordering::Acquire), 10);
+
+        #[cfg(unix)]
+        {
+            let link = dir.join("link");
+            std::os::unix::fs::symlink("abc.txt", &link).unwrap();
+            let props = item_properties(PitouFilePath::from_pathbuf(link));
+            assert_eq!(props.mime, "inode/symlink");
+            assert_eq!(
+                props.link_target.unwrap().path,
+                std::path::Path::new("abc.txt")
+            );
+            assert!(props.mode.is_some() && props.inode.is_some());
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_change_attributes() {
+        use super::apply_changes;
+        use crate::properties::{AttributeChanges, ModeChange};
+
+        let dir = std::env::temp_dir().join("pitou-attributes-test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("inner")).unwrap();
+        let file = dir.join("inner").join("script.sh");
+        std::fs::write(&file, "echo").unwrap();
+
+        let modified = chrono::DateTime::from_timestamp(1_000_000_000, 0)
+            .unwrap()
+            .naive_utc();
+        let changes = AttributeChanges {
+            modified: Some(crate::PitouDateTime { datetime: modified }),
+            recursive: true,
+            ..Default::default()
+        };
+        let missing = dir.join("missing");
+        let errors = apply_changes(vec![dir.clone(), missing.clone()], &changes);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].path.path, missing);
+        let mtime = std::fs::metadata(&file).unwrap().modified().unwrap();
+        assert_eq!(
+            mtime
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_secs(),
+            1_000_000_000
+        );
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = |path: &std::path::Path| {
+                std::fs::metadata(path).unwrap().permissions().mode() & 0o777
+            };
+            std::fs::set_permissions(&file, std::fs::Permissions::from_mode(0o644)).unwrap();
+            let changes = AttributeChanges {
+                mode: Some(ModeChange {
+                    set: 0o100,
+                    clear: 0o044,
+                }),
+                ..Default::default()
+            };
+            assert!(apply_changes(vec![file.clone()], &changes).is_empty());
+            assert_eq!(mode(&file), 0o700);
+
+            // folders keep their execute bits under a recursive change
+            let changes = AttributeChanges {
+                mode: Some(ModeChange {
+                    set: 0,
+                    clear: 0o111,
+                }),
+                recursive: true,
+                ..Default::default()
+            };
+            assert!(apply_changes(vec![dir.clone()], &changes).is_empty());
+            assert_eq!(mode(&file), 0o600);
+            assert_eq!(mode(&dir.join("inner")) & 0o100, 0o100);
+
+            let changes = AttributeChanges {
+                owner: Some(String::from("no-such-user-pitou")),
+                ..Default::default()
+            };
+            assert_eq!(apply_changes(vec![file.clone()], &changes).len(), 1);
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
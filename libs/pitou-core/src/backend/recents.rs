@@ -0,0 +1,180 @@
+use std::path::PathBuf;
+
+use chrono::{DateTime, Local, TimeZone};
+use tokio::sync::Mutex;
+
+use crate::{
+    recents::{Recent, RecentItem, RecentKind, RecentPeriod, Recents},
+    PitouDateTime, PitouFile, PitouFilePath,
+};
+
+fn store_path() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("pitou").join("recents.json"))
+}
+
+/// Held across every read-modify-write of the store, so that concurrent changes aren't lost.
+static STORE_LOCK: Mutex<()> = Mutex::const_new(());
+
+/// A missing store reads as no recents, with tracking on. One that can't be read or parsed is
+/// an error, so that it is neither overwritten nor taken for tracking turned back on.
+async fn stored() -> std::io::Result<Recents> {
+    let path = store_path().ok_or(std::io::ErrorKind::NotFound)?;
+    match tokio::fs::read(path).await {
+        Ok(contents) => Ok(serde_json::from_slice(&contents)?),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Recents::default()),
+        Err(e) => Err(e),
+    }
+}
+
+async fn store(recents: &Recents) -> std::io::Result<()> {
+    let path = store_path().ok_or(std::io::ErrorKind::NotFound)?;
+    let contents = serde_json::to_vec(recents)?;
+    super::store::write_atomic(&path, &contents).await
+}
+
+async fn update<T>(change: impl FnOnce(&mut Recents) -> T) -> std::io::Result<T> {
+    let _guard = STORE_LOCK.lock().await;
+    let mut recents = stored().await?;
+    let res = change(&mut recents);
+    store(&recents).await?;
+    Ok(res)
+}
+
+fn now() -> PitouDateTime {
+    PitouDateTime::from(std::time::SystemTime::now())
+}
+
+/// Lists `path` as just used, unless tracking is paused.
+pub async fn record(path: PitouFilePath, kind: RecentKind) -> std::io::Result<()> {
+    let used = now();
+    update(|recents| recents.record(Recent { path, kind, used })).await
+}
+
+/// The recents that still exist, with what the desktop's history gained since it was last read
+/// merged in, grouped by how long ago they were used. An unreadable store lists nothing.
+pub async fn recents() -> Vec<RecentItem> {
+    let history = desktop_history().await;
+    let items = {
+        let _guard = STORE_LOCK.lock().await;
+        let Ok(mut recents) = stored().await else {
+            return Vec::new();
+        };
+        if recents.merge(history) > 0 {
+            store(&recents).await.ok();
+        }
+        recents.items
+    };
+    let today = Local::now().date_naive();
+    items
+        .into_iter()
+        .filter(|recent| recent.path.path.exists())
+        .map(|recent| {
+            let day = Local.from_utc_datetime(&recent.used.datetime).date_naive();
+            let target = PitouFile::from_pathbuf(recent.path.path.clone());
+            RecentItem {
+                period: RecentPeriod::of(day, today),
+                recent,
+                target,
+            }
+        })
+        .collect()
+}
+
+pub async fn forget(paths: Vec<PitouFilePath>) -> std::io::Result<()> {
+    update(|recents| recents.forget(&paths)).await
+}
+
+pub async fn clear() -> std::io::Result<()> {
+    update(Recents::clear).await
+}
+
+/// Whether recents are tracked, which an unreadable store counts as not.
+pub async fn tracking() -> bool {
+    stored().await.is_ok_and(|recents| recents.tracking)
+}
+
+pub async fn set_tracking(tracking: bool) -> std::io::Result<()> {
+    let now = now();
+    update(|recents| recents.set_tracking(tracking, now)).await
+}
+
+/// The local files in `recently-used.xbel`, where GTK applications list what they opened.
+async fn desktop_history() -> Vec<Recent> {
+    let Some(path) = dirs::data_dir().map(|dir| dir.join("recently-used.xbel")) else {
+        return Vec::new();
+    };
+    match tokio::fs::read_to_string(path).await {
+        Ok(contents) => parse_xbel(&contents),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// Reads the `bookmark` elements of an xbel file, which look like
+/// `<bookmark href="file:///some/file" added="…" modified="…" visited="…">`. Remote locations
+/// are skipped and a folder is told apart by its `inode/directory` mime type.
+fn parse_xbel(contents: &str) -> Vec<Recent> {
+    contents
+        .split("<bookmark ")
+        .skip(1)
+        .filter_map(|bookmark| {
+            let tag = format!(" {}", &bookmark[..bookmark.find('>')?]);
+            let attribute = |name: &str| {
+                let start = tag.find(&format!(" {name}=\""))? + name.len() + 3;
+                let len = tag[start..].find('"')?;
+                Some(&tag[start..start + len])
+            };
+            let href = attribute("href")?.replace("&amp;", "&");
+            let path = super::uri::percent_decode(href.strip_prefix("file://")?)?;
+            let used = ["visited", "modified", "added"]
+                .into_iter()
+                .filter_map(|name| DateTime::parse_from_rfc3339(attribute(name)?).ok())
+                .max()?;
+            let body = &bookmark[..bookmark.find("</bookmark>").unwrap_or(bookmark.len())];
+            let kind = match body.contains("\"inode/directory\"") {
+                true => RecentKind::Folder,
+                false => RecentKind::File,
+            };
+            Some(Recent {
+                path: PitouFilePath::from_pathbuf(PathBuf::from(path)),
+                kind,
+                used: PitouDateTime {
+                    datetime: used.naive_utc(),
+                },
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test_mod {
+    use std::path::Path;
+
+    use crate::recents::RecentKind;
+
+    #[test]
+    fn test_parse_xbel() {
+        let contents = r#"<?xml version="1.0" encoding="UTF-8"?>
+<xbel version="1.0">
+  <bookmark href="file:///home/user/notes%20&amp;%20todo.txt" added="2024-03-01T10:00:00.000000Z" modified="2024-03-01T10:00:00.000000Z" visited="2024-03-02T08:30:00.123456Z">
+    <info><metadata owner="http://freedesktop.org"><mime:mime-type type="text/plain"/></metadata></info>
+  </bookmark>
+  <bookmark href="file:///home/user/Projects" added="2024-02-01T10:00:00Z" modified="2024-02-03T10:00:00Z">
+    <info><metadata owner="http://freedesktop.org"><mime:mime-type type="inode/directory"/></metadata></info>
+  </bookmark>
+  <bookmark href="sftp://host/srv/file.txt" added="2024-02-01T10:00:00Z" visited="2024-02-01T10:00:00Z"/>
+</xbel>"#;
+        let recents = super::parse_xbel(contents);
+        assert_eq!(recents.len(), 2);
+        assert_eq!(
+            recents[0].path.path,
+            Path::new("/home/user/notes & todo.txt")
+        );
+        assert!(recents[0].kind == RecentKind::File);
+        assert_eq!(
+            recents[0].used.datetime.to_string(),
+            "2024-03-02 08:30:00.123456"
+        );
+        assert!(recents[1].kind == RecentKind::Folder);
+        assert_eq!(recents[1].used.datetime.to_string(), "2024-02-03 10:00:00");
+    }
+}
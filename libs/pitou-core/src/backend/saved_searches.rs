@@ -0,0 +1,70 @@
+use std::path::PathBuf;
+
+use tokio::sync::Mutex;
+
+use crate::search::{SavedSearch, SearchFind};
+
+use super::search;
+
+fn store_path() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("pitou").join("saved_searches.json"))
+}
+
+/// Held across every read-modify-write of the store, so that concurrent changes aren't lost.
+static STORE_LOCK: Mutex<()> = Mutex::const_new(());
+
+/// The saved searches. A missing store reads as empty, while one that can't be read or parsed is
+/// an error rather than being taken for empty and overwritten.
+async fn stored() -> std::io::Result<Vec<SavedSearch>> {
+    let path = store_path().ok_or(std::io::ErrorKind::NotFound)?;
+    match tokio::fs::read(path).await {
+        Ok(contents) => Ok(serde_json::from_slice(&contents)?),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e),
+    }
+}
+
+async fn store(searches: &[SavedSearch]) -> std::io::Result<()> {
+    let path = store_path().ok_or(std::io::ErrorKind::NotFound)?;
+    let contents = serde_json::to_vec_pretty(searches)?;
+    super::store::write_atomic(&path, &contents).await
+}
+
+/// Applies `change` to the saved searches and stores them.
+async fn update(change: impl FnOnce(&mut Vec<SavedSearch>)) -> std::io::Result<()> {
+    let _guard = STORE_LOCK.lock().await;
+    let mut searches = stored().await?;
+    change(&mut searches);
+    store(&searches).await
+}
+
+/// Every saved search in the order it was first saved. An unreadable store lists nothing.
+pub async fn saved_searches() -> Vec<SavedSearch> {
+    stored().await.unwrap_or_default()
+}
+
+/// Saves `search`, replacing the saved search of the same name if there is one.
+pub async fn save_search(search: SavedSearch) -> std::io::Result<()> {
+    update(
+        |searches| match searches.iter_mut().find(|s| s.name == search.name) {
+            Some(existing) => *existing = search,
+            None => searches.push(search),
+        },
+    )
+    .await
+}
+
+pub async fn forget_search(name: String) -> std::io::Result<()> {
+    update(|searches| searches.retain(|s| s.name != name)).await
+}
+
+/// Opens the saved search `name` as a smart folder, running it afresh so that its children are always current.
+/// Returns `None` if no search is saved under `name` or its key no longer compiles.
+pub async fn smart_folder_children(name: String) -> Option<Vec<SearchFind>> {
+    let saved = saved_searches()
+        .await
+        .into_iter()
+        .find(|s| s.name == name)?;
+    let options = saved.options.try_into().ok()?;
+    Some(search::collect(options).await.into_iter().collect())
+}
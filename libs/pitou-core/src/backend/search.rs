@@ -1,29 +1,91 @@
-use std::{path::PathBuf, sync::Arc};
-
-use crate::{search::SimplifiedSearchOptions, PitouFile, PitouFileFilter};
+use std::{
+    collections::{HashSet, LinkedList},
+    fs::Metadata,
+    path::{Path, PathBuf},
+    sync::{Arc, Condvar, Mutex},
+};
+
+use crossbeam_deque::{Injector, Stealer, Worker};
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use stream::SearchStream;
+
+use super::vfs;
+use crate::{
+    search::{fuzzy, SearchError, SearchFind, SearchScope, SimplifiedSearchOptions},
+    vfs::Location,
+    PitouDrive, PitouFile, PitouFileFilter, PitouFilePath,
+};
 
 impl SimplifiedSearchOptions {
-    pub fn try_into(self) -> Option<SearchOptions> {
-        if let Some(search_type) = SearchType::parse_if_regex(self.search_kind, self.input) {
-            let obj = SearchOptions {
-                search_dir: self.search_dir,
-                filter: self.filter,
-                case_sensitive: self.case_sensitive,
-                hardware_accelerate: self.hardware_accelerate,
-                skip_errors: self.skip_errors,
-                depth: self.depth,
-                max_finds: self.max_finds,
-                search_type: search_type,
-            };
-            Some(obj)
-        } else {
-            None
+    /// The options a search runs with, failing if the search key or an exclude pattern doesn't compile.
+    pub fn try_into(self) -> Result<SearchOptions, SearchError> {
+        let search_type =
+            SearchType::parse_if_regex(self.search_kind, self.input, self.case_sensitive)
+                .ok_or(SearchError::InvalidKey)?;
+        let excludes = parse_excludes(&self.excludes, self.case_sensitive)?;
+        let obj = SearchOptions {
+            roots: self.scope.into_roots(),
+            filter: self.filter,
+            case_sensitive: self.case_sensitive,
+            hardware_accelerate: self.hardware_accelerate,
+            skip_errors: self.skip_errors,
+            depth: self.depth,
+            max_finds: self.max_finds,
+            search_type,
+            follow_links: self.follow_links,
+            same_filesystem: self.same_filesystem,
+            respect_ignore_files: self.respect_ignore_files,
+            excludes,
+        };
+        Ok(obj)
+    }
+}
+
+impl SearchScope {
+    /// The directories a search starts from. "All drives", like the empty "Drives" pseudo-path,
+    /// stands for the mount point of every drive.
+    fn into_roots(self) -> Vec<PathBuf> {
+        let drives = || {
+            PitouDrive::get_drives()
+                .into_iter()
+                .map(|d| d.mount_point.path)
+        };
+        match self {
+            Self::Roots(roots) => roots
+                .into_iter()
+                .flat_map(|root| match root.path {
+                    path if path.as_os_str().is_empty() => drives().collect(),
+                    path => vec![path],
+                })
+                .collect(),
+            Self::AllDrives => drives().collect(),
         }
     }
 }
 
+/// Compiles the user's exclude patterns, failing with the first one that is not a valid glob.
+fn parse_excludes(patterns: &[String], case_sensitive: bool) -> Result<GlobSet, SearchError> {
+    let invalid =
+        |e: globset::Error| SearchError::InvalidExclude(e.glob().unwrap_or_default().to_owned());
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns.iter().map(|p| p.trim()).filter(|p| !p.is_empty()) {
+        let glob = GlobBuilder::new(pattern)
+            .literal_separator(true)
+            .case_insensitive(!case_sensitive)
+            .build()
+            .map_err(invalid)?;
+        builder.add(glob);
+    }
+    builder.build().map_err(invalid)
+}
+
 impl SearchType {
-    pub(crate) fn parse_if_regex(search_kind: u8, search_key: String) -> Option<Self> {
+    pub(crate) fn parse_if_regex(
+        search_kind: u8,
+        search_key: String,
+        case_sensitive: bool,
+    ) -> Option<Self> {
         match search_kind {
             0 => regex::Regex::new(&search_key)
                 .map(|r| SearchType::Regex(r))
@@ -31,23 +93,69 @@ impl SearchType {
             1 => Some(SearchType::MatchBegining(search_key)),
             2 => Some(SearchType::MatchEnding(search_key)),
             3 => Some(SearchType::MatchMiddle(search_key)),
+            4 => globset::GlobBuilder::new(&search_key)
+                .literal_separator(true)
+                .case_insensitive(!case_sensitive)
+                .build()
+                .map(|g| SearchType::Glob(g.compile_matcher()))
+                .ok(),
+            5 => Some(SearchType::Fuzzy(search_key)),
             _ => None,
         }
     }
 }
 
 pub struct SearchOptions {
-    pub(crate) search_dir: PitouFile,
+    pub(crate) roots: Vec<PathBuf>,
     pub(crate) hardware_accelerate: bool,
     pub(crate) filter: PitouFileFilter,
     pub(crate) case_sensitive: bool,
-    pub(crate) depth: u8,
+    pub(crate) depth: Option<u32>,
     pub(crate) search_type: SearchType,
     pub(crate) skip_errors: bool,
     pub(crate) max_finds: usize,
+    pub(crate) follow_links: bool,
+    pub(crate) same_filesystem: bool,
+    pub(crate) respect_ignore_files: bool,
+    pub(crate) excludes: GlobSet,
 }
 
 impl SearchType {
+    /// Checks `file`, found under `root`, against the search key.
+    ///
+    /// Glob patterns containing a `/` are matched against the path relative to `root`,
+    /// every other search type only looks at the file name.
+    /// Fuzzy matches carry their relevance so that results can be ranked.
+    pub(crate) fn find(
+        &self,
+        file: PitouFile,
+        root: &std::path::Path,
+        sensitive: bool,
+    ) -> Option<SearchFind> {
+        let matched = match self {
+            Self::Fuzzy(key) => {
+                let relevance = fuzzy::fuzzy_match(key, file.name(), sensitive)?;
+                return Some(SearchFind {
+                    file,
+                    relevance: Some(relevance),
+                    root: PitouFilePath::from_pathbuf(root.to_path_buf()),
+                });
+            }
+            Self::Glob(matcher) if matcher.glob().glob().contains('/') => file
+                .path()
+                .path
+                .strip_prefix(root)
+                .map(|relative| matcher.is_match(relative))
+                .unwrap_or_default(),
+            _ => self.matches(file.name(), sensitive),
+        };
+        matched.then(|| SearchFind {
+            file,
+            relevance: None,
+            root: PitouFilePath::from_pathbuf(root.to_path_buf()),
+        })
+    }
+
     pub(crate) fn matches(&self, input: &str, sensitive: bool) -> bool {
         match self {
             Self::Regex(pattern) => pattern.is_match(input),
@@ -72,141 +180,149 @@ impl SearchType {
                     crate::extra::ends_with_ignore_case(key, input)
                 }
             }
+            Self::Glob(matcher) => matcher.is_match(input),
+            Self::Fuzzy(key) => fuzzy::fuzzy_match(key, input, sensitive).is_some(),
         }
     }
 }
 
 mod stream {
-    use std::{collections::LinkedList, sync::OnceLock};
-
-    use crate::{msg::SearchMsg, PitouFile};
-    use tokio::{sync::Mutex, task::JoinHandle};
-
-    type COUNT = Mutex<usize>;
-    type QUEUE = Mutex<Option<LinkedList<PitouFile>>>;
-    type SPAWNS = Mutex<LinkedList<JoinHandle<()>>>;
+    use std::{
+        collections::LinkedList,
+        sync::{
+            atomic::{AtomicBool, AtomicUsize, Ordering},
+            Arc, Mutex, OnceLock,
+        },
+    };
 
-    static HANDLES: OnceLock<SPAWNS> = OnceLock::new();
-    static STREAM: OnceLock<QUEUE> = OnceLock::new();
-    static FINDS: OnceLock<COUNT> = OnceLock::new();
+    use crate::{msg::SearchMsg, search::SearchFind};
 
-    fn get_finds() -> &'static COUNT {
-        FINDS.get_or_init(|| Mutex::new(0))
+    /// The finds of a single search. Every search gets its own stream so that
+    /// workers of a cancelled search can never write into the one that replaced it.
+    pub(super) struct SearchStream {
+        remaining: AtomicUsize,
+        terminated: AtomicBool,
+        queue: Mutex<LinkedList<SearchFind>>,
     }
 
-    fn get_handles() -> &'static SPAWNS {
-        HANDLES.get_or_init(|| Mutex::new(LinkedList::new()))
-    }
+    impl SearchStream {
+        pub(super) fn new(max_finds: usize) -> Self {
+            Self {
+                remaining: AtomicUsize::new(max_finds),
+                terminated: AtomicBool::new(false),
+                queue: Mutex::new(LinkedList::new()),
+            }
+        }
 
-    fn get_stream() -> &'static QUEUE {
-        STREAM.get_or_init(|| Mutex::new(None))
-    }
+        pub(super) fn is_terminated(&self) -> bool {
+            self.terminated.load(Ordering::Acquire)
+        }
 
-    /// decrements the count and returns true if the max_finds has not yet been exhusted
-    /// Automatically closes the finds if the count has dropped to zero.
-    async fn count_and_proceed() -> bool {
-        let mut c_val = get_finds().lock().await;
-        if *c_val == 0 {
-            false
-        } else {
-            *c_val -= 1;
-            true
+        pub(super) fn terminate(&self) {
+            self.terminated.store(true, Ordering::Release)
         }
-    }
 
-    pub(super) async fn terminate_stream() {
-        *get_finds().lock().await = 0;
-    }
+        /// Appends a batch of finds under a single lock, dropping whatever exceeds the remaining `max_finds`.
+        /// Automatically terminates the stream once `max_finds` has been exhausted.
+        pub(super) fn write(&self, mut finds: LinkedList<SearchFind>) {
+            if finds.is_empty() || self.is_terminated() {
+                return;
+            }
+            let wanted = finds.len();
+            let remaining = self
+                .remaining
+                .fetch_update(Ordering::AcqRel, Ordering::Acquire, |r| {
+                    Some(r.saturating_sub(wanted))
+                })
+                .unwrap_or_else(|r| r);
+            if remaining < wanted {
+                finds.split_off(remaining);
+            }
+            if let Ok(mut queue) = self.queue.lock() {
+                queue.append(&mut finds);
+            }
+            if remaining <= wanted {
+                self.terminate();
+            }
+        }
 
-    /// checks if the stream is terminated
-    pub(super) async fn is_terminated() -> bool {
-        *get_finds().lock().await == 0
-    }
+        /// Takes every find written so far.
+        pub(super) fn drain(&self) -> LinkedList<SearchFind> {
+            self.queue
+                .lock()
+                .map(|mut queue| queue.split_off(0))
+                .unwrap_or_default()
+        }
 
-    pub(super) async fn proceed_to_finish_stream() {
-        loop {
-            let mut hlock = get_handles().lock().await;
-            if hlock.is_empty() {
-                break;
-            }
-            let handles = hlock.split_off(0);
-            std::mem::drop(hlock);
-            for handle in handles.into_iter().rev() {
-                let _ = handle.await;
+        fn read(&self) -> SearchMsg {
+            let terminated = self.is_terminated();
+            let finds = self.drain();
+            if terminated {
+                SearchMsg::Terminated(finds)
+            } else {
+                SearchMsg::Active(finds)
             }
         }
-        terminate_stream().await;
     }
 
-    pub(super) async fn configure_stream(max_finds: usize) {
-        tokio::join! {
-            async move { let _ = get_stream().lock().await.insert(LinkedList::new()); },
-            async move { *get_finds().lock().await = max_finds },
-            async move { let _ = get_handles().lock().await.clear(); }
-        };
-    }
+    static STREAM: OnceLock<Mutex<Option<Arc<SearchStream>>>> = OnceLock::new();
 
-    pub(super) async fn read() -> SearchMsg {
-        if is_terminated().await {
-            get_stream()
-                .lock()
-                .await
-                .as_mut()
-                .map(|l| SearchMsg::Terminated(l.split_off(0)))
-                .unwrap_or(SearchMsg::Terminated(LinkedList::new()))
-        } else {
-            get_stream()
-                .lock()
-                .await
-                .as_mut()
-                .map(|l| SearchMsg::Active(l.split_off(0)))
-                .unwrap_or(SearchMsg::Terminated(LinkedList::new()))
-        }
+    fn get_stream() -> Option<Arc<SearchStream>> {
+        STREAM
+            .get_or_init(|| Mutex::new(None))
+            .lock()
+            .ok()
+            .and_then(|current| current.clone())
     }
 
-    pub(super) async fn write(find: PitouFile) {
-        if count_and_proceed().await {
-            get_stream()
-                .lock()
-                .await
-                .as_mut()
-                .map(|l| l.push_back(find));
-        } else {
-            abort_remaining_ops().await
+    /// Replaces the current stream with a fresh one, terminating the previous search if it is still running.
+    pub(super) fn configure_stream(max_finds: usize) -> Arc<SearchStream> {
+        let stream = Arc::new(SearchStream::new(max_finds));
+        if let Ok(mut current) = STREAM.get_or_init(|| Mutex::new(None)).lock() {
+            if let Some(previous) = current.replace(stream.clone()) {
+                previous.terminate();
+            }
         }
+        stream
     }
 
-    pub(super) async fn append_handle(handle: JoinHandle<()>) {
-        get_handles().lock().await.push_back(handle);
+    pub(super) fn terminate_stream() {
+        if let Some(stream) = get_stream() {
+            stream.terminate();
+        }
     }
 
-    pub(super) async fn abort_remaining_ops() {
-        let mut handles = get_handles().lock().await;
-        for handle in handles.split_off(0).into_iter().rev() {
-            handle.abort()
-        }
-        std::mem::drop(handles);
+    /// checks if the stream is terminated
+    pub(super) fn is_terminated() -> bool {
+        get_stream().map(|s| s.is_terminated()).unwrap_or(true)
     }
 
-    pub(super) async fn wait_for_all_ops() {
-        ()
+    pub(super) fn read() -> SearchMsg {
+        get_stream()
+            .map(|s| s.read())
+            .unwrap_or(SearchMsg::Terminated(LinkedList::new()))
     }
 }
 
-#[allow(unused)]
-#[derive(Clone)]
 struct SearchVariables {
     filter: PitouFileFilter,
     case_sensitive: bool,
-    depth: u8,
-    search_type: Arc<SearchType>,
+    depth: Option<u32>,
+    search_type: SearchType,
     skip_errors: bool,
+    roots: Vec<PathBuf>,
+    follow_links: bool,
+    same_filesystem: bool,
+    respect_ignore_files: bool,
+    excludes: GlobSet,
+    /// directories already queued, only tracked when following symlinks to break cycles
+    visited: Option<Mutex<HashSet<DirectoryId>>>,
 }
 
-impl From<SearchOptions> for (SearchVariables, PathBuf) {
+impl From<SearchOptions> for SearchVariables {
     fn from(value: SearchOptions) -> Self {
         let SearchOptions {
-            search_dir,
+            roots,
             hardware_accelerate: _,
             filter,
             case_sensitive,
@@ -214,83 +330,469 @@ impl From<SearchOptions> for (SearchVariables, PathBuf) {
             search_type,
             skip_errors,
             max_finds: _,
+            follow_links,
+            same_filesystem,
+            respect_ignore_files,
+            excludes,
         } = value;
-        (
-            SearchVariables {
-                filter,
-                case_sensitive,
-                depth,
-                skip_errors,
-                search_type: Arc::new(search_type),
-            },
-            search_dir.path.path,
-        )
+        SearchVariables {
+            filter,
+            case_sensitive,
+            depth,
+            skip_errors,
+            search_type,
+            roots,
+            follow_links,
+            same_filesystem,
+            respect_ignore_files,
+            excludes,
+            visited: follow_links.then(Mutex::default),
+        }
     }
 }
 
 impl SearchVariables {
-    fn include(&self, file: &PitouFile) -> bool {
-        ((file.is_file() && self.filter.files)
-            || (file.is_dir() && self.filter.dirs)
-            || (file.is_link() && self.filter.links)
-            || (file.is_sys_item() && self.filter.sys_items))
-            && self.search_type.matches(file.name(), self.case_sensitive)
+    fn find(&self, file: PitouFile, root: &SearchRoot) -> Option<SearchFind> {
+        if self.filter.includes(&file) {
+            self.search_type.find(file, &root.path, self.case_sensitive)
+        } else {
+            None
+        }
+    }
+
+    /// Checks `path` against the user's exclude globs, by name and by its path relative to the search root,
+    /// then against the ignore files in scope, the innermost one having the final say.
+    fn is_excluded(
+        &self,
+        path: &Path,
+        is_dir: bool,
+        root: &SearchRoot,
+        ignores: &[Gitignore],
+    ) -> bool {
+        if !self.excludes.is_empty() {
+            let relative = path.strip_prefix(&root.path).unwrap_or(path);
+            let by_name = path
+                .file_name()
+                .map(|name| self.excludes.is_match(name))
+                .unwrap_or_default();
+            if by_name || self.excludes.is_match(relative) {
+                return true;
+            }
+        }
+        ignores
+            .iter()
+            .rev()
+            .map(|ignore| ignore.matched(path, is_dir))
+            .find(|m| !m.is_none())
+            .map(|m| m.is_ignore())
+            .unwrap_or_default()
+    }
+
+    /// Decides whether the walk goes into `file`, following symlinks only when asked to
+    /// and refusing directories on other filesystems or ones that were already visited.
+    /// Mounted filesystems have neither links nor devices, so only local folders are checked.
+    fn should_descend(&self, file: &PitouFile, local: bool, root: &SearchRoot) -> bool {
+        if file.is_link() && !self.follow_links || !file.opens_as_dir() {
+            return false;
+        }
+        if !local || !self.same_filesystem && self.visited.is_none() {
+            return true;
+        }
+        let path = &file.path.path;
+        let Ok(target) = std::fs::metadata(path) else {
+            return false;
+        };
+        if self.same_filesystem && device_of(&target) != root.device {
+            return false;
+        }
+        match (&self.visited, directory_id(path, &target)) {
+            (Some(visited), Some(id)) => visited
+                .lock()
+                .map(|mut visited| visited.insert(id))
+                .unwrap_or_default(),
+            _ => true,
+        }
+    }
+
+    /// Reads a single directory, queueing its subdirectories on `worker` and writing its finds to the stream in one batch.
+    ///
+    /// Entries that can't be read are skipped when `skip_errors` is set, otherwise the first error is returned.
+    fn read_directory(
+        &self,
+        job: Job,
+        worker: &Worker<Job>,
+        pending: &Pending,
+        stream: &SearchStream,
+    ) -> std::io::Result<()> {
+        let route = vfs::route(&job.directory)?;
+        let ignores = if self.respect_ignore_files && route.is_local() {
+            with_ignore_files(&job.ignores, &job.directory)
+        } else {
+            job.ignores
+        };
+        let child_depth = match job.depth {
+            Some(depth) if depth <= 1 => None,
+            Some(depth) => Some(Some(depth - 1)),
+            None => Some(None),
+        };
+        let mut finds = LinkedList::new();
+        let mut subdirectories = Vec::new();
+        let mut outcome = Ok(());
+        for file in route.fs.read_dir(&route.path)? {
+            if stream.is_terminated() {
+                break;
+            }
+            let file = route.rebase(file);
+            let Some(metadata) = &file.metadata else {
+                if self.skip_errors {
+                    continue;
+                }
+                outcome = Err(std::io::ErrorKind::Other.into());
+                break;
+            };
+            if self.is_excluded(&file.path.path, metadata.is_dir(), &job.root, &ignores) {
+                continue;
+            }
+            // hidden folders aren't searched either when hidden items are left out
+            if !self.filter.hidden && metadata.is_hidden() {
+                continue;
+            }
+            if let Some(depth) = child_depth {
+                if self.should_descend(&file, route.is_local(), &job.root) {
+                    subdirectories.push(Job {
+                        directory: file.path.path.clone(),
+                        depth,
+                        ignores: ignores.clone(),
+                        root: job.root.clone(),
+                    });
+                }
+            }
+            if let Some(find) = self.find(file, &job.root) {
+                finds.push_back(find);
+            }
+        }
+        pending.push(worker, subdirectories);
+        stream.write(finds);
+        outcome
     }
 }
 
+#[cfg(unix)]
+type DirectoryId = (u64, u64);
+
+#[cfg(not(unix))]
+type DirectoryId = PathBuf;
+
+#[cfg(unix)]
+fn directory_id(_path: &Path, metadata: &Metadata) -> Option<DirectoryId> {
+    use std::os::unix::fs::MetadataExt;
+    Some((metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(unix))]
+fn directory_id(path: &Path, _metadata: &Metadata) -> Option<DirectoryId> {
+    path.canonicalize().ok()
+}
+
+#[cfg(unix)]
+fn device_of(metadata: &Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    Some(metadata.dev())
+}
+
+#[cfg(not(unix))]
+fn device_of(_metadata: &Metadata) -> Option<u64> {
+    None
+}
+
+/// The `.gitignore` and `.ignore` rules in scope of a directory, innermost last.
+type IgnoreStack = Arc<Vec<Gitignore>>;
+
+fn with_ignore_files(stack: &IgnoreStack, directory: &Path) -> IgnoreStack {
+    let mut builder = GitignoreBuilder::new(directory);
+    for name in [".gitignore", ".ignore"] {
+        let file = directory.join(name);
+        if file.is_file() {
+            // a partially invalid file still contributes its valid globs
+            let _ = builder.add(file);
+        }
+    }
+    match builder.build() {
+        Ok(ignore) if !ignore.is_empty() => {
+            let mut stack = Vec::clone(stack);
+            stack.push(ignore);
+            Arc::new(stack)
+        }
+        _ => stack.clone(),
+    }
+}
+
+/// Collects the ignore files of the search root's ancestors up to the enclosing git repository,
+/// so that searching inside a repository honours the rules declared above the search root.
+fn ancestor_ignore_files(root: &Path) -> IgnoreStack {
+    let repository = root
+        .ancestors()
+        .skip(1)
+        .position(|dir| dir.join(".git").exists());
+    match repository {
+        Some(depth) => root
+            .ancestors()
+            .skip(1)
+            .take(depth + 1)
+            .collect::<Vec<_>>()
+            .into_iter()
+            .rev()
+            .fold(IgnoreStack::default(), |stack, dir| {
+                with_ignore_files(&stack, dir)
+            }),
+        None => IgnoreStack::default(),
+    }
+}
+
+/// A directory waiting to be read, the number of levels (itself included) that may still be descended,
+/// the ignore rules inherited from its ancestors and the search root it lies under.
+struct Job {
+    directory: PathBuf,
+    depth: Option<u32>,
+    ignores: IgnoreStack,
+    root: Arc<SearchRoot>,
+}
+
+struct SearchRoot {
+    path: PathBuf,
+    device: Option<u64>,
+}
+
+/// Resolves the roots to walk, dropping those already covered by another root so that nothing is found twice.
+/// A nested root on another filesystem is kept when the walk stays on one filesystem, as its parent won't reach it.
+fn distinct_roots(paths: &[PathBuf], same_filesystem: bool) -> Vec<Arc<SearchRoot>> {
+    let mut roots = paths
+        .iter()
+        .map(|path| SearchRoot {
+            path: path.clone(),
+            device: std::fs::metadata(path).ok().and_then(|m| device_of(&m)),
+        })
+        .collect::<Vec<_>>();
+    roots.sort_by_key(|root| root.path.components().count());
+    let mut distinct: Vec<Arc<SearchRoot>> = Vec::with_capacity(roots.len());
+    for root in roots {
+        let covered = distinct.iter().any(|other| {
+            root.path.starts_with(&other.path) && (!same_filesystem || root.device == other.device)
+        });
+        if !covered {
+            distinct.push(Arc::new(root));
+        }
+    }
+    distinct
+}
+
+/// The directories queued but not read yet. Workers that run out of directories to read sleep
+/// on it until more are queued or the walk is over.
+struct Pending {
+    /// the number of directories queued and the number of times more were queued
+    state: Mutex<(usize, u64)>,
+    changed: Condvar,
+}
+
+impl Pending {
+    fn new(count: usize) -> Self {
+        Self {
+            state: Mutex::new((count, 0)),
+            changed: Condvar::new(),
+        }
+    }
+
+    /// Queues `jobs` on `worker` and wakes the idle workers to steal them.
+    fn push(&self, worker: &Worker<Job>, jobs: Vec<Job>) {
+        if jobs.is_empty() {
+            return;
+        }
+        // counted before they can be stolen, so that reading one can't end the walk early
+        if let Ok(mut state) = self.state.lock() {
+            state.0 += jobs.len();
+            jobs.into_iter().for_each(|job| worker.push(job));
+            state.1 += 1;
+        }
+        self.changed.notify_all();
+    }
+
+    /// Marks a directory as read, waking every idle worker once none is left.
+    fn done(&self) {
+        if let Ok(mut state) = self.state.lock() {
+            state.0 -= 1;
+            if state.0 == 0 {
+                self.changed.notify_all();
+            }
+        }
+    }
+
+    /// The number of times directories were queued so far, none once the walk is over.
+    fn queued(&self) -> Option<u64> {
+        self.state
+            .lock()
+            .ok()
+            .and_then(|state| (state.0 > 0).then_some(state.1))
+    }
+
+    /// Sleeps until directories were queued since `seen` was read, returning whether the walk goes on.
+    fn wait(&self, seen: u64, stream: &SearchStream) -> bool {
+        let Ok(state) = self.state.lock() else {
+            return false;
+        };
+        self.changed
+            .wait_while(state, |state| {
+                state.0 > 0 && state.1 == seen && !stream.is_terminated()
+            })
+            .is_ok_and(|state| state.0 > 0 && !stream.is_terminated())
+    }
+
+    /// Wakes the idle workers to see that the stream was terminated.
+    fn wake_all(&self) {
+        // holding the lock ensures no worker is between checking the stream and going to sleep
+        let _state = self.state.lock();
+        self.changed.notify_all();
+    }
+}
+
+fn find_job(local: &Worker<Job>, global: &Injector<Job>, stealers: &[Stealer<Job>]) -> Option<Job> {
+    local.pop().or_else(|| {
+        std::iter::repeat_with(|| {
+            global
+                .steal_batch_and_pop(local)
+                .or_else(|| stealers.iter().map(|s| s.steal()).collect())
+        })
+        .find(|s| !s.is_retry())
+        .and_then(|s| s.success())
+    })
+}
+
+/// Walks every search root on a pool of `threads` workers, the calling thread being one of them.
+///
+/// Each worker reads directories off its own queue and steals from the others once it runs dry,
+/// so a single deep subtree can't leave the rest of the pool idle. A worker with nothing to steal
+/// sleeps until more directories are queued.
+/// The walk ends once every queued directory has been read or the stream is terminated.
+fn walk(variables: &SearchVariables, stream: &SearchStream, threads: usize) {
+    if variables.depth == Some(0) {
+        return;
+    }
+    let injector = Injector::new();
+    let roots = distinct_roots(&variables.roots, variables.same_filesystem);
+    for root in &roots {
+        if let Some(visited) = &variables.visited {
+            let id = std::fs::metadata(&root.path)
+                .ok()
+                .and_then(|m| directory_id(&root.path, &m));
+            if let (Ok(mut visited), Some(id)) = (visited.lock(), id) {
+                visited.insert(id);
+            }
+        }
+        let local = matches!(Location::of(&root.path), Location::Local(_));
+        let ignores = if variables.respect_ignore_files && local {
+            ancestor_ignore_files(&root.path)
+        } else {
+            IgnoreStack::default()
+        };
+        injector.push(Job {
+            directory: root.path.clone(),
+            depth: variables.depth,
+            ignores,
+            root: root.clone(),
+        });
+    }
+    let pending = Pending::new(roots.len());
+    let workers = (0..threads.max(1))
+        .map(|_| Worker::new_lifo())
+        .collect::<Vec<_>>();
+    let stealers = workers.iter().map(|w| w.stealer()).collect::<Vec<_>>();
+
+    let work = |worker: Worker<Job>| {
+        while let Some(seen) = pending.queued() {
+            if stream.is_terminated() {
+                break;
+            }
+            match find_job(&worker, &injector, &stealers) {
+                Some(job) => {
+                    let outcome = variables.read_directory(job, &worker, &pending, stream);
+                    if outcome.is_err() && !variables.skip_errors {
+                        stream.terminate();
+                    }
+                    pending.done();
+                }
+                None if pending.wait(seen, stream) => (),
+                None => break,
+            }
+        }
+        pending.wake_all();
+    };
+
+    std::thread::scope(|scope| {
+        let mut workers = workers.into_iter();
+        let own = workers.next();
+        for worker in workers {
+            // a worker that fails to spawn leaves its share of the work to be stolen by the others
+            let _ = std::thread::Builder::new().spawn_scoped(scope, || work(worker));
+        }
+        if let Some(worker) = own {
+            work(worker);
+        }
+    });
+}
+
 pub async fn read_stream() -> crate::msg::SearchMsg {
-    stream::read().await
+    stream::read()
 }
 
 pub async fn terminate_search() {
-    stream::terminate_stream().await
+    stream::terminate_stream()
 }
 
 pub async fn is_terminated() -> bool {
-    stream::is_terminated().await
+    stream::is_terminated()
 }
 
+/// Starts a search in the background, its finds are collected through [`read_stream`].
+///
+/// Without `hardware_accelerate` the walk runs on a single thread to stay gentle on the system,
+/// otherwise it spreads across every available core.
 pub async fn search(options: SearchOptions) {
-    let hardware_accelerate = options.hardware_accelerate;
+    let threads = pool_size(options.hardware_accelerate);
     let max_finds = options.max_finds;
-    let (variables, directory) = options.into();
+    let variables = SearchVariables::from(options);
     if variables.filter.all_filtered() {
         return;
     }
-    stream::configure_stream(max_finds).await;
-    tokio::spawn(async move {
-        recursive_search(directory, variables).await;
-        stream::proceed_to_finish_stream().await;
-        if hardware_accelerate {
-            stream::wait_for_all_ops().await;
-        }
+    let stream = stream::configure_stream(max_finds);
+    tokio::task::spawn_blocking(move || {
+        walk(&variables, &stream, threads);
+        stream.terminate();
     });
 }
 
-#[async_recursion::async_recursion]
-async fn recursive_search(directory: PathBuf, mut variables: SearchVariables) {
-    if variables.depth == 0 || stream::is_terminated().await {
-        return;
+/// Runs a search to completion on its own stream and returns every find.
+/// Unlike [`search`], it leaves the search whose finds are read through [`read_stream`] untouched.
+pub async fn collect(options: SearchOptions) -> LinkedList<SearchFind> {
+    let threads = pool_size(options.hardware_accelerate);
+    let stream = SearchStream::new(options.max_finds);
+    let variables = SearchVariables::from(options);
+    if variables.filter.all_filtered() {
+        return LinkedList::new();
     }
-    variables.depth -= 1;
-    let mut read_dir = if let Ok(read_dir) = tokio::fs::read_dir(&directory).await {
-        read_dir
-    } else {
-        return;
-    };
+    tokio::task::spawn_blocking(move || {
+        walk(&variables, &stream, threads);
+        stream.drain()
+    })
+    .await
+    .unwrap_or_default()
+}
 
-    while let Ok(Some(de)) = read_dir.next_entry().await {
-        let file = PitouFile::new(de.path(), de.metadata().await.unwrap());
-        if file.is_dir() {
-            let vclone = variables.clone();
-            stream::append_handle(tokio::spawn(async move {
-                recursive_search(de.path(), vclone).await
-            }))
-            .await;
-        }
-        if variables.include(&file) {
-            stream::write(file).await;
-        }
+fn pool_size(hardware_accelerate: bool) -> usize {
+    if hardware_accelerate {
+        std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1)
+    } else {
+        1
     }
 }
 
@@ -300,4 +802,215 @@ pub enum SearchType {
     MatchBegining(String),
     MatchMiddle(String),
     MatchEnding(String),
+    Glob(globset::GlobMatcher),
+    Fuzzy(String),
+}
+
+#[cfg(test)]
+mod test_mod {
+    use std::{collections::LinkedList, io::Write, path::Path, path::PathBuf, sync::Arc};
+
+    use globset::GlobSet;
+
+    use super::{
+        distinct_roots, stream::SearchStream, walk, SearchOptions, SearchType, SearchVariables,
+    };
+    use crate::{
+        backend::vfs::{self, FileSystem, MemoryFs},
+        search::{SearchError, SearchFind},
+        PitouFileFilter,
+    };
+
+    fn options(roots: &[&str]) -> SearchOptions {
+        SearchOptions {
+            roots: roots.iter().map(PathBuf::from).collect(),
+            hardware_accelerate: false,
+            filter: PitouFileFilter::new(),
+            case_sensitive: false,
+            depth: None,
+            search_type: SearchType::Regex(regex::Regex::new(".").unwrap()),
+            skip_errors: false,
+            max_finds: usize::MAX,
+            follow_links: false,
+            same_filesystem: false,
+            respect_ignore_files: false,
+            excludes: GlobSet::empty(),
+        }
+    }
+
+    fn run(options: SearchOptions, threads: usize) -> (LinkedList<SearchFind>, bool) {
+        let stream = SearchStream::new(options.max_finds);
+        walk(&SearchVariables::from(options), &stream, threads);
+        (stream.drain(), stream.is_terminated())
+    }
+
+    fn names(finds: &LinkedList<SearchFind>) -> Vec<&str> {
+        finds.iter().map(|find| find.file.name()).collect()
+    }
+
+    /// Mounts a tree of `dirs` folders holding `files` files each, next to `top.txt`.
+    fn mount_tree(name: &str, dirs: usize, files: usize) {
+        let fs = MemoryFs::new();
+        for dir in 0..dirs {
+            let dir = PathBuf::from(format!("d{dir}"));
+            fs.create_dir(&dir).unwrap();
+            for file in 0..files {
+                fs.create_file(&dir.join(format!("f{file}"))).unwrap();
+            }
+        }
+        fs.create(Path::new("top.txt"))
+            .unwrap()
+            .write_all(b"top")
+            .unwrap();
+        assert!(vfs::mount(name, Arc::new(fs)));
+    }
+
+    #[test]
+    fn test_walk_order() {
+        mount_tree("walk-order", 2, 2);
+        // a single worker goes depth first, the latest folder queued first,
+        // and each folder's finds come in one batch in the order it lists them
+        let (finds, _) = run(options(&["walk-order://"]), 1);
+        assert_eq!(
+            names(&finds),
+            ["d0", "d1", "top.txt", "f0", "f1", "f0", "f1"]
+        );
+        assert!(finds
+            .iter()
+            .all(|find| find.root.path == Path::new("walk-order://")));
+        vfs::unmount("walk-order");
+    }
+
+    #[test]
+    fn test_walk_termination() {
+        mount_tree("walk-end", 40, 5);
+        for threads in [1, 2, 8] {
+            let (finds, _) = run(options(&["walk-end://"]), threads);
+            assert_eq!(finds.len(), 40 * 6 + 1);
+        }
+        // the walk also ends as soon as enough was found
+        let mut limited = options(&["walk-end://"]);
+        limited.max_finds = 3;
+        let (finds, terminated) = run(limited, 4);
+        assert_eq!(finds.len(), 3);
+        assert!(terminated);
+        vfs::unmount("walk-end");
+    }
+
+    #[test]
+    fn test_walk_errors() {
+        mount_tree("walk-errors", 1, 1);
+        // the first root can't be read, being a mount that isn't there
+        let roots = ["walk-missing://", "walk-errors://"];
+        let (finds, terminated) = run(options(&roots), 1);
+        assert!(finds.is_empty());
+        assert!(terminated);
+
+        let mut skipping = options(&roots);
+        skipping.skip_errors = true;
+        let (finds, _) = run(skipping, 1);
+        assert_eq!(names(&finds), ["d0", "top.txt", "f0"]);
+        vfs::unmount("walk-errors");
+    }
+
+    #[test]
+    fn test_distinct_roots() {
+        let paths = ["/a/b", "/c", "/a", "/a/b/c", "/ab"].map(PathBuf::from);
+        for same_filesystem in [false, true] {
+            let roots = distinct_roots(&paths, same_filesystem);
+            let roots = roots.iter().map(|root| root.path.to_str().unwrap());
+            assert_eq!(roots.collect::<Vec<_>>(), ["/c", "/a", "/ab"]);
+        }
+    }
+
+    #[test]
+    fn test_root_tags() {
+        mount_tree("tag-one", 1, 1);
+        mount_tree("tag-two", 2, 0);
+        // the key matches every name, like an empty "Contains" search
+        let mut both = options(&["tag-one://", "tag-two://"]);
+        both.search_type = SearchType::MatchMiddle(String::new());
+        let (finds, _) = run(both, 2);
+        assert_eq!(finds.len(), 6);
+        // every find is tagged with the root it lies under
+        assert!(finds
+            .iter()
+            .all(|find| find.file.path.path.starts_with(&find.root.path)));
+        let under = |root: &str| {
+            finds
+                .iter()
+                .filter(|f| f.root.path == Path::new(root))
+                .count()
+        };
+        assert_eq!((under("tag-one://"), under("tag-two://")), (3, 3));
+        vfs::unmount("tag-one");
+        vfs::unmount("tag-two");
+    }
+
+    #[test]
+    fn test_traversal_controls() {
+        let root = std::env::temp_dir().join("pitou-search-controls-test");
+        std::fs::remove_dir_all(&root).ok();
+        for dir in ["src/deep/deeper", "build", "node_modules"] {
+            std::fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        for file in [
+            "src/main.rs",
+            "src/deep/deeper/leaf.rs",
+            "build/out.o",
+            "node_modules/pkg.js",
+        ] {
+            std::fs::write(root.join(file), b"").unwrap();
+        }
+        std::fs::write(root.join(".gitignore"), "build/\n").unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(&root, root.join("src/loop")).unwrap();
+        let at = [root.to_str().unwrap()];
+        let found = |options: SearchOptions| {
+            let (finds, _) = run(options, 2);
+            let mut names = names(&finds)
+                .into_iter()
+                .map(str::to_owned)
+                .collect::<Vec<_>>();
+            names.sort();
+            names
+        };
+
+        let mut shallow = options(&at);
+        shallow.depth = Some(2);
+        assert!(found(shallow).contains(&"main.rs".to_owned()));
+        let mut shallow = options(&at);
+        shallow.depth = Some(1);
+        assert!(!found(shallow).contains(&"main.rs".to_owned()));
+
+        // excluded folders are left unsearched, and globs may hold commas
+        let mut excluding = options(&at);
+        excluding.excludes =
+            super::parse_excludes(&["node_modules".into(), "*.{o,js}".into()], false).unwrap();
+        let names = found(excluding);
+        assert!(!names
+            .iter()
+            .any(|n| ["node_modules", "pkg.js", "out.o"].contains(&&n[..])));
+        assert!(names.contains(&"build".to_owned()));
+
+        let mut ignoring = options(&at);
+        ignoring.respect_ignore_files = true;
+        let names = found(ignoring);
+        assert!(!names.iter().any(|n| n == "build" || n == "out.o"));
+        assert!(names.contains(&"node_modules".to_owned()));
+
+        // a link back up the tree is followed once, not round and round
+        #[cfg(unix)]
+        {
+            let mut following = options(&at);
+            following.follow_links = true;
+            let names = found(following);
+            assert_eq!(names.iter().filter(|n| *n == "leaf.rs").count(), 1);
+            assert!(names.contains(&"loop".to_owned()));
+        }
+
+        let invalid = super::parse_excludes(&["ok".into(), "[".into()], false);
+        assert_eq!(invalid.err(), Some(SearchError::InvalidExclude("[".into())));
+        std::fs::remove_dir_all(&root).ok();
+    }
 }
@@ -2,12 +2,24 @@ use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::{path::PathBuf, rc::Rc};
 
 use crate::{
-    msg::SearchMsg, search::SimplifiedSearchOptions, GeneralFolder, PitouDrive, PitouDriveKind,
-    PitouFile, PitouFileFilter, PitouFileMetadata, PitouFilePath, PitouTrashItem,
+    duplicates::{DuplicateGroup, DuplicateOptions},
+    favorites::{Favorite, FavoriteItem},
+    msg::{DuplicateMsg, DuplicateProgress, SearchMsg},
+    properties::{ChangeError, ItemProperties, Owner},
+    recents::{Recent, RecentItem, RecentKind, RecentPeriod, Recents},
+    search::{fuzzy::FuzzyMatch, SavedSearch, SearchFind, SearchScope, SimplifiedSearchOptions},
+    session::SavedTab,
+    trash::RestoreError,
+    usage::{LargeFile, TypeUsage, UsageNode, UsageReport},
+    vault::VaultEntry,
+    AppMenu, GeneralFolder, PitouDateTime, PitouDrive, PitouDriveKind, PitouFile, PitouFileFilter,
+    PitouFileKind, PitouFileMetadata, PitouFilePath, PitouFileSize, PitouLink, PitouTrashItem,
     PitouTrashItemMetadata,
 };
 
+#[cfg(windows)]
 const BMS: u8 = b'\\';
+#[cfg(windows)]
 const FMS: u8 = b'/';
 
 impl<'d> Deserialize<'d> for PitouFile {
@@ -75,6 +87,8 @@ impl Serialize for PitouFilePath {
     }
 }
 
+/// The frontend always sends `/` separated paths, restore windows separators and drive letters.
+#[cfg(windows)]
 fn parse_path(mut path_str: String) -> PathBuf {
     for bc in unsafe { path_str.as_bytes_mut() } {
         if *bc == FMS {
@@ -94,6 +108,11 @@ fn parse_path(mut path_str: String) -> PathBuf {
     PathBuf::from(path_str)
 }
 
+#[cfg(not(windows))]
+fn parse_path(path_str: String) -> PathBuf {
+    PathBuf::from(path_str)
+}
+
 #[inline]
 fn serialize_pathbuf<S: Serializer>(path: &PathBuf, sz: S) -> Result<S::Ok, S::Error> {
     let path = path.as_os_str().to_str().unwrap_or_default();
@@ -123,18 +142,22 @@ impl<'d> Deserialize<'d> for SimplifiedSearchOptions {
     fn deserialize<D: Deserializer<'d>>(dz: D) -> Result<Self, D::Error> {
         #[derive(Deserialize)]
         struct SimplifiedSearchOptions {
-            search_dir: PitouFile,
+            scope: SearchScope,
             hardware_accelerate: bool,
             filter: PitouFileFilter,
             case_sensitive: bool,
-            depth: u8,
+            depth: Option<u32>,
             input: String,
             search_kind: u8,
             skip_errors: bool,
             max_finds: usize,
+            follow_links: bool,
+            same_filesystem: bool,
+            respect_ignore_files: bool,
+            excludes: Vec<String>,
         }
         let SimplifiedSearchOptions {
-            search_dir,
+            scope,
             hardware_accelerate,
             filter,
             max_finds,
@@ -143,9 +166,13 @@ impl<'d> Deserialize<'d> for SimplifiedSearchOptions {
             input,
             search_kind,
             case_sensitive,
+            follow_links,
+            same_filesystem,
+            respect_ignore_files,
+            excludes,
         } = SimplifiedSearchOptions::deserialize(dz)?;
         let res = Self {
-            search_dir,
+            scope,
             hardware_accelerate,
             filter,
             case_sensitive,
@@ -154,6 +181,10 @@ impl<'d> Deserialize<'d> for SimplifiedSearchOptions {
             search_kind,
             skip_errors,
             max_finds,
+            follow_links,
+            same_filesystem,
+            respect_ignore_files,
+            excludes,
         };
         Ok(res)
     }
@@ -204,8 +235,8 @@ impl Serialize for SearchMsg {
         use std::collections::LinkedList;
         #[derive(Serialize)]
         enum SearchMsg<'a> {
-            Active(&'a LinkedList<PitouFile>),
-            Terminated(&'a LinkedList<PitouFile>),
+            Active(&'a LinkedList<SearchFind>),
+            Terminated(&'a LinkedList<SearchFind>),
         }
         let fake_msg = match self {
             Self::Active(ll) => SearchMsg::Active(ll),
@@ -215,3 +246,595 @@ impl Serialize for SearchMsg {
         fake_msg.serialize(sz)
     }
 }
+
+impl Serialize for SearchFind {
+    fn serialize<S: Serializer>(&self, sz: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct SearchFind<'a> {
+            file: &'a PitouFile,
+            relevance: &'a Option<FuzzyMatch>,
+            root: &'a PitouFilePath,
+        }
+
+        SearchFind {
+            file: &self.file,
+            relevance: &self.relevance,
+            root: &self.root,
+        }
+        .serialize(sz)
+    }
+}
+
+impl<'d> Deserialize<'d> for SearchScope {
+    fn deserialize<D: Deserializer<'d>>(dz: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        enum SearchScope {
+            Roots(Vec<PitouFilePath>),
+            AllDrives,
+        }
+
+        let res = match SearchScope::deserialize(dz)? {
+            SearchScope::Roots(roots) => Self::Roots(roots),
+            SearchScope::AllDrives => Self::AllDrives,
+        };
+        Ok(res)
+    }
+}
+
+impl Serialize for SimplifiedSearchOptions {
+    fn serialize<S: Serializer>(&self, sz: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct SimplifiedSearchOptions<'a> {
+            scope: &'a SearchScope,
+            hardware_accelerate: bool,
+            filter: PitouFileFilter,
+            case_sensitive: bool,
+            depth: Option<u32>,
+            input: &'a String,
+            search_kind: u8,
+            skip_errors: bool,
+            max_finds: usize,
+            follow_links: bool,
+            same_filesystem: bool,
+            respect_ignore_files: bool,
+            excludes: &'a Vec<String>,
+        }
+
+        SimplifiedSearchOptions {
+            scope: &self.scope,
+            hardware_accelerate: self.hardware_accelerate,
+            filter: self.filter,
+            case_sensitive: self.case_sensitive,
+            depth: self.depth,
+            input: &self.input,
+            search_kind: self.search_kind,
+            skip_errors: self.skip_errors,
+            max_finds: self.max_finds,
+            follow_links: self.follow_links,
+            same_filesystem: self.same_filesystem,
+            respect_ignore_files: self.respect_ignore_files,
+            excludes: &self.excludes,
+        }
+        .serialize(sz)
+    }
+}
+
+impl Serialize for SearchScope {
+    fn serialize<S: Serializer>(&self, sz: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        enum SearchScope<'a> {
+            Roots(&'a Vec<PitouFilePath>),
+            AllDrives,
+        }
+
+        match self {
+            Self::Roots(roots) => SearchScope::Roots(roots),
+            Self::AllDrives => SearchScope::AllDrives,
+        }
+        .serialize(sz)
+    }
+}
+
+impl Serialize for SavedSearch {
+    fn serialize<S: Serializer>(&self, sz: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct SavedSearch<'a> {
+            name: &'a String,
+            options: &'a SimplifiedSearchOptions,
+        }
+
+        SavedSearch {
+            name: &self.name,
+            options: &self.options,
+        }
+        .serialize(sz)
+    }
+}
+
+impl<'d> Deserialize<'d> for SavedSearch {
+    fn deserialize<D: Deserializer<'d>>(dz: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct SavedSearch {
+            name: String,
+            options: SimplifiedSearchOptions,
+        }
+
+        let SavedSearch { name, options } = SavedSearch::deserialize(dz)?;
+        Ok(Self { name, options })
+    }
+}
+
+impl<'d> Deserialize<'d> for DuplicateOptions {
+    fn deserialize<D: Deserializer<'d>>(dz: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct DuplicateOptions {
+            roots: Vec<PitouFilePath>,
+            min_size: u64,
+            hardware_accelerate: bool,
+        }
+
+        let DuplicateOptions {
+            roots,
+            min_size,
+            hardware_accelerate,
+        } = DuplicateOptions::deserialize(dz)?;
+        Ok(Self {
+            roots,
+            min_size,
+            hardware_accelerate,
+        })
+    }
+}
+
+impl Serialize for DuplicateGroup {
+    fn serialize<S: Serializer>(&self, sz: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct DuplicateGroup<'a> {
+            size: u64,
+            hash: &'a String,
+            files: &'a Vec<PitouFile>,
+        }
+
+        DuplicateGroup {
+            size: self.size,
+            hash: &self.hash,
+            files: &self.files,
+        }
+        .serialize(sz)
+    }
+}
+
+impl Serialize for DuplicateMsg {
+    fn serialize<S: Serializer>(&self, sz: S) -> Result<S::Ok, S::Error> {
+        use std::collections::LinkedList;
+        #[derive(Serialize)]
+        enum DuplicateMsg<'a> {
+            Active(DuplicateProgress, &'a LinkedList<DuplicateGroup>),
+            Terminated(DuplicateProgress, &'a LinkedList<DuplicateGroup>),
+        }
+
+        match self {
+            Self::Active(progress, groups) => DuplicateMsg::Active(*progress, groups),
+            Self::Terminated(progress, groups) => DuplicateMsg::Terminated(*progress, groups),
+        }
+        .serialize(sz)
+    }
+}
+
+impl Serialize for UsageNode {
+    fn serialize<S: Serializer>(&self, sz: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct UsageNode<'a> {
+            name: &'a String,
+            path: &'a PitouFilePath,
+            size: u64,
+            files: u64,
+            is_dir: bool,
+            children: &'a Vec<crate::usage::UsageNode>,
+        }
+
+        UsageNode {
+            name: &self.name,
+            path: &self.path,
+            size: self.size,
+            files: self.files,
+            is_dir: self.is_dir,
+            children: &self.children,
+        }
+        .serialize(sz)
+    }
+}
+
+impl Serialize for LargeFile {
+    fn serialize<S: Serializer>(&self, sz: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct LargeFile<'a> {
+            path: &'a PitouFilePath,
+            size: u64,
+        }
+
+        LargeFile {
+            path: &self.path,
+            size: self.size,
+        }
+        .serialize(sz)
+    }
+}
+
+impl Serialize for UsageReport {
+    fn serialize<S: Serializer>(&self, sz: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct UsageReport<'a> {
+            root: &'a PitouFilePath,
+            size: u64,
+            files: u64,
+            dirs: u64,
+            largest: &'a Vec<LargeFile>,
+            by_type: &'a Vec<TypeUsage>,
+            scanned_at: PitouDateTime,
+        }
+
+        UsageReport {
+            root: &self.root,
+            size: self.size,
+            files: self.files,
+            dirs: self.dirs,
+            largest: &self.largest,
+            by_type: &self.by_type,
+            scanned_at: self.scanned_at,
+        }
+        .serialize(sz)
+    }
+}
+
+impl Serialize for ItemProperties {
+    fn serialize<S: Serializer>(&self, sz: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct ItemProperties<'a> {
+            path: &'a PitouFilePath,
+            kind: Option<PitouFileKind>,
+            size: u64,
+            modified: Option<PitouDateTime>,
+            accessed: Option<PitouDateTime>,
+            created: Option<PitouDateTime>,
+            readonly: bool,
+            mode: Option<u32>,
+            owner: &'a Option<Owner>,
+            group: &'a Option<Owner>,
+            inode: Option<u64>,
+            links: Option<u64>,
+            mime: &'a String,
+            link_target: &'a Option<PitouFilePath>,
+        }
+
+        ItemProperties {
+            path: &self.path,
+            kind: self.kind,
+            size: self.size,
+            modified: self.modified,
+            accessed: self.accessed,
+            created: self.created,
+            readonly: self.readonly,
+            mode: self.mode,
+            owner: &self.owner,
+            group: &self.group,
+            inode: self.inode,
+            links: self.links,
+            mime: &self.mime,
+            link_target: &self.link_target,
+        }
+        .serialize(sz)
+    }
+}
+
+impl Serialize for ChangeError {
+    fn serialize<S: Serializer>(&self, sz: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct ChangeError<'a> {
+            path: &'a PitouFilePath,
+            message: &'a String,
+        }
+
+        ChangeError {
+            path: &self.path,
+            message: &self.message,
+        }
+        .serialize(sz)
+    }
+}
+
+impl Serialize for RestoreError {
+    fn serialize<S: Serializer>(&self, sz: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct RestoreError<'a> {
+            id: &'a String,
+            path: &'a PitouFilePath,
+            occupied: bool,
+            message: &'a String,
+        }
+
+        RestoreError {
+            id: &self.id,
+            path: &self.path,
+            occupied: self.occupied,
+            message: &self.message,
+        }
+        .serialize(sz)
+    }
+}
+
+impl Serialize for PitouLink {
+    fn serialize<S: Serializer>(&self, sz: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct PitouLink<'a> {
+            target: &'a PitouFilePath,
+            target_kind: Option<PitouFileKind>,
+        }
+
+        PitouLink {
+            target: &self.target,
+            target_kind: self.target_kind,
+        }
+        .serialize(sz)
+    }
+}
+
+impl Serialize for PitouFileMetadata {
+    fn serialize<S: Serializer>(&self, sz: S) -> Result<S::Ok, S::Error> {
+        // timestamps are sent as milliseconds since the epoch, negative before 1970
+        #[derive(Serialize)]
+        struct PitouFileMetadata<'a> {
+            modified: Option<i64>,
+            accessed: Option<i64>,
+            created: Option<i64>,
+            size: PitouFileSize,
+            kind: PitouFileKind,
+            attribute: u32,
+            hidden: bool,
+            link: &'a Option<PitouLink>,
+        }
+
+        let millis = |time: Option<PitouDateTime>| time.map(PitouDateTime::timestamp_millis);
+        PitouFileMetadata {
+            modified: millis(self.modified),
+            accessed: millis(self.accessed),
+            created: millis(self.created),
+            size: self.size,
+            kind: self.kind,
+            attribute: self.attribute,
+            hidden: self.hidden,
+            link: &self.link,
+        }
+        .serialize(sz)
+    }
+}
+
+impl Serialize for SavedTab {
+    fn serialize<S: Serializer>(&self, sz: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct SavedTab<'a> {
+            menu: AppMenu,
+            history: &'a Vec<PitouFilePath>,
+            current: usize,
+        }
+
+        SavedTab {
+            menu: self.menu,
+            history: &self.history,
+            current: self.current,
+        }
+        .serialize(sz)
+    }
+}
+
+impl<'d> Deserialize<'d> for SavedTab {
+    fn deserialize<D: Deserializer<'d>>(dz: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct SavedTab {
+            menu: AppMenu,
+            history: Vec<PitouFilePath>,
+            current: usize,
+        }
+
+        let SavedTab {
+            menu,
+            history,
+            current,
+        } = SavedTab::deserialize(dz)?;
+        Ok(Self {
+            menu,
+            history,
+            current,
+        })
+    }
+}
+
+impl Serialize for Favorite {
+    fn serialize<S: Serializer>(&self, sz: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct Favorite<'a> {
+            path: &'a PitouFilePath,
+            label: &'a Option<String>,
+        }
+
+        Favorite {
+            path: &self.path,
+            label: &self.label,
+        }
+        .serialize(sz)
+    }
+}
+
+impl<'d> Deserialize<'d> for Favorite {
+    fn deserialize<D: Deserializer<'d>>(dz: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct Favorite {
+            path: PitouFilePath,
+            label: Option<String>,
+        }
+
+        let Favorite { path, label } = Favorite::deserialize(dz)?;
+        Ok(Self { path, label })
+    }
+}
+
+impl Serialize for FavoriteItem {
+    fn serialize<S: Serializer>(&self, sz: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct FavoriteItem<'a> {
+            favorite: &'a Favorite,
+            target: &'a Option<PitouFile>,
+        }
+
+        FavoriteItem {
+            favorite: &self.favorite,
+            target: &self.target,
+        }
+        .serialize(sz)
+    }
+}
+
+impl Serialize for Recent {
+    fn serialize<S: Serializer>(&self, sz: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct Recent<'a> {
+            path: &'a PitouFilePath,
+            kind: RecentKind,
+            used: PitouDateTime,
+        }
+
+        Recent {
+            path: &self.path,
+            kind: self.kind,
+            used: self.used,
+        }
+        .serialize(sz)
+    }
+}
+
+impl<'d> Deserialize<'d> for Recent {
+    fn deserialize<D: Deserializer<'d>>(dz: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct Recent {
+            path: PitouFilePath,
+            kind: RecentKind,
+            used: PitouDateTime,
+        }
+
+        let Recent { path, kind, used } = Recent::deserialize(dz)?;
+        Ok(Self { path, kind, used })
+    }
+}
+
+impl Serialize for RecentItem {
+    fn serialize<S: Serializer>(&self, sz: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct RecentItem<'a> {
+            recent: &'a Recent,
+            period: RecentPeriod,
+            target: &'a PitouFile,
+        }
+
+        RecentItem {
+            recent: &self.recent,
+            period: self.period,
+            target: &self.target,
+        }
+        .serialize(sz)
+    }
+}
+
+impl Serialize for Recents {
+    fn serialize<S: Serializer>(&self, sz: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct Recents<'a> {
+            items: &'a Vec<Recent>,
+            tracking: bool,
+            merged_until: Option<PitouDateTime>,
+        }
+
+        Recents {
+            items: &self.items,
+            tracking: self.tracking,
+            merged_until: self.merged_until,
+        }
+        .serialize(sz)
+    }
+}
+
+impl<'d> Deserialize<'d> for Recents {
+    fn deserialize<D: Deserializer<'d>>(dz: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct Recents {
+            items: Vec<Recent>,
+            tracking: bool,
+            merged_until: Option<PitouDateTime>,
+        }
+
+        let Recents {
+            items,
+            tracking,
+            merged_until,
+        } = Recents::deserialize(dz)?;
+        Ok(Self {
+            items,
+            tracking,
+            merged_until,
+        })
+    }
+}
+
+impl Serialize for VaultEntry {
+    fn serialize<S: Serializer>(&self, sz: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct VaultEntry<'a> {
+            id: &'a String,
+            name: &'a String,
+            size: u64,
+            modified: Option<PitouDateTime>,
+            added: PitouDateTime,
+            origin: &'a PitouFilePath,
+        }
+
+        VaultEntry {
+            id: &self.id,
+            name: &self.name,
+            size: self.size,
+            modified: self.modified,
+            added: self.added,
+            origin: &self.origin,
+        }
+        .serialize(sz)
+    }
+}
+
+impl<'d> Deserialize<'d> for VaultEntry {
+    fn deserialize<D: Deserializer<'d>>(dz: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct VaultEntry {
+            id: String,
+            name: String,
+            size: u64,
+            modified: Option<PitouDateTime>,
+            added: PitouDateTime,
+            origin: PitouFilePath,
+        }
+
+        let VaultEntry {
+            id,
+            name,
+            size,
+            modified,
+            added,
+            origin,
+        } = VaultEntry::deserialize(dz)?;
+        Ok(Self {
+            id,
+            name,
+            size,
+            modified,
+            added,
+            origin,
+        })
+    }
+}
@@ -0,0 +1,31 @@
+use std::path::{Path, PathBuf};
+
+use crate::session::SavedSession;
+
+/// `$XDG_STATE_HOME/pitou/session.json`, falling back to the data folder where there is no state folder.
+fn session_path() -> Option<PathBuf> {
+    dirs::state_dir()
+        .or_else(dirs::data_dir)
+        .map(|dir| dir.join("pitou").join("session.json"))
+}
+
+pub async fn save(session: SavedSession) -> std::io::Result<()> {
+    let path = session_path().ok_or(std::io::ErrorKind::NotFound)?;
+    super::store::write_atomic(&path, &serde_json::to_vec_pretty(&session)?).await
+}
+
+/// The session saved when pitou last closed, without the folders that have since gone.
+pub async fn load() -> Option<SavedSession> {
+    let contents = tokio::fs::read(session_path()?).await.ok()?;
+    let session = serde_json::from_slice::<SavedSession>(&contents).ok()?;
+    session.retain_dirs(Path::is_dir)
+}
+
+/// The window size saved with the last session, read without blocking on a runtime so that
+/// the window can be sized while the app is set up.
+pub fn window_size() -> Option<(i32, i32)> {
+    let contents = std::fs::read(session_path()?).ok()?;
+    let session = serde_json::from_slice::<SavedSession>(&contents).ok()?;
+    (session.app_width > 0 && session.app_height > 0)
+        .then_some((session.app_width, session.app_height))
+}
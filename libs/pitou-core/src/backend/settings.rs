@@ -0,0 +1,89 @@
+use std::path::{Path, PathBuf};
+
+use crate::{settings::SavedSettings, PitouFilePath};
+
+/// `$XDG_CONFIG_HOME/pitou/settings.json`, or wherever the platform keeps configuration.
+fn settings_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("pitou").join("settings.json"))
+}
+
+async fn write(path: PathBuf, settings: &SavedSettings) -> std::io::Result<()> {
+    super::store::write_atomic(&path, settings.to_json().as_bytes()).await
+}
+
+/// The saved settings, migrated from whichever version wrote them. A missing file reads as the
+/// defaults, and so does one that can't be read or parsed, which is kept aside as
+/// `settings.json.bak` so that saving the defaults doesn't lose it.
+pub async fn load() -> SavedSettings {
+    match settings_path() {
+        Some(path) => read(&path).await,
+        None => SavedSettings::default(),
+    }
+}
+
+async fn read(path: &Path) -> SavedSettings {
+    let settings = match tokio::fs::read_to_string(path).await {
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return SavedSettings::default(),
+        Ok(json) => SavedSettings::from_json(&json),
+        Err(_) => None,
+    };
+    if let Some(settings) = settings {
+        return settings;
+    }
+    let mut aside = path.as_os_str().to_owned();
+    aside.push(".bak");
+    tokio::fs::rename(path, aside).await.ok();
+    SavedSettings::default()
+}
+
+pub async fn save(settings: SavedSettings) -> std::io::Result<()> {
+    let path = settings_path().ok_or(std::io::ErrorKind::NotFound)?;
+    write(path, &settings).await
+}
+
+/// Saves and returns the default settings.
+pub async fn reset() -> std::io::Result<SavedSettings> {
+    let settings = SavedSettings::default();
+    save(settings.clone()).await?;
+    Ok(settings)
+}
+
+/// Reads settings exported as `json`, possibly by an older pitou, and saves them as the current settings.
+pub async fn import(json: String) -> Option<SavedSettings> {
+    let settings = SavedSettings::from_json(&json)?;
+    save(settings.clone()).await.ok()?;
+    Some(settings)
+}
+
+/// Writes the saved settings to `pitou-settings.json` in the downloads folder, returning where they went.
+pub async fn export() -> Option<PitouFilePath> {
+    let path = dirs::download_dir()
+        .or_else(dirs::home_dir)?
+        .join("pitou-settings.json");
+    write(path.clone(), &load().await).await.ok()?;
+    Some(PitouFilePath::from_pathbuf(path))
+}
+
+#[cfg(test)]
+mod test_mod {
+    use crate::settings::SavedSettings;
+
+    #[test]
+    fn test_unreadable_settings_kept_aside() {
+        let dir = std::env::temp_dir().join("pitou-settings-test");
+        std::fs::remove_dir_all(&dir).ok();
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("settings.json");
+        tokio::runtime::Runtime::new().unwrap().block_on(async {
+            assert!(super::read(&path).await == SavedSettings::default());
+            assert!(!dir.join("settings.json.bak").exists());
+
+            std::fs::write(&path, "{ half written").unwrap();
+            assert!(super::read(&path).await == SavedSettings::default());
+            assert!(!path.exists());
+            let aside = std::fs::read_to_string(dir.join("settings.json.bak")).unwrap();
+            assert_eq!(aside, "{ half written");
+        });
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
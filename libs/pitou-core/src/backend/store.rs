@@ -0,0 +1,64 @@
+//! Writing the files the backend keeps its state in.
+
+use std::path::Path;
+
+use tokio::io::AsyncWriteExt;
+
+/// Replaces `path` with `contents` all at once, so that an interrupted write leaves the old
+/// contents in place.
+pub(super) async fn write_atomic(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    write_with(path, contents, false).await
+}
+
+/// Same as [`write_atomic`], for a file only the user can read as it holds secrets.
+pub(super) async fn write_private(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    write_with(path, contents, true).await
+}
+
+async fn write_with(path: &Path, contents: &[u8], private: bool) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    let partial = path.with_extension("partial");
+    let mut options = tokio::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    if private {
+        options.mode(0o600);
+    }
+    #[cfg(not(unix))]
+    let _ = private;
+    let mut file = options.open(&partial).await?;
+    file.write_all(contents).await?;
+    file.sync_all().await?;
+    drop(file);
+    tokio::fs::rename(partial, path).await
+}
+
+#[cfg(test)]
+mod test_mod {
+    use super::{write_atomic, write_private};
+
+    #[test]
+    fn test_write_atomic() {
+        let dir = std::env::temp_dir().join("pitou-store-test");
+        std::fs::remove_dir_all(&dir).ok();
+        let path = dir.join("state.json");
+        tokio::runtime::Runtime::new().unwrap().block_on(async {
+            write_atomic(&path, b"first").await.unwrap();
+            write_atomic(&path, b"second").await.unwrap();
+            assert_eq!(std::fs::read(&path).unwrap(), b"second");
+            assert!(!path.with_extension("partial").exists());
+
+            let secret = dir.join("secret.json");
+            write_private(&secret, b"key").await.unwrap();
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                let mode = std::fs::metadata(&secret).unwrap().permissions().mode();
+                assert_eq!(mode & 0o777, 0o600);
+            }
+        });
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,19 @@
+use crate::{themes::NamedTheme, PitouFilePath};
+
+/// Writes `theme` to `<name>.pitou-theme.json` in the downloads folder so that it can be shared,
+/// returning where it went.
+pub async fn export(theme: NamedTheme) -> Option<PitouFilePath> {
+    let name = theme
+        .name
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c => c,
+        })
+        .collect::<String>();
+    let path = dirs::download_dir()
+        .or_else(dirs::home_dir)?
+        .join(format!("{name}.pitou-theme.json"));
+    tokio::fs::write(&path, theme.to_json()).await.ok()?;
+    Some(PitouFilePath::from_pathbuf(path))
+}
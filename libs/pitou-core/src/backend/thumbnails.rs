@@ -0,0 +1,253 @@
+use std::{
+    fs::File,
+    io::{BufReader, Write},
+    path::{Path, PathBuf},
+    sync::OnceLock,
+    time::UNIX_EPOCH,
+};
+
+use base64::Engine;
+use image::{DynamicImage, ImageDecoder, ImageReader};
+use md5::{Digest, Md5};
+use tokio::sync::Semaphore;
+
+use crate::{
+    thumbnails::{has_thumbnail, ThumbnailSize},
+    PitouFilePath,
+};
+
+/// Images larger than this are never decoded, they would take too long for a thumbnail.
+const MAX_SOURCE_LEN: u64 = 64 * 1024 * 1024;
+
+/// Failed attempts are recorded under this name in the `fail` folder so they aren't retried
+/// until the image changes.
+const FAIL_DIR: &str = concat!("pitou-", env!("CARGO_PKG_VERSION"));
+
+/// The root of the freedesktop thumbnail cache, usually `~/.cache/thumbnails`.
+fn cache_root() -> Option<PathBuf> {
+    dirs::cache_dir().map(|dir| dir.join("thumbnails"))
+}
+
+/// The `file://` URI of `path`, percent-encoded as the thumbnail spec expects.
+fn file_uri(path: &Path) -> String {
+    let mut uri = String::from("file://");
+    for &byte in path.as_os_str().as_encoded_bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                uri.push(byte as char)
+            }
+            _ => uri.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    uri
+}
+
+/// The file name of the thumbnail of `uri` in any of the cache folders.
+fn cache_name(uri: &str) -> String {
+    let digest = Md5::digest(uri.as_bytes());
+    let mut name = digest
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect::<String>();
+    name.push_str(".png");
+    name
+}
+
+/// Whether the thumbnail at `thumb` was made of the current version of the image, judged by
+/// the modification time recorded in it.
+fn is_fresh(thumb: &Path, mtime: u64) -> bool {
+    let Ok(file) = File::open(thumb) else {
+        return false;
+    };
+    let Ok(reader) = png::Decoder::new(BufReader::new(file)).read_info() else {
+        return false;
+    };
+    let fresh = reader
+        .info()
+        .uncompressed_latin1_text
+        .iter()
+        .any(|chunk| chunk.keyword == "Thumb::MTime" && chunk.text == mtime.to_string());
+    fresh
+}
+
+fn decode(source: &Path) -> Option<DynamicImage> {
+    let mut decoder = ImageReader::open(source)
+        .ok()?
+        .with_guessed_format()
+        .ok()?
+        .into_decoder()
+        .ok()?;
+    let orientation = decoder.orientation().ok();
+    let mut image = DynamicImage::from_decoder(decoder).ok()?;
+    if let Some(orientation) = orientation {
+        image.apply_orientation(orientation);
+    }
+    Some(image)
+}
+
+/// Encodes `image` as a PNG carrying the attributes the thumbnail spec asks for.
+fn encode(image: &DynamicImage, uri: &str, mtime: u64, size: u64) -> Option<Vec<u8>> {
+    let image = image.to_rgba8();
+    let mut bytes = Vec::new();
+    let mut encoder = png::Encoder::new(&mut bytes, image.width(), image.height());
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let attributes = [
+        ("Thumb::URI", uri.to_owned()),
+        ("Thumb::MTime", mtime.to_string()),
+        ("Thumb::Size", size.to_string()),
+        ("Thumb::Image::Width", image.width().to_string()),
+        ("Thumb::Image::Height", image.height().to_string()),
+        ("Software", String::from("pitou")),
+    ];
+    for (keyword, text) in attributes {
+        encoder.add_text_chunk(keyword.to_owned(), text).ok()?;
+    }
+    let mut writer = encoder.write_header().ok()?;
+    writer.write_image_data(image.as_raw()).ok()?;
+    writer.finish().ok()?;
+    Some(bytes)
+}
+
+/// Writes the thumbnail through a temporary file in the same folder, so that other programs
+/// reading the cache never see a partial one. Both are private to the user as the spec requires.
+fn store(dest: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    let dir = dest.parent().unwrap_or(Path::new("."));
+    let mut builder = std::fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+    builder.create(dir)?;
+    let temp = dir.join(format!(
+        ".{}.{}",
+        std::process::id(),
+        dest.file_name().unwrap_or_default().to_string_lossy()
+    ));
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options.open(&temp)?.write_all(bytes)?;
+    std::fs::rename(&temp, dest).inspect_err(|_| {
+        std::fs::remove_file(&temp).ok();
+    })
+}
+
+/// The PNG thumbnail of the image at `source` from the cache under `root`, made and stored first if
+/// the cached one is missing or stale. `None` if the image can't be decoded, which is also remembered.
+fn thumbnail_png(root: &Path, source: &Path, size: ThumbnailSize) -> Option<Vec<u8>> {
+    if source.starts_with(root) {
+        return None;
+    }
+    let metadata = std::fs::metadata(source).ok()?;
+    if !metadata.is_file() || metadata.len() > MAX_SOURCE_LEN {
+        return None;
+    }
+    let mtime = metadata
+        .modified()
+        .ok()?
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let uri = file_uri(&std::path::absolute(source).ok()?);
+    let name = cache_name(&uri);
+    let dest = root.join(size.dir_name()).join(&name);
+    if is_fresh(&dest, mtime) {
+        return std::fs::read(&dest).ok();
+    }
+    let failed = root.join("fail").join(FAIL_DIR).join(&name);
+    if is_fresh(&failed, mtime) {
+        return None;
+    }
+    let Some(image) = decode(source) else {
+        let marker = DynamicImage::new_rgba8(1, 1);
+        if let Some(bytes) = encode(&marker, &uri, mtime, metadata.len()) {
+            store(&failed, &bytes).ok();
+        }
+        return None;
+    };
+    let pixels = size.pixels();
+    let image = if image.width() > pixels || image.height() > pixels {
+        image.thumbnail(pixels, pixels)
+    } else {
+        image
+    };
+    let bytes = encode(&image, &uri, mtime, metadata.len())?;
+    // a cache that can't be written to still gets its thumbnail
+    store(&dest, &bytes).ok();
+    Some(bytes)
+}
+
+/// Bounds the thumbnails being made at once so a folder full of images can't starve everything else.
+fn permits() -> &'static Semaphore {
+    static PERMITS: OnceLock<Semaphore> = OnceLock::new();
+    PERMITS.get_or_init(|| {
+        let threads = std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1);
+        Semaphore::new((threads / 2).max(1))
+    })
+}
+
+/// The thumbnail of the image at `file` as a `data:` URL that can be used as an image source.
+/// Thumbnails are kept in the freedesktop thumbnail cache, shared with other file managers.
+pub async fn thumbnail(file: PitouFilePath, size: ThumbnailSize) -> Option<String> {
+    if !has_thumbnail(file.extension()) {
+        return None;
+    }
+    let root = cache_root()?;
+    let _permit = permits().acquire().await.ok()?;
+    let bytes = tokio::task::spawn_blocking(move || thumbnail_png(&root, &file.path, size))
+        .await
+        .ok()??;
+    let encoded = base64::engine::general_purpose::STANDARD.encode(bytes);
+    Some(format!("data:image/png;base64,{encoded}"))
+}
+
+#[cfg(test)]
+mod test_mod {
+    use super::{cache_name, file_uri, is_fresh, thumbnail_png};
+    use crate::thumbnails::ThumbnailSize;
+
+    #[test]
+    fn test_thumbnail() {
+        assert_eq!(file_uri("/a b/ü.png".as_ref()), "file:///a%20b/%C3%BC.png");
+        // the example given by the thumbnail spec
+        assert_eq!(
+            cache_name("file:///home/jens/photos/me.png"),
+            "c6ee772d9e49320e97ec29a7eb5b1697.png"
+        );
+
+        let dir = std::env::temp_dir().join("pitou-thumbnails-test");
+        let _ = std::fs::remove_dir_all(&dir);
+        let root = dir.join("thumbnails");
+        std::fs::create_dir_all(&dir).unwrap();
+        let source = dir.join("wide.png");
+        image::RgbImage::new(400, 100).save(&source).unwrap();
+        let broken = dir.join("broken.jpg");
+        std::fs::write(&broken, "not an image").unwrap();
+
+        let bytes = thumbnail_png(&root, &source, ThumbnailSize::Normal).unwrap();
+        let thumb = image::load_from_memory(&bytes).unwrap();
+        assert_eq!((thumb.width(), thumb.height()), (128, 32));
+        let name = cache_name(&file_uri(&source));
+        let cached = root.join("normal").join(&name);
+        let mtime = std::fs::metadata(&source)
+            .unwrap()
+            .modified()
+            .unwrap()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        assert!(is_fresh(&cached, mtime));
+        assert!(!is_fresh(&cached, mtime + 1));
+
+        assert!(thumbnail_png(&root, &broken, ThumbnailSize::Large).is_none());
+        assert!(root
+            .join("fail")
+            .join(super::FAIL_DIR)
+            .join(cache_name(&file_uri(&broken)))
+            .exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
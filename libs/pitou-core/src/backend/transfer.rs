@@ -1,7 +1,5 @@
 use std::{
-    fs::File,
-    io::{Read, Seek, SeekFrom, Write},
-    path::PathBuf,
+    io::{Read, Write},
     sync::{Arc, Mutex, OnceLock},
     thread,
     time::Instant,
@@ -12,7 +10,10 @@ use crate::{
     PitouFile, PitouFilePath,
 };
 
-use super::clipboard;
+use super::{
+    clipboard,
+    vfs::{self, FileWriter, Route},
+};
 
 impl TransferState {
     /// Adds the supplied value to the current size. This method automatically checks if the transfer is completed changes the state from Active to Terminated
@@ -46,11 +47,19 @@ impl TransferState {
     }
 }
 
+#[derive(Clone, Copy, PartialEq)]
+enum TransferKind {
+    Copy,
+    Move,
+    Upload,
+    Download,
+}
+
 struct TransferConfig {
     id: TransferSessionID,
     state: Mutex<TransferState>,
     started: Mutex<Instant>,
-    copy: bool,
+    kind: TransferKind,
 }
 
 impl TransferConfig {
@@ -71,12 +80,16 @@ impl TransferConfig {
 
     fn begin_transfer(self: &Arc<Self>, items: Arc<Vec<PitouFile>>, dst: PitouFilePath) {
         let config = self.clone();
-        let copy = self.copy;
+        let copy = self.kind == TransferKind::Copy;
         let _ = thread::spawn(move || {
-            if copy {
-                AllItemsCopySesssion::init(config, items, dst)
+            let res = if copy {
+                AllItemsCopySesssion::init(config.clone(), items, dst)
             } else {
-                AllItemsCopySesssion::init(config, items, dst)
+                AllItemsCopySesssion::init(config.clone(), items, dst)
+            };
+            if res.is_err() {
+                config.state.lock().unwrap().end_init();
+                config.terminate_now();
             }
         });
     }
@@ -86,22 +99,81 @@ impl TransferConfig {
         let id = self.id;
         let time_elapsed = self.started.lock().unwrap().elapsed();
 
-        if self.copy {
-            TransferMsg::Copy {
+        match self.kind {
+            TransferKind::Copy => TransferMsg::Copy {
                 id,
                 state,
                 time_elapsed,
-            }
-        } else {
-            TransferMsg::Move {
+            },
+            TransferKind::Move => TransferMsg::Move {
                 id,
                 state,
                 time_elapsed,
-            }
+            },
+            TransferKind::Upload => TransferMsg::Upload {
+                id,
+                state,
+                time_elapsed,
+            },
+            TransferKind::Download => TransferMsg::Download {
+                id,
+                state,
+                time_elapsed,
+            },
         }
     }
 }
 
+/// A transfer carried out elsewhere, such as a cloud upload, which reports its progress here so
+/// that it shows among the pastes.
+#[derive(Clone)]
+pub struct TransferProgress(Arc<TransferConfig>);
+
+impl TransferProgress {
+    pub fn upload() -> Self {
+        Self(add_new_session(TransferKind::Upload))
+    }
+
+    pub fn download() -> Self {
+        Self(add_new_session(TransferKind::Download))
+    }
+
+    /// A transfer that isn't listed among the pastes, such as one a mounted storage makes to
+    /// read or write a file.
+    pub fn untracked() -> Self {
+        Self(Arc::new(TransferConfig {
+            id: generate_id(),
+            state: Mutex::new(TransferState::Initializing(0)),
+            started: Mutex::new(Instant::now()),
+            kind: TransferKind::Copy,
+        }))
+    }
+
+    pub fn id(&self) -> TransferSessionID {
+        self.0.id
+    }
+
+    /// Counts `bytes` more to transfer, while the transfer is still being sized up.
+    pub fn add_total(&self, bytes: u64) {
+        self.0.state.lock().unwrap().append_total(bytes)
+    }
+
+    /// Ends the sizing up, after which the time taken is counted.
+    pub fn start(&self) {
+        self.0.state.lock().unwrap().end_init();
+        self.0.start_now()
+    }
+
+    pub fn advance(&self, bytes: u64) {
+        self.0.state.lock().unwrap().append_current(bytes)
+    }
+
+    pub fn finish(&self) {
+        self.0.state.lock().unwrap().end_init();
+        self.0.terminate_now()
+    }
+}
+
 const TRANSFER_BUFFER_SIZE: usize = 1024;
 const HYPOTHETICAL_FOLDER_SIZE: u64 = 1;
 type CONFIGURATIONS = Mutex<Vec<Arc<TransferConfig>>>;
@@ -111,12 +183,12 @@ fn get_sessions() -> &'static CONFIGURATIONS {
     SESSIONS.get_or_init(|| Mutex::new(Vec::new()))
 }
 
-fn add_new_session(copy: bool) -> Arc<TransferConfig> {
+fn add_new_session(kind: TransferKind) -> Arc<TransferConfig> {
     let config = Arc::new(TransferConfig {
         id: generate_id(),
         state: Mutex::new(TransferState::Initializing(0)),
         started: Mutex::new(Instant::now()),
-        copy,
+        kind,
     });
     get_sessions().lock().unwrap().push(config.clone());
     config
@@ -132,13 +204,13 @@ fn generate_id() -> TransferSessionID {
     }
 }
 
-fn dst_temp(src: &PathBuf, dst: &PathBuf) -> PathBuf {
-    let name = src.file_name().unwrap();
+fn dst_temp(src: &Route, dst: &Route) -> Route {
+    let name = src.path.file_name().unwrap();
     dst.join(format!(".{}", name.to_str().unwrap()))
 }
 
-fn dst_real(src: &PathBuf, dst: &PathBuf) -> PathBuf {
-    let name = src.file_name().unwrap();
+fn dst_real(src: &Route, dst: &Route) -> Route {
+    let name = src.path.file_name().unwrap();
     dst.join(name)
 }
 
@@ -187,12 +259,12 @@ pub async fn paste_items(dst: PitouFilePath) -> Option<TransferSessionID> {
         None => None,
         Some(v) => match v {
             clipboard::ClipboardItem::Copied(items) => {
-                let config = add_new_session(true);
+                let config = add_new_session(TransferKind::Copy);
                 config.begin_transfer(items, dst);
                 Some(config.id)
             }
             clipboard::ClipboardItem::Cut(items) => {
-                let config = add_new_session(false);
+                let config = add_new_session(TransferKind::Move);
                 config.begin_transfer(items, dst);
                 Some(config.id)
             }
@@ -202,6 +274,8 @@ pub async fn paste_items(dst: PitouFilePath) -> Option<TransferSessionID> {
 
 #[cfg(test)]
 mod test_mod {
+    use std::path::PathBuf;
+
     use crate::PitouFileSize;
 
     use super::*;
@@ -246,55 +320,62 @@ mod test_mod {
 
 struct AllItemsCopySesssion {
     config: Arc<TransferConfig>,
-    items: Vec<Arc<PathBuf>>,
-    dst: Arc<PathBuf>,
+    items: Vec<(Route, bool)>,
+    dst: Route,
 }
 
 impl AllItemsCopySesssion {
-    fn init(config: Arc<TransferConfig>, items: Arc<Vec<PitouFile>>, dst: PitouFilePath) {
+    /// Sizes up the items and starts copying them, failing when an item or the destination
+    /// can't be reached, such as when its filesystem is no longer mounted.
+    fn init(
+        config: Arc<TransferConfig>,
+        items: Arc<Vec<PitouFile>>,
+        dst: PitouFilePath,
+    ) -> std::io::Result<()> {
+        let dst = vfs::route(&dst.path)?;
+        let routes = items
+            .iter()
+            .map(|item| vfs::route(&item.path.path))
+            .collect::<std::io::Result<Vec<_>>>()?;
         let config2 = config.clone();
         let items = std::thread::scope(move |s| {
-            let mut handles = Vec::with_capacity(items.len());
-            for item in items.iter() {
+            let mut handles = Vec::with_capacity(routes.len());
+            for route in routes {
                 let config = config.clone();
-                let path = item.path.path.clone();
-                let shc = s.spawn(move || Self::compute_size(path, config));
+                let shc = s.spawn(move || Self::compute_size(route, config));
                 handles.push(shc)
             }
             handles
                 .into_iter()
-                .map(|v| Arc::new(v.join().unwrap()))
-                .collect::<Vec<_>>()
-        });
+                .map(|v| {
+                    v.join()
+                        .unwrap_or_else(|_| Err(std::io::ErrorKind::Other.into()))
+                })
+                .collect::<std::io::Result<Vec<_>>>()
+        })?;
         let config = config2;
         config.state.lock().unwrap().end_init();
-        let session = Self {
-            config,
-            items,
-            dst: Arc::new(dst.path),
-        };
+        let session = Self { config, items, dst };
 
         session.config.start_now();
 
         session.proceed();
+        Ok(())
     }
 
     fn proceed(self) {
         let Self { config, items, dst } = self;
         let mut handles = Vec::with_capacity(items.len());
-        for item in items {
+        for (item, is_dir) in items {
             let config = config.clone();
             let dst = dst.clone();
             let hdl = std::thread::spawn(move || {
-                if item.is_dir() {
-                    CopyFolderSession::new(config, (*item).clone(), dst)
-                        .proceed()
-                        .unwrap();
+                if is_dir {
+                    CopyFolderSession::new(config, item, dst).proceed().ok();
                 } else {
-                    CopyFileSession::new(config, (*item).clone(), dst)
-                        .unwrap()
-                        .proceed()
-                        .unwrap();
+                    CopyFileSession::new(config, item, dst)
+                        .and_then(|session| session.proceed())
+                        .ok();
                 }
             });
             handles.push(hdl);
@@ -302,38 +383,42 @@ impl AllItemsCopySesssion {
 
         thread::spawn(move || {
             for handle in handles {
-                handle.join().unwrap();
+                handle.join().ok();
             }
             config.terminate_now();
         });
     }
 
-    fn compute_size(item: PathBuf, config: Arc<TransferConfig>) -> PathBuf {
+    /// Counts the size of `item` in the transfer, returning it along with whether it is copied
+    /// as a folder.
+    fn compute_size(item: Route, config: Arc<TransferConfig>) -> std::io::Result<(Route, bool)> {
         let mut size = 0;
-        let q = std::fs::metadata(&item).unwrap();
-        if q.is_dir() {
+        let file = item.fs.file(&item.path)?;
+        let is_dir = file.opens_as_dir();
+        if is_dir {
             size += HYPOTHETICAL_FOLDER_SIZE;
-            let mut rd = std::fs::read_dir(&item).unwrap();
-            while let Some(de) = rd.next() {
-                let entry = de.unwrap();
-                Self::compute_size(entry.path(), config.clone());
+            for child in item.fs.read_dir(&item.path)? {
+                let Some(name) = child.path.path.file_name() else {
+                    continue;
+                };
+                Self::compute_size(item.join(name), config.clone())?;
             }
         } else {
-            size += q.len();
+            size += file.metadata.map(|m| m.size.bytes).unwrap_or_default();
         }
         config.state.lock().unwrap().append_total(size);
-        item
+        Ok((item, is_dir))
     }
 }
 
 struct CopyFolderSession {
     config: Arc<TransferConfig>,
-    src_folder: PathBuf,
-    dst_folder: Arc<PathBuf>,
+    src_folder: Route,
+    dst_folder: Route,
 }
 
 impl CopyFolderSession {
-    fn new(config: Arc<TransferConfig>, src_folder: PathBuf, dst_folder: Arc<PathBuf>) -> Self {
+    fn new(config: Arc<TransferConfig>, src_folder: Route, dst_folder: Route) -> Self {
         Self {
             config,
             src_folder,
@@ -348,12 +433,15 @@ impl CopyFolderSession {
             dst_folder,
         } = self;
 
-        let mut rd = std::fs::read_dir(&src_folder)?;
-        let dst_folder = Arc::new(dst_real(&src_folder, &dst_folder));
-        std::fs::create_dir(&*dst_folder)?;
-        while let Some(en) = rd.next() {
-            let elem = en?.path();
-            if elem.is_dir() {
+        let children = src_folder.fs.read_dir(&src_folder.path)?;
+        let dst_folder = dst_real(&src_folder, &dst_folder);
+        dst_folder.fs.create_dir(&dst_folder.path)?;
+        for child in children {
+            let Some(name) = child.path.path.file_name() else {
+                continue;
+            };
+            let elem = src_folder.join(name);
+            if child.opens_as_dir() {
                 Self::new(config.clone(), elem, dst_folder.clone()).proceed()?;
             } else {
                 CopyFileSession::new(config.clone(), elem, dst_folder.clone())?.proceed()?;
@@ -369,41 +457,41 @@ impl CopyFolderSession {
 }
 
 struct CopyFileSession {
-    src_file: File,
-    dst_file: File,
-    temp_dst_path: PathBuf,
-    real_dst_path: PathBuf,
-    seek_ptr: u64,
+    src_file: Box<dyn Read + Send>,
+    dst_file: Box<dyn FileWriter>,
+    temp_dst: Route,
+    real_dst: Route,
     config: Arc<TransferConfig>,
 }
 
 impl CopyFileSession {
-    fn new(config: Arc<TransferConfig>, src: PathBuf, dst: Arc<PathBuf>) -> std::io::Result<Self> {
-        let temp_dst_path = dst_temp(&src, &dst);
-        let real_dst_path = dst_real(&src, &dst);
-        let src_file = File::open(&src)?;
-        let dst_file = File::create(&temp_dst_path)?;
+    fn new(config: Arc<TransferConfig>, src: Route, dst: Route) -> std::io::Result<Self> {
+        let temp_dst = dst_temp(&src, &dst);
+        let real_dst = dst_real(&src, &dst);
+        let src_file = src.fs.open(&src.path)?;
+        let dst_file = temp_dst.fs.create(&temp_dst.path)?;
 
         Ok(Self {
             src_file,
-            temp_dst_path,
             dst_file,
-            seek_ptr: 0,
+            temp_dst,
+            real_dst,
             config,
-            real_dst_path,
         })
     }
 
-    fn proceed(&mut self) -> Result<(), std::io::Error> {
-        self.src_file.seek(SeekFrom::Start(self.seek_ptr))?;
+    fn proceed(mut self) -> Result<(), std::io::Error> {
         let mut buffer = vec![0; TRANSFER_BUFFER_SIZE];
-        while self.seek_ptr < self.src_file.metadata()?.len() {
+        loop {
             let cnt = self.src_file.read(&mut buffer)?;
+            if cnt == 0 {
+                break;
+            }
             self.dst_file.write_all(&buffer[..cnt])?;
-            self.seek_ptr += cnt as u64;
-            self.src_file.seek(SeekFrom::Start(self.seek_ptr))?;
             self.config.state.lock().unwrap().append_current(cnt as u64);
         }
-        std::fs::rename(&self.temp_dst_path, &*self.real_dst_path)
+        self.dst_file.finish()?;
+        let fs = &self.temp_dst.fs;
+        fs.rename(&self.temp_dst.path, &self.real_dst.path)
     }
 }
@@ -0,0 +1,354 @@
+use std::{
+    ffi::{OsStr, OsString},
+    path::{Path, PathBuf},
+};
+
+use ::trash::{os_limited, TrashItem, TrashItemSize};
+use chrono::DateTime;
+
+use crate::{
+    trash::{RestoreConflict, RestoreError, TrashRetention, TrashSummary},
+    PitouDateTime, PitouFilePath, PitouFileSize, PitouTrashItem, PitouTrashItemMetadata,
+};
+
+pub fn items() -> Option<Vec<PitouTrashItem>> {
+    os_limited::list()
+        .map(|v| v.into_iter().filter_map(|u| u.try_into().ok()).collect())
+        .ok()
+}
+
+/// The trashed items with the given ids, skipping ids that are no longer in the trash.
+fn listed(ids: &[String]) -> Result<Vec<TrashItem>, ::trash::Error> {
+    let items = os_limited::list()?
+        .into_iter()
+        .filter(|item| ids.iter().any(|id| *id == item.id.to_string_lossy()))
+        .collect();
+    Ok(items)
+}
+
+/// Restores the items with the given ids to where they were deleted from, or into `destination` if given,
+/// creating the folder they go into if it no longer exists. Returns the items that couldn't be restored.
+pub fn restore(
+    ids: Vec<String>,
+    conflict: RestoreConflict,
+    destination: Option<PitouFilePath>,
+) -> Vec<RestoreError> {
+    let items = match listed(&ids) {
+        Ok(items) => items,
+        Err(err) => {
+            let message = err.to_string();
+            return ids
+                .into_iter()
+                .map(|id| RestoreError {
+                    id,
+                    path: PitouFilePath::from_pathbuf(PathBuf::new()),
+                    occupied: false,
+                    message: message.clone(),
+                })
+                .collect();
+        }
+    };
+    items
+        .into_iter()
+        .filter_map(|mut item| {
+            if let Some(destination) = &destination {
+                item.original_parent = destination.path.clone();
+            }
+            restore_one(item, conflict).err()
+        })
+        .collect()
+}
+
+fn restore_one(mut item: TrashItem, conflict: RestoreConflict) -> Result<(), RestoreError> {
+    let id = item.id.to_string_lossy().into_owned();
+    let target = item.original_parent.join(&item.name);
+    let error = |path: PathBuf, occupied, message: String| RestoreError {
+        id: id.clone(),
+        path: PitouFilePath::from_pathbuf(path),
+        occupied,
+        message,
+    };
+
+    if target.symlink_metadata().is_ok() {
+        match conflict {
+            RestoreConflict::Skip => {
+                let message = format!("{} already exists", target.display());
+                return Err(error(target, true, message));
+            }
+            RestoreConflict::Replace => {
+                ::trash::delete(&target).map_err(|e| error(target.clone(), true, e.to_string()))?
+            }
+            RestoreConflict::KeepBoth => {
+                let name = Path::new(&item.name).as_os_str();
+                let name = unique_name(&item.original_parent, name, is_folder(&item));
+                item.name = name.to_string_lossy().into_owned().into();
+            }
+        }
+    }
+
+    let target = item.original_parent.join(&item.name);
+    std::fs::create_dir_all(&item.original_parent)
+        .map_err(|e| error(target.clone(), false, e.to_string()))?;
+    os_limited::restore_all([item]).map_err(|e| error(target, false, e.to_string()))
+}
+
+/// The first of "name (2).ext", "name (3).ext" and so on that is free in `dir`.
+/// Folders are numbered at the end of their whole name.
+pub(super) fn unique_name(dir: &Path, name: &OsStr, is_dir: bool) -> OsString {
+    let path = Path::new(name);
+    let (stem, extension) = match (path.file_stem(), path.extension()) {
+        (Some(stem), Some(extension)) if !is_dir => (stem, Some(extension)),
+        _ => (name, None),
+    };
+    (2..)
+        .map(|n| {
+            let mut candidate = stem.to_owned();
+            candidate.push(format!(" ({n})"));
+            if let Some(extension) = extension {
+                candidate.push(".");
+                candidate.push(extension);
+            }
+            candidate
+        })
+        .find(|candidate| dir.join(candidate).symlink_metadata().is_err())
+        .unwrap_or_else(|| name.to_owned())
+}
+
+/// Permanently deletes the items with the given ids.
+pub fn purge(ids: Vec<String>) -> Result<(), ::trash::Error> {
+    os_limited::purge_all(listed(&ids)?)
+}
+
+/// Permanently deletes everything in the trash.
+pub fn empty() -> Result<(), ::trash::Error> {
+    os_limited::purge_all(os_limited::list()?)
+}
+
+pub fn summary() -> Option<TrashSummary> {
+    let items = os_limited::list().ok()?;
+    let mut summary = TrashSummary::default();
+    for item in &items {
+        summary.items += 1;
+        if is_folder(item) {
+            summary.folders += 1;
+        }
+        summary.bytes += trashed_bytes(item);
+    }
+    Some(summary)
+}
+
+fn is_folder(item: &TrashItem) -> bool {
+    matches!(
+        os_limited::metadata(item).map(|m| m.size),
+        Ok(TrashItemSize::Entries(_))
+    )
+}
+
+/// The size of a trashed item, counting the contents of folders where the platform says where they are kept.
+fn trashed_bytes(item: &TrashItem) -> u64 {
+    match os_limited::metadata(item).map(|m| m.size) {
+        Ok(TrashItemSize::Bytes(bytes)) => bytes,
+        Ok(TrashItemSize::Entries(_)) => trashed_folder(item)
+            .and_then(|dir| fs_extra::dir::get_size(dir).ok())
+            .unwrap_or(0),
+        Err(_) => 0,
+    }
+}
+
+/// Where a trashed folder is kept. The id of a freedesktop trash item is its `info/NAME.trashinfo` file,
+/// and the folder itself is `files/NAME` beside it.
+#[cfg(all(
+    unix,
+    not(target_os = "macos"),
+    not(target_os = "ios"),
+    not(target_os = "android")
+))]
+fn trashed_folder(item: &TrashItem) -> Option<PathBuf> {
+    let info = Path::new(&item.id);
+    let trash = info.parent()?.parent()?;
+    Some(trash.join("files").join(info.file_stem()?))
+}
+
+#[cfg(not(all(
+    unix,
+    not(target_os = "macos"),
+    not(target_os = "ios"),
+    not(target_os = "android")
+)))]
+fn trashed_folder(_: &TrashItem) -> Option<PathBuf> {
+    None
+}
+
+fn retention_path() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("pitou").join("trash_retention.json"))
+}
+
+/// The saved retention limits. A missing or unreadable store reads as unlimited.
+pub async fn retention() -> TrashRetention {
+    let Some(path) = retention_path() else {
+        return TrashRetention::default();
+    };
+    tokio::fs::read(path)
+        .await
+        .ok()
+        .and_then(|contents| serde_json::from_slice(&contents).ok())
+        .unwrap_or_default()
+}
+
+/// Saves `retention` and applies it straight away.
+pub async fn set_retention(retention: TrashRetention) -> std::io::Result<usize> {
+    let path = retention_path().ok_or(std::io::ErrorKind::NotFound)?;
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    tokio::fs::write(path, serde_json::to_vec_pretty(&retention)?).await?;
+    Ok(apply_retention().await)
+}
+
+/// Purges whatever the saved retention limits are past. Returns how many items were purged.
+pub async fn apply_retention() -> usize {
+    let retention = retention().await;
+    if retention.is_unlimited() {
+        return 0;
+    }
+    tokio::task::spawn_blocking(move || {
+        let Ok(items) = os_limited::list() else {
+            return 0;
+        };
+        let measured = items
+            .iter()
+            .map(|item| (item.time_deleted, trashed_bytes(item)))
+            .collect::<Vec<_>>();
+        let now = chrono::Utc::now().timestamp();
+        let beyond = beyond_retention(retention, &measured, now);
+        let count = beyond.len();
+        let mut items = items.into_iter().map(Some).collect::<Vec<_>>();
+        let purged = beyond.into_iter().filter_map(|idx| items[idx].take());
+        match os_limited::purge_all(purged) {
+            Ok(()) => count,
+            Err(_) => 0,
+        }
+    })
+    .await
+    .unwrap_or(0)
+}
+
+/// The positions in `items`, each given as when it was deleted in seconds since the epoch and its size,
+/// of the items that `retention` purges at `now`.
+fn beyond_retention(retention: TrashRetention, items: &[(i64, u64)], now: i64) -> Vec<usize> {
+    let mut oldest_first = (0..items.len()).collect::<Vec<_>>();
+    oldest_first.sort_by_key(|&idx| items[idx].0);
+
+    let (mut beyond, kept): (Vec<_>, Vec<_>) = match retention.max_age_days {
+        Some(days) => {
+            let cutoff = now - i64::from(days) * 86_400;
+            oldest_first
+                .into_iter()
+                .partition(|&idx| items[idx].0 < cutoff)
+        }
+        None => (Vec::new(), oldest_first),
+    };
+
+    if let Some(max_bytes) = retention.max_bytes {
+        let mut total = kept.iter().map(|&idx| items[idx].1).sum::<u64>();
+        for idx in kept {
+            if total <= max_bytes {
+                break;
+            }
+            total -= items[idx].1;
+            beyond.push(idx);
+        }
+    }
+    beyond
+}
+
+impl TryFrom<TrashItem> for PitouTrashItem {
+    type Error = ::trash::Error;
+    fn try_from(item: TrashItem) -> Result<Self, Self::Error> {
+        let (size, is_dir) = match os_limited::metadata(&item)?.size {
+            TrashItemSize::Bytes(val) => (val, false),
+            TrashItemSize::Entries(val) => (val as u64, true),
+        };
+
+        let TrashItem {
+            id,
+            name,
+            mut original_parent,
+            time_deleted,
+        } = item;
+
+        original_parent.push(&name);
+
+        let metadata = PitouTrashItemMetadata {
+            id: id.to_string_lossy().into_owned(),
+            deleted: PitouDateTime {
+                datetime: DateTime::from_timestamp(time_deleted, 0)
+                    .unwrap_or_default()
+                    .naive_utc(),
+            },
+            is_dir,
+            size: PitouFileSize::new(size),
+        };
+
+        Ok(PitouTrashItem {
+            original_path: PitouFilePath::from_pathbuf(original_parent),
+            metadata,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test_mod {
+    use crate::trash::TrashRetention;
+
+    use super::{beyond_retention, unique_name};
+
+    #[test]
+    fn test_unique_name() {
+        let dir = std::env::temp_dir().join("pitou-trash-test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("photos")).unwrap();
+        std::fs::write(dir.join("notes.txt"), "").unwrap();
+        std::fs::write(dir.join("notes (2).txt"), "").unwrap();
+
+        assert_eq!(
+            unique_name(&dir, "notes.txt".as_ref(), false),
+            "notes (3).txt"
+        );
+        assert_eq!(unique_name(&dir, "photos".as_ref(), true), "photos (2)");
+        assert_eq!(unique_name(&dir, ".bashrc".as_ref(), false), ".bashrc (2)");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_beyond_retention() {
+        const DAY: i64 = 86_400;
+        let now = 100 * DAY;
+        let items = [
+            (now - 40 * DAY, 10),
+            (now - DAY, 50),
+            (now - 5 * DAY, 30),
+            (now - 31 * DAY, 5),
+        ];
+
+        let by_age = TrashRetention {
+            max_age_days: Some(30),
+            max_bytes: None,
+        };
+        assert_eq!(beyond_retention(by_age, &items, now), [0, 3]);
+
+        let by_size = TrashRetention {
+            max_age_days: None,
+            max_bytes: Some(60),
+        };
+        assert_eq!(beyond_retention(by_size, &items, now), [0, 3, 2]);
+
+        let both = TrashRetention {
+            max_age_days: Some(30),
+            max_bytes: Some(50),
+        };
+        assert_eq!(beyond_retention(both, &items, now), [0, 3, 2]);
+        assert!(beyond_retention(TrashRetention::default(), &items, now).is_empty());
+    }
+}
@@ -0,0 +1,35 @@
+//! Helpers for the urls the backend reads paths out of, such as bookmark files and the answers
+//! of WebDAV servers.
+
+/// Decodes the `%XX` escapes of a url path, failing on a malformed escape or on bytes that
+/// aren't UTF-8.
+pub(super) fn percent_decode(encoded: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(encoded.len());
+    let mut rest = encoded.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        if byte == b'%' {
+            let hex = std::str::from_utf8(tail.get(..2)?).ok()?;
+            bytes.push(u8::from_str_radix(hex, 16).ok()?);
+            rest = &tail[2..];
+        } else {
+            bytes.push(byte);
+            rest = tail;
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+#[cfg(test)]
+mod test_mod {
+    use super::percent_decode;
+
+    #[test]
+    fn test_percent_decode() {
+        assert_eq!(percent_decode("My%20Music").as_deref(), Some("My Music"));
+        assert_eq!(percent_decode("caf%C3%A9").as_deref(), Some("café"));
+        assert_eq!(percent_decode("plain").as_deref(), Some("plain"));
+        assert_eq!(percent_decode("bad%2"), None);
+        assert_eq!(percent_decode("bad%zz"), None);
+        assert_eq!(percent_decode("%FF"), None);
+    }
+}
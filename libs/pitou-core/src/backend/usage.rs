@@ -0,0 +1,346 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, HashSet},
+    path::{Path, PathBuf},
+    sync::{atomic::Ordering, Arc, Mutex, OnceLock},
+    time::SystemTime,
+};
+
+use session::UsageSession;
+
+use crate::{
+    msg::UsageMsg,
+    usage::{LargeFile, TypeUsage, UsageNode, UsageReport},
+    PitouFilePath,
+};
+
+/// How many of the largest files a scan remembers.
+const LARGEST_LEN: usize = 100;
+
+mod session {
+    use std::sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex, OnceLock,
+    };
+
+    use crate::msg::{UsageMsg, UsageProgress};
+
+    #[derive(Default)]
+    pub(super) struct UsageSession {
+        terminated: AtomicBool,
+        pub(super) files: AtomicU64,
+        pub(super) dirs: AtomicU64,
+        pub(super) bytes: AtomicU64,
+    }
+
+    impl UsageSession {
+        pub(super) fn is_terminated(&self) -> bool {
+            self.terminated.load(Ordering::Acquire)
+        }
+
+        pub(super) fn terminate(&self) {
+            self.terminated.store(true, Ordering::Release)
+        }
+
+        pub(super) fn read(&self) -> UsageMsg {
+            let progress = UsageProgress {
+                files: self.files.load(Ordering::Acquire),
+                dirs: self.dirs.load(Ordering::Acquire),
+                bytes: self.bytes.load(Ordering::Acquire),
+            };
+            if self.is_terminated() {
+                UsageMsg::Terminated(progress)
+            } else {
+                UsageMsg::Active(progress)
+            }
+        }
+    }
+
+    static SESSION: OnceLock<Mutex<Option<Arc<UsageSession>>>> = OnceLock::new();
+
+    pub(super) fn get_session() -> Option<Arc<UsageSession>> {
+        SESSION
+            .get_or_init(|| Mutex::new(None))
+            .lock()
+            .ok()
+            .and_then(|current| current.clone())
+    }
+
+    /// Replaces the current session with a fresh one, terminating the previous scan if it is still running.
+    pub(super) fn configure_session() -> Arc<UsageSession> {
+        let session = Arc::new(UsageSession::default());
+        if let Ok(mut current) = SESSION.get_or_init(|| Mutex::new(None)).lock() {
+            if let Some(previous) = current.replace(session.clone()) {
+                previous.terminate();
+            }
+        }
+        session
+    }
+}
+
+/// A directory of a scanned tree. Its `size` and `files` are recursive once the scan is complete,
+/// `own_size` and `own_files` only count the files it holds directly.
+struct Directory {
+    path: PathBuf,
+    children: Vec<usize>,
+    own_size: u64,
+    own_files: u64,
+    size: u64,
+    files: u64,
+}
+
+/// The result of a scan. Directories are stored in the order they were found,
+/// so every directory comes before its subdirectories.
+struct UsageTree {
+    directories: Vec<Directory>,
+    index: HashMap<PathBuf, usize>,
+    largest: Vec<(u64, PathBuf)>,
+    by_type: HashMap<String, (u64, u64)>,
+    scanned_at: SystemTime,
+}
+
+type Cache = Mutex<HashMap<PathBuf, Arc<UsageTree>>>;
+
+static CACHE: OnceLock<Cache> = OnceLock::new();
+
+fn cache() -> &'static Cache {
+    CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+#[cfg(unix)]
+fn file_id(metadata: &std::fs::Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    // only files with several links can be met twice
+    (metadata.is_dir() || metadata.nlink() > 1).then(|| (metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(unix))]
+fn file_id(_metadata: &std::fs::Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+#[cfg(unix)]
+fn device_of(metadata: &std::fs::Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    Some(metadata.dev())
+}
+
+#[cfg(not(unix))]
+fn device_of(_metadata: &std::fs::Metadata) -> Option<u64> {
+    None
+}
+
+/// Scans the tree under `root`, staying on its filesystem and never following symlinks.
+/// Hard linked files are only counted once. Returns `None` if the session is terminated midway.
+fn scan(root: &Path, session: &UsageSession) -> Option<UsageTree> {
+    let device = std::fs::metadata(root).ok().and_then(|m| device_of(&m));
+    let mut tree = UsageTree {
+        directories: Vec::new(),
+        index: HashMap::new(),
+        largest: Vec::new(),
+        by_type: HashMap::new(),
+        scanned_at: SystemTime::now(),
+    };
+    let mut largest = BinaryHeap::new();
+    let mut seen = HashSet::new();
+    let mut pending: Vec<(PathBuf, Option<usize>)> = vec![(root.to_path_buf(), None)];
+    while let Some((path, parent)) = pending.pop() {
+        if session.is_terminated() {
+            return None;
+        }
+        let idx = tree.directories.len();
+        if let Some(parent) = parent {
+            tree.directories[parent].children.push(idx);
+        }
+        let mut directory = Directory {
+            path,
+            children: Vec::new(),
+            own_size: 0,
+            own_files: 0,
+            size: 0,
+            files: 0,
+        };
+        session.dirs.fetch_add(1, Ordering::Relaxed);
+        for entry in std::fs::read_dir(&directory.path).into_iter().flatten().flatten() {
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            if file_id(&metadata).is_some_and(|id| !seen.insert(id)) {
+                continue;
+            }
+            if metadata.is_dir() {
+                if device.is_none() || device_of(&metadata) == device {
+                    pending.push((entry.path(), Some(idx)));
+                }
+            } else if metadata.is_file() {
+                let size = metadata.len();
+                directory.own_size += size;
+                directory.own_files += 1;
+                session.files.fetch_add(1, Ordering::Relaxed);
+                session.bytes.fetch_add(size, Ordering::Relaxed);
+                let extension = entry
+                    .path()
+                    .extension()
+                    .map(|e| e.to_string_lossy().to_lowercase())
+                    .unwrap_or_default();
+                let by_type = tree.by_type.entry(extension).or_default();
+                by_type.0 += size;
+                by_type.1 += 1;
+                largest.push(Reverse((size, entry.path())));
+                if largest.len() > LARGEST_LEN {
+                    largest.pop();
+                }
+            }
+        }
+        tree.index.insert(directory.path.clone(), idx);
+        tree.directories.push(directory);
+    }
+    // subdirectories always come after their parent, so a reverse pass totals them bottom-up
+    for idx in (0..tree.directories.len()).rev() {
+        let (size, files) = tree.directories[idx]
+            .children
+            .iter()
+            .map(|&c| (tree.directories[c].size, tree.directories[c].files))
+            .fold((0, 0), |(s, f), (cs, cf)| (s + cs, f + cf));
+        let directory = &mut tree.directories[idx];
+        directory.size = directory.own_size + size;
+        directory.files = directory.own_files + files;
+    }
+    tree.largest = largest.into_sorted_vec().into_iter().map(|v| v.0).collect();
+    Some(tree)
+}
+
+impl UsageTree {
+    fn node(&self, idx: usize, depth: u32) -> UsageNode {
+        let directory = &self.directories[idx];
+        let mut children = Vec::new();
+        if depth > 0 {
+            children.extend(directory.children.iter().map(|&c| self.node(c, depth - 1)));
+            if directory.own_files > 0 {
+                children.push(UsageNode {
+                    name: String::from("(files)"),
+                    path: PitouFilePath::from_pathbuf(directory.path.clone()),
+                    size: directory.own_size,
+                    files: directory.own_files,
+                    is_dir: false,
+                    children: Vec::new(),
+                });
+            }
+            children.sort_unstable_by_key(|c| Reverse(c.size));
+        }
+        let path = PitouFilePath::from_pathbuf(directory.path.clone());
+        UsageNode {
+            name: path.name().to_owned(),
+            path,
+            size: directory.size,
+            files: directory.files,
+            is_dir: true,
+            children,
+        }
+    }
+
+    fn report(&self) -> UsageReport {
+        let root = &self.directories[0];
+        let mut by_type = self
+            .by_type
+            .iter()
+            .map(|(extension, &(size, count))| TypeUsage {
+                extension: extension.clone(),
+                size,
+                count,
+            })
+            .collect::<Vec<_>>();
+        by_type.sort_unstable_by_key(|t| Reverse(t.size));
+        UsageReport {
+            root: PitouFilePath::from_pathbuf(root.path.clone()),
+            size: root.size,
+            files: root.files,
+            dirs: self.directories.len() as u64,
+            largest: self
+                .largest
+                .iter()
+                .map(|(size, path)| LargeFile {
+                    path: PitouFilePath::from_pathbuf(path.clone()),
+                    size: *size,
+                })
+                .collect(),
+            by_type,
+            scanned_at: self.scanned_at.into(),
+        }
+    }
+}
+
+/// Starts scanning `root` in the background, its progress is read through [`read_usage`].
+/// The finished scan replaces whatever was cached for `root`.
+pub async fn scan_usage(root: PitouFilePath) {
+    let session = session::configure_session();
+    tokio::task::spawn_blocking(move || {
+        if let Some(tree) = scan(&root.path, &session) {
+            if let Ok(mut cache) = cache().lock() {
+                cache.insert(root.path, Arc::new(tree));
+            }
+        }
+        session.terminate();
+    });
+}
+
+pub async fn read_usage() -> UsageMsg {
+    match session::get_session() {
+        Some(session) => session.read(),
+        None => UsageMsg::Terminated(Default::default()),
+    }
+}
+
+pub async fn terminate_usage() {
+    if let Some(session) = session::get_session() {
+        session.terminate()
+    }
+}
+
+/// The usage of the directory at `path`, expanded `depth` levels down, from the most recent cached scan covering it.
+pub fn usage_tree(path: PitouFilePath, depth: u32) -> Option<UsageNode> {
+    let cache = cache().lock().ok()?;
+    cache
+        .values()
+        .filter_map(|tree| tree.index.get(&path.path).map(|&idx| (tree, idx)))
+        .max_by_key(|(tree, _)| tree.scanned_at)
+        .map(|(tree, idx)| tree.node(idx, depth))
+}
+
+/// The report of the cached scan of `root`, if it has been scanned.
+pub fn usage_report(root: PitouFilePath) -> Option<UsageReport> {
+    let cache = cache().lock().ok()?;
+    cache.get(&root.path).map(|tree| tree.report())
+}
+
+#[cfg(test)]
+mod test_mod {
+    use super::{scan, session::UsageSession};
+
+    #[test]
+    fn test_scan() {
+        let root = std::env::temp_dir().join("pitou-usage-test");
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("a/b")).unwrap();
+        std::fs::write(root.join("top.txt"), [0; 10]).unwrap();
+        std::fs::write(root.join("a/mid.rs"), [0; 100]).unwrap();
+        std::fs::write(root.join("a/b/deep.rs"), [0; 1000]).unwrap();
+        std::fs::write(root.join("a/b/noext"), [0; 1]).unwrap();
+
+        let tree = scan(&root, &UsageSession::default()).unwrap();
+        let node = tree.node(0, 1);
+        assert_eq!((node.size, node.files), (1111, 4));
+        assert_eq!(node.children[0].name, "a");
+        assert_eq!(node.children[0].size, 1101);
+        assert!(node.children[0].children.is_empty());
+        assert_eq!((node.children[1].size, node.children[1].is_dir), (10, false));
+
+        let report = tree.report();
+        assert_eq!(report.dirs, 3);
+        assert_eq!(report.largest[0].size, 1000);
+        assert_eq!(report.largest.len(), 4);
+        assert_eq!(report.by_type[0].extension, "rs");
+        assert_eq!((report.by_type[0].size, report.by_type[0].count), (1100, 2));
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
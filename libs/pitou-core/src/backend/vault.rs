@@ -0,0 +1,841 @@
+use std::{
+    collections::BTreeSet,
+    ffi::OsStr,
+    fs::File,
+    io::{BufReader, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex, MutexGuard, PoisonError,
+    },
+    time::{Duration, Instant},
+};
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chacha20poly1305::{
+    aead::{rand_core::RngCore, Aead, KeyInit, OsRng, Payload},
+    Key, XChaCha20Poly1305, XNonce,
+};
+use serde::{Deserialize, Serialize};
+use sysinfo::{Pid, ProcessRefreshKind, System};
+use zeroize::Zeroizing;
+
+use crate::{
+    vault::{VaultEntry, VaultError, VaultStatus, MIN_PASSPHRASE_LEN},
+    PitouDateTime, PitouFilePath,
+};
+
+const KEY_LEN: usize = 32;
+const NONCE_LEN: usize = 24;
+const TAG_LEN: usize = 16;
+/// The random part of the nonces of a file, which are followed by the index of the chunk and
+/// whether it is the last one.
+const PREFIX_LEN: usize = NONCE_LEN - 5;
+/// How much of a file is sealed under each nonce, so that files never have to fit in memory.
+const CHUNK_LEN: usize = 64 * 1024;
+/// Sealed into the header, where opening it tells whether a passphrase is right.
+const CHECK: &[u8] = b"pitou vault";
+/// Starts the name of each folder files are decrypted into for opening.
+const OPENED_PREFIX: &str = "pitou-vault-";
+
+type SecretKey = Zeroizing<[u8; KEY_LEN]>;
+
+impl From<std::io::Error> for VaultError {
+    fn from(_: std::io::Error) -> Self {
+        Self::Io
+    }
+}
+
+/// The costs of Argon2id, saved with the vault so that they can be raised for new vaults
+/// without locking anyone out of an older one.
+#[derive(Clone, Copy, Serialize, Deserialize)]
+struct KdfParams {
+    memory_kib: u32,
+    iterations: u32,
+    parallelism: u32,
+}
+
+impl KdfParams {
+    /// The costs OWASP recommends for Argon2id.
+    const DEFAULT: Self = Self {
+        memory_kib: 19 * 1024,
+        iterations: 2,
+        parallelism: 1,
+    };
+
+    fn derive(self, passphrase: &str, salt: &[u8]) -> Result<SecretKey, VaultError> {
+        let params = Params::new(
+            self.memory_kib,
+            self.iterations,
+            self.parallelism,
+            Some(KEY_LEN),
+        )
+        .map_err(|_| VaultError::Damaged)?;
+        let mut key = Zeroizing::new([0; KEY_LEN]);
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into(passphrase.as_bytes(), salt, key.as_mut())
+            .map_err(|_| VaultError::Damaged)?;
+        Ok(key)
+    }
+}
+
+/// `vault.json`, the one file of the vault that isn't encrypted.
+#[derive(Serialize, Deserialize)]
+struct Header {
+    version: u32,
+    /// base64, as is `check`
+    salt: String,
+    kdf: KdfParams,
+    check: String,
+    auto_lock_minutes: u32,
+}
+
+/// `$XDG_DATA_HOME/pitou/vault`, holding the header, the encrypted index and a file for each entry.
+fn vault_dir() -> Result<PathBuf, VaultError> {
+    let dir = dirs::data_dir().ok_or(VaultError::Io)?;
+    Ok(dir.join("pitou").join("vault"))
+}
+
+fn header_path() -> Result<PathBuf, VaultError> {
+    Ok(vault_dir()?.join("vault.json"))
+}
+
+fn index_path() -> Result<PathBuf, VaultError> {
+    Ok(vault_dir()?.join("index"))
+}
+
+fn item_path(id: &str) -> Result<PathBuf, VaultError> {
+    Ok(vault_dir()?.join("items").join(id))
+}
+
+fn read_header() -> Result<Header, VaultError> {
+    let contents = match std::fs::read(header_path()?) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Err(VaultError::NotCreated),
+        Err(e) => return Err(e.into()),
+    };
+    serde_json::from_slice(&contents).map_err(|_| VaultError::Damaged)
+}
+
+/// Replaces `path` with `contents` all at once, so that an interrupted write leaves the old
+/// contents in place.
+fn write_atomic(path: &Path, contents: &[u8]) -> Result<(), VaultError> {
+    let partial = path.with_extension("partial");
+    std::fs::write(&partial, contents)?;
+    std::fs::rename(partial, path)?;
+    Ok(())
+}
+
+fn write_header(header: &Header) -> Result<(), VaultError> {
+    let contents = serde_json::to_vec_pretty(header).map_err(|_| VaultError::Io)?;
+    write_atomic(&header_path()?, &contents)
+}
+
+fn random_id() -> String {
+    let mut bytes = [0u8; 16];
+    OsRng.fill_bytes(&mut bytes);
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// Seals `plaintext` under a random nonce, which is put before it.
+fn seal(cipher: &XChaCha20Poly1305, plaintext: &[u8], aad: &[u8]) -> Result<Vec<u8>, VaultError> {
+    let mut nonce = [0; NONCE_LEN];
+    OsRng.fill_bytes(&mut nonce);
+    let payload = Payload {
+        msg: plaintext,
+        aad,
+    };
+    let sealed = cipher
+        .encrypt(XNonce::from_slice(&nonce), payload)
+        .map_err(|_| VaultError::Io)?;
+    Ok([&nonce[..], &sealed].concat())
+}
+
+fn unseal(cipher: &XChaCha20Poly1305, sealed: &[u8], aad: &[u8]) -> Result<Vec<u8>, VaultError> {
+    if sealed.len() < NONCE_LEN {
+        return Err(VaultError::Damaged);
+    }
+    let (nonce, msg) = sealed.split_at(NONCE_LEN);
+    cipher
+        .decrypt(XNonce::from_slice(nonce), Payload { msg, aad })
+        .map_err(|_| VaultError::Damaged)
+}
+
+fn chunk_nonce(prefix: &[u8; PREFIX_LEN], idx: u32, last: bool) -> XNonce {
+    let mut nonce = [0; NONCE_LEN];
+    nonce[..PREFIX_LEN].copy_from_slice(prefix);
+    nonce[PREFIX_LEN..NONCE_LEN - 1].copy_from_slice(&idx.to_be_bytes());
+    nonce[NONCE_LEN - 1] = last as u8;
+    *XNonce::from_slice(&nonce)
+}
+
+/// Reads until `buf` is full or the reader runs out, returning how much was read.
+fn read_full(reader: &mut impl Read, buf: &mut [u8]) -> std::io::Result<usize> {
+    let mut len = 0;
+    while len < buf.len() {
+        match reader.read(&mut buf[len..]) {
+            Ok(0) => break,
+            Ok(read) => len += read,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => (),
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(len)
+}
+
+/// Encrypts `reader` into `writer` a chunk at a time. The nonce of each chunk holds its index and
+/// whether it is the last one, so chunks can't be reordered, dropped or cut off without the file
+/// failing to decrypt. A file that ends on a chunk boundary gets an empty last chunk.
+fn encrypt_stream(
+    cipher: &XChaCha20Poly1305,
+    id: &str,
+    mut reader: impl Read,
+    mut writer: impl Write,
+) -> Result<(), VaultError> {
+    let mut prefix = [0; PREFIX_LEN];
+    OsRng.fill_bytes(&mut prefix);
+    writer.write_all(&prefix)?;
+    let mut buf = Zeroizing::new(vec![0; CHUNK_LEN]);
+    for idx in 0..=u32::MAX {
+        let len = read_full(&mut reader, &mut buf)?;
+        let last = len < CHUNK_LEN;
+        let payload = Payload {
+            msg: &buf[..len],
+            aad: id.as_bytes(),
+        };
+        let sealed = cipher
+            .encrypt(&chunk_nonce(&prefix, idx, last), payload)
+            .map_err(|_| VaultError::Io)?;
+        writer.write_all(&sealed)?;
+        if last {
+            writer.flush()?;
+            return Ok(());
+        }
+    }
+    Err(VaultError::Io)
+}
+
+fn decrypt_stream(
+    cipher: &XChaCha20Poly1305,
+    id: &str,
+    mut reader: impl Read,
+    mut writer: impl Write,
+) -> Result<(), VaultError> {
+    let mut prefix = [0; PREFIX_LEN];
+    reader
+        .read_exact(&mut prefix)
+        .map_err(|_| VaultError::Damaged)?;
+    let mut buf = vec![0; CHUNK_LEN + TAG_LEN];
+    for idx in 0..=u32::MAX {
+        let len = read_full(&mut reader, &mut buf)?;
+        let last = len < buf.len();
+        let payload = Payload {
+            msg: &buf[..len],
+            aad: id.as_bytes(),
+        };
+        let plaintext = cipher
+            .decrypt(&chunk_nonce(&prefix, idx, last), payload)
+            .map(Zeroizing::new)
+            .map_err(|_| VaultError::Damaged)?;
+        writer.write_all(&plaintext)?;
+        if last {
+            writer.flush()?;
+            return Ok(());
+        }
+    }
+    Err(VaultError::Damaged)
+}
+
+fn read_index(cipher: &XChaCha20Poly1305) -> Result<Vec<VaultEntry>, VaultError> {
+    let sealed = std::fs::read(index_path()?)?;
+    let contents = Zeroizing::new(unseal(cipher, &sealed, b"index")?);
+    serde_json::from_slice(&contents).map_err(|_| VaultError::Damaged)
+}
+
+fn write_index(cipher: &XChaCha20Poly1305, entries: &[VaultEntry]) -> Result<(), VaultError> {
+    let contents = Zeroizing::new(serde_json::to_vec(entries).map_err(|_| VaultError::Io)?);
+    write_atomic(&index_path()?, &seal(cipher, &contents, b"index")?)
+}
+
+/// Held while the index is read, changed and written back, so that changes don't undo each other.
+fn index_guard() -> MutexGuard<'static, ()> {
+    static INDEX: Mutex<()> = Mutex::new(());
+    INDEX.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// The key of the unlocked vault, along with what goes away when it locks.
+struct Session {
+    key: SecretKey,
+    last_used: Instant,
+    /// `None` while the vault stays unlocked however long it goes unused
+    auto_lock: Option<Duration>,
+    /// the folders files were decrypted into to be opened
+    opened: Vec<PathBuf>,
+    /// tells the watcher of an earlier session that it is no longer needed
+    generation: u64,
+}
+
+impl Session {
+    fn is_idle(&self) -> bool {
+        self.auto_lock
+            .is_some_and(|limit| self.last_used.elapsed() >= limit)
+    }
+}
+
+fn session() -> MutexGuard<'static, Option<Session>> {
+    static SESSION: Mutex<Option<Session>> = Mutex::new(None);
+    SESSION.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn auto_lock(minutes: u32) -> Option<Duration> {
+    (minutes > 0).then(|| Duration::from_secs(minutes as u64 * 60))
+}
+
+/// Forgets the key and removes the files decrypted for opening.
+fn close(session: &mut Option<Session>) {
+    if let Some(closed) = session.take() {
+        for dir in closed.opened {
+            std::fs::remove_dir_all(dir).ok();
+        }
+    }
+}
+
+/// A cipher for the key of the unlocked vault, unless it has been idle long enough to lock.
+/// Asking for it counts as using the vault.
+fn cipher() -> Result<XChaCha20Poly1305, VaultError> {
+    let mut current = session();
+    if current.as_ref().is_some_and(Session::is_idle) {
+        close(&mut current);
+    }
+    let unlocked = current.as_mut().ok_or(VaultError::Locked)?;
+    unlocked.last_used = Instant::now();
+    Ok(XChaCha20Poly1305::new(Key::from_slice(&unlocked.key[..])))
+}
+
+/// Starts a session for `key` along with a task that locks the vault once it has gone unused
+/// for longer than the header allows, even if nothing asks for it in the meantime.
+fn start_session(key: SecretKey, auto_lock_minutes: u32) {
+    static GENERATION: AtomicU64 = AtomicU64::new(0);
+    let generation = GENERATION.fetch_add(1, Ordering::Relaxed);
+    {
+        let mut current = session();
+        close(&mut current);
+        *current = Some(Session {
+            key,
+            last_used: Instant::now(),
+            auto_lock: auto_lock(auto_lock_minutes),
+            opened: Vec::new(),
+            generation,
+        });
+    }
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(Duration::from_secs(10)).await;
+            let mut current = session();
+            match current.as_ref() {
+                Some(unlocked) if unlocked.generation == generation => {
+                    if unlocked.is_idle() {
+                        close(&mut current);
+                        break;
+                    }
+                }
+                _ => break,
+            }
+        }
+    });
+}
+
+async fn blocking<T: Send + 'static>(
+    task: impl FnOnce() -> Result<T, VaultError> + Send + 'static,
+) -> Result<T, VaultError> {
+    tokio::task::spawn_blocking(task)
+        .await
+        .unwrap_or(Err(VaultError::Io))
+}
+
+pub async fn status() -> VaultStatus {
+    let header = blocking(read_header).await;
+    let mut current = session();
+    if current.as_ref().is_some_and(Session::is_idle) {
+        close(&mut current);
+    }
+    VaultStatus {
+        created: header.is_ok(),
+        unlocked: current.is_some(),
+        auto_lock_minutes: header.map_or(5, |header| header.auto_lock_minutes),
+    }
+}
+
+/// Makes an empty vault locked with `passphrase`, which is left unlocked.
+pub async fn create(passphrase: String) -> Result<(), VaultError> {
+    let passphrase = Zeroizing::new(passphrase);
+    if passphrase.chars().count() < MIN_PASSPHRASE_LEN {
+        return Err(VaultError::WeakPassphrase);
+    }
+    let key = blocking(move || {
+        match read_header() {
+            Err(VaultError::NotCreated) => (),
+            Err(e) => return Err(e),
+            Ok(_) => return Err(VaultError::AlreadyCreated),
+        }
+        let mut salt = [0; 16];
+        OsRng.fill_bytes(&mut salt);
+        let kdf = KdfParams::DEFAULT;
+        let key = kdf.derive(&passphrase, &salt)?;
+        let cipher = XChaCha20Poly1305::new(Key::from_slice(&key[..]));
+        std::fs::create_dir_all(vault_dir()?.join("items"))?;
+        write_index(&cipher, &[])?;
+        write_header(&Header {
+            version: 1,
+            salt: STANDARD.encode(salt),
+            kdf,
+            check: STANDARD.encode(seal(&cipher, CHECK, b"check")?),
+            auto_lock_minutes: 5,
+        })?;
+        Ok(key)
+    })
+    .await?;
+    start_session(key, 5);
+    Ok(())
+}
+
+pub async fn unlock(passphrase: String) -> Result<(), VaultError> {
+    let passphrase = Zeroizing::new(passphrase);
+    let (key, minutes) = blocking(move || {
+        let header = read_header()?;
+        let salt = STANDARD
+            .decode(&header.salt)
+            .map_err(|_| VaultError::Damaged)?;
+        let check = STANDARD
+            .decode(&header.check)
+            .map_err(|_| VaultError::Damaged)?;
+        let key = header.kdf.derive(&passphrase, &salt)?;
+        let cipher = XChaCha20Poly1305::new(Key::from_slice(&key[..]));
+        match unseal(&cipher, &check, b"check") {
+            Ok(check) if check == CHECK => Ok((key, header.auto_lock_minutes)),
+            _ => Err(VaultError::WrongPassphrase),
+        }
+    })
+    .await?;
+    start_session(key, minutes);
+    Ok(())
+}
+
+/// Forgets the key and removes whatever was decrypted for opening. Called as pitou exits too.
+pub fn lock() {
+    close(&mut session())
+}
+
+/// Sets how long the vault may go unused before it locks itself, `0` being never.
+pub async fn set_auto_lock(minutes: u32) -> Result<(), VaultError> {
+    blocking(move || {
+        let mut header = read_header()?;
+        header.auto_lock_minutes = minutes;
+        write_header(&header)
+    })
+    .await?;
+    if let Some(unlocked) = session().as_mut() {
+        unlocked.auto_lock = auto_lock(minutes);
+    }
+    Ok(())
+}
+
+pub async fn entries() -> Result<Vec<VaultEntry>, VaultError> {
+    let cipher = cipher()?;
+    blocking(move || read_index(&cipher)).await
+}
+
+/// The files of `path` with the names they are kept under, which for a folder are their paths
+/// below it, starting with the name of the folder. Links and special files are left out, and
+/// stay where they are when the folder is moved in.
+fn files_of(path: &Path) -> std::io::Result<Vec<(String, PathBuf)>> {
+    let metadata = path.symlink_metadata()?;
+    let name = path
+        .file_name()
+        .map(OsStr::to_string_lossy)
+        .unwrap_or_default();
+    if metadata.is_file() {
+        return Ok(vec![(name.into_owned(), path.to_path_buf())]);
+    }
+    let mut files = Vec::new();
+    if metadata.is_dir() {
+        for entry in std::fs::read_dir(path)? {
+            for (inner, file) in files_of(&entry?.path())? {
+                files.push((format!("{name}/{inner}"), file));
+            }
+        }
+    }
+    Ok(files)
+}
+
+fn encrypt_file(
+    cipher: &XChaCha20Poly1305,
+    name: String,
+    path: PathBuf,
+) -> Result<VaultEntry, VaultError> {
+    let id = random_id();
+    let source = File::open(&path)?;
+    let metadata = source.metadata()?;
+    let destination = item_path(&id)?;
+    let written = File::create(&destination)
+        .map_err(VaultError::from)
+        .and_then(|file| encrypt_stream(cipher, &id, BufReader::new(source), BufWriter::new(file)));
+    if let Err(e) = written {
+        std::fs::remove_file(destination).ok();
+        return Err(e);
+    }
+    Ok(VaultEntry {
+        id,
+        name,
+        size: metadata.len(),
+        modified: metadata.modified().ok().map(PitouDateTime::from),
+        added: PitouDateTime::from(std::time::SystemTime::now()),
+        origin: PitouFilePath::from_pathbuf(path),
+    })
+}
+
+/// Moves `items` into the vault, returning how many were moved. An item's files are only removed
+/// once all of them are encrypted and listed, and an item that fails leaves nothing behind in the vault.
+pub async fn add(items: Vec<PitouFilePath>) -> Result<usize, VaultError> {
+    let cipher = cipher()?;
+    blocking(move || {
+        let _guard = index_guard();
+        let mut entries = read_index(&cipher)?;
+        let mut moved = Vec::new();
+        for item in items {
+            let Ok(files) = files_of(&item.path) else {
+                continue;
+            };
+            let mut added = Vec::new();
+            let complete = files.into_iter().all(|(name, path)| {
+                encrypt_file(&cipher, name, path)
+                    .map(|entry| added.push(entry))
+                    .is_ok()
+            });
+            if complete {
+                let files = added
+                    .iter()
+                    .map(|entry| entry.origin.path.clone())
+                    .collect::<Vec<_>>();
+                entries.append(&mut added);
+                moved.push((item, files));
+            } else {
+                for entry in added {
+                    std::fs::remove_file(item_path(&entry.id)?).ok();
+                }
+            }
+        }
+        write_index(&cipher, &entries)?;
+        for (item, files) in &moved {
+            remove_moved(&item.path, files);
+        }
+        Ok(moved.len())
+    })
+    .await
+}
+
+/// Removes the `files` of `item` once they are in the vault, then the folders they leave empty.
+/// Whatever the vault didn't take, such as links and empty folders, is left in place.
+fn remove_moved(item: &Path, files: &[PathBuf]) {
+    let mut dirs = BTreeSet::new();
+    for file in files {
+        std::fs::remove_file(file).ok();
+        let parents = file.ancestors().skip(1);
+        dirs.extend(
+            parents
+                .take_while(|dir| dir.starts_with(item))
+                .map(Path::to_path_buf),
+        );
+    }
+    // subfolders sort after their parents, and have to go first for the parents to empty
+    for dir in dirs.iter().rev() {
+        std::fs::remove_dir(dir).ok();
+    }
+}
+
+/// Creates `path`, readable and writable by its owner only.
+fn create_private_file(path: &Path) -> std::io::Result<File> {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options.open(path)
+}
+
+/// Tells a running pitou apart from any other: its pid, along with when it started so that the
+/// pid of one that is gone isn't mistaken for a later process given the same pid.
+fn owner_tag(pid: u32) -> Option<String> {
+    let pid = Pid::from_u32(pid);
+    let mut system = System::new();
+    system.refresh_process_specifics(pid, ProcessRefreshKind::new());
+    let started = system.process(pid)?.start_time();
+    Some(format!("{pid}-{started}"))
+}
+
+/// Creates a folder only its owner can enter in the temporary folder, named after this pitou so
+/// that another one doesn't take it for left behind.
+fn create_private_dir() -> std::io::Result<PathBuf> {
+    let owner = owner_tag(std::process::id()).ok_or(std::io::ErrorKind::NotFound)?;
+    let name = format!("{OPENED_PREFIX}{owner}-{}", random_id());
+    let dir = std::env::temp_dir().join(name);
+    let mut builder = std::fs::DirBuilder::new();
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+    builder.create(&dir)?;
+    Ok(dir)
+}
+
+/// Decrypts the entry into a private temporary folder and opens it with its default application.
+/// The decrypted copy is removed when the vault locks, so changes made to it aren't kept.
+pub async fn open(id: String) -> Result<(), VaultError> {
+    let cipher = cipher()?;
+    let (dir, path) = blocking(move || {
+        let entries = read_index(&cipher)?;
+        let entry = entries
+            .iter()
+            .find(|entry| entry.id == id)
+            .ok_or(VaultError::Missing)?;
+        let dir = create_private_dir()?;
+        let path = dir.join(entry.as_file().name());
+        let decrypted = File::open(item_path(&entry.id)?)
+            .map_err(VaultError::from)
+            .and_then(|source| {
+                let file = create_private_file(&path)?;
+                decrypt_stream(
+                    &cipher,
+                    &entry.id,
+                    BufReader::new(source),
+                    BufWriter::new(file),
+                )
+            });
+        match decrypted {
+            Ok(()) => Ok((dir, path)),
+            Err(e) => {
+                std::fs::remove_dir_all(dir).ok();
+                Err(e)
+            }
+        }
+    })
+    .await?;
+    match session().as_mut() {
+        Some(unlocked) => unlocked.opened.push(dir),
+        // locked while decrypting
+        None => {
+            std::fs::remove_dir_all(dir).ok();
+            return Err(VaultError::Locked);
+        }
+    }
+    open::that_detached(path)?;
+    Ok(())
+}
+
+/// Decrypts the entries with the given ids back to where they were moved in from and takes them
+/// out of the vault, returning how many were restored. A file in the way is kept and the entry
+/// restored beside it under a numbered name.
+pub async fn restore(ids: Vec<String>) -> Result<usize, VaultError> {
+    let cipher = cipher()?;
+    blocking(move || {
+        let _guard = index_guard();
+        let entries = read_index(&cipher)?;
+        let (chosen, mut kept): (Vec<_>, Vec<_>) = entries
+            .into_iter()
+            .partition(|entry| ids.contains(&entry.id));
+        let mut restored = 0;
+        for entry in chosen {
+            match restore_entry(&cipher, &entry) {
+                Ok(()) => restored += 1,
+                Err(_) => kept.push(entry),
+            }
+        }
+        write_index(&cipher, &kept)?;
+        Ok(restored)
+    })
+    .await
+}
+
+fn restore_entry(cipher: &XChaCha20Poly1305, entry: &VaultEntry) -> Result<(), VaultError> {
+    let origin = &entry.origin.path;
+    let (dir, name) = match (origin.parent(), origin.file_name()) {
+        (Some(dir), Some(name)) => (dir, name),
+        _ => return Err(VaultError::Missing),
+    };
+    std::fs::create_dir_all(dir)?;
+    let destination = match origin.symlink_metadata() {
+        Ok(_) => dir.join(super::trash::unique_name(dir, name, false)),
+        Err(_) => origin.clone(),
+    };
+    let source = File::open(item_path(&entry.id)?)?;
+    let file = std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&destination)?;
+    if let Err(e) = decrypt_stream(
+        cipher,
+        &entry.id,
+        BufReader::new(source),
+        BufWriter::new(file),
+    ) {
+        std::fs::remove_file(&destination).ok();
+        return Err(e);
+    }
+    if let Some(modified) = entry.modified {
+        let time = filetime::FileTime::from_unix_time(
+            modified.timestamp_millis().div_euclid(1000),
+            (modified.timestamp_millis().rem_euclid(1000) * 1_000_000) as u32,
+        );
+        filetime::set_file_mtime(&destination, time).ok();
+    }
+    std::fs::remove_file(item_path(&entry.id)?).ok();
+    Ok(())
+}
+
+/// Permanently deletes the entries with the given ids.
+pub async fn delete(ids: Vec<String>) -> Result<(), VaultError> {
+    let cipher = cipher()?;
+    blocking(move || {
+        let _guard = index_guard();
+        let mut entries = read_index(&cipher)?;
+        entries.retain(|entry| !ids.contains(&entry.id));
+        write_index(&cipher, &entries)?;
+        for id in ids {
+            std::fs::remove_file(item_path(&id)?).ok();
+        }
+        Ok(())
+    })
+    .await
+}
+
+/// Removes the decrypted copies left behind by a pitou that didn't get to lock its vault. Those
+/// of a pitou that is still running are left to it.
+pub async fn remove_stale_opened() {
+    let Ok(mut dir) = tokio::fs::read_dir(std::env::temp_dir()).await else {
+        return;
+    };
+    while let Ok(Some(entry)) = dir.next_entry().await {
+        let name = entry.file_name();
+        let Some(rest) = name
+            .to_str()
+            .and_then(|name| name.strip_prefix(OPENED_PREFIX))
+        else {
+            continue;
+        };
+        let stale = match owner_of(rest) {
+            Some((pid, tag)) => owner_tag(pid) != Some(tag),
+            // from a pitou that didn't tag its folders
+            None => true,
+        };
+        if stale {
+            tokio::fs::remove_dir_all(entry.path()).await.ok();
+        }
+    }
+}
+
+/// The pid and the owner tag in the name of a folder made by `create_private_dir`, without the prefix.
+fn owner_of(name: &str) -> Option<(u32, String)> {
+    let mut parts = name.splitn(3, '-');
+    let (pid, started) = (parts.next()?, parts.next()?);
+    parts.next()?;
+    Some((pid.parse().ok()?, format!("{pid}-{started}")))
+}
+
+#[cfg(test)]
+mod test_mod {
+    use chacha20poly1305::{Key, KeyInit, XChaCha20Poly1305};
+
+    use super::{
+        create_private_dir, decrypt_stream, encrypt_stream, files_of, owner_of, owner_tag,
+        remove_moved, remove_stale_opened, CHUNK_LEN, OPENED_PREFIX, PREFIX_LEN, TAG_LEN,
+    };
+    use crate::vault::VaultError;
+
+    fn roundtrip(cipher: &XChaCha20Poly1305, plaintext: &[u8]) -> Vec<u8> {
+        let mut sealed = Vec::new();
+        encrypt_stream(cipher, "id", plaintext, &mut sealed).unwrap();
+        sealed
+    }
+
+    #[test]
+    fn test_streams() {
+        let cipher = XChaCha20Poly1305::new(Key::from_slice(&[7; 32]));
+        for len in [0, 10, CHUNK_LEN, CHUNK_LEN * 2 + 5] {
+            let plaintext = (0..len).map(|idx| idx as u8).collect::<Vec<_>>();
+            let sealed = roundtrip(&cipher, &plaintext);
+            let chunks = len / CHUNK_LEN + 1;
+            assert_eq!(sealed.len(), PREFIX_LEN + len + chunks * TAG_LEN);
+            let mut opened = Vec::new();
+            decrypt_stream(&cipher, "id", &sealed[..], &mut opened).unwrap();
+            assert_eq!(opened, plaintext);
+
+            // cut short on a chunk boundary
+            let cut = &sealed[..sealed.len() - TAG_LEN - len % CHUNK_LEN];
+            let res = decrypt_stream(&cipher, "id", cut, &mut Vec::new());
+            assert!(res == Err(VaultError::Damaged));
+            // moved to another entry
+            let res = decrypt_stream(&cipher, "other", &sealed[..], &mut Vec::new());
+            assert!(res == Err(VaultError::Damaged));
+        }
+
+        let mut sealed = roundtrip(&cipher, b"secret");
+        sealed[PREFIX_LEN] ^= 1;
+        let res = decrypt_stream(&cipher, "id", &sealed[..], &mut Vec::new());
+        assert!(res == Err(VaultError::Damaged));
+
+        let kdf = super::KdfParams {
+            memory_kib: 64,
+            iterations: 1,
+            parallelism: 1,
+        };
+        let key = kdf.derive("passphrase", b"some salt value").unwrap();
+        assert_eq!(*key, *kdf.derive("passphrase", b"some salt value").unwrap());
+        assert_ne!(*key, *kdf.derive("passphrasf", b"some salt value").unwrap());
+    }
+
+    #[test]
+    fn test_opened_owners() {
+        let ours = create_private_dir().unwrap();
+        let name = ours.file_name().unwrap().to_str().unwrap();
+        let (pid, tag) = owner_of(name.strip_prefix(OPENED_PREFIX).unwrap()).unwrap();
+        assert_eq!(pid, std::process::id());
+        assert_eq!(owner_tag(pid), Some(tag));
+        // past the largest pid any system hands out
+        let gone = std::env::temp_dir().join(format!("{OPENED_PREFIX}4294967295-1-ab"));
+        std::fs::create_dir_all(&gone).unwrap();
+        tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(remove_stale_opened());
+        assert!(ours.is_dir());
+        assert!(!gone.exists());
+        std::fs::remove_dir_all(ours).unwrap();
+    }
+
+    #[test]
+    fn test_remove_moved() {
+        let dir = std::env::temp_dir().join("pitou-moved-test");
+        let _ = std::fs::remove_dir_all(&dir);
+        let item = dir.join("item");
+        std::fs::create_dir_all(item.join("full").join("deeper")).unwrap();
+        std::fs::create_dir_all(item.join("empty")).unwrap();
+        std::fs::write(item.join("top.txt"), "top").unwrap();
+        std::fs::write(item.join("full").join("deeper").join("low.txt"), "low").unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink("top.txt", item.join("link")).unwrap();
+
+        let mut files = files_of(&item).unwrap();
+        files.sort();
+        let names = files
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["item/full/deeper/low.txt", "item/top.txt"]);
+
+        let paths = files.into_iter().map(|(_, path)| path).collect::<Vec<_>>();
+        remove_moved(&item, &paths);
+        assert!(!item.join("top.txt").exists());
+        assert!(!item.join("full").exists());
+        assert!(item.join("empty").is_dir());
+        #[cfg(unix)]
+        assert!(item.join("link").symlink_metadata().is_ok());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
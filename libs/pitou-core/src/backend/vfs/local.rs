@@ -0,0 +1,126 @@
+use std::{
+    fs::File,
+    io::{self, Read},
+    path::{Component, Path, PathBuf},
+};
+
+use crate::{backend::fs_ops::HiddenList, PitouFile};
+
+use super::{FileSystem, FileWriter};
+
+/// The filesystem of this computer, or one of its folders mounted on its own.
+#[derive(Default)]
+pub struct LocalFs {
+    /// the folder paths are taken within, none for the whole filesystem
+    root: Option<PathBuf>,
+}
+
+impl LocalFs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The folder `root`, with paths relative to it. Paths can't lead out of it, not even
+    /// through symlinks, which are only followed to places within the root.
+    pub fn rooted(root: PathBuf) -> Self {
+        Self { root: Some(root) }
+    }
+
+    /// The full path of `path`, or an error if it resolves to somewhere out of the root. With
+    /// `follow`, a symlink at `path` must point within the root too; without it the call acts on
+    /// the link itself, so only the folders leading to it are checked.
+    fn resolve(&self, path: &Path, follow: bool) -> io::Result<PathBuf> {
+        let Some(root) = &self.root else {
+            return Ok(path.to_owned());
+        };
+        let full = path
+            .components()
+            .filter(|component| matches!(component, Component::Normal(_)))
+            .fold(root.clone(), |full, component| full.join(component));
+        if full == *root {
+            return Ok(full);
+        }
+        let resolved = match full.symlink_metadata() {
+            // a link that leads nowhere can't be shown to stay within the root
+            Ok(_) if follow => full.canonicalize()?,
+            _ => full.parent().unwrap_or(root).canonicalize()?,
+        };
+        match resolved.starts_with(root.canonicalize()?) {
+            true => Ok(full),
+            false => Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "the path leads out of the mounted folder",
+            )),
+        }
+    }
+
+    /// The file with its path within the filesystem.
+    fn within(&self, mut file: PitouFile) -> PitouFile {
+        if let Some(root) = &self.root {
+            if let Ok(path) = file.path.path.strip_prefix(root) {
+                file.path.path = path.to_owned()
+            }
+        }
+        file
+    }
+}
+
+impl FileSystem for LocalFs {
+    fn file(&self, path: &Path) -> io::Result<PitouFile> {
+        let path = self.resolve(path, false)?;
+        let metadata = std::fs::symlink_metadata(&path)?;
+        let hidden = HiddenList::of_parent(&path);
+        Ok(self.within(PitouFile::new(path, metadata, &hidden)))
+    }
+
+    /// Items whose metadata can't be read are listed without it.
+    fn read_dir(&self, dir: &Path) -> io::Result<Vec<PitouFile>> {
+        let dir = self.resolve(dir, true)?;
+        let hidden = HiddenList::read(&dir);
+        let mut files = Vec::new();
+        for entry in std::fs::read_dir(&dir)? {
+            let entry = entry?;
+            let file = match entry.metadata() {
+                Ok(metadata) => PitouFile::new(entry.path(), metadata, &hidden),
+                Err(_) => PitouFile::without_metadata(entry.path().into()),
+            };
+            files.push(self.within(file))
+        }
+        Ok(files)
+    }
+
+    fn count(&self, dir: &Path) -> io::Result<u64> {
+        Ok(std::fs::read_dir(self.resolve(dir, true)?)?.count() as u64)
+    }
+
+    fn create_dir(&self, path: &Path) -> io::Result<()> {
+        std::fs::create_dir(self.resolve(path, true)?)
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        std::fs::rename(self.resolve(from, false)?, self.resolve(to, false)?)
+    }
+
+    fn remove(&self, path: &Path) -> io::Result<()> {
+        let path = self.resolve(path, false)?;
+        match std::fs::symlink_metadata(&path)?.is_dir() {
+            true => std::fs::remove_dir_all(path),
+            false => std::fs::remove_file(path),
+        }
+    }
+
+    fn open(&self, path: &Path) -> io::Result<Box<dyn Read + Send>> {
+        Ok(Box::new(File::open(self.resolve(path, true)?)?))
+    }
+
+    fn create(&self, path: &Path) -> io::Result<Box<dyn FileWriter>> {
+        Ok(Box::new(File::create(self.resolve(path, true)?)?))
+    }
+}
+
+/// Written to the disk as it goes, so there is nothing left to do.
+impl FileWriter for File {
+    fn finish(self: Box<Self>) -> io::Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,218 @@
+use std::{
+    collections::BTreeMap,
+    io::{self, Cursor, Read, Write},
+    path::{Component, Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
+
+use crate::{PitouDateTime, PitouFile, PitouFileKind, PitouFileMetadata, PitouFilePath};
+
+use super::{FileSystem, FileWriter};
+
+enum Node {
+    Dir {
+        modified: PitouDateTime,
+    },
+    File {
+        contents: Vec<u8>,
+        modified: PitouDateTime,
+    },
+}
+
+type Nodes = Arc<Mutex<BTreeMap<PathBuf, Node>>>;
+
+/// A filesystem held in memory and lost with it, for scratch space and tests that shouldn't
+/// depend on the disk. Its top folder is the empty path.
+#[derive(Default, Clone)]
+pub struct MemoryFs {
+    nodes: Nodes,
+}
+
+impl MemoryFs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// `path` in the form nodes are kept under, relative and without `.` or `..`.
+fn key(path: &Path) -> io::Result<PathBuf> {
+    let mut key = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => key.push(part),
+            Component::CurDir | Component::RootDir => (),
+            Component::ParentDir | Component::Prefix(_) => {
+                return Err(io::ErrorKind::InvalidInput.into())
+            }
+        }
+    }
+    Ok(key)
+}
+
+fn now() -> PitouDateTime {
+    SystemTime::now().into()
+}
+
+fn is_dir(nodes: &BTreeMap<PathBuf, Node>, path: &Path) -> bool {
+    path.as_os_str().is_empty() || matches!(nodes.get(path), Some(Node::Dir { .. }))
+}
+
+/// Fails unless `path` is free and the folder it goes in is there.
+fn check_free(nodes: &BTreeMap<PathBuf, Node>, path: &Path) -> io::Result<()> {
+    if path.as_os_str().is_empty() || nodes.contains_key(path) {
+        return Err(io::ErrorKind::AlreadyExists.into());
+    }
+    match is_dir(nodes, path.parent().unwrap_or(Path::new(""))) {
+        true => Ok(()),
+        false => Err(io::ErrorKind::NotFound.into()),
+    }
+}
+
+fn file_of(path: PathBuf, node: Option<&Node>) -> PitouFile {
+    let (kind, size, modified) = match node {
+        Some(Node::File { contents, modified }) => {
+            (PitouFileKind::File, contents.len() as u64, Some(*modified))
+        }
+        Some(Node::Dir { modified }) => (PitouFileKind::Directory, 0, Some(*modified)),
+        None => (PitouFileKind::Directory, 0, None),
+    };
+    let hidden = path
+        .file_name()
+        .is_some_and(|name| name.as_encoded_bytes().starts_with(b"."));
+    let metadata = PitouFileMetadata {
+        modified,
+        accessed: None,
+        created: None,
+        size: size.into(),
+        kind,
+        attribute: 0,
+        hidden,
+        link: None,
+    };
+    PitouFile {
+        path: PitouFilePath::from_pathbuf(path),
+        metadata: Some(metadata),
+    }
+}
+
+impl FileSystem for MemoryFs {
+    fn file(&self, path: &Path) -> io::Result<PitouFile> {
+        let path = key(path)?;
+        let nodes = self.nodes.lock().unwrap();
+        match (path.as_os_str().is_empty(), nodes.get(&path)) {
+            (false, None) => Err(io::ErrorKind::NotFound.into()),
+            (_, node) => Ok(file_of(path.clone(), node)),
+        }
+    }
+
+    fn read_dir(&self, dir: &Path) -> io::Result<Vec<PitouFile>> {
+        let dir = key(dir)?;
+        let nodes = self.nodes.lock().unwrap();
+        if !is_dir(&nodes, &dir) {
+            return Err(io::ErrorKind::NotFound.into());
+        }
+        let files = nodes
+            .range(dir.clone()..)
+            .skip_while(|(path, _)| **path == dir)
+            .take_while(|(path, _)| path.starts_with(&dir))
+            .filter(|(path, _)| path.parent() == Some(&dir))
+            .map(|(path, node)| file_of(path.clone(), Some(node)))
+            .collect();
+        Ok(files)
+    }
+
+    fn create_dir(&self, path: &Path) -> io::Result<()> {
+        let path = key(path)?;
+        let mut nodes = self.nodes.lock().unwrap();
+        check_free(&nodes, &path)?;
+        nodes.insert(path, Node::Dir { modified: now() });
+        Ok(())
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        let (from, to) = (key(from)?, key(to)?);
+        let mut nodes = self.nodes.lock().unwrap();
+        if from.as_os_str().is_empty() || !nodes.contains_key(&from) {
+            return Err(io::ErrorKind::NotFound.into());
+        }
+        if to.starts_with(&from) {
+            return Err(io::ErrorKind::InvalidInput.into());
+        }
+        check_free(&nodes, &to)?;
+        let moved = nodes
+            .keys()
+            .filter(|path| path.starts_with(&from))
+            .cloned()
+            .collect::<Vec<_>>();
+        for path in moved {
+            if let Some(node) = nodes.remove(&path) {
+                let rest = path.strip_prefix(&from).unwrap_or(Path::new(""));
+                nodes.insert(to.join(rest), node);
+            }
+        }
+        Ok(())
+    }
+
+    fn remove(&self, path: &Path) -> io::Result<()> {
+        let path = key(path)?;
+        let mut nodes = self.nodes.lock().unwrap();
+        if path.as_os_str().is_empty() || !nodes.contains_key(&path) {
+            return Err(io::ErrorKind::NotFound.into());
+        }
+        nodes.retain(|kept, _| !kept.starts_with(&path));
+        Ok(())
+    }
+
+    fn open(&self, path: &Path) -> io::Result<Box<dyn Read + Send>> {
+        let path = key(path)?;
+        match self.nodes.lock().unwrap().get(&path) {
+            Some(Node::File { contents, .. }) => Ok(Box::new(Cursor::new(contents.clone()))),
+            Some(Node::Dir { .. }) => Err(io::ErrorKind::InvalidInput.into()),
+            None => Err(io::ErrorKind::NotFound.into()),
+        }
+    }
+
+    fn create(&self, path: &Path) -> io::Result<Box<dyn FileWriter>> {
+        let path = key(path)?;
+        let mut nodes = self.nodes.lock().unwrap();
+        if !matches!(nodes.get(&path), Some(Node::File { .. })) {
+            check_free(&nodes, &path)?;
+        }
+        let contents = Vec::new();
+        let modified = now();
+        nodes.insert(path.clone(), Node::File { contents, modified });
+        let nodes = self.nodes.clone();
+        Ok(Box::new(MemoryWriter { nodes, path }))
+    }
+}
+
+/// Appends what is written to a file of a [`MemoryFs`] as it comes.
+struct MemoryWriter {
+    nodes: Nodes,
+    path: PathBuf,
+}
+
+impl Write for MemoryWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self.nodes.lock().unwrap().get_mut(&self.path) {
+            Some(Node::File { contents, modified }) => {
+                contents.extend_from_slice(buf);
+                *modified = now();
+                Ok(buf.len())
+            }
+            // the file was removed or moved away while being written
+            _ => Err(io::ErrorKind::NotFound.into()),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl FileWriter for MemoryWriter {
+    fn finish(self: Box<Self>) -> io::Result<()> {
+        Ok(())
+    }
+}
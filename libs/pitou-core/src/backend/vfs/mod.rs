@@ -0,0 +1,425 @@
+use std::{
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, OnceLock},
+};
+
+use crate::{
+    vfs::{self, Location},
+    PitouFile, PitouFilePath,
+};
+
+mod local;
+mod memory;
+mod remote;
+
+pub use local::LocalFs;
+pub use memory::MemoryFs;
+pub(crate) use remote::RemoteFs;
+
+/// A tree of files and folders the backend works on. Paths are those within the filesystem,
+/// so that the same one can be mounted under any name, and the files it gives back carry them.
+///
+/// Calls block, like those of `std::fs`.
+pub trait FileSystem: Send + Sync {
+    /// The item at `path` itself, links not followed, without the number of items of a folder.
+    fn file(&self, path: &Path) -> io::Result<PitouFile>;
+
+    /// The items right inside `dir`, like [`Self::file`] gives them.
+    fn read_dir(&self, dir: &Path) -> io::Result<Vec<PitouFile>>;
+
+    /// The number of items right inside `dir`.
+    fn count(&self, dir: &Path) -> io::Result<u64> {
+        self.read_dir(dir).map(|items| items.len() as u64)
+    }
+
+    /// Makes the folder `path`, whose parent must already be there.
+    fn create_dir(&self, path: &Path) -> io::Result<()>;
+
+    /// Makes an empty file at `path`, emptying the one already there.
+    fn create_file(&self, path: &Path) -> io::Result<()> {
+        self.create(path)?.finish()
+    }
+
+    /// Moves the item at `from`, along with its contents if it is a folder, to `to`.
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()>;
+
+    /// Removes the item at `path`, along with its contents if it is a folder.
+    fn remove(&self, path: &Path) -> io::Result<()>;
+
+    /// Reads the contents of the file at `path`.
+    fn open(&self, path: &Path) -> io::Result<Box<dyn Read + Send>>;
+
+    /// Writes a file at `path`, replacing the one already there. The file is only sure to hold
+    /// what was written once [`FileWriter::finish`] succeeds, while a writer dropped before that
+    /// may leave the file empty, partly written or not there at all.
+    fn create(&self, path: &Path) -> io::Result<Box<dyn FileWriter>>;
+}
+
+/// A file being written by [`FileSystem::create`].
+pub trait FileWriter: Write + Send {
+    /// Completes the file with what was written, failing if it couldn't be kept. Filesystems that
+    /// only take whole files send it here, once.
+    fn finish(self: Box<Self>) -> io::Result<()>;
+}
+
+type Mounts = Mutex<Vec<(String, Arc<dyn FileSystem>)>>;
+
+static MOUNTS: Mounts = Mutex::new(Vec::new());
+
+fn local() -> Arc<dyn FileSystem> {
+    static LOCAL: OnceLock<Arc<dyn FileSystem>> = OnceLock::new();
+    LOCAL.get_or_init(|| Arc::new(LocalFs::new())).clone()
+}
+
+/// Makes the filesystem reachable at `<name>://`. Fails if the name isn't one a location can
+/// have or is already taken.
+pub fn mount(name: &str, fs: Arc<dyn FileSystem>) -> bool {
+    let mut mounts = MOUNTS.lock().unwrap();
+    if !vfs::is_mount_name(name) || mounts.iter().any(|(mounted, _)| mounted == name) {
+        return false;
+    }
+    mounts.push((name.to_owned(), fs));
+    true
+}
+
+pub fn unmount(name: &str) -> bool {
+    let mut mounts = MOUNTS.lock().unwrap();
+    let before = mounts.len();
+    mounts.retain(|(mounted, _)| mounted != name);
+    mounts.len() != before
+}
+
+pub fn mounts() -> Vec<String> {
+    MOUNTS
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(name, _)| name.clone())
+        .collect()
+}
+
+/// A path resolved to the filesystem it belongs to.
+#[derive(Clone)]
+pub(crate) struct Route {
+    pub(crate) fs: Arc<dyn FileSystem>,
+    /// the path within `fs`
+    pub(crate) path: PathBuf,
+    mount: Option<String>,
+}
+
+impl Route {
+    /// The route of the item named `name` inside this one.
+    pub(crate) fn join(&self, name: impl AsRef<Path>) -> Self {
+        Self {
+            fs: self.fs.clone(),
+            path: self.path.join(name),
+            mount: self.mount.clone(),
+        }
+    }
+
+    /// The full path of the item at `path` within the same filesystem.
+    pub(crate) fn outer(&self, path: &Path) -> PathBuf {
+        match &self.mount {
+            Some(mount) => PitouFilePath::mounted(mount, path).path,
+            None => path.to_owned(),
+        }
+    }
+
+    /// A file from the filesystem, with its full path.
+    pub(crate) fn rebase(&self, mut file: PitouFile) -> PitouFile {
+        if self.mount.is_some() {
+            file.path.path = self.outer(&file.path.path)
+        }
+        file
+    }
+
+    pub(crate) fn is_local(&self) -> bool {
+        self.mount.is_none()
+    }
+}
+
+/// The filesystem `path` belongs to, failing for a mount that isn't there.
+pub(crate) fn route(path: &Path) -> io::Result<Route> {
+    match Location::of(path) {
+        Location::Local(path) => Ok(Route {
+            fs: local(),
+            path: path.to_owned(),
+            mount: None,
+        }),
+        Location::Mounted { mount, path } => {
+            let fs = MOUNTS
+                .lock()
+                .unwrap()
+                .iter()
+                .find(|(mounted, _)| mounted == mount)
+                .map(|(_, fs)| fs.clone())
+                .ok_or(io::ErrorKind::NotFound)?;
+            Ok(Route {
+                fs,
+                path,
+                mount: Some(mount.to_owned()),
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_mod {
+    use std::{
+        io::{Read, Write},
+        path::Path,
+        sync::Arc,
+        time::Duration,
+    };
+
+    use async_trait::async_trait;
+
+    use super::{mount, unmount, FileSystem, LocalFs, MemoryFs, RemoteFs};
+    use crate::{
+        backend::{cloud::CloudProvider, fs_ops, transfer, transfer::TransferProgress},
+        cloud::{CloudEntry, CloudError},
+        msg::TransferState,
+        PitouFile, PitouFileFilter, PitouFilePath,
+    };
+
+    /// A provider keeping its entries in memory, to stand for a remote storage.
+    struct MemoryProvider(MemoryFs);
+
+    #[async_trait]
+    impl CloudProvider for MemoryProvider {
+        async fn list(&self, dir: &str) -> Result<Vec<CloudEntry>, CloudError> {
+            let files = self.0.read_dir(Path::new(dir))?;
+            let entries = files.into_iter().map(|file| {
+                let metadata = file.metadata.unwrap();
+                CloudEntry {
+                    path: file.path.path.to_string_lossy().replace('\\', "/"),
+                    is_dir: metadata.is_dir(),
+                    size: metadata.size.bytes,
+                    modified: metadata.modified,
+                }
+            });
+            Ok(entries.collect())
+        }
+
+        async fn create_dir(&self, path: &str) -> Result<(), CloudError> {
+            self.0
+                .create_dir(Path::new(path))
+                .map_err(|_| CloudError::Exists)
+        }
+
+        async fn upload(
+            &self,
+            local: &Path,
+            path: &str,
+            _: &TransferProgress,
+        ) -> Result<(), CloudError> {
+            let contents = std::fs::read(local)?;
+            let mut writer = self.0.create(Path::new(path))?;
+            writer.write_all(&contents)?;
+            Ok(writer.finish()?)
+        }
+
+        async fn download(
+            &self,
+            path: &str,
+            local: &Path,
+            _: &TransferProgress,
+        ) -> Result<(), CloudError> {
+            let mut contents = Vec::new();
+            self.0.open(Path::new(path))?.read_to_end(&mut contents)?;
+            Ok(std::fs::write(local, contents)?)
+        }
+
+        async fn rename(&self, entry: &CloudEntry, to: &str) -> Result<(), CloudError> {
+            Ok(self.0.rename(Path::new(&entry.path), Path::new(to))?)
+        }
+
+        async fn delete(&self, entry: &CloudEntry) -> Result<(), CloudError> {
+            Ok(self.0.remove(Path::new(&entry.path))?)
+        }
+    }
+
+    #[test]
+    fn test_memory_fs() {
+        let fs = MemoryFs::new();
+        fs.create_dir(Path::new("docs")).unwrap();
+        assert!(fs.create_dir(Path::new("docs")).is_err());
+        assert!(fs.create_dir(Path::new("missing/inner")).is_err());
+        fs.create(Path::new("docs/notes.txt"))
+            .unwrap()
+            .write_all(b"hello")
+            .unwrap();
+        fs.create_file(Path::new("docs/.hidden")).unwrap();
+
+        let items = fs.read_dir(Path::new("docs")).unwrap();
+        let names = items.iter().map(|f| f.name()).collect::<Vec<_>>();
+        assert_eq!(names, [".hidden", "notes.txt"]);
+        assert!(items[0].is_hidden());
+        assert_eq!(fs.count(Path::new("")).unwrap(), 1);
+
+        fs.rename(Path::new("docs"), Path::new("papers")).unwrap();
+        assert!(fs
+            .rename(Path::new("papers"), Path::new("papers/inner"))
+            .is_err());
+        let mut contents = String::new();
+        fs.open(Path::new("papers/notes.txt"))
+            .unwrap()
+            .read_to_string(&mut contents)
+            .unwrap();
+        assert_eq!(contents, "hello");
+        assert!(fs.file(Path::new("docs/notes.txt")).is_err());
+
+        fs.remove(Path::new("papers")).unwrap();
+        assert_eq!(fs.count(Path::new("")).unwrap(), 0);
+        assert!(fs.file(Path::new("../x")).is_err());
+    }
+
+    #[test]
+    fn test_rooted_local_fs() {
+        let root = std::env::temp_dir().join("pitou-vfs-rooted-test");
+        std::fs::remove_dir_all(&root).ok();
+        std::fs::create_dir_all(&root).unwrap();
+        let fs = LocalFs::rooted(root.clone());
+
+        // paths can't lead out of the root
+        fs.create_dir(Path::new("/../inner")).unwrap();
+        assert!(root.join("inner").is_dir());
+        let items = fs.read_dir(Path::new("")).unwrap();
+        assert_eq!(items[0].path.path, Path::new("inner"));
+
+        // nor through symlinks, which only act on themselves
+        #[cfg(unix)]
+        {
+            let outside = std::env::temp_dir().join("pitou-vfs-rooted-outside");
+            std::fs::create_dir_all(&outside).unwrap();
+            std::fs::write(outside.join("secret.txt"), "secret").unwrap();
+            std::os::unix::fs::symlink(&outside, root.join("out")).unwrap();
+            std::os::unix::fs::symlink(outside.join("gone"), root.join("gone")).unwrap();
+            std::os::unix::fs::symlink(root.join("inner"), root.join("within")).unwrap();
+            assert!(fs.read_dir(Path::new("out")).is_err());
+            assert!(fs.open(Path::new("out/secret.txt")).is_err());
+            assert!(fs.create(Path::new("out/new.txt")).is_err());
+            assert!(fs.create(Path::new("gone")).is_err());
+            assert!(!outside.join("gone").exists());
+            assert!(fs.read_dir(Path::new("within")).is_ok());
+            fs.remove(Path::new("out")).unwrap();
+            assert!(outside.join("secret.txt").exists());
+            std::fs::remove_dir_all(&outside).ok();
+        }
+        std::fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn test_remote_fs() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let storage = MemoryFs::new();
+        let provider = Arc::new(MemoryProvider(storage.clone()));
+        let fs = RemoteFs::new(provider, runtime.handle().clone());
+
+        fs.create_dir(Path::new("docs")).unwrap();
+        let mut writer = fs.create(Path::new("docs/notes.txt")).unwrap();
+        writer.write_all(b"notes").unwrap();
+        // nothing is sent until the writer is finished, flushing only writes the private copy
+        writer.flush().unwrap();
+        assert!(storage.file(Path::new("docs/notes.txt")).is_err());
+        writer.finish().unwrap();
+        assert!(storage.file(Path::new("docs/notes.txt")).is_ok());
+        let mut dropped = fs.create(Path::new("docs/dropped.txt")).unwrap();
+        dropped.write_all(b"lost").unwrap();
+        drop(dropped);
+        assert!(storage.file(Path::new("docs/dropped.txt")).is_err());
+        fs.create_file(Path::new("docs/empty.txt")).unwrap();
+
+        let items = fs.read_dir(Path::new("docs")).unwrap();
+        assert_eq!(items.len(), 2);
+        assert!(items
+            .iter()
+            .any(|item| item.path.path == Path::new("docs/notes.txt")));
+        assert!(fs.file(Path::new("")).unwrap().opens_as_dir());
+        assert!(fs.file(Path::new("docs")).unwrap().opens_as_dir());
+        assert!(fs.file(Path::new("docs/missing.txt")).is_err());
+        assert!(fs.create_dir(Path::new("../docs")).is_err());
+
+        let mut contents = String::new();
+        let mut reader = fs.open(Path::new("docs/notes.txt")).unwrap();
+        reader.read_to_string(&mut contents).unwrap();
+        assert_eq!(contents, "notes");
+        drop(reader);
+
+        fs.rename(Path::new("docs/notes.txt"), Path::new("docs/final.txt"))
+            .unwrap();
+        assert!(storage.file(Path::new("docs/final.txt")).is_ok());
+        fs.remove(Path::new("docs")).unwrap();
+        assert!(storage.read_dir(Path::new("")).unwrap().is_empty());
+        let err = fs.remove(Path::new("docs")).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn test_mounted_operations() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(async move {
+            let fs = MemoryFs::new();
+            assert!(mount("vfs-test", Arc::new(fs.clone())));
+            assert!(!mount("vfs-test", Arc::new(MemoryFs::new())));
+            assert!(!mount("C", Arc::new(MemoryFs::new())));
+            let at = |path: &str| PitouFilePath::mounted("vfs-test", Path::new(path));
+
+            fs_ops::create_dir(at("docs")).await.unwrap();
+            fs_ops::create_file(at("docs/draft.txt")).await.unwrap();
+            fs.create(Path::new("docs/draft.txt"))
+                .unwrap()
+                .write_all(b"draft")
+                .unwrap();
+            fs_ops::rename(at("docs/draft.txt"), "final.txt".into())
+                .await
+                .unwrap();
+
+            let filter = PitouFileFilter::new();
+            let top = fs_ops::children(at(""), filter, None).await.unwrap();
+            assert_eq!(top.len(), 1);
+            assert!(top[0].path == at("docs"));
+            assert_eq!(top[0].metadata.as_ref().unwrap().size.bytes, 1);
+            let docs = fs_ops::children(at("docs"), filter, None).await.unwrap();
+            assert!(docs[0].path == at("docs/final.txt"));
+
+            // pasting from the mount onto the disk
+            let dst = std::env::temp_dir().join("pitou-vfs-paste-test");
+            std::fs::remove_dir_all(&dst).ok();
+            std::fs::create_dir_all(&dst).unwrap();
+            fs_ops::copy(vec![PitouFile::without_metadata(at("docs"))]).await;
+            let id = transfer::paste_items(PitouFilePath::from_pathbuf(dst.clone()))
+                .await
+                .unwrap();
+            while !matches!(
+                transfer::get_session_with_id(id).map(|msg| msg.details().0),
+                Some(TransferState::Terminated(_)) | None
+            ) {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+            let pasted = std::fs::read_to_string(dst.join("docs/final.txt")).unwrap();
+            assert_eq!(pasted, "draft");
+            std::fs::remove_dir_all(&dst).ok();
+
+            assert!(unmount("vfs-test"));
+            assert!(fs_ops::children(at(""), filter, None).await.is_err());
+            assert!(fs_ops::create_dir(at("more")).await.is_err());
+            assert!(fs_ops::create_file(at("more.txt")).await.is_err());
+            assert!(fs_ops::rename(at("docs"), "papers".into()).await.is_err());
+
+            // pasting from a mount that is gone ends the transfer
+            fs_ops::copy(vec![PitouFile::without_metadata(at("docs"))]).await;
+            let id = transfer::paste_items(PitouFilePath::from_pathbuf(dst.clone()))
+                .await
+                .unwrap();
+            while !matches!(
+                transfer::get_session_with_id(id).map(|msg| msg.details().0),
+                Some(TransferState::Terminated(_)) | None
+            ) {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+            assert!(!dst.exists());
+        });
+    }
+}
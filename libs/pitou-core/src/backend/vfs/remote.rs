@@ -0,0 +1,214 @@
+use std::{
+    fs::File,
+    io::{self, Read, Write},
+    path::{Component, Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, OnceLock,
+    },
+};
+
+use tokio::runtime::Handle;
+
+use crate::{
+    backend::{cloud::CloudProvider, transfer::TransferProgress},
+    cloud::{self, CloudEntry},
+    PitouFile, PitouFileKind, PitouFileMetadata, PitouFilePath,
+};
+
+use super::{FileSystem, FileWriter};
+
+/// A cloud or SFTP storage seen as a filesystem, so that it can be mounted and browsed and
+/// pasted to like the disk. Its top folder is where the account opens.
+///
+/// Files are read and written through a private copy on the disk, as providers only transfer
+/// whole files.
+pub(crate) struct RemoteFs {
+    provider: Arc<dyn CloudProvider>,
+    /// the runtime the provider's calls are run on, as those of a filesystem block
+    runtime: Handle,
+}
+
+impl RemoteFs {
+    pub(crate) fn new(provider: Arc<dyn CloudProvider>, runtime: Handle) -> Self {
+        Self { provider, runtime }
+    }
+
+    /// The entry at `path`, looked up in the folder holding it.
+    fn entry(&self, path: &str) -> io::Result<CloudEntry> {
+        let listed = self
+            .runtime
+            .block_on(self.provider.list(cloud::parent(path)))?;
+        listed
+            .into_iter()
+            .find(|entry| entry.path == path)
+            .ok_or_else(|| io::ErrorKind::NotFound.into())
+    }
+}
+
+/// `path` in the form the entries of a provider use, without `.` or `..`.
+fn remote(path: &Path) -> io::Result<String> {
+    let mut parts = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => parts.push(part.to_string_lossy()),
+            Component::CurDir | Component::RootDir => (),
+            Component::ParentDir | Component::Prefix(_) => {
+                return Err(io::ErrorKind::InvalidInput.into())
+            }
+        }
+    }
+    Ok(parts.join("/"))
+}
+
+fn file_of(entry: CloudEntry) -> PitouFile {
+    let mut file = entry.as_file();
+    file.path = PitouFilePath::from_pathbuf(PathBuf::from(entry.path));
+    file
+}
+
+/// A path for a private copy of a remote file, in a folder only the user can reach.
+fn staged() -> io::Result<PathBuf> {
+    static DIR: OnceLock<PathBuf> = OnceLock::new();
+    static NEXT: AtomicU64 = AtomicU64::new(0);
+    let dir = DIR
+        .get_or_init(|| std::env::temp_dir().join(format!("pitou-remote-{}", std::process::id())));
+    let mut builder = std::fs::DirBuilder::new();
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+    match builder.create(dir) {
+        Err(e) if e.kind() != io::ErrorKind::AlreadyExists => return Err(e),
+        _ => (),
+    }
+    Ok(dir.join(NEXT.fetch_add(1, Ordering::Relaxed).to_string()))
+}
+
+impl FileSystem for RemoteFs {
+    fn file(&self, path: &Path) -> io::Result<PitouFile> {
+        let path = remote(path)?;
+        if path.is_empty() {
+            let metadata = PitouFileMetadata {
+                modified: None,
+                accessed: None,
+                created: None,
+                size: 0.into(),
+                kind: PitouFileKind::Directory,
+                attribute: 0,
+                hidden: false,
+                link: None,
+            };
+            return Ok(PitouFile {
+                path: PitouFilePath::from_pathbuf(PathBuf::new()),
+                metadata: Some(metadata),
+            });
+        }
+        self.entry(&path).map(file_of)
+    }
+
+    fn read_dir(&self, dir: &Path) -> io::Result<Vec<PitouFile>> {
+        let dir = remote(dir)?;
+        let listed = self.runtime.block_on(self.provider.list(&dir))?;
+        Ok(listed.into_iter().map(file_of).collect())
+    }
+
+    /// Left unknown, as it would take a request for every folder listed.
+    fn count(&self, _dir: &Path) -> io::Result<u64> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+
+    fn create_dir(&self, path: &Path) -> io::Result<()> {
+        let path = remote(path)?;
+        Ok(self.runtime.block_on(self.provider.create_dir(&path))?)
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        let (from, to) = (remote(from)?, remote(to)?);
+        let entry = self.entry(&from)?;
+        Ok(self.runtime.block_on(self.provider.rename(&entry, &to))?)
+    }
+
+    fn remove(&self, path: &Path) -> io::Result<()> {
+        let entry = self.entry(&remote(path)?)?;
+        Ok(self.runtime.block_on(self.provider.delete(&entry))?)
+    }
+
+    fn open(&self, path: &Path) -> io::Result<Box<dyn Read + Send>> {
+        let path = remote(path)?;
+        let local = staged()?;
+        let progress = TransferProgress::untracked();
+        let downloaded = self
+            .runtime
+            .block_on(self.provider.download(&path, &local, &progress));
+        if let Err(e) = downloaded {
+            std::fs::remove_file(&local).ok();
+            return Err(e.into());
+        }
+        let file = File::open(&local)?;
+        Ok(Box::new(Staged { file, local }))
+    }
+
+    /// What is written is sent when the writer is finished, and dropped along with it if it
+    /// isn't.
+    fn create(&self, path: &Path) -> io::Result<Box<dyn FileWriter>> {
+        let path = remote(path)?;
+        let local = staged()?;
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let file = options.open(&local)?;
+        Ok(Box::new(Upload {
+            staged: Staged { file, local },
+            provider: self.provider.clone(),
+            runtime: self.runtime.clone(),
+            path,
+        }))
+    }
+}
+
+/// A private copy of a remote file, removed once done with.
+struct Staged {
+    file: File,
+    local: PathBuf,
+}
+
+impl Read for Staged {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.file.read(buf)
+    }
+}
+
+impl Drop for Staged {
+    fn drop(&mut self) {
+        std::fs::remove_file(&self.local).ok();
+    }
+}
+
+/// Writes a file to a private copy, sent to the provider when finished.
+struct Upload {
+    staged: Staged,
+    provider: Arc<dyn CloudProvider>,
+    runtime: Handle,
+    path: String,
+}
+
+impl Write for Upload {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.staged.file.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.staged.file.flush()
+    }
+}
+
+impl FileWriter for Upload {
+    fn finish(mut self: Box<Self>) -> io::Result<()> {
+        self.flush()?;
+        let progress = TransferProgress::untracked();
+        let uploaded = self
+            .provider
+            .upload(&self.staged.local, &self.path, &progress);
+        Ok(self.runtime.block_on(uploaded)?)
+    }
+}
@@ -0,0 +1,222 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    PitouDateTime, PitouFile, PitouFileKind, PitouFileMetadata, PitouFilePath, PitouFileSize,
+};
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum CloudKind {
+    /// Nextcloud, ownCloud and other WebDAV servers
+    WebDav,
+    /// Amazon S3, MinIO and other servers speaking the S3 API
+    S3,
+    /// any server reached over SSH
+    Sftp,
+}
+
+impl CloudKind {
+    pub const ALL: [Self; 3] = [Self::WebDav, Self::S3, Self::Sftp];
+
+    /// How the locations of accounts of the kind start.
+    pub fn scheme(self) -> &'static str {
+        match self {
+            Self::WebDav => "webdav",
+            Self::S3 => "s3",
+            Self::Sftp => "sftp",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::WebDav => "WebDAV (Nextcloud, ownCloud)",
+            Self::S3 => "S3 compatible (AWS, MinIO)",
+            Self::Sftp => "SFTP (SSH servers)",
+        }
+    }
+}
+
+/// The details needed to reach a cloud storage, kept on this computer only.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct CloudAccount {
+    /// empty for an account that hasn't been saved yet
+    pub id: String,
+    pub name: String,
+    pub kind: CloudKind,
+    /// the url of the folder the account opens at for WebDAV, or of the server for S3. For
+    /// SFTP, `[user@]host[:port][/folder]` where the host may be one named in `~/.ssh/config`.
+    pub endpoint: String,
+    /// the user name for WebDAV and SFTP, or the access key for S3
+    pub username: String,
+    /// the password for WebDAV, the secret key for S3, or the password or key passphrase for
+    /// SFTP. Left empty when accounts are listed, and left empty on saving to keep the one
+    /// already stored.
+    pub secret: String,
+    /// the bucket browsed, for S3 only
+    pub bucket: String,
+    /// the region requests are signed for, for S3 only
+    pub region: String,
+    /// the private key to sign in with, for SFTP only. When empty, the ssh agent and the keys
+    /// `~/.ssh/config` names or ssh looks for are tried.
+    #[serde(default)]
+    pub identity: String,
+}
+
+impl CloudAccount {
+    pub fn new(kind: CloudKind) -> Self {
+        Self {
+            id: String::new(),
+            name: String::new(),
+            kind,
+            endpoint: String::new(),
+            username: String::new(),
+            secret: String::new(),
+            bucket: String::new(),
+            region: "us-east-1".to_owned(),
+            identity: String::new(),
+        }
+    }
+
+    /// The name the account is mounted under, so that its files are reached at
+    /// `<mount>://path` like those of any location.
+    pub fn mount(&self) -> String {
+        format!("{}-{}", self.kind.scheme(), self.id)
+    }
+}
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub enum CloudError {
+    /// no saved account has the id asked for
+    UnknownAccount,
+    /// the endpoint isn't a valid url
+    BadEndpoint,
+    Unreachable,
+    Unauthorized,
+    /// the server knows the user but doesn't let them do that
+    Denied,
+    /// the server's key isn't the one known for it, so it may not be the server it claims
+    HostKeyChanged,
+    /// the server's key isn't known yet, and is to be trusted by the user before connecting
+    UnknownHostKey {
+        fingerprint: String,
+    },
+    NotFound,
+    /// something already exists where an item was to be put
+    Exists,
+    /// the server turned the request down with the given status
+    Rejected(u16),
+    /// the server answered with something that couldn't be read
+    BadResponse,
+    Io,
+}
+
+impl CloudError {
+    pub fn message(&self) -> String {
+        match self {
+            Self::UnknownAccount => "The account is no longer saved".to_owned(),
+            Self::BadEndpoint => "The address of the account isn't a valid url".to_owned(),
+            Self::Unreachable => "The server couldn't be reached".to_owned(),
+            Self::Unauthorized => "The server didn't accept the credentials".to_owned(),
+            Self::Denied => "The server doesn't allow that".to_owned(),
+            Self::HostKeyChanged => {
+                "The server's key has changed since it was last reached, so it may be an impostor"
+                    .to_owned()
+            }
+            Self::UnknownHostKey { fingerprint } => {
+                format!("The server's key isn't known yet, its fingerprint is {fingerprint}")
+            }
+            Self::NotFound => "The item is no longer there".to_owned(),
+            Self::Exists => "An item with that name already exists".to_owned(),
+            Self::Rejected(status) => format!("The server refused the request ({status})"),
+            Self::BadResponse => "The server's answer couldn't be understood".to_owned(),
+            Self::Io => "A local file couldn't be read or written".to_owned(),
+        }
+    }
+}
+
+/// A file or folder in a cloud storage. Its path is relative to where the account opens,
+/// separated by `/` and without a leading or trailing one.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct CloudEntry {
+    pub path: String,
+    pub is_dir: bool,
+    pub size: u64,
+    pub modified: Option<PitouDateTime>,
+}
+
+impl CloudEntry {
+    pub fn name(&self) -> &str {
+        file_name(&self.path)
+    }
+
+    /// The entry as a file named after it, so that it lists like the files of a folder.
+    pub fn as_file(&self) -> PitouFile {
+        let kind = match self.is_dir {
+            true => PitouFileKind::Directory,
+            false => PitouFileKind::File,
+        };
+        let metadata = PitouFileMetadata {
+            modified: self.modified,
+            accessed: None,
+            created: None,
+            size: PitouFileSize { bytes: self.size },
+            kind,
+            attribute: 0,
+            hidden: false,
+            link: None,
+        };
+        PitouFile {
+            path: PitouFilePath::from_pathbuf(PathBuf::from(self.name())),
+            metadata: Some(metadata),
+        }
+    }
+}
+
+/// A listed folder along with the error that kept it from being listed, if any.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct CloudListing {
+    pub entries: Vec<CloudEntry>,
+    pub error: Option<CloudError>,
+}
+
+/// `name` within the cloud folder `dir`.
+pub fn join(dir: &str, name: &str) -> String {
+    match dir.is_empty() {
+        true => name.to_owned(),
+        false => format!("{dir}/{name}"),
+    }
+}
+
+/// The folder holding `path`, the empty path for items at the top.
+pub fn parent(path: &str) -> &str {
+    path.rfind('/').map(|idx| &path[..idx]).unwrap_or_default()
+}
+
+pub fn file_name(path: &str) -> &str {
+    path.rfind('/').map(|idx| &path[idx + 1..]).unwrap_or(path)
+}
+
+/// Tidies a path typed in or built from another one into the form entries use.
+pub fn normalize(path: &str) -> String {
+    path.split('/')
+        .filter(|part| !part.is_empty() && *part != ".")
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[cfg(test)]
+mod test_mod {
+    use super::{file_name, join, normalize, parent};
+
+    #[test]
+    fn test_cloud_paths() {
+        assert_eq!(join("", "notes.txt"), "notes.txt");
+        assert_eq!(join("docs/2024", "notes.txt"), "docs/2024/notes.txt");
+        assert_eq!(parent("docs/2024/notes.txt"), "docs/2024");
+        assert_eq!(parent("notes.txt"), "");
+        assert_eq!(file_name("docs/2024/notes.txt"), "notes.txt");
+        assert_eq!(file_name("notes.txt"), "notes.txt");
+        assert_eq!(normalize("/docs//./2024/"), "docs/2024");
+    }
+}
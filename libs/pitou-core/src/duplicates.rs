@@ -0,0 +1,114 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{PitouFile, PitouFilePath};
+
+pub struct DuplicateOptions {
+    pub roots: Vec<PitouFilePath>,
+    /// files smaller than this are never reported, empty files are always skipped
+    pub min_size: u64,
+    pub hardware_accelerate: bool,
+}
+
+/// Files with identical contents, as established by a full hash of each.
+/// A group always holds at least two files.
+pub struct DuplicateGroup {
+    pub size: u64,
+    pub hash: String,
+    pub files: Vec<PitouFile>,
+}
+
+impl DuplicateGroup {
+    /// The space freed by keeping a single copy of the group.
+    pub fn reclaimable(&self) -> u64 {
+        self.size * (self.files.len().max(1) - 1) as u64
+    }
+}
+
+/// Decides which file of a duplicate group survives an automatic selection.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum KeepRule {
+    Newest,
+    Oldest,
+    ShortestPath,
+}
+
+impl KeepRule {
+    /// The index of the file to keep, the first one wins ties.
+    /// Files without metadata are only kept by date rules when no file of the group has any.
+    pub fn keeper<'a>(self, files: impl IntoIterator<Item = &'a PitouFile>) -> Option<usize> {
+        let mut files = files.into_iter().enumerate();
+        let (mut best, first) = files.next()?;
+        let mut best_file = first;
+        for (idx, file) in files {
+            if self.prefers(file, best_file) {
+                best = idx;
+                best_file = file;
+            }
+        }
+        Some(best)
+    }
+
+    fn prefers(self, file: &PitouFile, other: &PitouFile) -> bool {
+        let modified = |f: &PitouFile| {
+            f.metadata
+                .as_ref()
+                .and_then(|m| m.modified)
+                .map(|t| t.datetime)
+        };
+        match self {
+            Self::Newest => modified(file) > modified(other),
+            Self::Oldest => match (modified(file), modified(other)) {
+                (Some(a), Some(b)) => a < b,
+                (Some(_), None) => true,
+                _ => false,
+            },
+            Self::ShortestPath => {
+                file.full_path_str().chars().count() < other.full_path_str().chars().count()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_mod {
+    use std::path::PathBuf;
+
+    use chrono::DateTime;
+
+    use super::KeepRule;
+    use crate::{
+        PitouDateTime, PitouFile, PitouFileKind, PitouFileMetadata, PitouFilePath, PitouFileSize,
+    };
+
+    fn file(path: &str, modified: i64) -> PitouFile {
+        let datetime = PitouDateTime {
+            datetime: DateTime::from_timestamp(modified, 0).unwrap().naive_utc(),
+        };
+        PitouFile {
+            path: PitouFilePath::from_pathbuf(PathBuf::from(path)),
+            metadata: Some(PitouFileMetadata {
+                modified: Some(datetime),
+                accessed: Some(datetime),
+                created: Some(datetime),
+                size: PitouFileSize::new(1),
+                kind: PitouFileKind::File,
+                attribute: 0,
+                hidden: false,
+                link: None,
+            }),
+        }
+    }
+
+    #[test]
+    fn test_keeper() {
+        let files = [
+            file("/home/me/Downloads/photo (1).jpg", 20),
+            file("/home/me/photo.jpg", 10),
+            file("/home/me/Pictures/photo.jpg", 30),
+        ];
+        assert_eq!(KeepRule::Newest.keeper(&files), Some(2));
+        assert_eq!(KeepRule::Oldest.keeper(&files), Some(1));
+        assert_eq!(KeepRule::ShortestPath.keeper(&files), Some(1));
+        assert_eq!(KeepRule::Newest.keeper(&[]), None);
+    }
+}
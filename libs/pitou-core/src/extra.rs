@@ -1,5 +1,6 @@
 use crate::{PitouFile, PitouFilePath, PitouFileSize};
-use std::{hash::Hash, path::PathBuf};
+use icu_normalizer::DecomposingNormalizerBorrowed;
+use std::{cmp::Ordering, hash::Hash, iter::Peekable, path::PathBuf};
 
 impl From<u64> for PitouFileSize {
     fn from(bytes: u64) -> Self {
@@ -57,6 +58,9 @@ pub fn ends_with_ignore_case(key: &str, input: &str) -> bool {
 pub fn contains_ignore_case(key: &str, input: &str) -> bool {
     let key = key.as_bytes();
     let input = input.as_bytes();
+    if key.is_empty() {
+        return true;
+    }
     if input.len() < key.len() {
         return false;
     }
@@ -64,3 +68,102 @@ pub fn contains_ignore_case(key: &str, input: &str) -> bool {
         (0..window.len()).all(|idx| (key[idx] as char).eq_ignore_ascii_case(&(window[idx] as char)))
     })
 }
+
+/// Compares file names ignoring case unless `case_sensitive`, using Unicode lowercase, and
+/// comparing runs of digits by their value if `natural`. Accents only break ties, so `émile`
+/// sorts with `emile` rather than after `z`. Names that are still equal are put in a fixed
+/// order by their bytes.
+pub fn compare_names(a: &str, b: &str, natural: bool, case_sensitive: bool) -> Ordering {
+    compare_chars(a, b, natural, case_sensitive).then_with(|| a.cmp(b))
+}
+
+fn compare_chars(a: &str, b: &str, natural: bool, case_sensitive: bool) -> Ordering {
+    let mut a = fold_accents(a).peekable();
+    let mut b = fold_accents(b).peekable();
+    loop {
+        let ord = match (a.peek().copied(), b.peek().copied()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if natural && x.is_ascii_digit() && y.is_ascii_digit() => {
+                compare_numbers(&take_digits(&mut a), &take_digits(&mut b))
+            }
+            (Some(x), Some(y)) => {
+                a.next();
+                b.next();
+                if case_sensitive {
+                    x.cmp(&y)
+                } else {
+                    x.to_lowercase().cmp(y.to_lowercase())
+                }
+            }
+        };
+        if ord != Ordering::Equal {
+            return ord;
+        }
+    }
+}
+
+/// The chars of `name` decomposed, with the combining marks that carry its accents dropped.
+fn fold_accents(name: &str) -> impl Iterator<Item = char> + '_ {
+    DecomposingNormalizerBorrowed::new_nfd()
+        .normalize_iter(name.chars())
+        .filter(|c| !matches!(c, '\u{0300}'..='\u{036f}'))
+}
+
+fn take_digits(chars: &mut Peekable<impl Iterator<Item = char>>) -> String {
+    let mut digits = String::new();
+    while let Some(digit) = chars.next_if(char::is_ascii_digit) {
+        digits.push(digit);
+    }
+    digits
+}
+
+/// Compares two runs of digits by value, the one with fewer leading zeros first if equal.
+fn compare_numbers(a: &str, b: &str) -> Ordering {
+    let (a_value, b_value) = (a.trim_start_matches('0'), b.trim_start_matches('0'));
+    a_value
+        .len()
+        .cmp(&b_value.len())
+        .then_with(|| a_value.cmp(b_value))
+        .then_with(|| a.len().cmp(&b.len()))
+}
+
+#[cfg(test)]
+mod test_mod {
+    use std::cmp::Ordering;
+
+    use super::{compare_names, contains_ignore_case};
+
+    #[test]
+    fn test_compare_names() {
+        let mut names = vec!["file10", "Zeta", "file2", "alpha", "File1", "file02"];
+        names.sort_by(|a, b| compare_names(a, b, true, false));
+        assert_eq!(
+            names,
+            ["alpha", "File1", "file2", "file02", "file10", "Zeta"]
+        );
+
+        names.sort_by(|a, b| compare_names(a, b, false, true));
+        assert_eq!(
+            names,
+            ["File1", "Zeta", "alpha", "file02", "file10", "file2"]
+        );
+
+        assert_eq!(compare_names("a", "A", true, false), Ordering::Greater);
+        assert_eq!(compare_names("émile", "Ézra", true, false), Ordering::Less);
+        assert_eq!(compare_names("émile", "zeta", true, false), Ordering::Less);
+        assert_eq!(
+            compare_names("Ångström", "Zulu", false, true),
+            Ordering::Less
+        );
+        assert_eq!(compare_names("emile", "émile", true, false), Ordering::Less);
+    }
+
+    #[test]
+    fn test_contains_ignore_case() {
+        assert!(contains_ignore_case("READ", "readme.md"));
+        assert!(contains_ignore_case("", "readme.md"));
+        assert!(!contains_ignore_case("readme.md.bak", "readme.md"));
+    }
+}
@@ -0,0 +1,96 @@
+use crate::{PitouFile, PitouFilePath};
+
+/// A bookmarked file or folder.
+pub struct Favorite {
+    pub path: PitouFilePath,
+    /// the name the favorite is listed under instead of its file name, if any
+    pub label: Option<String>,
+}
+
+impl Favorite {
+    pub fn new(path: PitouFilePath) -> Self {
+        Self { path, label: None }
+    }
+
+    /// The label, else the file name, else the whole path for a root that has no file name.
+    pub fn label(&self) -> &str {
+        match (self.label.as_deref(), self.path.name()) {
+            (Some(label), _) => label,
+            (None, "") => self.path.path.to_str().unwrap_or_default(),
+            (None, name) => name,
+        }
+    }
+}
+
+/// A favorite as listed, along with what its path leads to now.
+pub struct FavoriteItem {
+    pub favorite: Favorite,
+    /// `None` once the target has been moved or removed
+    pub target: Option<PitouFile>,
+}
+
+impl FavoriteItem {
+    pub fn is_missing(&self) -> bool {
+        self.target.is_none()
+    }
+}
+
+/// Appends a favorite for each of `paths` that isn't a favorite yet, returning how many were added.
+pub fn add(favorites: &mut Vec<Favorite>, paths: impl IntoIterator<Item = Favorite>) -> usize {
+    let before = favorites.len();
+    for favorite in paths {
+        if !favorites.iter().any(|saved| saved.path == favorite.path) {
+            favorites.push(favorite)
+        }
+    }
+    favorites.len() - before
+}
+
+/// Moves the favorite of `path` to `position`, or to the end if `position` is past it.
+pub fn reorder(favorites: &mut Vec<Favorite>, path: &PitouFilePath, position: usize) {
+    if let Some(idx) = favorites.iter().position(|saved| &saved.path == path) {
+        let favorite = favorites.remove(idx);
+        favorites.insert(position.min(favorites.len()), favorite);
+    }
+}
+
+/// Names `label` the favorite of `path`. A blank label goes back to the file name.
+pub fn rename(favorites: &mut [Favorite], path: &PitouFilePath, label: &str) {
+    if let Some(favorite) = favorites.iter_mut().find(|saved| &saved.path == path) {
+        let label = label.trim();
+        favorite.label = (!label.is_empty()).then(|| label.to_owned());
+    }
+}
+
+#[cfg(test)]
+mod test_mod {
+    use std::path::PathBuf;
+
+    use super::Favorite;
+    use crate::PitouFilePath;
+
+    fn path(dir: &str) -> PitouFilePath {
+        PitouFilePath::from_pathbuf(PathBuf::from(dir))
+    }
+
+    #[test]
+    fn test_favorites() {
+        let mut favorites = Vec::new();
+        let added = super::add(
+            &mut favorites,
+            ["/a", "/b", "/a", "/c"].map(|dir| Favorite::new(path(dir))),
+        );
+        assert_eq!(added, 3);
+        assert_eq!(super::add(&mut favorites, [Favorite::new(path("/b"))]), 0);
+
+        super::reorder(&mut favorites, &path("/c"), 0);
+        super::reorder(&mut favorites, &path("/a"), 9);
+        let order = favorites.iter().map(|f| f.label()).collect::<Vec<_>>();
+        assert_eq!(order, ["c", "b", "a"]);
+
+        super::rename(&mut favorites, &path("/b"), " Work ");
+        assert_eq!(favorites[1].label(), "Work");
+        super::rename(&mut favorites, &path("/b"), "  ");
+        assert_eq!(favorites[1].label(), "b");
+    }
+}
@@ -0,0 +1,161 @@
+use crate::usage::UsageNode;
+
+/// A rectangle in the unit square, positions and sizes are fractions of the chart.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Rect {
+    pub x: f64,
+    pub y: f64,
+    pub w: f64,
+    pub h: f64,
+}
+
+impl Rect {
+    pub const UNIT: Self = Self {
+        x: 0.0,
+        y: 0.0,
+        w: 1.0,
+        h: 1.0,
+    };
+}
+
+/// Lays `sizes`, sorted largest first, out as a squarified treemap filling `bounds`.
+/// Every rectangle's area is proportional to its size, zero sizes get empty rectangles.
+pub fn treemap(sizes: &[u64], bounds: Rect) -> Vec<Rect> {
+    let total = sizes.iter().sum::<u64>() as f64;
+    if total == 0.0 {
+        return vec![
+            Rect {
+                w: 0.0,
+                h: 0.0,
+                ..bounds
+            };
+            sizes.len()
+        ];
+    }
+    let scale = bounds.w * bounds.h / total;
+    let areas = sizes.iter().map(|&s| s as f64 * scale).collect::<Vec<_>>();
+    let mut rects = Vec::with_capacity(sizes.len());
+    let mut free = bounds;
+    let mut start = 0;
+    while start < areas.len() {
+        let side = free.w.min(free.h);
+        // grow the row while it makes its worst aspect ratio better
+        let mut end = start + 1;
+        while end < areas.len()
+            && worst_ratio(&areas[start..=end], side) <= worst_ratio(&areas[start..end], side)
+        {
+            end += 1;
+        }
+        let row = &areas[start..end];
+        let row_area = row.iter().sum::<f64>();
+        let thickness = if side > 0.0 { row_area / side } else { 0.0 };
+        let mut offset = 0.0;
+        for &area in row {
+            let length = if thickness > 0.0 {
+                area / thickness
+            } else {
+                0.0
+            };
+            rects.push(if free.w >= free.h {
+                Rect {
+                    x: free.x,
+                    y: free.y + offset,
+                    w: thickness,
+                    h: length,
+                }
+            } else {
+                Rect {
+                    x: free.x + offset,
+                    y: free.y,
+                    w: length,
+                    h: thickness,
+                }
+            });
+            offset += length;
+        }
+        if free.w >= free.h {
+            free.x += thickness;
+            free.w = (free.w - thickness).max(0.0);
+        } else {
+            free.y += thickness;
+            free.h = (free.h - thickness).max(0.0);
+        }
+        start = end;
+    }
+    rects
+}
+
+fn worst_ratio(row: &[f64], side: f64) -> f64 {
+    let sum = row.iter().sum::<f64>();
+    let (min, max) = row
+        .iter()
+        .fold((f64::MAX, 0f64), |(min, max), &a| (min.min(a), max.max(a)));
+    if sum == 0.0 || min == 0.0 {
+        return f64::MAX;
+    }
+    let side = side * side;
+    (side * max / (sum * sum)).max(sum * sum / (side * min))
+}
+
+/// A ring segment of a sunburst, angles are fractions of a full turn and rings count outwards from 1.
+pub struct Segment<'a> {
+    pub node: &'a UsageNode,
+    pub start: f64,
+    pub end: f64,
+    pub ring: u32,
+}
+
+/// Lays the descendants of `root` out as rings around it, each taking the share of its parent's angle
+/// that its size takes of its parent's size.
+pub fn sunburst(root: &UsageNode) -> Vec<Segment<'_>> {
+    fn layout<'a>(
+        node: &'a UsageNode,
+        start: f64,
+        end: f64,
+        ring: u32,
+        out: &mut Vec<Segment<'a>>,
+    ) {
+        let total = node
+            .children
+            .iter()
+            .map(|c| c.size)
+            .sum::<u64>()
+            .max(node.size) as f64;
+        if total == 0.0 {
+            return;
+        }
+        let mut angle = start;
+        for child in &node.children {
+            let sweep = (end - start) * child.size as f64 / total;
+            out.push(Segment {
+                node: child,
+                start: angle,
+                end: angle + sweep,
+                ring,
+            });
+            layout(child, angle, angle + sweep, ring + 1, out);
+            angle += sweep;
+        }
+    }
+    let mut segments = Vec::new();
+    layout(root, 0.0, 1.0, 1, &mut segments);
+    segments
+}
+
+#[cfg(test)]
+mod test_mod {
+    use super::{treemap, Rect};
+
+    #[test]
+    fn test_treemap() {
+        let sizes = [6, 6, 4, 3, 2, 2, 1];
+        let rects = treemap(&sizes, Rect::UNIT);
+        let total = sizes.iter().sum::<u64>() as f64;
+        for (rect, size) in rects.iter().zip(sizes) {
+            assert!((rect.w * rect.h - size as f64 / total).abs() < 1e-9);
+            assert!(rect.x >= -1e-9 && rect.x + rect.w <= 1.0 + 1e-9);
+            assert!(rect.y >= -1e-9 && rect.y + rect.h <= 1.0 + 1e-9);
+        }
+        assert_eq!(treemap(&[0, 0], Rect::UNIT)[1].w, 0.0);
+    }
+}
@@ -1,6 +1,11 @@
-use std::{rc::Rc, slice::Iter};
+use std::{cmp::Reverse, collections::HashSet, path::PathBuf, rc::Rc, slice::Iter};
 
-use crate::PitouFile;
+use crate::{
+    duplicates::{DuplicateGroup, KeepRule},
+    msg::DuplicateProgress,
+    search::{fuzzy::FuzzyMatch, SearchFind},
+    PitouFile, PitouFilePath,
+};
 
 pub struct FolderTracker {
     pub items: Vec<Rc<PitouFile>>,
@@ -63,3 +68,230 @@ impl FolderTracker {
 pub struct DirChildren {
     pub children: Vec<Rc<PitouFile>>,
 }
+
+pub struct SearchResult {
+    pub file: Rc<PitouFile>,
+    pub relevance: Option<FuzzyMatch>,
+    pub root: Rc<PitouFilePath>,
+}
+
+impl SearchResult {
+    pub fn score(&self) -> Option<i32> {
+        self.relevance.as_ref().map(|v| v.score)
+    }
+
+    /// Appends new finds to `results`, keeping ranked (fuzzy) results ordered by relevance.
+    /// Only the new batch is sorted and then merged in, with results of equal relevance kept
+    /// in the order they were found.
+    pub fn extend_ranked(results: &mut Vec<Rc<Self>>, items: impl Iterator<Item = SearchFind>) {
+        let mut batch = items.map(|v| Rc::new(Self::from(v))).collect::<Vec<_>>();
+        if batch.iter().all(|v| v.relevance.is_none()) {
+            results.append(&mut batch);
+            return;
+        }
+        batch.sort_by_key(|v| Reverse(v.score()));
+        let mut older = std::mem::take(results).into_iter().peekable();
+        results.reserve(older.len() + batch.len());
+        for item in batch {
+            while let Some(v) = older.next_if(|v| v.score() >= item.score()) {
+                results.push(v);
+            }
+            results.push(item);
+        }
+        results.extend(older);
+    }
+
+    /// The char indices of the file name that matched the search key, if the search ranks its finds.
+    pub fn matched_positions(&self) -> &[usize] {
+        self.relevance
+            .as_ref()
+            .map(|v| &v.positions[..])
+            .unwrap_or_default()
+    }
+}
+
+impl From<SearchFind> for SearchResult {
+    fn from(value: SearchFind) -> Self {
+        Self {
+            file: Rc::new(value.file),
+            relevance: value.relevance,
+            root: Rc::new(value.root),
+        }
+    }
+}
+
+pub struct DuplicateSet {
+    pub size: u64,
+    pub hash: String,
+    pub files: Vec<Rc<PitouFile>>,
+}
+
+impl DuplicateSet {
+    pub fn reclaimable(&self) -> u64 {
+        self.size * (self.files.len().max(1) - 1) as u64
+    }
+}
+
+impl From<DuplicateGroup> for DuplicateSet {
+    fn from(value: DuplicateGroup) -> Self {
+        Self {
+            size: value.size,
+            hash: value.hash,
+            files: value.files.into_iter().map(Rc::new).collect(),
+        }
+    }
+}
+
+/// The groups streamed so far by the duplicate finder, with the files picked for deletion.
+#[derive(Default)]
+pub struct DuplicateFinder {
+    pub groups: Vec<Rc<DuplicateSet>>,
+    pub progress: DuplicateProgress,
+    pub running: bool,
+    pub selected: HashSet<PathBuf>,
+}
+
+impl DuplicateFinder {
+    /// Adds newly confirmed groups, keeping those that free the most space first.
+    pub fn extend(&mut self, groups: impl Iterator<Item = DuplicateGroup>) {
+        self.groups.extend(groups.map(|g| Rc::new(g.into())));
+        self.groups.sort_by_key(|g| Reverse(g.reclaimable()));
+    }
+
+    pub fn is_selected(&self, file: &PitouFile) -> bool {
+        self.selected.contains(&file.path.path)
+    }
+
+    /// Flips the selection of `file`, unless selecting it would leave its group without a copy.
+    pub fn toggle(&mut self, file: &PitouFile) {
+        if self.selected.remove(&file.path.path) {
+            return;
+        }
+        let last_copy = self
+            .groups
+            .iter()
+            .find(|g| g.files.iter().any(|f| f.path.path == file.path.path))
+            .map(|g| {
+                g.files
+                    .iter()
+                    .all(|f| f.path.path == file.path.path || self.is_selected(f))
+            })
+            .unwrap_or_default();
+        if !last_copy {
+            self.selected.insert(file.path.path.clone());
+        }
+    }
+
+    /// Selects every file but the one `rule` keeps in each group.
+    pub fn auto_select(&mut self, rule: KeepRule) {
+        self.selected.clear();
+        for group in &self.groups {
+            let keep = rule.keeper(group.files.iter().map(|f| &**f));
+            for (idx, file) in group.files.iter().enumerate() {
+                if Some(idx) != keep {
+                    self.selected.insert(file.path.path.clone());
+                }
+            }
+        }
+    }
+
+    pub fn selections(&self) -> Vec<Rc<PitouFile>> {
+        self.groups
+            .iter()
+            .flat_map(|g| g.files.iter())
+            .filter(|f| self.is_selected(f))
+            .cloned()
+            .collect()
+    }
+
+    pub fn selected_size(&self) -> u64 {
+        self.groups
+            .iter()
+            .map(|g| g.size * g.files.iter().filter(|f| self.is_selected(f)).count() as u64)
+            .sum()
+    }
+
+    /// Drops the selected files once deleted, along with the groups they leave without a duplicate.
+    pub fn remove_selected(&mut self) {
+        let selected = std::mem::take(&mut self.selected);
+        self.groups = self
+            .groups
+            .iter()
+            .filter_map(|g| {
+                let files = g
+                    .files
+                    .iter()
+                    .filter(|f| !selected.contains(&f.path.path))
+                    .cloned()
+                    .collect::<Vec<_>>();
+                (files.len() > 1).then(|| {
+                    Rc::new(DuplicateSet {
+                        size: g.size,
+                        hash: g.hash.clone(),
+                        files,
+                    })
+                })
+            })
+            .collect();
+    }
+}
+
+#[cfg(test)]
+mod test_mod {
+    use std::path::PathBuf;
+
+    use super::{DuplicateFinder, SearchResult};
+    use crate::{
+        duplicates::DuplicateGroup, search::fuzzy::FuzzyMatch, search::SearchFind, PitouFile,
+        PitouFilePath,
+    };
+
+    fn find(name: &str, score: i32) -> SearchFind {
+        SearchFind {
+            file: PitouFile::without_metadata(PitouFilePath::from_pathbuf(PathBuf::from(name))),
+            relevance: Some(FuzzyMatch {
+                score,
+                positions: Vec::new(),
+            }),
+            root: PitouFilePath::from_pathbuf(PathBuf::new()),
+        }
+    }
+
+    #[test]
+    fn test_extend_ranked() {
+        let mut results = Vec::new();
+        SearchResult::extend_ranked(&mut results, [find("a", 3), find("b", 9)].into_iter());
+        SearchResult::extend_ranked(
+            &mut results,
+            [find("c", 1), find("d", 9), find("e", 5)].into_iter(),
+        );
+        let names = results.iter().map(|v| v.file.name()).collect::<Vec<_>>();
+        // finds of equal relevance keep the order they came in
+        assert_eq!(names, ["b", "d", "e", "a", "c"]);
+    }
+
+    #[test]
+    fn test_duplicate_toggle() {
+        let file = |name: &str| {
+            PitouFile::without_metadata(PitouFilePath::from_pathbuf(PathBuf::from(name)))
+        };
+        let mut finder = DuplicateFinder::default();
+        finder.extend(
+            [DuplicateGroup {
+                size: 10,
+                hash: String::from("h"),
+                files: vec![file("a"), file("b"), file("c")],
+            }]
+            .into_iter(),
+        );
+        finder.toggle(&file("a"));
+        finder.toggle(&file("b"));
+        // the last unselected copy of a group can't be picked for deletion
+        finder.toggle(&file("c"));
+        assert!(finder.is_selected(&file("a")) && finder.is_selected(&file("b")));
+        assert!(!finder.is_selected(&file("c")));
+        finder.toggle(&file("a"));
+        finder.toggle(&file("c"));
+        assert!(!finder.is_selected(&file("a")) && finder.is_selected(&file("c")));
+    }
+}
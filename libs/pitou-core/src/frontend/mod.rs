@@ -1,20 +1,30 @@
 use std::{
     cell::RefCell,
-    collections::HashSet,
+    collections::{HashMap, HashSet},
     hash::{Hash, Hasher},
+    path::PathBuf,
     rc::Rc,
 };
 
+use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    AppMenu, AppSettings, ColorTheme, FrontendSearchOptions, GeneralFolder, ItemsView, PitouDrive,
-    PitouFile, PitouFileFilter, PitouFileSort, PitouTrashItem,
+    cloud::CloudEntry,
+    search::{SavedSearch, SearchFind, SearchScope, SimplifiedSearchOptions},
+    session::{SavedSession, SavedTab},
+    settings::SavedSettings,
+    themes::ThemeSettings,
+    thumbnails::ThumbnailSize,
+    AppMenu, AppSettings, ColorTheme, FrontendSearchOptions, FrontendSearchScope, GeneralFolder,
+    ItemsView, PitouDrive, PitouFile, PitouFileFilter, PitouFilePath, PitouFileSort,
+    PitouTrashItem,
 };
 
-use self::extra::FolderTracker;
+use self::extra::{DuplicateFinder, FolderTracker, SearchResult};
 pub mod ser_de;
 
+pub mod charts;
 pub mod extra;
 
 impl FrontendSearchOptions {
@@ -22,12 +32,49 @@ impl FrontendSearchOptions {
         Self {
             input: String::new(),
             search_kind: 1,
-            depth: 6,
+            depth: Some(6),
             case_sensitive: false,
             hardware_accelerate: false,
             skip_errors: true,
             filter: PitouFileFilter::new(),
             max_finds: 25,
+            follow_links: false,
+            same_filesystem: false,
+            respect_ignore_files: false,
+            excludes: Vec::new(),
+            scope: FrontendSearchScope::CurrentFolder,
+        }
+    }
+
+    /// Restores the options of a saved search, its resolved roots becoming a list of folders.
+    pub fn from_saved(options: &SimplifiedSearchOptions) -> Self {
+        let scope = match &options.scope {
+            SearchScope::Roots(roots) => FrontendSearchScope::Folders(
+                roots
+                    .iter()
+                    .map(|root| {
+                        Rc::new(PitouFile::without_metadata(PitouFilePath::from_pathbuf(
+                            root.path.clone(),
+                        )))
+                    })
+                    .collect(),
+            ),
+            SearchScope::AllDrives => FrontendSearchScope::AllDrives,
+        };
+        Self {
+            input: options.input.clone(),
+            search_kind: options.search_kind,
+            depth: options.depth,
+            case_sensitive: options.case_sensitive,
+            hardware_accelerate: options.hardware_accelerate,
+            skip_errors: options.skip_errors,
+            filter: options.filter,
+            max_finds: options.max_finds,
+            follow_links: options.follow_links,
+            same_filesystem: options.same_filesystem,
+            respect_ignore_files: options.respect_ignore_files,
+            excludes: options.excludes.clone(),
+            scope,
         }
     }
 }
@@ -35,8 +82,11 @@ impl FrontendSearchOptions {
 pub struct TabCtx {
     pub folder_tracker: RefCell<Option<FolderTracker>>,
     pub current_menu: RefCell<AppMenu>,
-    pub search_results: RefCell<Option<Rc<RefCell<Vec<Rc<PitouFile>>>>>>,
+    pub search_results: RefCell<Option<Rc<RefCell<Vec<Rc<SearchResult>>>>>>,
     pub search_options: RefCell<FrontendSearchOptions>,
+    /// the saved search whose results are shown as a smart folder, if any
+    pub smart_folder: RefCell<Option<Rc<String>>>,
+    pub duplicates: RefCell<Option<Rc<RefCell<DuplicateFinder>>>>,
     pub dir_children: RefCell<Option<Rc<Vec<Rc<PitouFile>>>>>,
     pub dir_siblings: RefCell<Option<Rc<Vec<Rc<PitouFile>>>>>,
 }
@@ -46,14 +96,43 @@ impl TabCtx {
         (*self.search_options.borrow()).clone()
     }
 
-    pub fn get_or_init_search_results(&self) -> Rc<RefCell<Vec<Rc<PitouFile>>>> {
+    pub fn get_or_init_search_results(&self) -> Rc<RefCell<Vec<Rc<SearchResult>>>> {
         self.search_results
             .borrow_mut()
             .get_or_insert_with(|| Rc::new(RefCell::new(Vec::new())))
             .clone()
     }
 
-    pub fn search_results(&self) -> Option<Rc<RefCell<Vec<Rc<PitouFile>>>>> {
+    pub fn duplicate_finder(&self) -> Rc<RefCell<DuplicateFinder>> {
+        self.duplicates
+            .borrow_mut()
+            .get_or_insert_with(|| Rc::new(RefCell::new(DuplicateFinder::default())))
+            .clone()
+    }
+
+    pub fn reset_duplicate_finder(&self) -> Rc<RefCell<DuplicateFinder>> {
+        let finder = Rc::new(RefCell::new(DuplicateFinder::default()));
+        *self.duplicates.borrow_mut() = Some(finder.clone());
+        finder
+    }
+
+    pub fn smart_folder(&self) -> Option<Rc<String>> {
+        (*self.smart_folder.borrow()).clone()
+    }
+
+    pub fn update_smart_folder(&self, name: Option<Rc<String>>) {
+        *self.smart_folder.borrow_mut() = name;
+    }
+
+    /// Shows `saved` as a smart folder with its options restored. Its results are cleared
+    /// for the search view to run it afresh.
+    pub fn open_smart_folder(&self, saved: &SavedSearch) {
+        *self.search_options.borrow_mut() = FrontendSearchOptions::from_saved(&saved.options);
+        self.update_smart_folder(Some(Rc::new(saved.name.clone())));
+        *self.search_results.borrow_mut() = None;
+    }
+
+    pub fn search_results(&self) -> Option<Rc<RefCell<Vec<Rc<SearchResult>>>>> {
         (*self.search_results.borrow()).clone()
     }
 
@@ -61,12 +140,8 @@ impl TabCtx {
         (*self.search_results.borrow_mut()) = Some(Rc::new(RefCell::new(Vec::new())))
     }
 
-    pub fn append_search_result(&self, items: impl Iterator<Item = Rc<PitouFile>>) {
-        let mut bm = self.search_results.borrow_mut();
-        match &mut *bm {
-            Some(list) => list.borrow_mut().extend(items),
-            None => *bm = Some(Rc::new(RefCell::new(Vec::from_iter(items)))),
-        }
+    pub fn append_search_result(&self, items: impl Iterator<Item = SearchFind>) {
+        SearchResult::extend_ranked(&mut self.get_or_init_search_results().borrow_mut(), items)
     }
 
     pub fn display_name(&self) -> String {
@@ -83,6 +158,8 @@ impl TabCtx {
             AppMenu::Locked => "Vault".to_owned(),
             AppMenu::Recents => "Recent Files".to_owned(),
             AppMenu::Cloud => "Cloud Storage".to_owned(),
+            AppMenu::Duplicates => "Duplicate Files".to_owned(),
+            AppMenu::DiskUsage => "Disk Usage".to_owned(),
             AppMenu::Settings => "Settings".to_owned(),
         }
     }
@@ -166,17 +243,59 @@ impl TabCtx {
             folder_tracker: RefCell::new(Some(FolderTracker::new(current_dir))),
             current_menu: RefCell::new(menu),
             search_results: RefCell::new(None),
+            smart_folder: RefCell::new(None),
+            duplicates: RefCell::new(None),
             dir_children: RefCell::new(None),
             dir_siblings: RefCell::new(None),
         }
     }
 
+    /// The menu and folder history that are restored with the tab when pitou is reopened.
+    pub fn saved(&self) -> SavedTab {
+        let (history, current) = match &*self.folder_tracker.borrow() {
+            Some(tracker) => (
+                tracker
+                    .all()
+                    .map(|dir| PitouFilePath::from_pathbuf(dir.path.path.clone()))
+                    .collect(),
+                tracker.idx,
+            ),
+            None => (Vec::new(), 0),
+        };
+        SavedTab {
+            menu: *self.current_menu.borrow(),
+            history,
+            current,
+        }
+    }
+
+    pub fn from_saved(saved: SavedTab) -> Self {
+        let SavedTab {
+            menu,
+            history,
+            current,
+        } = saved;
+        let tab = Self::default();
+        if !history.is_empty() {
+            let items = history
+                .into_iter()
+                .map(|path| Rc::new(PitouFile::without_metadata(path)))
+                .collect::<Vec<_>>();
+            let idx = current.min(items.len() - 1);
+            *tab.folder_tracker.borrow_mut() = Some(FolderTracker { items, idx });
+        }
+        tab.update_cur_menu(menu);
+        tab
+    }
+
     pub fn default() -> Self {
         Self {
             search_options: RefCell::new(FrontendSearchOptions::init()),
             folder_tracker: RefCell::new(None),
             current_menu: RefCell::new(AppMenu::Home),
             search_results: RefCell::new(None),
+            smart_folder: RefCell::new(None),
+            duplicates: RefCell::new(None),
             dir_children: RefCell::new(None),
             dir_siblings: RefCell::new(None),
         }
@@ -319,11 +438,27 @@ pub struct FolderEntrySelections {
     items: HashSet<FolderEntry>,
 }
 
+/// The cloud folder browsed last and the entries selected in it, which the ribbon uploads into
+/// and downloads from.
+pub struct CloudBrowsing {
+    pub account: String,
+    pub dir: String,
+    pub selected: Vec<CloudEntry>,
+}
+
 pub struct StaticData {
     pub drives: RefCell<Option<Rc<Vec<Rc<PitouDrive>>>>>,
     pub selections: RefCell<Selections>,
     pub trash_items: RefCell<Option<Rc<Vec<Rc<PitouTrashItem>>>>>,
     pub gen_dirs: RefCell<Option<Rc<Vec<Rc<GeneralFolder>>>>>,
+    pub cloud: RefCell<Option<CloudBrowsing>>,
+    thumbnails: RefCell<HashMap<(PathBuf, ThumbnailSize), CachedThumbnail>>,
+}
+
+/// The thumbnail fetched for a file as it was when last modified, `None` if it has none.
+struct CachedThumbnail {
+    modified: Option<NaiveDateTime>,
+    src: Option<Rc<str>>,
 }
 
 impl StaticData {
@@ -333,10 +468,54 @@ impl StaticData {
             selections: RefCell::new(Selections::Drives(HashSet::new())),
             trash_items: RefCell::new(None),
             gen_dirs: RefCell::new(None),
+            cloud: RefCell::new(None),
+            thumbnails: RefCell::new(HashMap::new()),
         }
     }
 
-    pub fn are_all_selected_search_results(&self, items: Rc<RefCell<Vec<Rc<PitouFile>>>>) -> bool {
+    /// The thumbnail already fetched for `file`, unless it has been modified since.
+    pub fn cached_thumbnail(
+        &self,
+        file: &PitouFile,
+        size: ThumbnailSize,
+    ) -> Option<Option<Rc<str>>> {
+        let modified = file
+            .metadata()
+            .as_ref()
+            .and_then(|m| m.modified)
+            .map(|t| t.datetime);
+        self.thumbnails
+            .borrow()
+            .get(&(file.path().path.clone(), size))
+            .filter(|cached| cached.modified == modified)
+            .map(|cached| cached.src.clone())
+    }
+
+    pub fn cache_thumbnail(
+        &self,
+        file: &PitouFile,
+        size: ThumbnailSize,
+        src: Option<String>,
+    ) -> Option<Rc<str>> {
+        let src = src.map(Rc::from);
+        let cached = CachedThumbnail {
+            modified: file
+                .metadata()
+                .as_ref()
+                .and_then(|m| m.modified)
+                .map(|t| t.datetime),
+            src: src.clone(),
+        };
+        self.thumbnails
+            .borrow_mut()
+            .insert((file.path().path.clone(), size), cached);
+        src
+    }
+
+    pub fn are_all_selected_search_results(
+        &self,
+        items: Rc<RefCell<Vec<Rc<SearchResult>>>>,
+    ) -> bool {
         if let Selections::SearchResults(sr) = &*self.selections.borrow() {
             if sr.len() < items.borrow().len() {
                 return false;
@@ -344,7 +523,7 @@ impl StaticData {
             items
                 .borrow()
                 .iter()
-                .all(|item| sr.contains(&PitouFileWrap::new(item.clone())))
+                .all(|item| sr.contains(&PitouFileWrap::new(item.file.clone())))
         } else {
             false
         }
@@ -582,6 +761,37 @@ pub struct GenCtx {
     pub app_height: i32,
     pub color_theme: ColorTheme,
     pub app_settings: AppSettings,
+    pub themes: ThemeSettings,
+}
+
+impl GenCtx {
+    /// The part of the context that is saved between runs.
+    pub fn settings(&self) -> SavedSettings {
+        SavedSettings {
+            version: SavedSettings::VERSION,
+            color_theme: self.color_theme,
+            app_settings: self.app_settings.clone(),
+            themes: self.themes.clone(),
+        }
+    }
+
+    pub fn apply_settings(&mut self, settings: SavedSettings) {
+        self.color_theme = settings.color_theme;
+        self.app_settings = settings.app_settings;
+        self.themes = settings.themes;
+    }
+
+    /// Switches to the theme for the desktop's color scheme if the theme follows it,
+    /// returning whether the theme changed.
+    pub fn follow_system_theme(&mut self, prefers_dark: bool) -> bool {
+        let Some(system) = self.themes.follow_system else {
+            return false;
+        };
+        let theme = system.pick(prefers_dark);
+        let changed = self.color_theme != theme;
+        self.color_theme = theme;
+        changed
+    }
 }
 
 impl Default for GenCtx {
@@ -591,6 +801,7 @@ impl Default for GenCtx {
             app_height: 768,
             color_theme: ColorTheme::DEFAULT_DARK,
             app_settings: AppSettings::default(),
+            themes: ThemeSettings::default(),
         }
     }
 }
@@ -644,6 +855,39 @@ impl AllTabsCtx {
         self.all_tabs.borrow()[self.active_tab].clone()
     }
 
+    /// The open tabs along with the window size in `gen_ctx`, as saved when pitou closes.
+    pub fn saved(&self, gen_ctx: &GenCtx) -> SavedSession {
+        let tabs = self
+            .all_tabs
+            .borrow()
+            .iter()
+            .map(|tab| tab.saved())
+            .collect();
+        SavedSession {
+            tabs,
+            active_tab: self.active_tab,
+            app_width: gen_ctx.app_width,
+            app_height: gen_ctx.app_height,
+        }
+    }
+
+    /// Reopens the tabs of a saved session, whose window size is left for the caller to apply.
+    pub fn from_saved(session: SavedSession) -> Self {
+        let all_tabs = session
+            .tabs
+            .into_iter()
+            .map(|tab| Rc::new(TabCtx::from_saved(tab)))
+            .collect::<Vec<_>>();
+        if all_tabs.is_empty() {
+            return Self::default();
+        }
+        let active_tab = session.active_tab.min(all_tabs.len() - 1);
+        Self {
+            all_tabs: Rc::new(RefCell::new(all_tabs)),
+            active_tab,
+        }
+    }
+
     pub fn change_menu(self, menu: AppMenu) -> Self {
         let current_tab = self.current_tab();
         *current_tab.current_menu.borrow_mut() = menu;
@@ -701,6 +945,24 @@ impl ApplicationContext {
         self.gen_ctx.borrow_mut().app_settings.hide_system_files = hide_system_files
     }
 
+    pub fn hide_hidden_files(&self) -> bool {
+        self.gen_ctx.borrow().app_settings.hide_hidden_files
+    }
+
+    pub fn update_hide_hidden_files(&self, hide_hidden_files: bool) {
+        self.gen_ctx.borrow_mut().app_settings.hide_hidden_files = hide_hidden_files
+    }
+
+    /// The filter for folder listings, following the hidden and system files settings.
+    pub fn items_filter(&self) -> PitouFileFilter {
+        let settings = &self.gen_ctx.borrow().app_settings;
+        PitouFileFilter {
+            sys_items: !settings.hide_system_files,
+            hidden: !settings.hide_hidden_files,
+            ..PitouFileFilter::new()
+        }
+    }
+
     pub fn refresh_rate_as_millis(&self) -> u32 {
         self.gen_ctx.borrow().app_settings.refresh_rate_as_millis()
     }
@@ -756,6 +1018,14 @@ impl ApplicationContext {
         self.gen_ctx.borrow_mut().color_theme = new_theme;
     }
 
+    pub fn theme_settings(&self) -> ThemeSettings {
+        self.gen_ctx.borrow().themes.clone()
+    }
+
+    pub fn update_theme_settings<T>(&self, update: impl FnOnce(&mut ThemeSettings) -> T) -> T {
+        update(&mut self.gen_ctx.borrow_mut().themes)
+    }
+
     pub fn update_refresh_rate(&self, new_rate: u8) {
         if new_rate < 1 || new_rate > 60 {
             return;
@@ -775,6 +1045,10 @@ impl ApplicationContext {
         self.gen_ctx.borrow_mut().app_settings.show_thumbnails = new_val;
     }
 
+    pub fn toggle_show_preview(&self, new_val: bool) {
+        self.gen_ctx.borrow_mut().app_settings.show_preview = new_val;
+    }
+
     pub fn toggle_show_parents(&self, new_val: bool) {
         self.gen_ctx.borrow_mut().app_settings.show_parents = new_val;
     }
@@ -806,4 +1080,8 @@ impl ApplicationContext {
     pub fn show_parents(&self) -> bool {
         self.gen_ctx.borrow().app_settings.show_parents
     }
+
+    pub fn show_preview(&self) -> bool {
+        self.gen_ctx.borrow().app_settings.show_preview
+    }
 }
@@ -6,8 +6,18 @@ use serde::{
 };
 
 use crate::{
-    msg::SearchMsg, search::SimplifiedSearchOptions, GeneralFolder, PitouDrive, PitouDriveKind,
-    PitouFile, PitouFileFilter, PitouFileMetadata, PitouFilePath, PitouTrashItem,
+    duplicates::{DuplicateGroup, DuplicateOptions},
+    favorites::{Favorite, FavoriteItem},
+    msg::{DuplicateMsg, DuplicateProgress, SearchMsg},
+    properties::{ChangeError, ItemProperties, Owner},
+    recents::{Recent, RecentItem, RecentKind, RecentPeriod},
+    search::{fuzzy::FuzzyMatch, SavedSearch, SearchFind, SearchScope, SimplifiedSearchOptions},
+    session::SavedTab,
+    trash::RestoreError,
+    usage::{LargeFile, TypeUsage, UsageNode, UsageReport},
+    vault::VaultEntry,
+    AppMenu, GeneralFolder, PitouDateTime, PitouDrive, PitouDriveKind, PitouFile, PitouFileFilter,
+    PitouFileKind, PitouFileMetadata, PitouFilePath, PitouFileSize, PitouLink, PitouTrashItem,
     PitouTrashItemMetadata,
 };
 
@@ -133,19 +143,23 @@ impl Serialize for SimplifiedSearchOptions {
     fn serialize<S: Serializer>(&self, sz: S) -> Result<S::Ok, S::Error> {
         #[derive(Serialize)]
         struct SimplifiedSearchOptions<'a> {
-            search_dir: &'a PitouFile,
+            scope: &'a SearchScope,
             hardware_accelerate: bool,
             filter: PitouFileFilter,
             case_sensitive: bool,
-            depth: u8,
+            depth: Option<u32>,
             input: &'a String,
             search_kind: u8,
             skip_errors: bool,
             max_finds: usize,
+            follow_links: bool,
+            same_filesystem: bool,
+            respect_ignore_files: bool,
+            excludes: &'a Vec<String>,
         }
 
         SimplifiedSearchOptions {
-            search_dir: &self.search_dir,
+            scope: &self.scope,
             hardware_accelerate: self.hardware_accelerate,
             filter: self.filter,
             depth: self.depth,
@@ -154,6 +168,10 @@ impl Serialize for SimplifiedSearchOptions {
             skip_errors: self.skip_errors,
             max_finds: self.max_finds,
             case_sensitive: self.case_sensitive,
+            follow_links: self.follow_links,
+            same_filesystem: self.same_filesystem,
+            respect_ignore_files: self.respect_ignore_files,
+            excludes: &self.excludes,
         }
         .serialize(sz)
     }
@@ -208,8 +226,8 @@ impl<'d> Deserialize<'d> for SearchMsg {
         use std::collections::LinkedList;
         #[derive(Deserialize)]
         enum SearchMsg {
-            Active(LinkedList<PitouFile>),
-            Terminated(LinkedList<PitouFile>),
+            Active(LinkedList<SearchFind>),
+            Terminated(LinkedList<SearchFind>),
         }
         let smg = SearchMsg::deserialize(dz)?;
         let real_msg = match smg {
@@ -219,3 +237,547 @@ impl<'d> Deserialize<'d> for SearchMsg {
         Ok(real_msg)
     }
 }
+
+impl<'d> Deserialize<'d> for SearchFind {
+    fn deserialize<D: Deserializer<'d>>(dz: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct SearchFind {
+            file: PitouFile,
+            relevance: Option<FuzzyMatch>,
+            root: PitouFilePath,
+        }
+
+        let SearchFind {
+            file,
+            relevance,
+            root,
+        } = SearchFind::deserialize(dz)?;
+        Ok(Self {
+            file,
+            relevance,
+            root,
+        })
+    }
+}
+
+impl Serialize for SearchScope {
+    fn serialize<S: Serializer>(&self, sz: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        enum SearchScope<'a> {
+            Roots(&'a Vec<PitouFilePath>),
+            AllDrives,
+        }
+
+        match self {
+            Self::Roots(roots) => SearchScope::Roots(roots),
+            Self::AllDrives => SearchScope::AllDrives,
+        }
+        .serialize(sz)
+    }
+}
+
+impl<'d> Deserialize<'d> for SimplifiedSearchOptions {
+    fn deserialize<D: Deserializer<'d>>(dz: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct SimplifiedSearchOptions {
+            scope: SearchScope,
+            hardware_accelerate: bool,
+            filter: PitouFileFilter,
+            case_sensitive: bool,
+            depth: Option<u32>,
+            input: String,
+            search_kind: u8,
+            skip_errors: bool,
+            max_finds: usize,
+            follow_links: bool,
+            same_filesystem: bool,
+            respect_ignore_files: bool,
+            excludes: Vec<String>,
+        }
+        let SimplifiedSearchOptions {
+            scope,
+            hardware_accelerate,
+            filter,
+            case_sensitive,
+            depth,
+            input,
+            search_kind,
+            skip_errors,
+            max_finds,
+            follow_links,
+            same_filesystem,
+            respect_ignore_files,
+            excludes,
+        } = SimplifiedSearchOptions::deserialize(dz)?;
+        Ok(Self {
+            scope,
+            hardware_accelerate,
+            filter,
+            case_sensitive,
+            depth,
+            input,
+            search_kind,
+            skip_errors,
+            max_finds,
+            follow_links,
+            same_filesystem,
+            respect_ignore_files,
+            excludes,
+        })
+    }
+}
+
+impl<'d> Deserialize<'d> for SearchScope {
+    fn deserialize<D: Deserializer<'d>>(dz: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        enum SearchScope {
+            Roots(Vec<PitouFilePath>),
+            AllDrives,
+        }
+
+        let res = match SearchScope::deserialize(dz)? {
+            SearchScope::Roots(roots) => Self::Roots(roots),
+            SearchScope::AllDrives => Self::AllDrives,
+        };
+        Ok(res)
+    }
+}
+
+impl Serialize for SavedSearch {
+    fn serialize<S: Serializer>(&self, sz: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct SavedSearch<'a> {
+            name: &'a String,
+            options: &'a SimplifiedSearchOptions,
+        }
+
+        SavedSearch {
+            name: &self.name,
+            options: &self.options,
+        }
+        .serialize(sz)
+    }
+}
+
+impl<'d> Deserialize<'d> for SavedSearch {
+    fn deserialize<D: Deserializer<'d>>(dz: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct SavedSearch {
+            name: String,
+            options: SimplifiedSearchOptions,
+        }
+
+        let SavedSearch { name, options } = SavedSearch::deserialize(dz)?;
+        Ok(Self { name, options })
+    }
+}
+
+impl Serialize for DuplicateOptions {
+    fn serialize<S: Serializer>(&self, sz: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct DuplicateOptions<'a> {
+            roots: &'a Vec<PitouFilePath>,
+            min_size: u64,
+            hardware_accelerate: bool,
+        }
+
+        DuplicateOptions {
+            roots: &self.roots,
+            min_size: self.min_size,
+            hardware_accelerate: self.hardware_accelerate,
+        }
+        .serialize(sz)
+    }
+}
+
+impl<'d> Deserialize<'d> for DuplicateGroup {
+    fn deserialize<D: Deserializer<'d>>(dz: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct DuplicateGroup {
+            size: u64,
+            hash: String,
+            files: Vec<PitouFile>,
+        }
+
+        let DuplicateGroup { size, hash, files } = DuplicateGroup::deserialize(dz)?;
+        Ok(Self { size, hash, files })
+    }
+}
+
+impl<'d> Deserialize<'d> for DuplicateMsg {
+    fn deserialize<D: Deserializer<'d>>(dz: D) -> Result<Self, D::Error> {
+        use std::collections::LinkedList;
+        #[derive(Deserialize)]
+        enum DuplicateMsg {
+            Active(DuplicateProgress, LinkedList<DuplicateGroup>),
+            Terminated(DuplicateProgress, LinkedList<DuplicateGroup>),
+        }
+
+        let res = match DuplicateMsg::deserialize(dz)? {
+            DuplicateMsg::Active(progress, groups) => Self::Active(progress, groups),
+            DuplicateMsg::Terminated(progress, groups) => Self::Terminated(progress, groups),
+        };
+        Ok(res)
+    }
+}
+
+impl<'d> Deserialize<'d> for UsageNode {
+    fn deserialize<D: Deserializer<'d>>(dz: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct UsageNode {
+            name: String,
+            path: PitouFilePath,
+            size: u64,
+            files: u64,
+            is_dir: bool,
+            children: Vec<crate::usage::UsageNode>,
+        }
+
+        let UsageNode {
+            name,
+            path,
+            size,
+            files,
+            is_dir,
+            children,
+        } = UsageNode::deserialize(dz)?;
+        Ok(Self {
+            name,
+            path,
+            size,
+            files,
+            is_dir,
+            children,
+        })
+    }
+}
+
+impl<'d> Deserialize<'d> for LargeFile {
+    fn deserialize<D: Deserializer<'d>>(dz: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct LargeFile {
+            path: PitouFilePath,
+            size: u64,
+        }
+
+        let LargeFile { path, size } = LargeFile::deserialize(dz)?;
+        Ok(Self { path, size })
+    }
+}
+
+impl<'d> Deserialize<'d> for UsageReport {
+    fn deserialize<D: Deserializer<'d>>(dz: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct UsageReport {
+            root: PitouFilePath,
+            size: u64,
+            files: u64,
+            dirs: u64,
+            largest: Vec<LargeFile>,
+            by_type: Vec<TypeUsage>,
+            scanned_at: PitouDateTime,
+        }
+
+        let UsageReport {
+            root,
+            size,
+            files,
+            dirs,
+            largest,
+            by_type,
+            scanned_at,
+        } = UsageReport::deserialize(dz)?;
+        Ok(Self {
+            root,
+            size,
+            files,
+            dirs,
+            largest,
+            by_type,
+            scanned_at,
+        })
+    }
+}
+
+impl<'d> Deserialize<'d> for ItemProperties {
+    fn deserialize<D: Deserializer<'d>>(dz: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct ItemProperties {
+            path: PitouFilePath,
+            kind: Option<PitouFileKind>,
+            size: u64,
+            modified: Option<PitouDateTime>,
+            accessed: Option<PitouDateTime>,
+            created: Option<PitouDateTime>,
+            readonly: bool,
+            mode: Option<u32>,
+            owner: Option<Owner>,
+            group: Option<Owner>,
+            inode: Option<u64>,
+            links: Option<u64>,
+            mime: String,
+            link_target: Option<PitouFilePath>,
+        }
+
+        let ItemProperties {
+            path,
+            kind,
+            size,
+            modified,
+            accessed,
+            created,
+            readonly,
+            mode,
+            owner,
+            group,
+            inode,
+            links,
+            mime,
+            link_target,
+        } = ItemProperties::deserialize(dz)?;
+        Ok(Self {
+            path,
+            kind,
+            size,
+            modified,
+            accessed,
+            created,
+            readonly,
+            mode,
+            owner,
+            group,
+            inode,
+            links,
+            mime,
+            link_target,
+        })
+    }
+}
+
+impl<'d> Deserialize<'d> for ChangeError {
+    fn deserialize<D: Deserializer<'d>>(dz: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct ChangeError {
+            path: PitouFilePath,
+            message: String,
+        }
+
+        let ChangeError { path, message } = ChangeError::deserialize(dz)?;
+        Ok(Self { path, message })
+    }
+}
+
+impl<'d> Deserialize<'d> for RestoreError {
+    fn deserialize<D: Deserializer<'d>>(dz: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct RestoreError {
+            id: String,
+            path: PitouFilePath,
+            occupied: bool,
+            message: String,
+        }
+
+        let RestoreError {
+            id,
+            path,
+            occupied,
+            message,
+        } = RestoreError::deserialize(dz)?;
+        Ok(Self {
+            id,
+            path,
+            occupied,
+            message,
+        })
+    }
+}
+
+impl<'d> Deserialize<'d> for PitouLink {
+    fn deserialize<D: Deserializer<'d>>(dz: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct PitouLink {
+            target: PitouFilePath,
+            target_kind: Option<PitouFileKind>,
+        }
+
+        let PitouLink {
+            target,
+            target_kind,
+        } = PitouLink::deserialize(dz)?;
+        Ok(Self {
+            target,
+            target_kind,
+        })
+    }
+}
+
+impl<'d> Deserialize<'d> for PitouFileMetadata {
+    fn deserialize<D: Deserializer<'d>>(dz: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct PitouFileMetadata {
+            modified: Option<i64>,
+            accessed: Option<i64>,
+            created: Option<i64>,
+            size: PitouFileSize,
+            kind: PitouFileKind,
+            attribute: u32,
+            hidden: bool,
+            link: Option<PitouLink>,
+        }
+
+        let PitouFileMetadata {
+            modified,
+            accessed,
+            created,
+            size,
+            kind,
+            attribute,
+            hidden,
+            link,
+        } = PitouFileMetadata::deserialize(dz)?;
+        let time = |millis: Option<i64>| millis.and_then(PitouDateTime::from_timestamp_millis);
+        Ok(Self {
+            modified: time(modified),
+            accessed: time(accessed),
+            created: time(created),
+            size,
+            kind,
+            attribute,
+            hidden,
+            link,
+        })
+    }
+}
+
+impl Serialize for SavedTab {
+    fn serialize<S: Serializer>(&self, sz: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct SavedTab<'a> {
+            menu: AppMenu,
+            history: &'a Vec<PitouFilePath>,
+            current: usize,
+        }
+
+        SavedTab {
+            menu: self.menu,
+            history: &self.history,
+            current: self.current,
+        }
+        .serialize(sz)
+    }
+}
+
+impl<'d> Deserialize<'d> for SavedTab {
+    fn deserialize<D: Deserializer<'d>>(dz: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct SavedTab {
+            menu: AppMenu,
+            history: Vec<PitouFilePath>,
+            current: usize,
+        }
+
+        let SavedTab {
+            menu,
+            history,
+            current,
+        } = SavedTab::deserialize(dz)?;
+        Ok(Self {
+            menu,
+            history,
+            current,
+        })
+    }
+}
+
+impl<'d> Deserialize<'d> for Favorite {
+    fn deserialize<D: Deserializer<'d>>(dz: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct Favorite {
+            path: PitouFilePath,
+            label: Option<String>,
+        }
+
+        let Favorite { path, label } = Favorite::deserialize(dz)?;
+        Ok(Self { path, label })
+    }
+}
+
+impl<'d> Deserialize<'d> for FavoriteItem {
+    fn deserialize<D: Deserializer<'d>>(dz: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct FavoriteItem {
+            favorite: Favorite,
+            target: Option<PitouFile>,
+        }
+
+        let FavoriteItem { favorite, target } = FavoriteItem::deserialize(dz)?;
+        Ok(Self { favorite, target })
+    }
+}
+
+impl<'d> Deserialize<'d> for Recent {
+    fn deserialize<D: Deserializer<'d>>(dz: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct Recent {
+            path: PitouFilePath,
+            kind: RecentKind,
+            used: PitouDateTime,
+        }
+
+        let Recent { path, kind, used } = Recent::deserialize(dz)?;
+        Ok(Self { path, kind, used })
+    }
+}
+
+impl<'d> Deserialize<'d> for RecentItem {
+    fn deserialize<D: Deserializer<'d>>(dz: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct RecentItem {
+            recent: Recent,
+            period: RecentPeriod,
+            target: PitouFile,
+        }
+
+        let RecentItem {
+            recent,
+            period,
+            target,
+        } = RecentItem::deserialize(dz)?;
+        Ok(Self {
+            recent,
+            period,
+            target,
+        })
+    }
+}
+
+impl<'d> Deserialize<'d> for VaultEntry {
+    fn deserialize<D: Deserializer<'d>>(dz: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct VaultEntry {
+            id: String,
+            name: String,
+            size: u64,
+            modified: Option<PitouDateTime>,
+            added: PitouDateTime,
+            origin: PitouFilePath,
+        }
+
+        let VaultEntry {
+            id,
+            name,
+            size,
+            modified,
+            added,
+            origin,
+        } = VaultEntry::deserialize(dz)?;
+        Ok(Self {
+            id,
+            name,
+            size,
+            modified,
+            added,
+            origin,
+        })
+    }
+}
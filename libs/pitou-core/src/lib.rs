@@ -1,6 +1,6 @@
 use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
-use std::{cmp::Reverse, path::PathBuf, rc::Rc};
+use std::{cmp::Ordering, path::PathBuf, rc::Rc};
 pub mod extra;
 
 #[cfg(feature = "frontend")]
@@ -9,9 +9,23 @@ pub mod frontend;
 #[cfg(feature = "backend")]
 pub mod backend;
 
+pub mod cloud;
 pub mod collections;
+pub mod duplicates;
+pub mod favorites;
 pub mod msg;
+pub mod preview;
+pub mod properties;
+pub mod recents;
 pub mod search;
+pub mod session;
+pub mod settings;
+pub mod themes;
+pub mod thumbnails;
+pub mod trash;
+pub mod usage;
+pub mod vault;
+pub mod vfs;
 
 pub(crate) mod ser_de;
 
@@ -72,6 +86,16 @@ pub struct PitouDateTime {
 }
 
 impl PitouDateTime {
+    /// Milliseconds since the unix epoch, negative for times before 1970.
+    pub fn timestamp_millis(self) -> i64 {
+        self.datetime.and_utc().timestamp_millis()
+    }
+
+    pub fn from_timestamp_millis(millis: i64) -> Option<Self> {
+        let datetime = chrono::DateTime::from_timestamp_millis(millis)?.naive_utc();
+        Some(Self { datetime })
+    }
+
     pub fn format_duration(duration: std::time::Duration) -> String {
         const MINS: u64 = 60;
         const HRS: u64 = 60 * 60;
@@ -96,11 +120,51 @@ impl PitouDateTime {
     }
 }
 
-#[derive(PartialEq, Clone, Copy, Serialize, Deserialize)]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Serialize, Deserialize)]
 pub enum PitouFileKind {
     Directory,
     File,
     Link,
+    Socket,
+    Fifo,
+    BlockDevice,
+    CharDevice,
+}
+
+impl PitouFileKind {
+    /// Sockets, named pipes and devices, which can't be opened or previewed like files.
+    pub fn is_special(self) -> bool {
+        matches!(
+            self,
+            Self::Socket | Self::Fifo | Self::BlockDevice | Self::CharDevice
+        )
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Self::Directory => "Folder",
+            Self::File => "File",
+            Self::Link => "Symbolic link",
+            Self::Socket => "Socket",
+            Self::Fifo => "Named pipe",
+            Self::BlockDevice => "Block device",
+            Self::CharDevice => "Character device",
+        }
+    }
+}
+
+/// Where a symbolic link points to.
+pub struct PitouLink {
+    /// the target as stored in the link, which may be relative to the folder holding the link
+    pub target: PitouFilePath,
+    /// the kind of the item the link resolves to, `None` if the link is broken
+    pub target_kind: Option<PitouFileKind>,
+}
+
+impl PitouLink {
+    pub fn is_broken(&self) -> bool {
+        self.target_kind.is_none()
+    }
 }
 
 #[derive(Clone, Copy)]
@@ -143,14 +207,21 @@ impl PitouFileSize {
     }
 }
 
-#[derive(Serialize, Deserialize)]
+/// Timestamps are missing where the filesystem or the platform doesn't record them,
+/// as is often the case for the creation time on Linux.
 pub struct PitouFileMetadata {
-    pub modified: PitouDateTime,
-    pub accessed: PitouDateTime,
-    pub created: PitouDateTime,
+    pub modified: Option<PitouDateTime>,
+    pub accessed: Option<PitouDateTime>,
+    pub created: Option<PitouDateTime>,
     pub size: PitouFileSize,
     pub kind: PitouFileKind,
+    /// the Windows file attributes, always `0` on other platforms
     pub attribute: u32,
+    /// dot files, items listed in the `.hidden` file of their folder and, on Windows,
+    /// items with the hidden attribute
+    pub hidden: bool,
+    /// only set for symbolic links
+    pub link: Option<PitouLink>,
 }
 
 impl PitouFileMetadata {
@@ -162,12 +233,14 @@ impl PitouFileMetadata {
         self.kind
     }
 
+    /// Items with the Windows system attribute, and sockets, named pipes and devices.
     pub fn is_sys_item(&self) -> bool {
-        match self.attribute {
-            2 => true,
-            256 => true,
-            _ => false,
-        }
+        const FILE_ATTRIBUTE_SYSTEM: u32 = 4;
+        self.attribute & FILE_ATTRIBUTE_SYSTEM != 0 || self.kind.is_special()
+    }
+
+    pub fn is_hidden(&self) -> bool {
+        self.hidden
     }
 }
 
@@ -252,6 +325,13 @@ impl PitouFile {
         }
     }
 
+    pub fn is_hidden(&self) -> bool {
+        match &self.metadata {
+            None => false,
+            Some(metadata) => metadata.is_hidden(),
+        }
+    }
+
     pub fn is_link(&self) -> bool {
         match &self.metadata {
             None => false,
@@ -265,6 +345,35 @@ impl PitouFile {
         }
     }
 
+    pub fn is_special(&self) -> bool {
+        self.kind().map(PitouFileKind::is_special).unwrap_or(false)
+    }
+
+    pub fn link(&self) -> Option<&PitouLink> {
+        self.metadata.as_ref().and_then(|m| m.link.as_ref())
+    }
+
+    pub fn link_target(&self) -> Option<&PitouFilePath> {
+        self.link().map(|link| &link.target)
+    }
+
+    pub fn is_broken_link(&self) -> bool {
+        self.link().map(PitouLink::is_broken).unwrap_or(false)
+    }
+
+    /// The kind of the item itself, or of what it points to for a link that isn't broken.
+    pub fn resolved_kind(&self) -> Option<PitouFileKind> {
+        match self.link() {
+            Some(link) => link.target_kind,
+            None => self.kind(),
+        }
+    }
+
+    /// Whether opening the item navigates into it, true for folders and links to folders.
+    pub fn opens_as_dir(&self) -> bool {
+        matches!(self.resolved_kind(), Some(PitouFileKind::Directory))
+    }
+
     pub fn name(&self) -> &str {
         self.path.name()
     }
@@ -331,59 +440,150 @@ pub enum PitouFileSortOrder {
 }
 
 #[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
-pub enum PitouFileSort {
-    DateCreated(PitouFileSortOrder),
-    DateModified(PitouFileSortOrder),
-    DateAccessed(PitouFileSortOrder),
-    Name(PitouFileSortOrder),
+pub enum PitouFileSortKey {
+    Name,
+    DateCreated,
+    DateModified,
+    DateAccessed,
+    /// the entry count for folders
+    Size,
+    Extension,
+    Kind,
+    /// the MIME type guessed from the name
+    Mime,
+}
+
+impl PitouFileSortKey {
+    pub const ALL: [Self; 8] = [
+        Self::Name,
+        Self::DateCreated,
+        Self::DateModified,
+        Self::DateAccessed,
+        Self::Size,
+        Self::Extension,
+        Self::Kind,
+        Self::Mime,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Name => "Name",
+            Self::DateCreated => "Date Created",
+            Self::DateModified => "Date Modified",
+            Self::DateAccessed => "Date Accessed",
+            Self::Size => "Size",
+            Self::Extension => "Extension",
+            Self::Kind => "Kind",
+            Self::Mime => "Type",
+        }
+    }
+}
+
+/// How the items of a folder are ordered. Ties left by `key` are broken by `then`,
+/// and by the name after that.
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PitouFileSort {
+    pub key: PitouFileSortKey,
+    pub order: PitouFileSortOrder,
+    /// always compared in increasing order
+    pub then: Option<PitouFileSortKey>,
+    /// folders and links to folders come before everything else, whatever the order
+    pub folders_first: bool,
+    /// runs of digits in names are compared by their value, so `file2` comes before `file10`
+    pub natural: bool,
+    pub case_sensitive: bool,
 }
 
 impl PitouFileSort {
-    pub fn sorted(self, mut items: Vec<PitouFile>) -> Vec<PitouFile> {
-        match self {
-            PitouFileSort::DateCreated(order) => match order {
-                PitouFileSortOrder::Increasing => {
-                    items.sort_unstable_by_key(|v| v.metadata.as_ref().map(|m| m.created.datetime))
-                }
-                PitouFileSortOrder::Decreasing => items.sort_unstable_by_key(|v| {
-                    v.metadata.as_ref().map(|m| Reverse(m.created.datetime))
-                }),
-            },
-            PitouFileSort::Name(order) => match order {
-                PitouFileSortOrder::Increasing => {
-                    items.sort_unstable_by(|a, b| a.name().cmp(&b.name()))
-                }
-                PitouFileSortOrder::Decreasing => {
-                    items.sort_unstable_by(|a, b| b.name().cmp(&a.name()))
-                }
-            },
-            PitouFileSort::DateModified(order) => match order {
-                PitouFileSortOrder::Increasing => {
-                    items.sort_unstable_by_key(|v| v.metadata.as_ref().map(|m| m.modified.datetime))
-                }
-                PitouFileSortOrder::Decreasing => items.sort_unstable_by_key(|v| {
-                    v.metadata.as_ref().map(|m| Reverse(m.modified.datetime))
-                }),
-            },
-            PitouFileSort::DateAccessed(order) => match order {
-                PitouFileSortOrder::Increasing => {
-                    items.sort_unstable_by_key(|v| v.metadata.as_ref().map(|m| m.accessed.datetime))
-                }
-                PitouFileSortOrder::Decreasing => items.sort_unstable_by_key(|v| {
-                    v.metadata.as_ref().map(|m| Reverse(m.accessed.datetime))
-                }),
-            },
+    pub fn new(key: PitouFileSortKey, order: PitouFileSortOrder) -> Self {
+        Self {
+            key,
+            order,
+            then: None,
+            folders_first: true,
+            natural: true,
+            case_sensitive: false,
+        }
+    }
+
+    /// Sorts `items`, calling `mime` once per item when sorting by MIME type.
+    pub fn sorted_with<M: Ord>(
+        self,
+        items: Vec<PitouFile>,
+        mime: impl Fn(&PitouFile) -> M,
+    ) -> Vec<PitouFile> {
+        let uses_mime = [Some(self.key), self.then].contains(&Some(PitouFileSortKey::Mime));
+        let mut items = items
+            .into_iter()
+            .map(|item| {
+                let mime = uses_mime.then(|| mime(&item));
+                (item, mime)
+            })
+            .collect::<Vec<_>>();
+        items.sort_by(|(a, a_mime), (b, b_mime)| self.compare(a, b, || a_mime.cmp(b_mime)));
+        items.into_iter().map(|(item, _)| item).collect()
+    }
+
+    fn compare(self, a: &PitouFile, b: &PitouFile, mime: impl Fn() -> Ordering) -> Ordering {
+        let folders = if self.folders_first {
+            b.opens_as_dir().cmp(&a.opens_as_dir())
+        } else {
+            Ordering::Equal
+        };
+        let primary = match self.order {
+            PitouFileSortOrder::Increasing => self.compare_by(self.key, a, b, &mime),
+            PitouFileSortOrder::Decreasing => self.compare_by(self.key, a, b, &mime).reverse(),
+        };
+        folders
+            .then(primary)
+            .then_with(|| match self.then {
+                Some(key) => self.compare_by(key, a, b, &mime),
+                None => Ordering::Equal,
+            })
+            .then_with(|| self.compare_by(PitouFileSortKey::Name, a, b, &mime))
+    }
+
+    fn compare_by(
+        self,
+        key: PitouFileSortKey,
+        a: &PitouFile,
+        b: &PitouFile,
+        mime: &impl Fn() -> Ordering,
+    ) -> Ordering {
+        let time = |file: &PitouFile, get: fn(&PitouFileMetadata) -> Option<PitouDateTime>| {
+            file.metadata.as_ref().and_then(get).map(|t| t.datetime)
+        };
+        let size = |file: &PitouFile| file.metadata.as_ref().map(|m| m.size.bytes);
+        match key {
+            PitouFileSortKey::Name => {
+                extra::compare_names(a.name(), b.name(), self.natural, self.case_sensitive)
+            }
+            PitouFileSortKey::DateCreated => time(a, |m| m.created).cmp(&time(b, |m| m.created)),
+            PitouFileSortKey::DateModified => time(a, |m| m.modified).cmp(&time(b, |m| m.modified)),
+            PitouFileSortKey::DateAccessed => time(a, |m| m.accessed).cmp(&time(b, |m| m.accessed)),
+            PitouFileSortKey::Size => size(a).cmp(&size(b)),
+            PitouFileSortKey::Extension => extra::compare_names(
+                a.path.extension(),
+                b.path.extension(),
+                self.natural,
+                self.case_sensitive,
+            ),
+            PitouFileSortKey::Kind => a.kind().cmp(&b.kind()),
+            PitouFileSortKey::Mime => mime(),
         }
-        items
     }
 }
 
+/// Picks items by kind, then leaves out hidden and system items unless they are allowed.
 #[derive(Clone, Copy, Serialize, Deserialize, PartialEq)]
 pub struct PitouFileFilter {
     pub files: bool,
     pub links: bool,
     pub dirs: bool,
     pub sys_items: bool,
+    /// filters saved before hidden items could be left out included them
+    #[serde(default = "PitouFileFilter::default_hidden")]
+    pub hidden: bool,
 }
 
 impl PitouFileFilter {
@@ -393,6 +593,7 @@ impl PitouFileFilter {
             links: true,
             dirs: true,
             sys_items: false,
+            hidden: true,
         }
     }
 
@@ -402,19 +603,23 @@ impl PitouFileFilter {
             links: false,
             dirs: true,
             sys_items: false,
+            hidden: true,
         }
     }
 
+    fn default_hidden() -> bool {
+        true
+    }
+
+    pub fn includes(self, file: &PitouFile) -> bool {
+        let kind = (file.is_dir() && self.dirs)
+            || ((file.is_file() || file.is_special()) && self.files)
+            || (file.is_link() && self.links);
+        kind && (self.hidden || !file.is_hidden()) && (self.sys_items || !file.is_sys_item())
+    }
+
     pub fn map(self, file: PitouFile) -> Option<PitouFile> {
-        if (file.is_dir() && self.dirs)
-            || (file.is_file() && self.files)
-            || (file.is_link() && self.links)
-            || (file.is_sys_item() && self.sys_items)
-        {
-            Some(file)
-        } else {
-            None
-        }
+        self.includes(&file).then_some(file)
     }
 
     pub fn all_filtered(self) -> bool {
@@ -481,6 +686,12 @@ impl ColorTheme {
         spare1: Color(12, 12, 12, 255),
         spare2: Color(129, 199, 132, 255),         // Light green accent
     };
+
+    /// Whether the theme's surface is dark, judged by its perceived brightness.
+    pub fn is_dark(&self) -> bool {
+        let Color(r, g, b, _) = self.background1;
+        299 * r as u32 + 587 * g as u32 + 114 * b as u32 <= 128_000
+    }
 }
 
 #[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
@@ -493,6 +704,8 @@ pub enum AppMenu {
     Locked,
     Recents,
     Cloud,
+    Duplicates,
+    DiskUsage,
     Settings,
 }
 
@@ -553,12 +766,14 @@ pub enum ItemsView {
     Tiles,
 }
 
-#[derive(PartialEq, Serialize, Deserialize)]
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
 pub struct AppSettings {
     pub refresh_rate: u8, // a number in the range 1..=60 (15000)
     pub show_extensions: bool,
     pub hide_system_files: bool,
+    pub hide_hidden_files: bool,
     pub show_thumbnails: bool,
+    pub show_preview: bool,
     pub items_view: ItemsView,
     pub show_parents: bool,
     pub items_sort: Option<PitouFileSort>,
@@ -585,7 +800,9 @@ impl Default for AppSettings {
             refresh_rate: Self::default_refresh_rate(),
             show_extensions: true,
             hide_system_files: true,
+            hide_hidden_files: true,
             show_thumbnails: false,
+            show_preview: false,
             items_view: ItemsView::Rows,
             show_parents: false,
             items_zoom: 1.0,
@@ -594,20 +811,29 @@ impl Default for AppSettings {
     }
 }
 
-#[derive(Serialize, Deserialize)]
-pub struct DirChild {
-    name: String,
-    metadata: Option<PitouFileMetadata>,
-}
-
 #[derive(PartialEq, Clone)]
 pub struct FrontendSearchOptions {
     pub input: String,
     pub search_kind: u8,
-    pub depth: u8,
+    /// `None` searches to any depth
+    pub depth: Option<u32>,
     pub case_sensitive: bool,
     pub hardware_accelerate: bool,
     pub skip_errors: bool,
     pub filter: PitouFileFilter,
     pub max_finds: usize,
+    pub follow_links: bool,
+    pub same_filesystem: bool,
+    pub respect_ignore_files: bool,
+    pub excludes: Vec<String>,
+    pub scope: FrontendSearchScope,
+}
+
+#[derive(PartialEq, Clone)]
+pub enum FrontendSearchScope {
+    /// the folder open in the active tab
+    CurrentFolder,
+    /// the listed folders, searched side by side
+    Folders(Vec<Rc<PitouFile>>),
+    AllDrives,
 }
@@ -1,10 +1,57 @@
-use crate::PitouFile;
+use crate::{duplicates::DuplicateGroup, search::SearchFind};
 use serde::{Deserialize, Serialize};
 use std::{collections::LinkedList, time::Duration};
 
 pub enum SearchMsg {
-    Active(LinkedList<PitouFile>),
-    Terminated(LinkedList<PitouFile>),
+    Active(LinkedList<SearchFind>),
+    Terminated(LinkedList<SearchFind>),
+}
+
+/// The groups confirmed since the last read, along with the overall progress of the duplicate finder.
+pub enum DuplicateMsg {
+    Active(DuplicateProgress, LinkedList<DuplicateGroup>),
+    Terminated(DuplicateProgress, LinkedList<DuplicateGroup>),
+}
+
+#[derive(Clone, Copy, Default, Serialize, Deserialize)]
+pub struct DuplicateProgress {
+    /// files walked so far
+    pub scanned: u64,
+    /// files sharing their size with another file, which all need hashing
+    pub candidates: u64,
+    pub hashed: u64,
+}
+
+#[derive(Clone, Copy, Default, Serialize, Deserialize)]
+pub struct UsageProgress {
+    pub files: u64,
+    pub dirs: u64,
+    pub bytes: u64,
+}
+
+/// The progress of a disk usage scan, terminated once the scan is over and its results are cached.
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub enum UsageMsg {
+    Active(UsageProgress),
+    Terminated(UsageProgress),
+}
+
+/// Recursive totals of the items shown in the properties dialog. Selected folders count
+/// their contents but not themselves.
+#[derive(Clone, Copy, Default, Serialize, Deserialize)]
+pub struct ItemTotals {
+    pub files: u64,
+    pub dirs: u64,
+    pub bytes: u64,
+    /// folders whose contents couldn't be read
+    pub unreadable: u64,
+}
+
+/// The totals counted so far, terminated once every item has been counted.
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub enum TotalsMsg {
+    Active(ItemTotals),
+    Terminated(ItemTotals),
 }
 
 #[derive(Clone, Copy, Serialize, Deserialize)]
@@ -32,6 +79,16 @@ pub enum TransferMsg {
         state: TransferState,
         time_elapsed: Duration,
     },
+    Upload {
+        id: TransferSessionID,
+        state: TransferState,
+        time_elapsed: Duration,
+    },
+    Download {
+        id: TransferSessionID,
+        state: TransferState,
+        time_elapsed: Duration,
+    },
 }
 
 
@@ -48,6 +105,16 @@ impl TransferMsg {
                 state,
                 time_elapsed,
             } => (state, time_elapsed),
+            TransferMsg::Upload {
+                id: _,
+                state,
+                time_elapsed,
+            } => (state, time_elapsed),
+            TransferMsg::Download {
+                id: _,
+                state,
+                time_elapsed,
+            } => (state, time_elapsed),
         }
     }
 
@@ -55,6 +122,8 @@ impl TransferMsg {
         match self {
             Self::Copy { id, state: _, time_elapsed: _ } => *id,
             Self::Move { id, state: _, time_elapsed: _ } => *id,
+            Self::Upload { id, state: _, time_elapsed: _ } => *id,
+            Self::Download { id, state: _, time_elapsed: _ } => *id,
         }
     }
 
@@ -62,6 +131,8 @@ impl TransferMsg {
         match self {
             TransferMsg::Copy { id: _, state, time_elapsed: _ } => state.is_terminted(),
             TransferMsg::Move { id: _, state, time_elapsed: _ } => state.is_terminted(),
+            TransferMsg::Upload { id: _, state, time_elapsed: _ } => state.is_terminted(),
+            TransferMsg::Download { id: _, state, time_elapsed: _ } => state.is_terminted(),
         }
     }
 }
@@ -0,0 +1,113 @@
+use serde::{Deserialize, Serialize};
+
+/// Text files are only previewed up to this many bytes.
+pub const MAX_TEXT_LEN: u64 = 256 * 1024;
+/// Images larger than this are not decoded for a preview.
+pub const MAX_IMAGE_LEN: u64 = 32 * 1024 * 1024;
+/// Binary files show this many bytes of their start.
+pub const MAX_HEX_LEN: u64 = 4 * 1024;
+/// Directories list at most this many of their entries.
+pub const MAX_ENTRIES: usize = 200;
+
+/// What the preview pane shows for a file.
+///
+/// Code and Markdown come rendered as HTML built from escaped text, so they can be inserted as is.
+#[derive(Serialize, Deserialize)]
+pub enum Preview {
+    Code {
+        language: String,
+        html: String,
+        truncated: bool,
+    },
+    Markdown {
+        html: String,
+        truncated: bool,
+    },
+    /// `src` is a `data:` URL of the image, scaled down if it was too large
+    Image {
+        src: String,
+        width: u32,
+        height: u32,
+    },
+    /// the direct contents of a directory, folders first
+    Directory {
+        dirs: u64,
+        files: u64,
+        size: u64,
+        entries: Vec<DirectoryEntry>,
+    },
+    /// the first bytes of a file that isn't text, `len` is the length of the whole file
+    Hex {
+        bytes: Vec<u8>,
+        len: u64,
+    },
+    Empty,
+    Unavailable(String),
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct DirectoryEntry {
+    pub name: String,
+    pub is_dir: bool,
+}
+
+/// A line of a hex dump: the offset, sixteen bytes in hex and their printable ASCII characters.
+pub struct HexRow {
+    pub offset: usize,
+    pub hex: String,
+    pub ascii: String,
+}
+
+pub fn hex_rows(bytes: &[u8]) -> impl Iterator<Item = HexRow> + '_ {
+    bytes.chunks(16).enumerate().map(|(idx, chunk)| {
+        let hex = chunk
+            .iter()
+            .enumerate()
+            .map(|(i, b)| {
+                // an extra space splits the row in halves
+                let sep = if i == 8 {
+                    "  "
+                } else if i > 0 {
+                    " "
+                } else {
+                    ""
+                };
+                format!("{sep}{b:02x}")
+            })
+            .collect();
+        let ascii = chunk
+            .iter()
+            .map(|&b| {
+                if b.is_ascii_graphic() || b == b' ' {
+                    b as char
+                } else {
+                    '.'
+                }
+            })
+            .collect();
+        HexRow {
+            offset: idx * 16,
+            hex,
+            ascii,
+        }
+    })
+}
+
+#[cfg(test)]
+mod test_mod {
+    use super::hex_rows;
+
+    #[test]
+    fn test_hex_rows() {
+        let bytes = (0..20).map(|b| b + 60).collect::<Vec<u8>>();
+        let rows = hex_rows(&bytes).collect::<Vec<_>>();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(
+            rows[0].hex,
+            "3c 3d 3e 3f 40 41 42 43  44 45 46 47 48 49 4a 4b"
+        );
+        assert_eq!(rows[0].ascii, "<=>?@ABCDEFGHIJK");
+        assert_eq!((rows[1].offset, rows[1].ascii.as_str()), (16, "LMNO"));
+        assert_eq!(hex_rows(&[0, 255]).next().unwrap().ascii, "..");
+    }
+}
@@ -0,0 +1,173 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+use crate::{PitouDateTime, PitouFileKind, PitouFilePath};
+
+/// A Unix user or group. The name is missing for ids the system doesn't know about.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Owner {
+    pub id: u32,
+    pub name: Option<String>,
+}
+
+impl Owner {
+    pub fn display(&self) -> String {
+        match &self.name {
+            Some(name) => format!("{name} ({})", self.id),
+            None => self.id.to_string(),
+        }
+    }
+}
+
+/// Everything the properties dialog shows about a single item.
+///
+/// The metadata is that of the item itself, symlinks are not followed.
+/// The Unix specific fields are missing on other platforms.
+pub struct ItemProperties {
+    pub path: PitouFilePath,
+    pub kind: Option<PitouFileKind>,
+    pub size: u64,
+    pub modified: Option<PitouDateTime>,
+    pub accessed: Option<PitouDateTime>,
+    pub created: Option<PitouDateTime>,
+    pub readonly: bool,
+    /// the permission bits along with the file type bits, as given by `st_mode`
+    pub mode: Option<u32>,
+    pub owner: Option<Owner>,
+    pub group: Option<Owner>,
+    pub inode: Option<u64>,
+    pub links: Option<u64>,
+    pub mime: String,
+    /// where a symlink points to, as stored in the link
+    pub link_target: Option<PitouFilePath>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ChecksumKind {
+    Md5,
+    Sha256,
+    Blake3,
+}
+
+impl ChecksumKind {
+    pub const ALL: [Self; 3] = [Self::Md5, Self::Sha256, Self::Blake3];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Md5 => "MD5",
+            Self::Sha256 => "SHA-256",
+            Self::Blake3 => "BLAKE3",
+        }
+    }
+}
+
+/// Permission bits to turn on and off, the others are left as they are.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModeChange {
+    pub set: u32,
+    pub clear: u32,
+}
+
+impl ModeChange {
+    pub fn apply(self, mode: u32) -> u32 {
+        (mode & !self.clear) | self.set
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.set == 0 && self.clear == 0
+    }
+}
+
+/// Changes made from the properties dialog to every selected item, and to everything under
+/// the selected folders if `recursive`. Fields left as `None` are not changed.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct AttributeChanges {
+    pub mode: Option<ModeChange>,
+    /// a user name or a numeric id
+    pub owner: Option<String>,
+    /// a group name or a numeric id
+    pub group: Option<String>,
+    pub modified: Option<PitouDateTime>,
+    pub accessed: Option<PitouDateTime>,
+    pub recursive: bool,
+}
+
+/// An item that couldn't be changed, along with why.
+pub struct ChangeError {
+    pub path: PitouFilePath,
+    pub message: String,
+}
+
+/// The value of an `<input type="datetime-local">` showing `time`.
+pub fn datetime_input(time: PitouDateTime) -> String {
+    time.datetime.format("%Y-%m-%dT%H:%M:%S").to_string()
+}
+
+/// Reads the value of an `<input type="datetime-local">`, which may leave out the seconds.
+pub fn parse_datetime_input(value: &str) -> Option<PitouDateTime> {
+    let value = value.trim();
+    let datetime = NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S")
+        .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M"))
+        .ok()?;
+    Some(PitouDateTime { datetime })
+}
+
+/// Formats `mode` the way `ls -l` does, e.g. `drwxr-xr-x`.
+pub fn mode_string(mode: u32) -> String {
+    let kind = match mode & 0o170000 {
+        0o040000 => 'd',
+        0o120000 => 'l',
+        0o140000 => 's',
+        0o010000 => 'p',
+        0o060000 => 'b',
+        0o020000 => 'c',
+        _ => '-',
+    };
+    let mut out = String::with_capacity(10);
+    out.push(kind);
+    // (read, write, execute, special bit, letter for the special bit)
+    let triples = [(6, 0o4000, 's'), (3, 0o2000, 's'), (0, 0o1000, 't')];
+    for (shift, special, letter) in triples {
+        let bits = (mode >> shift) & 0o7;
+        out.push(if bits & 0o4 != 0 { 'r' } else { '-' });
+        out.push(if bits & 0o2 != 0 { 'w' } else { '-' });
+        let exec = bits & 0o1 != 0;
+        out.push(match (mode & special != 0, exec) {
+            (true, true) => letter,
+            (true, false) => letter.to_ascii_uppercase(),
+            (false, true) => 'x',
+            (false, false) => '-',
+        });
+    }
+    out
+}
+
+#[cfg(test)]
+mod test_mod {
+    use super::{datetime_input, mode_string, parse_datetime_input, ModeChange};
+
+    #[test]
+    fn test_mode_string() {
+        assert_eq!(mode_string(0o040755), "drwxr-xr-x");
+        assert_eq!(mode_string(0o100644), "-rw-r--r--");
+        assert_eq!(mode_string(0o104755), "-rwsr-xr-x");
+        assert_eq!(mode_string(0o041777), "drwxrwxrwt");
+        assert_eq!(mode_string(0o102640), "-rw-r-S---");
+        assert_eq!(mode_string(0o120777), "lrwxrwxrwx");
+
+        let change = ModeChange {
+            set: 0o111,
+            clear: 0o022,
+        };
+        assert_eq!(change.apply(0o100664), 0o100755);
+    }
+
+    #[test]
+    fn test_datetime_input() {
+        let time = parse_datetime_input("2024-02-29T13:05").unwrap();
+        assert_eq!(datetime_input(time), "2024-02-29T13:05:00");
+        let time = parse_datetime_input(&datetime_input(time)).unwrap();
+        assert_eq!(datetime_input(time), "2024-02-29T13:05:00");
+        assert!(parse_datetime_input("").is_none());
+    }
+}
@@ -0,0 +1,205 @@
+use std::cmp::Reverse;
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::{PitouDateTime, PitouFile, PitouFilePath};
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RecentKind {
+    File,
+    Folder,
+}
+
+/// A file that was opened or a folder that was visited.
+pub struct Recent {
+    pub path: PitouFilePath,
+    pub kind: RecentKind,
+    /// when it was last opened or visited
+    pub used: PitouDateTime,
+}
+
+/// How long ago a recent was used, which is what the recents are grouped by.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RecentPeriod {
+    Today,
+    Yesterday,
+    ThisWeek,
+    ThisMonth,
+    Older,
+}
+
+impl RecentPeriod {
+    /// The period of something used on `day`, seen on `today`. Days after `today`, which a
+    /// change of the clock can leave behind, count as today.
+    pub fn of(day: NaiveDate, today: NaiveDate) -> Self {
+        match (today - day).num_days() {
+            ..=0 => Self::Today,
+            1 => Self::Yesterday,
+            2..=6 => Self::ThisWeek,
+            7..=30 => Self::ThisMonth,
+            _ => Self::Older,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Today => "Today",
+            Self::Yesterday => "Yesterday",
+            Self::ThisWeek => "Earlier this week",
+            Self::ThisMonth => "Earlier this month",
+            Self::Older => "Older",
+        }
+    }
+}
+
+/// A recent as listed, along with the file it leads to.
+pub struct RecentItem {
+    pub recent: Recent,
+    pub period: RecentPeriod,
+    pub target: PitouFile,
+}
+
+/// The recent files and folders, most recently used first.
+pub struct Recents {
+    pub items: Vec<Recent>,
+    /// unset while the user has paused tracking, which leaves what is already listed alone
+    pub tracking: bool,
+    /// the last use taken from the desktop's history, so that merging it again doesn't bring
+    /// back what was forgotten or cleared since
+    pub merged_until: Option<PitouDateTime>,
+}
+
+impl Default for Recents {
+    fn default() -> Self {
+        Self {
+            items: Vec::new(),
+            tracking: true,
+            merged_until: None,
+        }
+    }
+}
+
+impl Recents {
+    /// How many recents are kept, the least recently used being dropped first.
+    pub const LIMIT: usize = 500;
+
+    /// Lists `recent` first, in place of an earlier use of the same path.
+    pub fn record(&mut self, recent: Recent) {
+        if !self.tracking {
+            return;
+        }
+        self.items.retain(|saved| saved.path != recent.path);
+        self.items.insert(0, recent);
+        self.items.truncate(Self::LIMIT);
+    }
+
+    /// Adds the uses from the desktop's history made since the last merge, keeping the later use
+    /// of a path that is listed already. Returns how many uses were taken.
+    pub fn merge(&mut self, history: Vec<Recent>) -> usize {
+        if !self.tracking {
+            return 0;
+        }
+        let since = self.merged_until.map(|time| time.datetime);
+        let mut merged = 0;
+        for recent in history {
+            if since.is_some_and(|since| recent.used.datetime <= since) {
+                continue;
+            }
+            let latest = self.merged_until.map(|time| time.datetime);
+            if latest < Some(recent.used.datetime) {
+                self.merged_until = Some(recent.used);
+            }
+            merged += 1;
+            match self
+                .items
+                .iter_mut()
+                .find(|saved| saved.path == recent.path)
+            {
+                Some(saved) if saved.used.datetime >= recent.used.datetime => (),
+                Some(saved) => *saved = recent,
+                None => self.items.push(recent),
+            }
+        }
+        if merged > 0 {
+            self.items
+                .sort_by_key(|recent| Reverse(recent.used.datetime));
+            self.items.truncate(Self::LIMIT);
+        }
+        merged
+    }
+
+    pub fn forget(&mut self, paths: &[PitouFilePath]) {
+        self.items.retain(|saved| !paths.contains(&saved.path))
+    }
+
+    pub fn clear(&mut self) {
+        self.items.clear()
+    }
+
+    /// Pauses or resumes tracking. What the desktop's history gained while tracking was paused
+    /// is skipped when it resumes.
+    pub fn set_tracking(&mut self, tracking: bool, now: PitouDateTime) {
+        if tracking && !self.tracking {
+            self.merged_until = Some(now);
+        }
+        self.tracking = tracking;
+    }
+}
+
+#[cfg(test)]
+mod test_mod {
+    use std::path::PathBuf;
+
+    use chrono::NaiveDate;
+
+    use super::{Recent, RecentKind, RecentPeriod, Recents};
+    use crate::{PitouDateTime, PitouFilePath};
+
+    fn recent(path: &str, used: i64) -> Recent {
+        Recent {
+            path: PitouFilePath::from_pathbuf(PathBuf::from(path)),
+            kind: RecentKind::File,
+            used: PitouDateTime::from_timestamp_millis(used).unwrap(),
+        }
+    }
+
+    fn listed(recents: &Recents) -> Vec<&str> {
+        recents
+            .items
+            .iter()
+            .map(|recent| recent.path.name())
+            .collect()
+    }
+
+    #[test]
+    fn test_recents() {
+        let mut recents = Recents::default();
+        recents.record(recent("/a", 10));
+        recents.record(recent("/b", 20));
+        recents.record(recent("/a", 30));
+        assert_eq!(listed(&recents), ["a", "b"]);
+
+        assert_eq!(recents.merge(vec![recent("/c", 25), recent("/a", 5)]), 2);
+        assert_eq!(listed(&recents), ["a", "c", "b"]);
+        assert_eq!(recents.items[0].used.timestamp_millis(), 30);
+
+        recents.forget(&[recent("/c", 0).path]);
+        assert_eq!(recents.merge(vec![recent("/c", 25), recent("/d", 40)]), 1);
+        assert_eq!(listed(&recents), ["d", "a", "b"]);
+
+        recents.set_tracking(false, PitouDateTime::from_timestamp_millis(50).unwrap());
+        recents.record(recent("/e", 60));
+        assert_eq!(recents.merge(vec![recent("/f", 70)]), 0);
+        recents.set_tracking(true, PitouDateTime::from_timestamp_millis(80).unwrap());
+        assert_eq!(recents.merge(vec![recent("/f", 70)]), 0);
+        assert_eq!(listed(&recents), ["d", "a", "b"]);
+
+        let today = NaiveDate::from_ymd_opt(2024, 3, 10).unwrap();
+        let period = |day| RecentPeriod::of(NaiveDate::from_ymd_opt(2024, 3, day).unwrap(), today);
+        assert!(period(11) == RecentPeriod::Today);
+        assert!(period(9) == RecentPeriod::Yesterday);
+        assert!(period(4) == RecentPeriod::ThisWeek);
+        assert!(period(3) == RecentPeriod::ThisMonth);
+    }
+}
@@ -0,0 +1,138 @@
+use serde::{Deserialize, Serialize};
+
+/// The outcome of a successful fuzzy match: a relevance score (higher is better)
+/// and the char indices of `input` that were matched against the key.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct FuzzyMatch {
+    pub score: i32,
+    pub positions: Vec<usize>,
+}
+
+const MATCH_SCORE: i32 = 16;
+const CONSECUTIVE_BONUS: i32 = 8;
+const BOUNDARY_BONUS: i32 = 10;
+const CAMEL_BONUS: i32 = 6;
+const GAP_START_PENALTY: i32 = 3;
+const GAP_EXTENSION_PENALTY: i32 = 1;
+
+fn is_separator(c: char) -> bool {
+    matches!(c, '_' | '-' | '.' | ' ' | '/' | '\\')
+}
+
+fn char_bonus(prev: Option<char>, cur: char) -> i32 {
+    match prev {
+        None => BOUNDARY_BONUS,
+        Some(p) if is_separator(p) => BOUNDARY_BONUS,
+        Some(p) if p.is_lowercase() && cur.is_uppercase() => CAMEL_BONUS,
+        Some(p) if !p.is_ascii_digit() && cur.is_ascii_digit() => CAMEL_BONUS,
+        _ => 0,
+    }
+}
+
+fn chars_eq(a: char, b: char, sensitive: bool) -> bool {
+    if sensitive {
+        a == b
+    } else {
+        a == b || a.to_lowercase().eq(b.to_lowercase())
+    }
+}
+
+/// Scores `input` against `key`, requiring every char of the key to appear in `input` in order.
+///
+/// Matches that land on word boundaries (start of name, after `_`, `-`, `.`, spaces or on a camelCase hump)
+/// and runs of consecutive chars are rewarded, while gaps between matched chars are penalized,
+/// so `"cgt"` ranks `Cargo.toml` above `accounting.txt`.
+pub fn fuzzy_match(key: &str, input: &str, sensitive: bool) -> Option<FuzzyMatch> {
+    let key = key
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<Vec<_>>();
+    let input = input.chars().collect::<Vec<_>>();
+    let (n, m) = (key.len(), input.len());
+    if n == 0 || n > m {
+        return None;
+    }
+
+    let bonuses = (0..m)
+        .map(|j| char_bonus(j.checked_sub(1).map(|p| input[p]), input[j]))
+        .collect::<Vec<_>>();
+
+    // scores[i][j] is the best score of matching key[..=i] with key[i] landing on input[j],
+    // and from[i][j] remembers where key[i - 1] landed for that best score.
+    let mut scores = vec![vec![None::<i32>; m]; n];
+    let mut from = vec![vec![0usize; m]; n];
+
+    for j in 0..m {
+        if chars_eq(key[0], input[j], sensitive) {
+            // leading unmatched chars cost less than gaps between matched chars
+            scores[0][j] = Some(MATCH_SCORE + bonuses[j] - j as i32 * GAP_EXTENSION_PENALTY / 2);
+        }
+    }
+
+    for i in 1..n {
+        // best value of (scores[i - 1][k] + k) over k < j - 1, used for non-adjacent predecessors
+        let mut gapped: Option<(i32, usize)> = None;
+        for j in i..m {
+            if j >= 2 {
+                if let Some(prev) = scores[i - 1][j - 2] {
+                    let val = prev + (j - 2) as i32 * GAP_EXTENSION_PENALTY;
+                    if gapped.map(|(best, _)| val > best).unwrap_or(true) {
+                        gapped = Some((val, j - 2));
+                    }
+                }
+            }
+            if !chars_eq(key[i], input[j], sensitive) {
+                continue;
+            }
+            let adjacent = scores[i - 1][j - 1].map(|s| (s + CONSECUTIVE_BONUS, j - 1));
+            let separated = gapped.map(|(best, k)| {
+                let gap_cost = GAP_START_PENALTY + (j as i32 - 1) * GAP_EXTENSION_PENALTY;
+                (best - gap_cost, k)
+            });
+            let best = match (adjacent, separated) {
+                (Some(a), Some(s)) => Some(if a.0 >= s.0 { a } else { s }),
+                (a, s) => a.or(s),
+            };
+            if let Some((score, k)) = best {
+                scores[i][j] = Some(score + MATCH_SCORE + bonuses[j]);
+                from[i][j] = k;
+            }
+        }
+    }
+
+    let (mut end, score) = scores[n - 1]
+        .iter()
+        .enumerate()
+        .filter_map(|(j, s)| s.map(|s| (j, s)))
+        .max_by_key(|&(j, s)| (s, std::cmp::Reverse(j)))?;
+
+    let mut positions = vec![0; n];
+    for i in (0..n).rev() {
+        positions[i] = end;
+        end = from[i][end];
+    }
+
+    Some(FuzzyMatch { score, positions })
+}
+
+#[cfg(test)]
+mod test_mod {
+    use super::*;
+
+    #[test]
+    fn test_fuzzy_match() {
+        assert!(fuzzy_match("xyz", "Cargo.toml", false).is_none());
+        assert!(fuzzy_match("CT", "cargo.toml", true).is_none());
+
+        let m = fuzzy_match("cgt", "Cargo.toml", false).unwrap();
+        assert_eq!(m.positions, vec![0, 3, 6]);
+
+        let boundary = fuzzy_match("cgt", "Cargo.toml", false).unwrap().score;
+        let buried = fuzzy_match("cgt", "accounting.txt", false).unwrap().score;
+        assert!(boundary > buried);
+
+        let consecutive = fuzzy_match("main", "main.rs", false).unwrap().score;
+        let scattered = fuzzy_match("main", "my_animation.rs", false).unwrap().score;
+        assert!(consecutive > scattered);
+    }
+}
@@ -1,23 +1,98 @@
 use std::rc::Rc;
 
-use crate::{FrontendSearchOptions, PitouFile, PitouFileFilter};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    FrontendSearchOptions, FrontendSearchScope, PitouFile, PitouFileFilter, PitouFilePath,
+};
+
+pub mod fuzzy;
+
+use fuzzy::FuzzyMatch;
+
+/// A single search result tagged with the search root it was found under.
+/// `relevance` is only set by searches that rank their finds (fuzzy searches).
+pub struct SearchFind {
+    pub file: PitouFile,
+    pub relevance: Option<FuzzyMatch>,
+    pub root: PitouFilePath,
+}
+
+impl SearchFind {
+    pub fn score(&self) -> Option<i32> {
+        self.relevance.as_ref().map(|v| v.score)
+    }
+}
+
+/// Why a search couldn't start.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub enum SearchError {
+    /// the search key isn't a valid pattern of the search type
+    InvalidKey,
+    /// the exclude pattern isn't a valid glob
+    InvalidExclude(String),
+}
+
+impl SearchError {
+    pub fn message(&self) -> String {
+        match self {
+            Self::InvalidKey => "The search key isn't a valid pattern".to_owned(),
+            Self::InvalidExclude(pattern) => format!("\"{pattern}\" isn't a valid exclude pattern"),
+        }
+    }
+}
+
+/// Where a search looks. Every root is searched side by side and the finds are tagged with their root.
+pub enum SearchScope {
+    Roots(Vec<PitouFilePath>),
+    AllDrives,
+}
+
+impl SearchScope {
+    /// Resolves the frontend's scope against the folder open in the active tab.
+    /// Searching from the "Drives" pseudo-folder searches every drive.
+    pub fn build_from(scope: FrontendSearchScope, current_dir: Rc<PitouFile>) -> Self {
+        match scope {
+            FrontendSearchScope::CurrentFolder
+                if current_dir.path().path.as_os_str().is_empty() =>
+            {
+                Self::AllDrives
+            }
+            FrontendSearchScope::CurrentFolder => Self::Roots(vec![current_dir.clone_inner().path]),
+            FrontendSearchScope::Folders(folders) => {
+                Self::Roots(folders.iter().map(|f| f.clone_inner().path).collect())
+            }
+            FrontendSearchScope::AllDrives => Self::AllDrives,
+        }
+    }
+}
+
+/// A search saved under a name. It opens as a smart folder whose children are its results at the time of opening.
+pub struct SavedSearch {
+    pub name: String,
+    pub options: SimplifiedSearchOptions,
+}
 
 pub struct SimplifiedSearchOptions {
-    pub search_dir: PitouFile,
+    pub scope: SearchScope,
     pub input: String,
     pub search_kind: u8,
-    pub depth: u8,
+    pub depth: Option<u32>,
     pub case_sensitive: bool,
     pub hardware_accelerate: bool,
     pub skip_errors: bool,
     pub filter: PitouFileFilter,
     pub max_finds: usize,
+    pub follow_links: bool,
+    pub same_filesystem: bool,
+    pub respect_ignore_files: bool,
+    pub excludes: Vec<String>,
 }
 
 impl SimplifiedSearchOptions {
     pub fn build_from(options: FrontendSearchOptions, current_dir: Rc<PitouFile>) -> Self {
         Self {
-            search_dir: current_dir.clone_inner(),
+            scope: SearchScope::build_from(options.scope, current_dir),
             input: options.input,
             search_kind: options.search_kind,
             depth: options.depth,
@@ -26,6 +101,10 @@ impl SimplifiedSearchOptions {
             skip_errors: options.skip_errors,
             filter: options.filter,
             max_finds: options.max_finds,
+            follow_links: options.follow_links,
+            same_filesystem: options.same_filesystem,
+            respect_ignore_files: options.respect_ignore_files,
+            excludes: options.excludes,
         }
     }
 }
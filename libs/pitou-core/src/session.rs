@@ -0,0 +1,121 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{AppMenu, PitouFilePath};
+
+/// A tab as it was when pitou last closed.
+#[derive(PartialEq)]
+pub struct SavedTab {
+    pub menu: AppMenu,
+    /// the folders visited in the tab, oldest first
+    pub history: Vec<PitouFilePath>,
+    /// the position in `history` of the folder the tab was showing
+    pub current: usize,
+}
+
+impl SavedTab {
+    /// Drops the folders `exists` rejects from the history, keeping the drives list which has no path.
+    /// The tab moves to the closest folder before the one it showed, or after it if none is left before it,
+    /// and back home if it was exploring and its history is gone.
+    fn retain_dirs(&mut self, exists: &impl Fn(&Path) -> bool) {
+        let mut kept_before = 0;
+        let mut idx = 0;
+        self.history.retain(|dir| {
+            let keep = dir.path.as_os_str().is_empty() || exists(&dir.path);
+            if keep && idx <= self.current {
+                kept_before += 1;
+            }
+            idx += 1;
+            keep
+        });
+        self.current = kept_before.max(1) - 1;
+        if self.history.is_empty() && self.menu == AppMenu::Explorer {
+            self.menu = AppMenu::Home;
+        }
+    }
+}
+
+/// The open tabs and window size as they were when pitou last closed.
+#[derive(PartialEq, Serialize, Deserialize)]
+pub struct SavedSession {
+    pub tabs: Vec<SavedTab>,
+    pub active_tab: usize,
+    pub app_width: i32,
+    pub app_height: i32,
+}
+
+impl SavedSession {
+    /// Drops the folders that `exists` rejects from every tab, as they may have been removed
+    /// since the session was saved. Returns `None` if there is no tab to restore.
+    pub fn retain_dirs(mut self, exists: impl Fn(&Path) -> bool) -> Option<Self> {
+        self.tabs
+            .iter_mut()
+            .for_each(|tab| tab.retain_dirs(&exists));
+        if self.tabs.is_empty() {
+            return None;
+        }
+        self.active_tab = self.active_tab.min(self.tabs.len() - 1);
+        Some(self)
+    }
+}
+
+#[cfg(test)]
+mod test_mod {
+    use std::path::{Path, PathBuf};
+
+    use super::{SavedSession, SavedTab};
+    use crate::{AppMenu, PitouFilePath};
+
+    fn tab(menu: AppMenu, history: &[&str], current: usize) -> SavedTab {
+        let history = history
+            .iter()
+            .map(|dir| PitouFilePath::from_pathbuf(PathBuf::from(dir)))
+            .collect();
+        SavedTab {
+            menu,
+            history,
+            current,
+        }
+    }
+
+    #[test]
+    fn test_retain_dirs() {
+        let session = SavedSession {
+            tabs: vec![
+                tab(AppMenu::Explorer, &["/a", "/gone", "", "/b", "/gone/c"], 1),
+                tab(AppMenu::Explorer, &["/gone", "/a"], 0),
+                tab(AppMenu::Explorer, &["/gone"], 0),
+                tab(AppMenu::Search, &["/gone"], 0),
+            ],
+            active_tab: 7,
+            app_width: 800,
+            app_height: 600,
+        };
+        let exists = |dir: &Path| !dir.starts_with("/gone");
+        let session = session.retain_dirs(exists).unwrap();
+
+        let histories = session
+            .tabs
+            .iter()
+            .map(|tab| {
+                let dirs = tab.history.iter().map(|dir| dir.path.to_str().unwrap());
+                (dirs.collect::<Vec<_>>(), tab.current)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(histories[0], (vec!["/a", "", "/b"], 0));
+        assert_eq!(histories[1], (vec!["/a"], 0));
+        assert_eq!(histories[2], (vec![], 0));
+        assert!(session.tabs[2].menu == AppMenu::Home);
+        assert!(session.tabs[3].menu == AppMenu::Search);
+        assert_eq!(session.active_tab, 3);
+
+        let empty = SavedSession {
+            tabs: Vec::new(),
+            active_tab: 0,
+            app_width: 800,
+            app_height: 600,
+        };
+        assert!(empty.retain_dirs(exists).is_none());
+    }
+}
@@ -0,0 +1,117 @@
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{themes::ThemeSettings, AppSettings, ColorTheme};
+
+/// The settings pitou keeps between runs, as written to its settings file.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedSettings {
+    /// the `SavedSettings::VERSION` the file was written with
+    pub version: u32,
+    pub color_theme: ColorTheme,
+    pub app_settings: AppSettings,
+    pub themes: ThemeSettings,
+}
+
+impl Default for SavedSettings {
+    fn default() -> Self {
+        Self {
+            version: Self::VERSION,
+            color_theme: ColorTheme::DEFAULT_DARK,
+            app_settings: AppSettings::default(),
+            themes: ThemeSettings::default(),
+        }
+    }
+}
+
+impl SavedSettings {
+    /// Version 0 is the bare `GenCtx` exported before settings were versioned, which holds the same
+    /// fields. A later version that renames or reshapes a field converts it in `migrate`.
+    pub const VERSION: u32 = 1;
+
+    /// Reads settings written by any version of pitou. Fields that are missing or no longer valid keep their
+    /// defaults and fields that aren't known are dropped, so only input that isn't a JSON object fails.
+    pub fn migrate(saved: Value) -> Option<Self> {
+        let Value::Object(saved) = saved else {
+            return None;
+        };
+        let mut settings = Self::default();
+        if let Some(theme) = saved.get("color_theme") {
+            settings.color_theme = overlay(settings.color_theme, theme);
+        }
+        if let Some(app_settings) = saved.get("app_settings") {
+            settings.app_settings = overlay(settings.app_settings, app_settings);
+        }
+        if let Some(themes) = saved.get("themes") {
+            settings.themes = ThemeSettings::migrate(themes);
+        }
+        // a refresh rate of 0 would divide by zero
+        let rate = &mut settings.app_settings.refresh_rate;
+        *rate = (*rate).clamp(1, 60);
+        Some(settings)
+    }
+
+    pub fn from_json(json: &str) -> Option<Self> {
+        serde_json::from_str(json).ok().and_then(Self::migrate)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+}
+
+/// `base` with each field of `saved` that reads as the field of the same name in it swapped in.
+pub(crate) fn overlay<T: Serialize + DeserializeOwned>(base: T, saved: &Value) -> T {
+    let (Ok(Value::Object(mut merged)), Value::Object(saved)) =
+        (serde_json::to_value(&base), saved)
+    else {
+        return base;
+    };
+    for (key, value) in saved {
+        let Some(previous) = merged.get(key).cloned() else {
+            continue;
+        };
+        merged.insert(key.clone(), value.clone());
+        if serde_json::from_value::<T>(Value::Object(merged.clone())).is_err() {
+            merged.insert(key.clone(), previous);
+        }
+    }
+    serde_json::from_value(Value::Object(merged)).unwrap_or(base)
+}
+
+#[cfg(test)]
+mod test_mod {
+    use serde_json::json;
+
+    use super::SavedSettings;
+    use crate::{AppSettings, ColorTheme, ItemsView};
+
+    #[test]
+    fn test_migrate() {
+        let unversioned = json!({
+            "app_width": 1366,
+            "app_height": 768,
+            "color_theme": { "background1": [1, 2, 3, 4] },
+            "app_settings": {
+                "refresh_rate": 0,
+                "show_extensions": false,
+                "items_view": "Carousel",
+                "removed_setting": true
+            }
+        });
+        let settings = SavedSettings::migrate(unversioned).unwrap();
+        assert_eq!(settings.version, SavedSettings::VERSION);
+        assert!(settings.color_theme.background1 == crate::Color(1, 2, 3, 4));
+        assert!(settings.color_theme.spare2 == ColorTheme::DEFAULT_DARK.spare2);
+        assert_eq!(settings.app_settings.refresh_rate, 1);
+        assert!(!settings.app_settings.show_extensions);
+        assert!(settings.app_settings.items_view == ItemsView::Rows);
+        assert!(
+            settings.app_settings.hide_hidden_files == AppSettings::default().hide_hidden_files
+        );
+
+        let saved = SavedSettings::default().to_json();
+        assert!(SavedSettings::from_json(&saved).unwrap() == SavedSettings::default());
+        assert!(SavedSettings::from_json("[1, 2]").is_none());
+    }
+}
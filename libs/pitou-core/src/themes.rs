@@ -0,0 +1,230 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{settings::overlay, Color, ColorTheme};
+
+/// A color theme with the name it is listed under, which is also the format of a theme file.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct NamedTheme {
+    pub name: String,
+    pub theme: ColorTheme,
+}
+
+impl NamedTheme {
+    fn new(name: &str, theme: ColorTheme) -> Self {
+        Self {
+            name: name.to_owned(),
+            theme,
+        }
+    }
+
+    pub fn built_in() -> Vec<Self> {
+        vec![
+            Self::new("Default Dark", ColorTheme::DEFAULT_DARK),
+            Self::new("Default Light", ColorTheme::DEFAULT_LIGHT),
+            Self::new("Gem Dark", ColorTheme::GEM_DARK),
+            Self::new("Polish Dark", ColorTheme::POLISH_DARK),
+        ]
+    }
+
+    /// Reads a theme that may have been written by hand. Slots that are missing or invalid are
+    /// taken from the default dark theme and a missing name reads as "Imported theme".
+    pub fn from_value(saved: &Value) -> Option<Self> {
+        let theme = saved.get("theme").filter(|theme| theme.is_object())?;
+        let name = saved
+            .get("name")
+            .and_then(Value::as_str)
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .unwrap_or("Imported theme");
+        Some(Self::new(name, overlay(ColorTheme::DEFAULT_DARK, theme)))
+    }
+
+    pub fn from_json(json: &str) -> Option<Self> {
+        Self::from_value(&serde_json::from_str(json).ok()?)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+}
+
+/// The themes used while the desktop prefers a light or a dark color scheme.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SystemThemes {
+    pub light: ColorTheme,
+    pub dark: ColorTheme,
+}
+
+impl Default for SystemThemes {
+    fn default() -> Self {
+        Self {
+            light: ColorTheme::DEFAULT_LIGHT,
+            dark: ColorTheme::DEFAULT_DARK,
+        }
+    }
+}
+
+impl SystemThemes {
+    pub fn pick(&self, prefers_dark: bool) -> ColorTheme {
+        if prefers_dark {
+            self.dark
+        } else {
+            self.light
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct ThemeSettings {
+    /// themes made or imported by the user, listed after the built-in ones
+    pub custom: Vec<NamedTheme>,
+    /// set while the theme follows the desktop's preferred color scheme
+    pub follow_system: Option<SystemThemes>,
+}
+
+impl ThemeSettings {
+    /// Reads saved theme settings, dropping the custom themes that can't be read.
+    pub fn migrate(saved: &Value) -> Self {
+        let custom = saved
+            .get("custom")
+            .and_then(Value::as_array)
+            .map(|themes| themes.iter().filter_map(NamedTheme::from_value).collect())
+            .unwrap_or_default();
+        let follow_system = saved
+            .get("follow_system")
+            .filter(|themes| themes.is_object())
+            .map(|themes| overlay(SystemThemes::default(), themes));
+        Self {
+            custom,
+            follow_system,
+        }
+    }
+
+    /// The built-in themes followed by the custom ones.
+    pub fn available(&self) -> Vec<NamedTheme> {
+        let mut themes = NamedTheme::built_in();
+        themes.extend(self.custom.iter().cloned());
+        themes
+    }
+
+    pub fn is_custom(&self, name: &str) -> bool {
+        self.custom.iter().any(|theme| theme.name == name)
+    }
+
+    /// Adds `theme` in place of the custom theme of the same name, if any. A theme named like a
+    /// built-in one is numbered so that both stay listed. Returns the name it was added under.
+    pub fn add(&mut self, mut theme: NamedTheme) -> String {
+        let built_in = NamedTheme::built_in();
+        let taken = |name: &str| built_in.iter().any(|theme| theme.name == name);
+        if taken(&theme.name) {
+            let base = std::mem::take(&mut theme.name);
+            theme.name = (2..)
+                .map(|n| format!("{base} {n}"))
+                .find(|name| !taken(name))
+                .unwrap_or(base);
+        }
+        let name = theme.name.clone();
+        match self.custom.iter_mut().find(|saved| saved.name == name) {
+            Some(saved) => *saved = theme,
+            None => self.custom.push(theme),
+        }
+        name
+    }
+
+    pub fn remove(&mut self, name: &str) {
+        self.custom.retain(|theme| theme.name != name)
+    }
+}
+
+impl Color {
+    /// The color as `#rrggbb`, the value of a color input, which leaves out the alpha.
+    pub fn to_hex(self) -> String {
+        format!("#{:02x}{:02x}{:02x}", self.0, self.1, self.2)
+    }
+
+    /// Reads `#rrggbb` with the given alpha.
+    pub fn from_hex(hex: &str, alpha: u8) -> Option<Self> {
+        let hex = hex.strip_prefix('#')?;
+        if hex.len() != 6 || !hex.is_ascii() {
+            return None;
+        }
+        let channel = |idx: usize| u8::from_str_radix(&hex[idx..idx + 2], 16).ok();
+        Some(Self(channel(0)?, channel(2)?, channel(4)?, alpha))
+    }
+}
+
+impl ColorTheme {
+    /// The name of each slot of a theme, in the order `slots` lists them.
+    pub const SLOT_NAMES: [&'static str; 6] = [
+        "Primary background",
+        "Secondary background",
+        "Primary foreground",
+        "Secondary foreground",
+        "Primary spare",
+        "Secondary spare",
+    ];
+
+    pub fn slots(&self) -> [Color; 6] {
+        [
+            self.background1,
+            self.background2,
+            self.foreground1,
+            self.foreground2,
+            self.spare1,
+            self.spare2,
+        ]
+    }
+
+    pub fn slot_mut(&mut self, idx: usize) -> Option<&mut Color> {
+        match idx {
+            0 => Some(&mut self.background1),
+            1 => Some(&mut self.background2),
+            2 => Some(&mut self.foreground1),
+            3 => Some(&mut self.foreground2),
+            4 => Some(&mut self.spare1),
+            5 => Some(&mut self.spare2),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_mod {
+    use serde_json::json;
+
+    use super::{NamedTheme, ThemeSettings};
+    use crate::{Color, ColorTheme};
+
+    #[test]
+    fn test_themes() {
+        let file = json!({ "name": "  ", "theme": { "spare1": [1, 2, 3, 4], "spare2": "red" } });
+        let imported = NamedTheme::from_value(&file).unwrap();
+        assert_eq!(imported.name, "Imported theme");
+        assert!(imported.theme.spare1 == Color(1, 2, 3, 4));
+        assert!(imported.theme.spare2 == ColorTheme::DEFAULT_DARK.spare2);
+        assert!(NamedTheme::from_json(r#"{ "name": "no theme" }"#).is_none());
+        assert!(NamedTheme::from_json(&imported.to_json()).unwrap() == imported);
+
+        let mut themes = ThemeSettings::default();
+        let named = |name: &str| NamedTheme {
+            name: name.to_owned(),
+            theme: ColorTheme::GEM_DARK,
+        };
+        assert_eq!(themes.add(named("Default Dark")), "Default Dark 2");
+        assert_eq!(themes.add(named("Mine")), "Mine");
+        assert_eq!(themes.add(named("Mine")), "Mine");
+        assert_eq!(themes.available().len(), NamedTheme::built_in().len() + 2);
+        themes.remove("Mine");
+        assert!(!themes.is_custom("Mine") && themes.is_custom("Default Dark 2"));
+
+        let saved = json!({ "custom": [{ "name": "Kept", "theme": {} }, 7], "follow_system": {} });
+        let migrated = ThemeSettings::migrate(&saved);
+        assert_eq!(migrated.custom.len(), 1);
+        assert!(migrated.follow_system.unwrap().dark == ColorTheme::DEFAULT_DARK);
+
+        let color = Color(18, 171, 255, 128);
+        assert!(Color::from_hex(&color.to_hex(), 128).unwrap() == color);
+        assert!(Color::from_hex("12ab", 255).is_none());
+    }
+}
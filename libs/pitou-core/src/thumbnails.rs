@@ -0,0 +1,34 @@
+use serde::{Deserialize, Serialize};
+
+/// The thumbnail sizes of the freedesktop thumbnail cache, each stored in its own folder.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ThumbnailSize {
+    Normal,
+    Large,
+}
+
+impl ThumbnailSize {
+    /// The longest side of a thumbnail of this size, smaller images are never scaled up.
+    pub fn pixels(self) -> u32 {
+        match self {
+            ThumbnailSize::Normal => 128,
+            ThumbnailSize::Large => 256,
+        }
+    }
+
+    pub fn dir_name(self) -> &'static str {
+        match self {
+            ThumbnailSize::Normal => "normal",
+            ThumbnailSize::Large => "large",
+        }
+    }
+}
+
+/// Extensions of the image formats thumbnails can be made of.
+pub const THUMBNAIL_EXTENSIONS: [&str; 7] = ["png", "jpg", "jpeg", "jpe", "webp", "gif", "bmp"];
+
+pub fn has_thumbnail(extension: &str) -> bool {
+    THUMBNAIL_EXTENSIONS
+        .iter()
+        .any(|e| e.eq_ignore_ascii_case(extension))
+}
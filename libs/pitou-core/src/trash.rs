@@ -0,0 +1,101 @@
+use std::cmp::Ordering;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{PitouFilePath, PitouTrashItem};
+
+/// What restoring an item does when something already occupies the path it is restored to.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RestoreConflict {
+    /// leave the item in the trash and report it
+    Skip,
+    /// move whatever is in the way to the trash first
+    Replace,
+    /// restore the item under a numbered name beside whatever is in the way
+    KeepBoth,
+}
+
+/// An item that wasn't restored, along with why.
+pub struct RestoreError {
+    pub id: String,
+    pub path: PitouFilePath,
+    /// whether the item was only held back because its destination is taken,
+    /// in which case it can be restored again with another `RestoreConflict`
+    pub occupied: bool,
+    pub message: String,
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct TrashSummary {
+    pub items: usize,
+    pub folders: usize,
+    /// the size of everything in the trash, leaving out the contents of folders
+    /// on platforms that don't say where trashed folders are kept
+    pub bytes: u64,
+}
+
+/// Limits past which trashed items are purged automatically. Items older than `max_age_days` go first,
+/// then the oldest of the rest until the trash is no bigger than `max_bytes`.
+#[derive(Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct TrashRetention {
+    pub max_age_days: Option<u32>,
+    pub max_bytes: Option<u64>,
+}
+
+impl TrashRetention {
+    pub fn is_unlimited(&self) -> bool {
+        self.max_age_days.is_none() && self.max_bytes.is_none()
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TrashSortKey {
+    Name,
+    OriginalPath,
+    Deleted,
+    /// the entry count for folders, which sort after files
+    Size,
+}
+
+impl TrashSortKey {
+    pub const ALL: [Self; 4] = [Self::Name, Self::OriginalPath, Self::Deleted, Self::Size];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Name => "Name",
+            Self::OriginalPath => "Original location",
+            Self::Deleted => "Date deleted",
+            Self::Size => "Size",
+        }
+    }
+
+    pub fn compare(self, a: &PitouTrashItem, b: &PitouTrashItem) -> Ordering {
+        match self {
+            Self::Name => crate::extra::compare_names(a.name(), b.name(), true, false),
+            Self::OriginalPath => a.path().path.cmp(&b.path().path),
+            Self::Deleted => a
+                .metadata
+                .deleted
+                .datetime
+                .cmp(&b.metadata.deleted.datetime),
+            Self::Size => {
+                (a.is_dir(), a.metadata.size.bytes).cmp(&(b.is_dir(), b.metadata.size.bytes))
+            }
+        }
+    }
+}
+
+impl PitouTrashItem {
+    /// Whether `query` appears in the name or original location of this item, ignoring case.
+    pub fn matches(&self, query: &str) -> bool {
+        let query = query.trim().to_lowercase();
+        query.is_empty()
+            || self.name().to_lowercase().contains(&query)
+            || self
+                .path()
+                .path
+                .to_string_lossy()
+                .to_lowercase()
+                .contains(&query)
+    }
+}
@@ -0,0 +1,42 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{PitouDateTime, PitouFilePath};
+
+/// A directory of a scanned tree with its recursive size, or the files held directly by one.
+///
+/// Subdirectories are only expanded down to the depth that was asked for, deeper ones come without children.
+/// The files held directly by a directory are gathered into a single leaf that isn't a directory.
+pub struct UsageNode {
+    pub name: String,
+    pub path: PitouFilePath,
+    pub size: u64,
+    pub files: u64,
+    pub is_dir: bool,
+    pub children: Vec<UsageNode>,
+}
+
+pub struct LargeFile {
+    pub path: PitouFilePath,
+    pub size: u64,
+}
+
+/// The space taken by files of one extension. Files without one are reported under an empty extension.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct TypeUsage {
+    pub extension: String,
+    pub size: u64,
+    pub count: u64,
+}
+
+/// Summary of a scanned tree, typically a whole drive.
+pub struct UsageReport {
+    pub root: PitouFilePath,
+    pub size: u64,
+    pub files: u64,
+    pub dirs: u64,
+    /// the largest files, largest first
+    pub largest: Vec<LargeFile>,
+    /// largest first
+    pub by_type: Vec<TypeUsage>,
+    pub scanned_at: PitouDateTime,
+}
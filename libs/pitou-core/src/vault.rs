@@ -0,0 +1,222 @@
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    vfs::Location, PitouDateTime, PitouFile, PitouFileKind, PitouFileMetadata, PitouFilePath,
+    PitouFileSize,
+};
+
+/// Where the items of the vault are listed. Nothing is mounted there, so that they are never
+/// taken for the files they came from.
+pub const VAULT_MOUNT: &str = "vault";
+
+/// The shortest passphrase a vault is made with.
+pub const MIN_PASSPHRASE_LEN: usize = 8;
+
+/// The minutes of inactivity after which an unlocked vault can be set to lock itself.
+pub const AUTO_LOCK_CHOICES: [u32; 6] = [1, 5, 15, 30, 60, 0];
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct VaultStatus {
+    pub created: bool,
+    pub unlocked: bool,
+    /// `0` leaves the vault unlocked until it is locked by hand or pitou closes
+    pub auto_lock_minutes: u32,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum VaultError {
+    NotCreated,
+    AlreadyCreated,
+    Locked,
+    WrongPassphrase,
+    WeakPassphrase,
+    /// a file of the vault failed to decrypt, having been changed or cut short
+    Damaged,
+    /// what was asked for isn't in the vault
+    Missing,
+    Io,
+}
+
+impl VaultError {
+    pub fn message(self) -> &'static str {
+        match self {
+            Self::NotCreated => "The vault hasn't been made yet",
+            Self::AlreadyCreated => "A vault has already been made",
+            Self::Locked => "The vault is locked",
+            Self::WrongPassphrase => "The passphrase is wrong",
+            Self::WeakPassphrase => "The passphrase is too short",
+            Self::Damaged => "A file of the vault has been damaged",
+            Self::Missing => "The item is no longer in the vault",
+            Self::Io => "The vault couldn't be read or written",
+        }
+    }
+}
+
+/// A file kept encrypted in the vault. Everything but the id is only stored encrypted.
+pub struct VaultEntry {
+    pub id: String,
+    /// the file name, preceded by the folders below the one that was moved in, if any
+    pub name: String,
+    pub size: u64,
+    pub modified: Option<PitouDateTime>,
+    pub added: PitouDateTime,
+    /// where the file was before it was moved in, which is where it goes back to
+    pub origin: PitouFilePath,
+}
+
+impl VaultEntry {
+    /// Where the entry lists in the vault, below the folders it is kept under.
+    pub fn path(&self) -> PitouFilePath {
+        PitouFilePath::mounted(VAULT_MOUNT, &self.path_in(Path::new("")))
+    }
+
+    /// Whether the entry is `item` of the vault or is kept below it.
+    pub fn is_within(&self, item: &PitouFilePath) -> bool {
+        match item.location() {
+            Location::Mounted { mount, path } if mount == VAULT_MOUNT => {
+                self.path_in(Path::new("")).starts_with(path)
+            }
+            _ => false,
+        }
+    }
+
+    /// The entry as a file at its place in the vault, so that it lists like the files of a folder.
+    pub fn as_file(&self) -> PitouFile {
+        let metadata = PitouFileMetadata {
+            modified: self.modified,
+            accessed: None,
+            created: Some(self.added),
+            size: PitouFileSize { bytes: self.size },
+            kind: PitouFileKind::File,
+            attribute: 0,
+            hidden: false,
+            link: None,
+        };
+        PitouFile {
+            path: self.path(),
+            metadata: Some(metadata),
+        }
+    }
+
+    /// The name of the entry within `dir`, with its folders made relative to it.
+    pub fn path_in(&self, dir: &Path) -> PathBuf {
+        self.name
+            .split('/')
+            .filter(|part| !part.is_empty() && *part != "." && *part != "..")
+            .fold(dir.to_path_buf(), |path, part| path.join(part))
+    }
+}
+
+/// The items right inside `folder` of the vault, empty for its top, as the explorer lists them:
+/// a folder for each one entries are kept under, then the entries right in it.
+pub fn vault_children(entries: &[VaultEntry], folder: &Path) -> Vec<PitouFile> {
+    let mut folders = BTreeSet::new();
+    let mut files = Vec::new();
+    for entry in entries {
+        let path = entry.path_in(Path::new(""));
+        let Ok(rest) = path.strip_prefix(folder) else {
+            continue;
+        };
+        let mut parts = rest.components();
+        match (parts.next(), parts.next()) {
+            (Some(_), None) => files.push(entry.as_file()),
+            (Some(first), Some(_)) => {
+                folders.insert(folder.join(first));
+            }
+            _ => (),
+        }
+    }
+    let folders = folders.into_iter().map(|path| {
+        let metadata = PitouFileMetadata {
+            modified: None,
+            accessed: None,
+            created: None,
+            size: PitouFileSize { bytes: 0 },
+            kind: PitouFileKind::Directory,
+            attribute: 0,
+            hidden: false,
+            link: None,
+        };
+        PitouFile {
+            path: PitouFilePath::mounted(VAULT_MOUNT, &path),
+            metadata: Some(metadata),
+        }
+    });
+    folders.chain(files).collect()
+}
+
+#[cfg(test)]
+mod test_mod {
+    use std::path::{Path, PathBuf};
+
+    use super::{vault_children, VaultEntry};
+    use crate::{PitouDateTime, PitouFilePath};
+
+    #[test]
+    fn test_entry_paths() {
+        let entry = VaultEntry {
+            id: "0".to_owned(),
+            name: "../photos/./2024/beach.jpg".to_owned(),
+            size: 3,
+            modified: None,
+            added: PitouDateTime::from_timestamp_millis(0).unwrap(),
+            origin: PitouFilePath::from_pathbuf(PathBuf::from("/home/user/photos/2024/beach.jpg")),
+        };
+        let restored = entry.path_in(Path::new("/tmp/out"));
+        assert_eq!(restored, Path::new("/tmp/out/photos/2024/beach.jpg"));
+        assert_eq!(entry.as_file().name(), "beach.jpg");
+        assert!(entry.path().path == Path::new("vault://photos/2024/beach.jpg"));
+    }
+
+    #[test]
+    fn test_vault_children() {
+        let entry = |id: &str, name: &str| VaultEntry {
+            id: id.to_owned(),
+            name: name.to_owned(),
+            size: 1,
+            modified: None,
+            added: PitouDateTime::from_timestamp_millis(0).unwrap(),
+            origin: PitouFilePath::from_pathbuf(PathBuf::from("/x")),
+        };
+        let entries = [
+            entry("0", "notes.txt"),
+            entry("1", "photos/2024/beach.jpg"),
+            entry("2", "photos/cat.jpg"),
+            entry("3", "music/song.ogg"),
+        ];
+        let names = |folder: &str| {
+            vault_children(&entries, Path::new(folder))
+                .iter()
+                .map(|f| (f.name().to_owned(), f.is_dir()))
+                .collect::<Vec<_>>()
+        };
+        let named = |name: &str, dir| (name.to_owned(), dir);
+        assert_eq!(
+            names(""),
+            [
+                named("music", true),
+                named("photos", true),
+                named("notes.txt", false)
+            ]
+        );
+        assert_eq!(
+            names("photos"),
+            [named("2024", true), named("cat.jpg", false)]
+        );
+        assert!(names("notes.txt").is_empty());
+
+        let photos = &vault_children(&entries, Path::new(""))[1].path;
+        let within = entries
+            .iter()
+            .filter(|e| e.is_within(photos))
+            .map(|e| e.id.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(within, ["1", "2"]);
+        assert!(!entries[0].is_within(&PitouFilePath::from_pathbuf(PathBuf::from("/"))));
+    }
+}
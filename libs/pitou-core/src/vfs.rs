@@ -0,0 +1,110 @@
+use std::path::{Component, Path, PathBuf};
+
+use crate::PitouFilePath;
+
+/// Where a path leads. Paths of a mounted filesystem are written `<mount>://path/within`,
+/// where the mount name is made of at least two lowercase letters, digits, `+`, `-` or `.`
+/// so that it can't be taken for a Windows drive letter.
+#[derive(PartialEq, Debug)]
+pub enum Location<'a> {
+    /// a path of the local filesystem, as it is
+    Local(&'a Path),
+    /// the path within the filesystem mounted under `mount`, empty for its top
+    Mounted { mount: &'a str, path: PathBuf },
+}
+
+impl<'a> Location<'a> {
+    pub fn of(path: &'a Path) -> Self {
+        let mut components = path.components();
+        let mount = match components.next() {
+            Some(Component::Normal(first)) => first
+                .to_str()
+                .and_then(|first| first.strip_suffix(':'))
+                .filter(|mount| is_mount_name(mount)),
+            _ => None,
+        };
+        match mount {
+            Some(mount) => Self::Mounted {
+                mount,
+                path: components.as_path().to_owned(),
+            },
+            None => Self::Local(path),
+        }
+    }
+}
+
+pub fn is_mount_name(name: &str) -> bool {
+    name.len() >= 2
+        && name.starts_with(|c: char| c.is_ascii_lowercase())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '+' | '-' | '.'))
+}
+
+impl PitouFilePath {
+    pub fn location(&self) -> Location<'_> {
+        Location::of(&self.path)
+    }
+
+    /// The item at `path` within the filesystem mounted under `mount`.
+    pub fn mounted(mount: &str, path: &Path) -> Self {
+        let within = path
+            .components()
+            .filter_map(|component| match component {
+                Component::Normal(part) => Some(part.to_string_lossy()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join("/");
+        Self::from_pathbuf(PathBuf::from(format!("{mount}://{within}")))
+    }
+
+    pub fn is_mounted(&self) -> bool {
+        matches!(self.location(), Location::Mounted { .. })
+    }
+}
+
+#[cfg(test)]
+mod test_mod {
+    use std::path::{Path, PathBuf};
+
+    use super::Location;
+    use crate::PitouFilePath;
+
+    #[test]
+    fn test_locations() {
+        let mounted = PitouFilePath::mounted("scratch", Path::new("docs/notes.txt"));
+        assert_eq!(mounted.path, PathBuf::from("scratch://docs/notes.txt"));
+        assert_eq!(
+            mounted.location(),
+            Location::Mounted {
+                mount: "scratch",
+                path: PathBuf::from("docs/notes.txt")
+            }
+        );
+        assert_eq!(mounted.name(), "notes.txt");
+
+        // going up from the top of a mount stays in it
+        let top =
+            PitouFilePath::from_pathbuf(mounted.path.parent().unwrap().parent().unwrap().into());
+        assert_eq!(
+            top.location(),
+            Location::Mounted {
+                mount: "scratch",
+                path: PathBuf::new()
+            }
+        );
+
+        let local = Path::new("/home/me/docs");
+        assert_eq!(Location::of(local), Location::Local(local));
+        assert_eq!(
+            Location::of(Path::new("c:")),
+            Location::Local(Path::new("c:"))
+        );
+        assert_eq!(
+            Location::of(Path::new("Notes:/x")),
+            Location::Local(Path::new("Notes:/x"))
+        );
+        assert!(!PitouFilePath::from_pathbuf(PathBuf::new()).is_mounted());
+    }
+}
@@ -1,7 +1,20 @@
 #![allow(unused)]
 use pitou_core::{
-    msg::{SearchMsg, TransferMsg, TransferSessionID},
-    search::SimplifiedSearchOptions,
+    cloud::{CloudAccount, CloudEntry, CloudError, CloudListing},
+    duplicates::DuplicateOptions,
+    favorites::FavoriteItem,
+    msg::{DuplicateMsg, SearchMsg, TotalsMsg, TransferMsg, TransferSessionID, UsageMsg},
+    preview::Preview,
+    properties::{AttributeChanges, ChangeError, ChecksumKind, ItemProperties},
+    recents::{RecentItem, RecentKind},
+    search::{SavedSearch, SearchError, SearchFind, SimplifiedSearchOptions},
+    session::SavedSession,
+    settings::SavedSettings,
+    themes::NamedTheme,
+    thumbnails::ThumbnailSize,
+    trash::{RestoreConflict, RestoreError, TrashRetention, TrashSummary},
+    usage::{UsageNode, UsageReport},
+    vault::{VaultEntry, VaultError, VaultStatus},
     *,
 };
 
@@ -30,8 +43,298 @@ pub async fn children(
 }
 
 #[tauri::command]
-pub fn thrash_items() -> Option<Vec<PitouTrashItem>> {
-    pitou_core::backend::trash_items()
+pub async fn trash_items() -> Option<Vec<PitouTrashItem>> {
+    pitou_core::backend::trash::items()
+}
+
+#[tauri::command]
+pub async fn restore_trash(
+    ids: Vec<String>,
+    conflict: RestoreConflict,
+    destination: Option<PitouFilePath>,
+) -> Vec<RestoreError> {
+    pitou_core::backend::trash::restore(ids, conflict, destination)
+}
+
+#[tauri::command]
+pub async fn purge_trash(ids: Vec<String>) -> bool {
+    pitou_core::backend::trash::purge(ids).is_ok()
+}
+
+#[tauri::command]
+pub async fn empty_trash() -> bool {
+    pitou_core::backend::trash::empty().is_ok()
+}
+
+#[tauri::command]
+pub async fn trash_summary() -> Option<TrashSummary> {
+    pitou_core::backend::trash::summary()
+}
+
+#[tauri::command]
+pub async fn trash_retention() -> TrashRetention {
+    pitou_core::backend::trash::retention().await
+}
+
+#[tauri::command]
+pub async fn set_trash_retention(retention: TrashRetention) -> Option<usize> {
+    pitou_core::backend::trash::set_retention(retention)
+        .await
+        .ok()
+}
+
+#[tauri::command]
+pub async fn load_settings() -> SavedSettings {
+    pitou_core::backend::settings::load().await
+}
+
+#[tauri::command]
+pub async fn save_settings(settings: SavedSettings) -> bool {
+    pitou_core::backend::settings::save(settings).await.is_ok()
+}
+
+#[tauri::command]
+pub async fn reset_settings() -> Option<SavedSettings> {
+    pitou_core::backend::settings::reset().await.ok()
+}
+
+#[tauri::command]
+pub async fn import_settings(json: String) -> Option<SavedSettings> {
+    pitou_core::backend::settings::import(json).await
+}
+
+#[tauri::command]
+pub async fn export_settings() -> Option<PitouFilePath> {
+    pitou_core::backend::settings::export().await
+}
+
+#[tauri::command]
+pub async fn export_theme(theme: NamedTheme) -> Option<PitouFilePath> {
+    pitou_core::backend::themes::export(theme).await
+}
+
+#[tauri::command]
+pub async fn favorites() -> Vec<FavoriteItem> {
+    pitou_core::backend::favorites::favorites().await
+}
+
+#[tauri::command]
+pub async fn add_favorites(items: Vec<PitouFile>) -> usize {
+    let items = items.into_iter().map(|item| item.path).collect();
+    pitou_core::backend::favorites::add(items)
+        .await
+        .unwrap_or(0)
+}
+
+#[tauri::command]
+pub async fn remove_favorites(items: Vec<PitouFile>) -> bool {
+    let items = items.into_iter().map(|item| item.path).collect();
+    pitou_core::backend::favorites::remove(items).await.is_ok()
+}
+
+#[tauri::command]
+pub async fn rename_favorite(pitou: PitouFile, name: String) -> bool {
+    pitou_core::backend::favorites::rename(pitou.path, name)
+        .await
+        .is_ok()
+}
+
+#[tauri::command]
+pub async fn move_favorite(pitou: PitouFile, position: usize) -> bool {
+    pitou_core::backend::favorites::reorder(pitou.path, position)
+        .await
+        .is_ok()
+}
+
+#[tauri::command]
+pub async fn forget_missing_favorites() -> usize {
+    pitou_core::backend::favorites::forget_missing()
+        .await
+        .unwrap_or(0)
+}
+
+#[tauri::command]
+pub async fn import_gtk_bookmarks() -> Option<usize> {
+    pitou_core::backend::favorites::import_gtk_bookmarks()
+        .await
+        .ok()
+}
+
+#[tauri::command]
+pub async fn recents() -> Vec<RecentItem> {
+    pitou_core::backend::recents::recents().await
+}
+
+#[tauri::command]
+pub async fn record_visit(pitou: PitouFile) {
+    pitou_core::backend::recents::record(pitou.path, RecentKind::Folder)
+        .await
+        .ok();
+}
+
+#[tauri::command]
+pub async fn forget_recents(items: Vec<PitouFile>) -> bool {
+    let items = items.into_iter().map(|item| item.path).collect();
+    pitou_core::backend::recents::forget(items).await.is_ok()
+}
+
+#[tauri::command]
+pub async fn clear_recents() -> bool {
+    pitou_core::backend::recents::clear().await.is_ok()
+}
+
+#[tauri::command]
+pub async fn recents_tracking() -> bool {
+    pitou_core::backend::recents::tracking().await
+}
+
+#[tauri::command]
+pub async fn set_recents_tracking(tracking: bool) -> bool {
+    pitou_core::backend::recents::set_tracking(tracking)
+        .await
+        .is_ok()
+}
+
+#[tauri::command]
+pub async fn vault_status() -> VaultStatus {
+    pitou_core::backend::vault::status().await
+}
+
+#[tauri::command]
+pub async fn create_vault(passphrase: String) -> Option<VaultError> {
+    pitou_core::backend::vault::create(passphrase).await.err()
+}
+
+#[tauri::command]
+pub async fn unlock_vault(passphrase: String) -> Option<VaultError> {
+    pitou_core::backend::vault::unlock(passphrase).await.err()
+}
+
+#[tauri::command]
+pub fn lock_vault() {
+    pitou_core::backend::vault::lock()
+}
+
+#[tauri::command]
+pub async fn set_vault_auto_lock(minutes: u32) -> Option<VaultError> {
+    pitou_core::backend::vault::set_auto_lock(minutes)
+        .await
+        .err()
+}
+
+#[tauri::command]
+pub async fn vault_entries() -> Option<Vec<VaultEntry>> {
+    pitou_core::backend::vault::entries().await.ok()
+}
+
+#[tauri::command]
+pub async fn add_to_vault(items: Vec<PitouFile>) -> Option<usize> {
+    let items = items.into_iter().map(|item| item.path).collect();
+    pitou_core::backend::vault::add(items).await.ok()
+}
+
+#[tauri::command]
+pub async fn open_vault_entry(id: String) -> Option<VaultError> {
+    pitou_core::backend::vault::open(id).await.err()
+}
+
+#[tauri::command]
+pub async fn restore_vault_entries(ids: Vec<String>) -> Option<usize> {
+    pitou_core::backend::vault::restore(ids).await.ok()
+}
+
+#[tauri::command]
+pub async fn delete_vault_entries(ids: Vec<String>) -> Option<VaultError> {
+    pitou_core::backend::vault::delete(ids).await.err()
+}
+
+#[tauri::command]
+pub async fn cloud_accounts() -> Vec<CloudAccount> {
+    pitou_core::backend::cloud::accounts().await
+}
+
+#[tauri::command]
+pub async fn save_cloud_account(account: CloudAccount) -> Option<CloudError> {
+    pitou_core::backend::cloud::save_account(account)
+        .await
+        .err()
+}
+
+#[tauri::command]
+pub async fn trust_cloud_host_key(
+    account: CloudAccount,
+    fingerprint: String,
+) -> Option<CloudError> {
+    pitou_core::backend::cloud::trust_host_key(account, fingerprint)
+        .await
+        .err()
+}
+
+#[tauri::command]
+pub async fn ssh_hosts() -> Vec<String> {
+    pitou_core::backend::cloud::ssh_hosts().await
+}
+
+#[tauri::command]
+pub async fn remove_cloud_account(id: String) -> bool {
+    pitou_core::backend::cloud::remove_account(id).await.is_ok()
+}
+
+#[tauri::command]
+pub async fn cloud_listing(id: String, dir: String) -> CloudListing {
+    pitou_core::backend::cloud::list(id, dir).await
+}
+
+#[tauri::command]
+pub async fn create_cloud_folder(id: String, dir: String, name: String) -> Option<CloudError> {
+    pitou_core::backend::cloud::create_dir(id, dir, name)
+        .await
+        .err()
+}
+
+#[tauri::command]
+pub async fn rename_cloud_entry(id: String, entry: CloudEntry, name: String) -> Option<CloudError> {
+    pitou_core::backend::cloud::rename(id, entry, name)
+        .await
+        .err()
+}
+
+#[tauri::command]
+pub async fn delete_cloud_entries(id: String, entries: Vec<CloudEntry>) -> Option<CloudError> {
+    pitou_core::backend::cloud::delete(id, entries).await.err()
+}
+
+#[tauri::command]
+pub async fn upload_to_cloud(
+    id: String,
+    items: Vec<PitouFile>,
+    dir: String,
+) -> Option<TransferSessionID> {
+    let items = items.into_iter().map(|item| item.path).collect();
+    pitou_core::backend::cloud::upload(id, items, dir)
+        .await
+        .ok()
+}
+
+#[tauri::command]
+pub async fn download_from_cloud(
+    id: String,
+    entries: Vec<CloudEntry>,
+    pitou: PitouFile,
+) -> Option<TransferSessionID> {
+    pitou_core::backend::cloud::download(id, entries, pitou.path)
+        .await
+        .ok()
+}
+
+#[tauri::command]
+pub async fn load_session() -> Option<SavedSession> {
+    pitou_core::backend::session::load().await
+}
+
+#[tauri::command]
+pub async fn save_session(session: SavedSession) -> bool {
+    pitou_core::backend::session::save(session).await.is_ok()
 }
 
 #[tauri::command]
@@ -61,12 +364,12 @@ pub async fn cut(items: Vec<PitouFile>) {
 
 #[tauri::command]
 pub async fn open(pitou: PitouFile) {
-    pitou_core::backend::open(pitou.path).ok();
+    pitou_core::backend::open(pitou.path).await.ok();
 }
 
 #[tauri::command]
 pub async fn open_with(pitou: PitouFile) {
-    pitou_core::backend::open_with(pitou.path).ok();
+    pitou_core::backend::open_with(pitou.path).await.ok();
 }
 
 #[tauri::command]
@@ -75,18 +378,18 @@ pub async fn archive(pitou: Vec<PitouFilePath>) {
 }
 
 #[tauri::command]
-pub async fn create_dir(pitou: PitouFile) {
-    pitou_core::backend::create_dir(pitou.path).await
+pub async fn create_dir(pitou: PitouFile) -> bool {
+    pitou_core::backend::create_dir(pitou.path).await.is_ok()
 }
 
 #[tauri::command]
-pub async fn create_file(pitou: PitouFile) {
-    pitou_core::backend::create_file(pitou.path).await
+pub async fn create_file(pitou: PitouFile) -> bool {
+    pitou_core::backend::create_file(pitou.path).await.is_ok()
 }
 
 #[tauri::command]
-pub async fn rename(pitou: PitouFile, name: String) {
-    pitou_core::backend::rename(pitou.path, name).await
+pub async fn rename(pitou: PitouFile, name: String) -> bool {
+    pitou_core::backend::rename(pitou.path, name).await.is_ok()
 }
 
 #[tauri::command]
@@ -95,9 +398,13 @@ pub async fn extract(pitou: PitouFile) {
 }
 
 #[tauri::command]
-pub async fn search(options: SimplifiedSearchOptions) {
-    if let Some(options) = options.try_into() {
-        pitou_core::backend::search::search(options).await;
+pub async fn search(options: SimplifiedSearchOptions) -> Option<SearchError> {
+    match options.try_into() {
+        Ok(options) => {
+            pitou_core::backend::search::search(options).await;
+            None
+        }
+        Err(e) => Some(e),
     }
 }
 
@@ -116,6 +423,116 @@ pub async fn is_searching() -> bool {
     !pitou_core::backend::search::is_terminated().await
 }
 
+#[tauri::command]
+pub async fn saved_searches() -> Vec<SavedSearch> {
+    pitou_core::backend::saved_searches::saved_searches().await
+}
+
+#[tauri::command]
+pub async fn save_search(search: SavedSearch) -> bool {
+    pitou_core::backend::saved_searches::save_search(search)
+        .await
+        .is_ok()
+}
+
+#[tauri::command]
+pub async fn forget_search(name: String) -> bool {
+    pitou_core::backend::saved_searches::forget_search(name)
+        .await
+        .is_ok()
+}
+
+#[tauri::command]
+pub async fn smart_folder(name: String) -> Option<Vec<SearchFind>> {
+    pitou_core::backend::saved_searches::smart_folder_children(name).await
+}
+
+#[tauri::command]
+pub async fn find_duplicates(options: DuplicateOptions) {
+    pitou_core::backend::duplicates::find_duplicates(options).await
+}
+
+#[tauri::command]
+pub async fn duplicates_msg() -> DuplicateMsg {
+    pitou_core::backend::duplicates::read_duplicates().await
+}
+
+#[tauri::command]
+pub async fn terminate_duplicates() {
+    pitou_core::backend::duplicates::terminate_duplicates().await
+}
+
+#[tauri::command]
+pub async fn scan_usage(root: PitouFilePath) {
+    pitou_core::backend::usage::scan_usage(root).await
+}
+
+#[tauri::command]
+pub async fn usage_msg() -> UsageMsg {
+    pitou_core::backend::usage::read_usage().await
+}
+
+#[tauri::command]
+pub async fn terminate_usage() {
+    pitou_core::backend::usage::terminate_usage().await
+}
+
+#[tauri::command]
+pub fn usage_tree(path: PitouFilePath, depth: u32) -> Option<UsageNode> {
+    pitou_core::backend::usage::usage_tree(path, depth)
+}
+
+#[tauri::command]
+pub fn usage_report(root: PitouFilePath) -> Option<UsageReport> {
+    pitou_core::backend::usage::usage_report(root)
+}
+
+#[tauri::command]
+pub async fn properties(items: Vec<PitouFile>) -> Vec<ItemProperties> {
+    let paths = items.into_iter().map(|item| item.path).collect();
+    pitou_core::backend::properties::properties(paths).await
+}
+
+#[tauri::command]
+pub async fn count_totals(items: Vec<PitouFile>) {
+    let paths = items.into_iter().map(|item| item.path).collect();
+    pitou_core::backend::properties::count_totals(paths).await
+}
+
+#[tauri::command]
+pub async fn totals_msg() -> TotalsMsg {
+    pitou_core::backend::properties::read_totals().await
+}
+
+#[tauri::command]
+pub async fn terminate_totals() {
+    pitou_core::backend::properties::terminate_totals().await
+}
+
+#[tauri::command]
+pub async fn checksum(path: PitouFilePath, kind: ChecksumKind) -> Option<String> {
+    pitou_core::backend::properties::checksum(path, kind).await
+}
+
+#[tauri::command]
+pub async fn change_attributes(
+    items: Vec<PitouFile>,
+    changes: AttributeChanges,
+) -> Vec<ChangeError> {
+    let paths = items.into_iter().map(|item| item.path).collect();
+    pitou_core::backend::properties::change_attributes(paths, changes).await
+}
+
+#[tauri::command]
+pub async fn preview(path: PitouFilePath, dark: bool) -> Preview {
+    pitou_core::backend::preview::preview(path, dark).await
+}
+
+#[tauri::command]
+pub async fn thumbnail(path: PitouFilePath, size: ThumbnailSize) -> Option<String> {
+    pitou_core::backend::thumbnails::thumbnail(path, size).await
+}
+
 #[tauri::command]
 pub async fn transfer_sessions() -> Vec<TransferMsg> {
     pitou_core::backend::transfer::get_all_active_sessions()
@@ -3,16 +3,77 @@
 
 mod cmds;
 use cmds::*;
+use tauri::Manager;
 
 fn main() {
     tauri::Builder::default()
         .plugin(tauri_plugin_shell::init())
+        .setup(|app| {
+            let size = pitou_core::backend::session::window_size();
+            if let (Some((width, height)), Some(window)) = (size, app.get_webview_window("main")) {
+                window
+                    .set_size(tauri::LogicalSize::new(width as f64, height as f64))
+                    .ok();
+            }
+            tauri::async_runtime::spawn(pitou_core::backend::trash::apply_retention());
+            tauri::async_runtime::spawn(pitou_core::backend::vault::remove_stale_opened());
+            tauri::async_runtime::spawn(pitou_core::backend::cloud::mount_accounts());
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             general_folders,
             default_folder,
             drives,
             children,
-            thrash_items,
+            trash_items,
+            restore_trash,
+            purge_trash,
+            empty_trash,
+            trash_summary,
+            trash_retention,
+            set_trash_retention,
+            load_settings,
+            save_settings,
+            reset_settings,
+            import_settings,
+            export_settings,
+            export_theme,
+            favorites,
+            add_favorites,
+            remove_favorites,
+            rename_favorite,
+            move_favorite,
+            forget_missing_favorites,
+            import_gtk_bookmarks,
+            recents,
+            record_visit,
+            forget_recents,
+            clear_recents,
+            recents_tracking,
+            set_recents_tracking,
+            vault_status,
+            create_vault,
+            unlock_vault,
+            lock_vault,
+            set_vault_auto_lock,
+            vault_entries,
+            add_to_vault,
+            open_vault_entry,
+            restore_vault_entries,
+            delete_vault_entries,
+            cloud_accounts,
+            save_cloud_account,
+            trust_cloud_host_key,
+            remove_cloud_account,
+            ssh_hosts,
+            cloud_listing,
+            create_cloud_folder,
+            rename_cloud_entry,
+            delete_cloud_entries,
+            upload_to_cloud,
+            download_from_cloud,
+            load_session,
+            save_session,
             clipboard_empty,
             copy,
             cut,
@@ -29,9 +90,35 @@ fn main() {
             terminate_search,
             search_msg,
             is_searching,
+            saved_searches,
+            save_search,
+            forget_search,
+            smart_folder,
+            find_duplicates,
+            duplicates_msg,
+            terminate_duplicates,
+            scan_usage,
+            usage_msg,
+            terminate_usage,
+            usage_tree,
+            usage_report,
+            thumbnail,
+            preview,
+            properties,
+            count_totals,
+            totals_msg,
+            terminate_totals,
+            checksum,
+            change_attributes,
             transfer_sessions,
             transfer_session_with_id,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        .run(|_, event| {
+            // decrypted copies of vault files don't outlive the app
+            if let tauri::RunEvent::Exit = event {
+                pitou_core::backend::vault::lock()
+            }
+        });
 }
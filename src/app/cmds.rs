@@ -1,15 +1,35 @@
 use std::rc::Rc;
 
 use pitou_core::{
+    cloud::{CloudAccount, CloudEntry, CloudError, CloudListing},
+    duplicates::DuplicateOptions,
+    favorites::FavoriteItem,
     frontend::extra::DirChildren,
-    msg::{SearchMsg, TransferMsg, TransferSessionID},
-    search::SimplifiedSearchOptions,
+    msg::{DuplicateMsg, SearchMsg, TotalsMsg, TransferMsg, TransferSessionID, UsageMsg},
+    preview::Preview,
+    properties::{AttributeChanges, ChangeError, ChecksumKind, ItemProperties},
+    recents::RecentItem,
+    search::{SavedSearch, SearchError, SearchFind, SimplifiedSearchOptions},
+    session::SavedSession,
+    settings::SavedSettings,
+    themes::NamedTheme,
+    thumbnails::ThumbnailSize,
+    trash::{RestoreConflict, RestoreError, TrashRetention, TrashSummary},
+    usage::{UsageNode, UsageReport},
+    vault::{VaultEntry, VaultError, VaultStatus},
     *,
 };
 
 use super::{
     args::ValueArg,
-    reusables::{DirChildrenArgs, ItemsArg, NoArg, PitouArg, RenameArg, SearchOptionsArg},
+    reusables::{
+        AccountArg, AttributeChangesArg, AutoLockArg, ChecksumArg, CloudDirArg, CloudDownloadArg,
+        CloudEntriesArg, CloudFolderArg, CloudRenameArg, CloudUploadArg, DirChildrenArgs,
+        DuplicateOptionsArg, HostKeyArg, IdArg, ItemsArg, JsonArg, MoveArg, NameArg, NoArg,
+        PassphraseArg, PitouArg, PitouTrashItemsVec, PreviewArg, RenameArg, RestoreTrashArg,
+        SavedSearchArg, SearchOptionsArg, SessionArg, SettingsArg, ThemeArg, ThumbnailArg,
+        TrackingArg, TrashIdsArg, TrashRetentionArg, UsageRootArg, UsageTreeArg,
+    },
 };
 
 pub async fn open(pitou: Rc<PitouFile>) -> Result<(), tauri_sys::Error> {
@@ -36,7 +56,7 @@ pub async fn delete(items: &Vec<Rc<PitouFile>>) -> Result<(), tauri_sys::Error>
     tauri_sys::tauri::invoke("delete", &ItemsArg { items }).await
 }
 
-pub async fn rename(pitou: Rc<PitouFile>, name: String) -> Result<(), tauri_sys::Error> {
+pub async fn rename(pitou: Rc<PitouFile>, name: String) -> Result<bool, tauri_sys::Error> {
     tauri_sys::tauri::invoke("rename", &RenameArg { pitou, name }).await
 }
 
@@ -52,11 +72,11 @@ pub async fn extract(pitou: Rc<PitouFile>) -> Result<(), tauri_sys::Error> {
     tauri_sys::tauri::invoke("extract", &PitouArg { pitou }).await
 }
 
-pub async fn create_dir(pitou: Rc<PitouFile>) -> Result<(), tauri_sys::Error> {
+pub async fn create_dir(pitou: Rc<PitouFile>) -> Result<bool, tauri_sys::Error> {
     tauri_sys::tauri::invoke("create_dir", &PitouArg { pitou }).await
 }
 
-pub async fn create_file(pitou: Rc<PitouFile>) -> Result<(), tauri_sys::Error> {
+pub async fn create_file(pitou: Rc<PitouFile>) -> Result<bool, tauri_sys::Error> {
     tauri_sys::tauri::invoke("create_file", &PitouArg { pitou }).await
 }
 
@@ -64,7 +84,9 @@ pub async fn copy_path(_pitou: Rc<PitouFile>) -> Result<(), tauri_sys::Error> {
     Ok(())
 }
 
-pub async fn search(options: SimplifiedSearchOptions) -> Result<(), tauri_sys::Error> {
+pub async fn search(
+    options: SimplifiedSearchOptions,
+) -> Result<Option<SearchError>, tauri_sys::Error> {
     tauri_sys::tauri::invoke("search", &SearchOptionsArg { options }).await
 }
 
@@ -76,6 +98,351 @@ pub async fn search_msg() -> Result<SearchMsg, tauri_sys::Error> {
     tauri_sys::tauri::invoke("search_msg", &NoArg).await
 }
 
+pub async fn saved_searches() -> Result<Vec<SavedSearch>, tauri_sys::Error> {
+    tauri_sys::tauri::invoke("saved_searches", &NoArg).await
+}
+
+pub async fn save_search(search: SavedSearch) -> Result<bool, tauri_sys::Error> {
+    tauri_sys::tauri::invoke("save_search", &SavedSearchArg { search }).await
+}
+
+pub async fn forget_search(name: String) -> Result<bool, tauri_sys::Error> {
+    tauri_sys::tauri::invoke("forget_search", &NameArg { name }).await
+}
+
+pub async fn smart_folder(name: String) -> Result<Option<Vec<SearchFind>>, tauri_sys::Error> {
+    tauri_sys::tauri::invoke("smart_folder", &NameArg { name }).await
+}
+
+pub async fn load_settings() -> Result<SavedSettings, tauri_sys::Error> {
+    tauri_sys::tauri::invoke("load_settings", &NoArg).await
+}
+
+pub async fn save_settings(settings: &SavedSettings) -> Result<bool, tauri_sys::Error> {
+    tauri_sys::tauri::invoke("save_settings", &SettingsArg { settings }).await
+}
+
+pub async fn reset_settings() -> Result<Option<SavedSettings>, tauri_sys::Error> {
+    tauri_sys::tauri::invoke("reset_settings", &NoArg).await
+}
+
+pub async fn import_settings(json: String) -> Result<Option<SavedSettings>, tauri_sys::Error> {
+    tauri_sys::tauri::invoke("import_settings", &JsonArg { json }).await
+}
+
+pub async fn export_settings() -> Result<Option<PitouFilePath>, tauri_sys::Error> {
+    tauri_sys::tauri::invoke("export_settings", &NoArg).await
+}
+
+pub async fn export_theme(theme: &NamedTheme) -> Result<Option<PitouFilePath>, tauri_sys::Error> {
+    tauri_sys::tauri::invoke("export_theme", &ThemeArg { theme }).await
+}
+
+pub async fn favorites() -> Result<Vec<FavoriteItem>, tauri_sys::Error> {
+    tauri_sys::tauri::invoke("favorites", &NoArg).await
+}
+
+pub async fn add_favorites(items: &Vec<Rc<PitouFile>>) -> Result<usize, tauri_sys::Error> {
+    tauri_sys::tauri::invoke("add_favorites", &ItemsArg { items }).await
+}
+
+pub async fn remove_favorites(items: &Vec<Rc<PitouFile>>) -> Result<bool, tauri_sys::Error> {
+    tauri_sys::tauri::invoke("remove_favorites", &ItemsArg { items }).await
+}
+
+pub async fn rename_favorite(pitou: Rc<PitouFile>, name: String) -> Result<bool, tauri_sys::Error> {
+    tauri_sys::tauri::invoke("rename_favorite", &RenameArg { pitou, name }).await
+}
+
+pub async fn move_favorite(
+    pitou: Rc<PitouFile>,
+    position: usize,
+) -> Result<bool, tauri_sys::Error> {
+    tauri_sys::tauri::invoke("move_favorite", &MoveArg { pitou, position }).await
+}
+
+pub async fn forget_missing_favorites() -> Result<usize, tauri_sys::Error> {
+    tauri_sys::tauri::invoke("forget_missing_favorites", &NoArg).await
+}
+
+pub async fn import_gtk_bookmarks() -> Result<Option<usize>, tauri_sys::Error> {
+    tauri_sys::tauri::invoke("import_gtk_bookmarks", &NoArg).await
+}
+
+pub async fn recents() -> Result<Vec<RecentItem>, tauri_sys::Error> {
+    tauri_sys::tauri::invoke("recents", &NoArg).await
+}
+
+/// Lists `pitou` with the recent folders, unless tracking is paused.
+pub async fn record_visit(pitou: Rc<PitouFile>) -> Result<(), tauri_sys::Error> {
+    tauri_sys::tauri::invoke("record_visit", &PitouArg { pitou }).await
+}
+
+pub async fn forget_recents(items: &Vec<Rc<PitouFile>>) -> Result<bool, tauri_sys::Error> {
+    tauri_sys::tauri::invoke("forget_recents", &ItemsArg { items }).await
+}
+
+pub async fn clear_recents() -> Result<bool, tauri_sys::Error> {
+    tauri_sys::tauri::invoke("clear_recents", &NoArg).await
+}
+
+pub async fn recents_tracking() -> Result<bool, tauri_sys::Error> {
+    tauri_sys::tauri::invoke("recents_tracking", &NoArg).await
+}
+
+pub async fn set_recents_tracking(tracking: bool) -> Result<bool, tauri_sys::Error> {
+    tauri_sys::tauri::invoke("set_recents_tracking", &TrackingArg { tracking }).await
+}
+
+pub async fn vault_status() -> Result<VaultStatus, tauri_sys::Error> {
+    tauri_sys::tauri::invoke("vault_status", &NoArg).await
+}
+
+/// Makes the vault, leaving it unlocked. Returns why it couldn't, if it couldn't.
+pub async fn create_vault(passphrase: String) -> Result<Option<VaultError>, tauri_sys::Error> {
+    tauri_sys::tauri::invoke("create_vault", &PassphraseArg { passphrase }).await
+}
+
+pub async fn unlock_vault(passphrase: String) -> Result<Option<VaultError>, tauri_sys::Error> {
+    tauri_sys::tauri::invoke("unlock_vault", &PassphraseArg { passphrase }).await
+}
+
+pub async fn lock_vault() -> Result<(), tauri_sys::Error> {
+    tauri_sys::tauri::invoke("lock_vault", &NoArg).await
+}
+
+pub async fn set_vault_auto_lock(minutes: u32) -> Result<Option<VaultError>, tauri_sys::Error> {
+    tauri_sys::tauri::invoke("set_vault_auto_lock", &AutoLockArg { minutes }).await
+}
+
+/// The files in the vault, or `None` while it is locked.
+pub async fn vault_entries() -> Result<Option<Vec<VaultEntry>>, tauri_sys::Error> {
+    tauri_sys::tauri::invoke("vault_entries", &NoArg).await
+}
+
+/// Moves `items` into the vault, returning how many were moved, or `None` while it is locked.
+pub async fn add_to_vault(items: &Vec<Rc<PitouFile>>) -> Result<Option<usize>, tauri_sys::Error> {
+    tauri_sys::tauri::invoke("add_to_vault", &ItemsArg { items }).await
+}
+
+pub async fn open_vault_entry(id: String) -> Result<Option<VaultError>, tauri_sys::Error> {
+    tauri_sys::tauri::invoke("open_vault_entry", &IdArg { id }).await
+}
+
+pub async fn restore_vault_entries(ids: &[String]) -> Result<Option<usize>, tauri_sys::Error> {
+    tauri_sys::tauri::invoke("restore_vault_entries", &TrashIdsArg { ids }).await
+}
+
+pub async fn delete_vault_entries(ids: &[String]) -> Result<Option<VaultError>, tauri_sys::Error> {
+    tauri_sys::tauri::invoke("delete_vault_entries", &TrashIdsArg { ids }).await
+}
+
+/// The saved cloud accounts, without their secrets.
+pub async fn cloud_accounts() -> Result<Vec<CloudAccount>, tauri_sys::Error> {
+    tauri_sys::tauri::invoke("cloud_accounts", &NoArg).await
+}
+
+/// The hosts named in `~/.ssh/config`.
+pub async fn ssh_hosts() -> Result<Vec<String>, tauri_sys::Error> {
+    tauri_sys::tauri::invoke("ssh_hosts", &NoArg).await
+}
+
+/// Saves the account, as a new one if its id is empty. Returns why it couldn't, if it couldn't.
+pub async fn save_cloud_account(
+    account: &CloudAccount,
+) -> Result<Option<CloudError>, tauri_sys::Error> {
+    tauri_sys::tauri::invoke("save_cloud_account", &AccountArg { account }).await
+}
+
+pub async fn trust_cloud_host_key(
+    account: &CloudAccount,
+    fingerprint: &str,
+) -> Result<Option<CloudError>, tauri_sys::Error> {
+    let arg = HostKeyArg {
+        account,
+        fingerprint,
+    };
+    tauri_sys::tauri::invoke("trust_cloud_host_key", &arg).await
+}
+
+pub async fn remove_cloud_account(id: &str) -> Result<bool, tauri_sys::Error> {
+    tauri_sys::tauri::invoke("remove_cloud_account", &IdArg { id: id.to_owned() }).await
+}
+
+pub async fn cloud_listing(id: &str, dir: &str) -> Result<CloudListing, tauri_sys::Error> {
+    tauri_sys::tauri::invoke("cloud_listing", &CloudDirArg { id, dir }).await
+}
+
+pub async fn create_cloud_folder(
+    id: &str,
+    dir: &str,
+    name: &str,
+) -> Result<Option<CloudError>, tauri_sys::Error> {
+    tauri_sys::tauri::invoke("create_cloud_folder", &CloudFolderArg { id, dir, name }).await
+}
+
+pub async fn rename_cloud_entry(
+    id: &str,
+    entry: &CloudEntry,
+    name: &str,
+) -> Result<Option<CloudError>, tauri_sys::Error> {
+    tauri_sys::tauri::invoke("rename_cloud_entry", &CloudRenameArg { id, entry, name }).await
+}
+
+pub async fn delete_cloud_entries(
+    id: &str,
+    entries: &[CloudEntry],
+) -> Result<Option<CloudError>, tauri_sys::Error> {
+    tauri_sys::tauri::invoke("delete_cloud_entries", &CloudEntriesArg { id, entries }).await
+}
+
+/// Starts uploading `items` into the cloud folder `dir`, returning the transfer to follow.
+pub async fn upload_to_cloud(
+    id: &str,
+    items: &Vec<Rc<PitouFile>>,
+    dir: &str,
+) -> Result<Option<TransferSessionID>, tauri_sys::Error> {
+    tauri_sys::tauri::invoke("upload_to_cloud", &CloudUploadArg { id, items, dir }).await
+}
+
+/// Starts downloading `entries` into the folder `pitou`, returning the transfer to follow.
+pub async fn download_from_cloud(
+    id: &str,
+    entries: &[CloudEntry],
+    pitou: Rc<PitouFile>,
+) -> Result<Option<TransferSessionID>, tauri_sys::Error> {
+    let arg = CloudDownloadArg { id, entries, pitou };
+    tauri_sys::tauri::invoke("download_from_cloud", &arg).await
+}
+
+pub async fn load_session() -> Result<Option<SavedSession>, tauri_sys::Error> {
+    tauri_sys::tauri::invoke("load_session", &NoArg).await
+}
+
+pub async fn save_session(session: &SavedSession) -> Result<bool, tauri_sys::Error> {
+    tauri_sys::tauri::invoke("save_session", &SessionArg { session }).await
+}
+
+pub async fn find_duplicates(options: DuplicateOptions) -> Result<(), tauri_sys::Error> {
+    tauri_sys::tauri::invoke("find_duplicates", &DuplicateOptionsArg { options }).await
+}
+
+pub async fn duplicates_msg() -> Result<DuplicateMsg, tauri_sys::Error> {
+    tauri_sys::tauri::invoke("duplicates_msg", &NoArg).await
+}
+
+pub async fn terminate_duplicates() -> Result<(), tauri_sys::Error> {
+    tauri_sys::tauri::invoke("terminate_duplicates", &NoArg).await
+}
+
+pub async fn scan_usage(root: &PitouFilePath) -> Result<(), tauri_sys::Error> {
+    tauri_sys::tauri::invoke("scan_usage", &UsageRootArg { root }).await
+}
+
+pub async fn usage_msg() -> Result<UsageMsg, tauri_sys::Error> {
+    tauri_sys::tauri::invoke("usage_msg", &NoArg).await
+}
+
+pub async fn terminate_usage() -> Result<(), tauri_sys::Error> {
+    tauri_sys::tauri::invoke("terminate_usage", &NoArg).await
+}
+
+pub async fn usage_tree(
+    path: &PitouFilePath,
+    depth: u32,
+) -> Result<Option<UsageNode>, tauri_sys::Error> {
+    tauri_sys::tauri::invoke("usage_tree", &UsageTreeArg { path, depth }).await
+}
+
+pub async fn usage_report(root: &PitouFilePath) -> Result<Option<UsageReport>, tauri_sys::Error> {
+    tauri_sys::tauri::invoke("usage_report", &UsageRootArg { root }).await
+}
+
+pub async fn preview(path: &PitouFilePath, dark: bool) -> Result<Preview, tauri_sys::Error> {
+    tauri_sys::tauri::invoke("preview", &PreviewArg { path, dark }).await
+}
+
+pub async fn properties(
+    items: &Vec<Rc<PitouFile>>,
+) -> Result<Vec<ItemProperties>, tauri_sys::Error> {
+    tauri_sys::tauri::invoke("properties", &ItemsArg { items }).await
+}
+
+pub async fn count_totals(items: &Vec<Rc<PitouFile>>) -> Result<(), tauri_sys::Error> {
+    tauri_sys::tauri::invoke("count_totals", &ItemsArg { items }).await
+}
+
+pub async fn totals_msg() -> Result<TotalsMsg, tauri_sys::Error> {
+    tauri_sys::tauri::invoke("totals_msg", &NoArg).await
+}
+
+pub async fn terminate_totals() -> Result<(), tauri_sys::Error> {
+    tauri_sys::tauri::invoke("terminate_totals", &NoArg).await
+}
+
+pub async fn checksum(
+    path: &PitouFilePath,
+    kind: ChecksumKind,
+) -> Result<Option<String>, tauri_sys::Error> {
+    tauri_sys::tauri::invoke("checksum", &ChecksumArg { path, kind }).await
+}
+
+pub async fn change_attributes(
+    items: &Vec<Rc<PitouFile>>,
+    changes: AttributeChanges,
+) -> Result<Vec<ChangeError>, tauri_sys::Error> {
+    let args = AttributeChangesArg { items, changes };
+    tauri_sys::tauri::invoke("change_attributes", &args).await
+}
+
+pub async fn trash_items() -> Result<PitouTrashItemsVec, tauri_sys::Error> {
+    tauri_sys::tauri::invoke("trash_items", &NoArg).await
+}
+
+/// Restores the trashed items with the given ids, into `destination` instead of where they were deleted from if given.
+pub async fn restore_trash(
+    ids: &[String],
+    conflict: RestoreConflict,
+    destination: Option<&PitouFilePath>,
+) -> Result<Vec<RestoreError>, tauri_sys::Error> {
+    let args = RestoreTrashArg {
+        ids,
+        conflict,
+        destination,
+    };
+    tauri_sys::tauri::invoke("restore_trash", &args).await
+}
+
+pub async fn purge_trash(ids: &[String]) -> Result<bool, tauri_sys::Error> {
+    tauri_sys::tauri::invoke("purge_trash", &TrashIdsArg { ids }).await
+}
+
+pub async fn empty_trash() -> Result<bool, tauri_sys::Error> {
+    tauri_sys::tauri::invoke("empty_trash", &NoArg).await
+}
+
+pub async fn trash_summary() -> Result<Option<TrashSummary>, tauri_sys::Error> {
+    tauri_sys::tauri::invoke("trash_summary", &NoArg).await
+}
+
+pub async fn trash_retention() -> Result<TrashRetention, tauri_sys::Error> {
+    tauri_sys::tauri::invoke("trash_retention", &NoArg).await
+}
+
+/// Saves and applies `retention`, returning how many items it purged.
+pub async fn set_trash_retention(
+    retention: TrashRetention,
+) -> Result<Option<usize>, tauri_sys::Error> {
+    tauri_sys::tauri::invoke("set_trash_retention", &TrashRetentionArg { retention }).await
+}
+
+pub async fn thumbnail(
+    path: &PitouFilePath,
+    size: ThumbnailSize,
+) -> Result<Option<String>, tauri_sys::Error> {
+    tauri_sys::tauri::invoke("thumbnail", &ThumbnailArg { path, size }).await
+}
+
 pub async fn transfer_session_with_id(value: TransferSessionID) -> Option<TransferMsg> {
     tauri_sys::tauri::invoke("transfer_session_with_id", &ValueArg { value })
         .await
@@ -88,16 +455,15 @@ pub async fn is_searching() -> bool {
         .unwrap()
 }
 
-pub async fn children_dirs(dir: &Rc<PitouFile>) -> Result<DirChildren, tauri_sys::Error> {
-    let arg = DirChildrenArgs::new(
-        &dir.path,
-        PitouFileFilter {
-            dirs: true,
-            files: false,
-            links: false,
-            sys_items: false,
-        },
-        None,
-    );
+/// The folders in `dir`, leaving out the hidden ones unless `hidden`.
+pub async fn children_dirs(
+    dir: &Rc<PitouFile>,
+    hidden: bool,
+) -> Result<DirChildren, tauri_sys::Error> {
+    let filter = PitouFileFilter {
+        hidden,
+        ..PitouFileFilter::only_dirs()
+    };
+    let arg = DirChildrenArgs::new(&dir.path, filter, None);
     tauri_sys::tauri::invoke("children", &arg).await
 }
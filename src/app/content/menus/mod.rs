@@ -1,4 +1,7 @@
-use pitou_core::*;
+use std::rc::Rc;
+
+use pitou_core::{search::SavedSearch, *};
+use wasm_bindgen_futures::spawn_local;
 use yew::prelude::*;
 
 use crate::app::{reusables::MatIcon, ApplicationContext};
@@ -23,7 +26,10 @@ pub fn Menus(props: &MenuProps) -> Html {
             <FavoritesMenu onactivate = { props.onswitchmenu.clone() } />
             <RecentsMenu onactivate = { props.onswitchmenu.clone() } />
             <SearchMenu onactivate = { props.onswitchmenu.clone() } />
+            <SavedSearchMenus onactivate = { props.onswitchmenu.clone() } />
             <CloudMenu onactivate = { props.onswitchmenu.clone() } />
+            <DuplicatesMenu onactivate = { props.onswitchmenu.clone() } />
+            <DiskUsageMenu onactivate = { props.onswitchmenu.clone() } />
             <SettingsMenu onactivate = { props.onswitchmenu.clone() } />
             <TrashMenu onactivate = { props.onswitchmenu.clone() } />
         </div>
@@ -163,6 +169,62 @@ fn SearchMenu(props: &EachMenuProps) -> Html {
     }
 }
 
+/// One entry per saved search, each opening it as a smart folder.
+#[function_component]
+fn SavedSearchMenus(props: &EachMenuProps) -> Html {
+    let ctx = use_context::<ApplicationContext>().unwrap();
+    let saved_searches = use_state(|| Rc::new(Vec::<Rc<SavedSearch>>::new()));
+
+    {
+        let saved_searches = saved_searches.clone();
+        use_effect_with(ctx.refresher_state(), move |_| {
+            spawn_local(async move {
+                if let Ok(items) = crate::app::cmds::saved_searches().await {
+                    saved_searches.set(Rc::new(items.into_iter().map(Rc::new).collect()));
+                }
+            })
+        });
+    }
+
+    let open = if matches!(ctx.current_menu(), AppMenu::Search) {
+        ctx.active_tab.smart_folder()
+    } else {
+        None
+    };
+
+    let entries = saved_searches
+        .iter()
+        .map(|saved| {
+            let class = if open.as_deref() == Some(&saved.name) {
+                "menu-item active-menu"
+            } else {
+                "menu-item"
+            };
+            let onclick = {
+                let ctx = ctx.clone();
+                let saved = saved.clone();
+                let onactivate = props.onactivate.clone();
+                move |_| {
+                    ctx.static_data.clear_all_selections();
+                    ctx.active_tab.open_smart_folder(&saved);
+                    onactivate.emit(AppMenu::Search)
+                }
+            };
+            html! {
+                <div {class} {onclick} title={saved.name.clone()}>
+                    <MatIcon name="saved_search" class="menu-item-icon" />
+                </div>
+            }
+        })
+        .collect::<Html>();
+
+    html! {
+        <div id="saved-search-menus">
+            { entries }
+        </div>
+    }
+}
+
 #[function_component]
 fn CloudMenu(props: &EachMenuProps) -> Html {
     let ctx = use_context::<ApplicationContext>().unwrap();
@@ -182,6 +244,44 @@ fn CloudMenu(props: &EachMenuProps) -> Html {
     }
 }
 
+#[function_component]
+fn DuplicatesMenu(props: &EachMenuProps) -> Html {
+    let ctx = use_context::<ApplicationContext>().unwrap();
+    let class = if matches!(ctx.current_menu(), AppMenu::Duplicates) {
+        "menu-item active-menu"
+    } else {
+        "menu-item"
+    };
+    let onclick = {
+        let onactivate = props.onactivate.clone();
+        move |_| onactivate.emit(AppMenu::Duplicates)
+    };
+    html! {
+        <div id="duplicates-menu" {class} {onclick} title="duplicate files">
+            <MatIcon name="file_copy" class="menu-item-icon" />
+        </div>
+    }
+}
+
+#[function_component]
+fn DiskUsageMenu(props: &EachMenuProps) -> Html {
+    let ctx = use_context::<ApplicationContext>().unwrap();
+    let class = if matches!(ctx.current_menu(), AppMenu::DiskUsage) {
+        "menu-item active-menu"
+    } else {
+        "menu-item"
+    };
+    let onclick = {
+        let onactivate = props.onactivate.clone();
+        move |_| onactivate.emit(AppMenu::DiskUsage)
+    };
+    html! {
+        <div id="disk-usage-menu" {class} {onclick} title="disk usage">
+            <MatIcon name="pie_chart" class="menu-item-icon" />
+        </div>
+    }
+}
+
 #[function_component]
 fn HomeMenu(props: &EachMenuProps) -> Html {
     let ctx = use_context::<ApplicationContext>().unwrap();
@@ -3,6 +3,8 @@ use std::rc::Rc;
 use pitou_core::*;
 use yew::prelude::*;
 
+use crate::app::reusables::QuickLook;
+
 pub mod menus;
 pub mod pane;
 pub mod ribbon;
@@ -31,6 +33,7 @@ pub fn Content(props: &ContentProps) -> Html {
             <Menus onswitchmenu = { props.onswitchmenu.clone() } />
             <Status />
             <Pane onupdatedir={props.onupdatedir.clone()} onupdatetheme={props.onupdatetheme.clone()} reload={props.reload.clone()} quietreload={props.quietreload.clone()} />
+            <QuickLook />
         </div>
     }
 }
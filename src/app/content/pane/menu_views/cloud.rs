@@ -1 +1,952 @@
+use std::{collections::HashSet, path::Path, rc::Rc};
 
+use pitou_core::{
+    cloud::{self, CloudAccount, CloudEntry, CloudError, CloudKind},
+    frontend::{ApplicationContext, CloudBrowsing},
+    PitouFile, PitouFilePath,
+};
+use wasm_bindgen_futures::spawn_local;
+use web_sys::{HtmlInputElement, HtmlSelectElement};
+use yew::prelude::*;
+
+use crate::app::reusables::list_file_type_icon;
+
+/// Uploads the files selected in the explorer into the cloud folder browsed last, following the
+/// upload in the status bar. Returns false if there is nothing to upload or nowhere to.
+pub(crate) async fn upload_selections(ctx: &ApplicationContext) -> bool {
+    let Some(items) = ctx
+        .static_data
+        .folder_entry_selections()
+        .filter(|items| !items.is_empty())
+    else {
+        return false;
+    };
+    let Some((id, dir)) = ctx
+        .static_data
+        .cloud
+        .borrow()
+        .as_ref()
+        .map(|browsing| (browsing.account.clone(), browsing.dir.clone()))
+    else {
+        return false;
+    };
+    match crate::app::cmds::upload_to_cloud(&id, &items, &dir).await {
+        Ok(Some(session)) => {
+            crate::app::events::emit_event("pasting", &session).await;
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Downloads the entries selected in the cloud folder browsed last into the folder open in the
+/// explorer, following the download in the status bar. Returns false if there is nothing to
+/// download or nowhere to.
+pub(crate) async fn download_selections(ctx: &ApplicationContext) -> bool {
+    let Some(pitou) = ctx.active_tab.current_dir() else {
+        return false;
+    };
+    let Some((id, entries)) = ctx
+        .static_data
+        .cloud
+        .borrow()
+        .as_ref()
+        .filter(|browsing| !browsing.selected.is_empty())
+        .map(|browsing| (browsing.account.clone(), browsing.selected.clone()))
+    else {
+        return false;
+    };
+    match crate::app::cmds::download_from_cloud(&id, &entries, pitou).await {
+        Ok(Some(session)) => {
+            crate::app::events::emit_event("pasting", &session).await;
+            true
+        }
+        _ => false,
+    }
+}
+
+/// A cloud entry along with the file it lists as.
+struct ListedEntry {
+    entry: CloudEntry,
+    file: Rc<PitouFile>,
+}
+
+/// What the name typed in the toolbar is for.
+#[derive(Clone)]
+enum Naming {
+    NewFolder,
+    Rename(Rc<ListedEntry>),
+}
+
+#[derive(Properties, PartialEq)]
+pub struct CloudViewProps {
+    pub onopen: Callback<Rc<PitouFile>>,
+}
+
+#[function_component]
+pub fn CloudView(props: &CloudViewProps) -> Html {
+    let ctx = use_context::<ApplicationContext>().unwrap();
+    let accounts = use_state(|| None::<Rc<Vec<CloudAccount>>>);
+    let current = use_state(|| {
+        let browsing = ctx.static_data.cloud.borrow();
+        browsing.as_ref().map(|browsing| browsing.account.clone())
+    });
+    let dir = use_state(|| {
+        let browsing = ctx.static_data.cloud.borrow();
+        browsing
+            .as_ref()
+            .map(|browsing| browsing.dir.clone())
+            .unwrap_or_default()
+    });
+    let editing = use_state(|| None::<CloudAccount>);
+    let listing = use_state(|| None::<Rc<Vec<Rc<ListedEntry>>>>);
+    let selected = use_state(HashSet::<String>::new);
+    let message = use_state(|| None::<String>);
+    let naming = use_state(|| None::<Naming>);
+    let confirm_delete = use_state_eq(|| false);
+
+    let reload_accounts = {
+        let accounts = accounts.clone();
+        let current = current.clone();
+        Callback::from(move |pick_last: bool| {
+            let accounts = accounts.clone();
+            let current = current.clone();
+            spawn_local(async move {
+                let saved = crate::app::cmds::cloud_accounts().await.unwrap_or_default();
+                let kept = current
+                    .as_ref()
+                    .filter(|id| saved.iter().any(|account| &account.id == *id));
+                // a new account is saved last
+                let picked = match pick_last {
+                    true => saved.last(),
+                    false => kept
+                        .and_then(|id| saved.iter().find(|account| &account.id == id))
+                        .or(saved.first()),
+                };
+                current.set(picked.map(|account| account.id.clone()));
+                accounts.set(Some(Rc::new(saved)))
+            })
+        })
+    };
+
+    {
+        let reload_accounts = reload_accounts.clone();
+        use_effect_with((), move |()| reload_accounts.emit(false));
+    }
+
+    let reload = {
+        let current = current.clone();
+        let dir = dir.clone();
+        let listing = listing.clone();
+        let selected = selected.clone();
+        let message = message.clone();
+        Callback::from(move |()| {
+            let Some(id) = (*current).clone() else {
+                listing.set(None);
+                return;
+            };
+            let dir = (*dir).clone();
+            let listing = listing.clone();
+            let selected = selected.clone();
+            let message = message.clone();
+            spawn_local(async move {
+                match crate::app::cmds::cloud_listing(&id, &dir).await {
+                    Ok(listed) => {
+                        let entries = listed
+                            .entries
+                            .into_iter()
+                            .map(|entry| {
+                                let file = Rc::new(entry.as_file());
+                                Rc::new(ListedEntry { entry, file })
+                            })
+                            .collect();
+                        message.set(listed.error.map(|e| e.message()));
+                        listing.set(Some(Rc::new(entries)))
+                    }
+                    Err(_) => listing.set(Some(Rc::new(Vec::new()))),
+                }
+                selected.set(HashSet::new())
+            })
+        })
+    };
+
+    {
+        let reload = reload.clone();
+        let deps = ((*current).clone(), (*dir).clone(), ctx.refresher_state());
+        use_effect_with(deps, move |_| reload.emit(()));
+    }
+
+    // the ribbon uploads into and downloads from what is browsed here
+    {
+        let ctx = ctx.clone();
+        let current = current.clone();
+        let dir = dir.clone();
+        let listing = listing.clone();
+        let selected = selected.clone();
+        use_effect(move || {
+            let browsing = (*current).clone().map(|account| CloudBrowsing {
+                account,
+                dir: (*dir).clone(),
+                selected: listing
+                    .iter()
+                    .flat_map(|listed| listed.iter())
+                    .filter(|listed| selected.contains(&listed.entry.path))
+                    .map(|listed| listed.entry.clone())
+                    .collect(),
+            });
+            *ctx.static_data.cloud.borrow_mut() = browsing;
+        });
+    }
+
+    let onpickaccount = {
+        let current = current.clone();
+        let dir = dir.clone();
+        let editing = editing.clone();
+        let message = message.clone();
+        Callback::from(move |id: String| {
+            if current.as_deref() != Some(id.as_str()) {
+                dir.set(String::new());
+                message.set(None);
+            }
+            editing.set(None);
+            current.set(Some(id))
+        })
+    };
+
+    let onaddaccount = {
+        let editing = editing.clone();
+        move |_| editing.set(Some(CloudAccount::new(CloudKind::WebDav)))
+    };
+
+    let onsaved = {
+        let editing = editing.clone();
+        let dir = dir.clone();
+        let reload_accounts = reload_accounts.clone();
+        Callback::from(move |new: bool| {
+            if new {
+                dir.set(String::new());
+            }
+            editing.set(None);
+            reload_accounts.emit(new)
+        })
+    };
+
+    let oncanceledit = {
+        let editing = editing.clone();
+        Callback::from(move |()| editing.set(None))
+    };
+
+    let onremoved = {
+        let editing = editing.clone();
+        let current = current.clone();
+        let dir = dir.clone();
+        let reload_accounts = reload_accounts.clone();
+        Callback::from(move |()| {
+            editing.set(None);
+            current.set(None);
+            dir.set(String::new());
+            reload_accounts.emit(false)
+        })
+    };
+
+    let account_list = accounts
+        .iter()
+        .flat_map(|accounts| accounts.iter())
+        .map(|account| {
+            let class = match current.as_deref() == Some(account.id.as_str()) {
+                true => "cloud-account selected",
+                false => "cloud-account",
+            };
+            let onclick = {
+                let onpickaccount = onpickaccount.clone();
+                let id = account.id.clone();
+                move |_| onpickaccount.emit(id.clone())
+            };
+            html! {
+                <div {class} {onclick} title={account.endpoint.clone()}>
+                    <div class="cloud-account-name">{ account.name.clone() }</div>
+                    <div class="cloud-account-kind">{ account.kind.label() }</div>
+                </div>
+            }
+        });
+
+    let current_account = accounts
+        .iter()
+        .flat_map(|accounts| accounts.iter())
+        .find(|account| current.as_deref() == Some(account.id.as_str()))
+        .cloned();
+
+    let browser = if let Some(account) = (*editing).clone() {
+        let key = account.id.clone();
+        html! {
+            <CloudAccountForm {key} {account} {onsaved} oncancel={oncanceledit} {onremoved}/>
+        }
+    } else if let Some(account) = current_account {
+        let props = CloudBrowserProps {
+            account: Rc::new(account),
+            dir: dir.clone(),
+            listing: listing.clone(),
+            selected: selected.clone(),
+            message: message.clone(),
+            naming: naming.clone(),
+            confirm_delete: confirm_delete.clone(),
+            editing: editing.clone(),
+            reload,
+            onopen: props.onopen.clone(),
+        };
+        html! { <CloudBrowser ..props/> }
+    } else if accounts.is_some() {
+        html! {
+            <div class="cloud-empty">
+                { "Add a WebDAV account, such as one of Nextcloud or ownCloud, an S3 compatible bucket or an SSH server to browse it here" }
+            </div>
+        }
+    } else {
+        html! {}
+    };
+
+    html! {
+        <div id="cloud-pane" class="fullpane">
+            <div id="cloud-accounts">
+                { for account_list }
+                <button onclick={onaddaccount}>{ "Add account" }</button>
+            </div>
+            <div id="cloud-browser">
+                { browser }
+            </div>
+        </div>
+    }
+}
+
+#[derive(Properties)]
+struct CloudBrowserProps {
+    account: Rc<CloudAccount>,
+    dir: UseStateHandle<String>,
+    listing: UseStateHandle<Option<Rc<Vec<Rc<ListedEntry>>>>>,
+    selected: UseStateHandle<HashSet<String>>,
+    message: UseStateHandle<Option<String>>,
+    naming: UseStateHandle<Option<Naming>>,
+    confirm_delete: UseStateHandle<bool>,
+    editing: UseStateHandle<Option<CloudAccount>>,
+    reload: Callback<()>,
+    onopen: Callback<Rc<PitouFile>>,
+}
+
+impl PartialEq for CloudBrowserProps {
+    fn eq(&self, other: &Self) -> bool {
+        self.account == other.account
+            && self.dir == other.dir
+            && self.listing.as_ref().map(Rc::as_ptr) == other.listing.as_ref().map(Rc::as_ptr)
+            && self.selected == other.selected
+            && self.message == other.message
+            && self.naming.is_some() == other.naming.is_some()
+            && self.confirm_delete == other.confirm_delete
+    }
+}
+
+#[function_component]
+fn CloudBrowser(props: &CloudBrowserProps) -> Html {
+    let ctx = use_context::<ApplicationContext>().unwrap();
+    let name_ref = use_node_ref();
+    let id = Rc::new(props.account.id.clone());
+
+    let entries = props.listing.as_deref().cloned().unwrap_or_default();
+    let selected_entries = entries
+        .iter()
+        .filter(|listed| props.selected.contains(&listed.entry.path))
+        .cloned()
+        .collect::<Vec<_>>();
+
+    let ongo = {
+        let dir = props.dir.clone();
+        let naming = props.naming.clone();
+        Callback::from(move |to: String| {
+            naming.set(None);
+            dir.set(to)
+        })
+    };
+
+    let onup = {
+        let ongo = ongo.clone();
+        let dir = props.dir.clone();
+        move |_| ongo.emit(cloud::parent(&dir).to_owned())
+    };
+
+    let onrefresh = {
+        let reload = props.reload.clone();
+        move |_| reload.emit(())
+    };
+
+    let onnewfolder = {
+        let naming = props.naming.clone();
+        move |_| naming.set(Some(Naming::NewFolder))
+    };
+
+    let onrename = {
+        let naming = props.naming.clone();
+        let selected_entries = selected_entries.clone();
+        move |_| {
+            if let [listed] = &selected_entries[..] {
+                naming.set(Some(Naming::Rename(listed.clone())))
+            }
+        }
+    };
+
+    let onnamed = {
+        let id = id.clone();
+        let dir = props.dir.clone();
+        let naming = props.naming.clone();
+        let message = props.message.clone();
+        let reload = props.reload.clone();
+        let name_ref = name_ref.clone();
+        move |e: SubmitEvent| {
+            e.prevent_default();
+            let name = name_ref
+                .cast::<HtmlInputElement>()
+                .map(|input| input.value())
+                .unwrap_or_default();
+            let Some(purpose) = (*naming).clone() else {
+                return;
+            };
+            if name.trim().is_empty() || name.contains('/') {
+                message.set(Some("Names can't be empty or hold a /".to_owned()));
+                return;
+            }
+            let id = id.clone();
+            let dir = (*dir).clone();
+            let naming = naming.clone();
+            let message = message.clone();
+            let reload = reload.clone();
+            spawn_local(async move {
+                let res = match purpose {
+                    Naming::NewFolder => {
+                        crate::app::cmds::create_cloud_folder(&id, &dir, &name).await
+                    }
+                    Naming::Rename(listed) => {
+                        crate::app::cmds::rename_cloud_entry(&id, &listed.entry, &name).await
+                    }
+                };
+                match res {
+                    Ok(Some(e)) => message.set(Some(e.message())),
+                    _ => {
+                        naming.set(None);
+                        message.set(None);
+                        reload.emit(())
+                    }
+                }
+            })
+        }
+    };
+
+    let oncancelnaming = {
+        let naming = props.naming.clone();
+        move |_| naming.set(None)
+    };
+
+    let onupload = {
+        let ctx = ctx.clone();
+        let message = props.message.clone();
+        move |_| {
+            let ctx = ctx.clone();
+            let message = message.clone();
+            spawn_local(async move {
+                if !upload_selections(&ctx).await {
+                    message.set(Some(
+                        "Select files or folders in the explorer to upload them here".to_owned(),
+                    ))
+                }
+            })
+        }
+    };
+
+    let ondownload = {
+        let ctx = ctx.clone();
+        let message = props.message.clone();
+        move |_| {
+            let ctx = ctx.clone();
+            let message = message.clone();
+            spawn_local(async move {
+                let prompt = match download_selections(&ctx).await {
+                    true => "Downloading into the folder open in the explorer",
+                    false => "Open a folder in the explorer to download into",
+                };
+                message.set(Some(prompt.to_owned()))
+            })
+        }
+    };
+
+    let ondelete = {
+        let confirm_delete = props.confirm_delete.clone();
+        move |_| confirm_delete.set(true)
+    };
+
+    let oncanceldelete = {
+        let confirm_delete = props.confirm_delete.clone();
+        move |_| confirm_delete.set(false)
+    };
+
+    let onconfirmdelete = {
+        let id = id.clone();
+        let confirm_delete = props.confirm_delete.clone();
+        let message = props.message.clone();
+        let reload = props.reload.clone();
+        let entries = selected_entries
+            .iter()
+            .map(|listed| listed.entry.clone())
+            .collect::<Vec<_>>();
+        move |_| {
+            let id = id.clone();
+            let message = message.clone();
+            let reload = reload.clone();
+            let entries = entries.clone();
+            confirm_delete.set(false);
+            spawn_local(async move {
+                if let Ok(Some(e)) = crate::app::cmds::delete_cloud_entries(&id, &entries).await {
+                    message.set(Some(e.message()))
+                }
+                reload.emit(())
+            })
+        }
+    };
+
+    // the account is mounted, so its folders open in the explorer like local ones
+    let onexplore = {
+        let onopen = props.onopen.clone();
+        let mount = props.account.mount();
+        let dir = props.dir.clone();
+        move |_| {
+            let path = PitouFilePath::mounted(&mount, Path::new(&*dir));
+            onopen.emit(Rc::new(PitouFile::without_metadata(path)))
+        }
+    };
+
+    let oneditaccount = {
+        let editing = props.editing.clone();
+        let account = props.account.clone();
+        move |_| editing.set(Some((*account).clone()))
+    };
+
+    let ontoggle = {
+        let selected = props.selected.clone();
+        Callback::from(move |path: String| {
+            let mut new = (*selected).clone();
+            if !new.remove(&path) {
+                new.insert(path);
+            }
+            selected.set(new)
+        })
+    };
+
+    // the folders above, each a way back to it
+    let mut crumbs = vec![html! {
+        <span class="cloud-crumb" onclick={ let ongo = ongo.clone(); move |_| ongo.emit(String::new()) }>
+            { props.account.name.clone() }
+        </span>
+    }];
+    let mut walked = String::new();
+    for part in props.dir.split('/').filter(|part| !part.is_empty()) {
+        walked = cloud::join(&walked, part);
+        let onclick = {
+            let ongo = ongo.clone();
+            let to = walked.clone();
+            move |_| ongo.emit(to.clone())
+        };
+        crumbs.push(html! { <span class="cloud-crumb-separator">{ "/" }</span> });
+        crumbs.push(html! { <span class="cloud-crumb" {onclick}>{ part.to_owned() }</span> });
+    }
+
+    let naming_controls = match &*props.naming {
+        Some(purpose) => {
+            let value = match purpose {
+                Naming::NewFolder => String::new(),
+                Naming::Rename(listed) => listed.entry.name().to_owned(),
+            };
+            let action = match purpose {
+                Naming::NewFolder => "Make folder",
+                Naming::Rename(_) => "Rename",
+            };
+            html! {
+                <form class="cloud-naming" onsubmit={onnamed}>
+                    <input type="text" ref={name_ref} {value} placeholder="name"/>
+                    <button type="submit">{ action }</button>
+                    <button type="button" onclick={oncancelnaming}>{ "Cancel" }</button>
+                </form>
+            }
+        }
+        None => html! {},
+    };
+
+    let delete_controls = if *props.confirm_delete {
+        html! {
+            <>
+                <span class="cloud-confirm">{ format!("Delete {} items from the cloud?", selected_entries.len()) }</span>
+                <button onclick={onconfirmdelete}>{ "Delete" }</button>
+                <button onclick={oncanceldelete}>{ "Cancel" }</button>
+            </>
+        }
+    } else {
+        html! { <button onclick={ondelete} disabled={selected_entries.is_empty()}>{ "Delete" }</button> }
+    };
+
+    let rows = entries.iter().map(|listed| {
+        html! {
+            <CloudListItem
+                listed={listed.clone()}
+                selected={props.selected.contains(&listed.entry.path)}
+                ontoggle={ontoggle.clone()}
+                onopen={ongo.clone()}
+            />
+        }
+    });
+
+    let empty = if props.listing.is_some() && entries.is_empty() && props.message.is_none() {
+        html! { <div class="cloud-empty">{ "This folder is empty" }</div> }
+    } else {
+        html! {}
+    };
+
+    html! {
+        <>
+            <div class="cloud-options">
+                <button onclick={onup} disabled={props.dir.is_empty()}>{ "Up" }</button>
+                <div class="cloud-crumbs">{ for crumbs }</div>
+                <button onclick={onrefresh}>{ "Refresh" }</button>
+                <button onclick={onexplore} title="browse this folder in the explorer, where items can be copied to and from it">{ "Open in explorer" }</button>
+                <button onclick={oneditaccount}>{ "Edit account" }</button>
+            </div>
+            <div class="cloud-options">
+                <button onclick={onnewfolder}>{ "New folder" }</button>
+                <button onclick={onupload} title="upload what is selected in the explorer">{ "Upload" }</button>
+                <button onclick={ondownload} disabled={selected_entries.is_empty()} title="download into the folder open in the explorer">{ "Download" }</button>
+                <button onclick={onrename} disabled={selected_entries.len() != 1}>{ "Rename" }</button>
+                { delete_controls }
+                { naming_controls }
+            </div>
+            <div class="cloud-status">{ props.message.as_deref().unwrap_or_default() }</div>
+            { empty }
+            <div id="cloud-list">
+                { for rows }
+            </div>
+        </>
+    }
+}
+
+#[derive(Properties)]
+struct CloudListItemProps {
+    listed: Rc<ListedEntry>,
+    selected: bool,
+    ontoggle: Callback<String>,
+    onopen: Callback<String>,
+}
+
+impl PartialEq for CloudListItemProps {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.listed, &other.listed) && self.selected == other.selected
+    }
+}
+
+#[function_component]
+fn CloudListItem(props: &CloudListItemProps) -> Html {
+    let entry = &props.listed.entry;
+
+    let onchange = {
+        let ontoggle = props.ontoggle.clone();
+        let path = entry.path.clone();
+        move |_| ontoggle.emit(path.clone())
+    };
+
+    let ondblclick = {
+        let onopen = props.onopen.clone();
+        let ontoggle = props.ontoggle.clone();
+        let path = entry.path.clone();
+        let is_dir = entry.is_dir;
+        move |_| match is_dir {
+            true => onopen.emit(path.clone()),
+            false => ontoggle.emit(path.clone()),
+        }
+    };
+
+    let modified = entry
+        .modified
+        .map(|t| t.datetime.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_default();
+    let size = match entry.is_dir {
+        true => String::new(),
+        false => pitou_core::PitouFileSize { bytes: entry.size }.format(),
+    };
+    let class = if props.selected {
+        "list-item cloud-item selected"
+    } else {
+        "list-item cloud-item"
+    };
+
+    html! {
+        <div {class} {ondblclick} title={entry.path.clone()}>
+            <div class="list-checkbox-container">
+                <input class="list-checkbox" type="checkbox" checked={props.selected} {onchange}/>
+            </div>
+            <div class="list-filetypeicon-container">
+                { list_file_type_icon(&props.listed.file) }
+            </div>
+            <div class="list-filename-container">
+                <div class="list-filename">{ entry.name().to_owned() }</div>
+            </div>
+            <div class="cloud-modified">{ modified }</div>
+            <div class="cloud-size">{ size }</div>
+        </div>
+    }
+}
+
+#[derive(PartialEq, Properties)]
+struct CloudAccountFormProps {
+    account: CloudAccount,
+    /// called with whether the account is a new one
+    onsaved: Callback<bool>,
+    oncancel: Callback<()>,
+    onremoved: Callback<()>,
+}
+
+#[function_component]
+fn CloudAccountForm(props: &CloudAccountFormProps) -> Html {
+    let kind = use_state_eq(|| props.account.kind);
+    let name_ref = use_node_ref();
+    let endpoint_ref = use_node_ref();
+    let username_ref = use_node_ref();
+    let secret_ref = use_node_ref();
+    let bucket_ref = use_node_ref();
+    let region_ref = use_node_ref();
+    let identity_ref = use_node_ref();
+    let ssh_hosts = use_state(Vec::new);
+    let message = use_state(|| None::<String>);
+    let busy = use_state_eq(|| false);
+    // an SFTP account whose server's key is waiting to be trusted, with the key's fingerprint
+    let unknown_key = use_state(|| None::<(CloudAccount, String)>);
+    let is_new = props.account.id.is_empty();
+
+    {
+        let ssh_hosts = ssh_hosts.clone();
+        use_effect_with((), move |_| {
+            spawn_local(async move {
+                if let Ok(hosts) = crate::app::cmds::ssh_hosts().await {
+                    ssh_hosts.set(hosts)
+                }
+            })
+        });
+    }
+
+    let onkind = {
+        let kind = kind.clone();
+        move |e: Event| {
+            let idx = e
+                .target_dyn_into::<HtmlSelectElement>()
+                .and_then(|select| select.value().parse::<usize>().ok());
+            if let Some(picked) = idx.and_then(|idx| CloudKind::ALL.get(idx)) {
+                kind.set(*picked)
+            }
+        }
+    };
+
+    // saves the account, trusting the fingerprint of its server's key first if given
+    let save = {
+        let message = message.clone();
+        let busy = busy.clone();
+        let unknown_key = unknown_key.clone();
+        let onsaved = props.onsaved.clone();
+        Callback::from(move |(account, trusted): (CloudAccount, Option<String>)| {
+            let message = message.clone();
+            let busy = busy.clone();
+            let unknown_key = unknown_key.clone();
+            let onsaved = onsaved.clone();
+            busy.set(true);
+            spawn_local(async move {
+                let trust = match &trusted {
+                    Some(fingerprint) => {
+                        crate::app::cmds::trust_cloud_host_key(&account, fingerprint).await
+                    }
+                    None => Ok(None),
+                };
+                let saved = match trust {
+                    Ok(None) => crate::app::cmds::save_cloud_account(&account).await,
+                    failed => failed,
+                };
+                match saved {
+                    Ok(None) => onsaved.emit(account.id.is_empty()),
+                    Ok(Some(CloudError::UnknownHostKey { fingerprint })) => {
+                        message.set(None);
+                        unknown_key.set(Some((account, fingerprint)))
+                    }
+                    Ok(Some(e)) => message.set(Some(e.message())),
+                    Err(_) => message.set(Some("The account couldn't be saved".to_owned())),
+                }
+                busy.set(false)
+            })
+        })
+    };
+
+    let onsubmit = {
+        let refs = [
+            name_ref.clone(),
+            endpoint_ref.clone(),
+            username_ref.clone(),
+            secret_ref.clone(),
+            bucket_ref.clone(),
+            region_ref.clone(),
+            identity_ref.clone(),
+        ];
+        let kind = kind.clone();
+        let message = message.clone();
+        let save = save.clone();
+        let id = props.account.id.clone();
+        move |e: SubmitEvent| {
+            e.prevent_default();
+            let [name, endpoint, username, secret, bucket, region, identity] =
+                refs.clone().map(|node| {
+                    node.cast::<HtmlInputElement>()
+                        .map(|input| input.value())
+                        .unwrap_or_default()
+                });
+            if name.trim().is_empty() {
+                message.set(Some("The account needs a name".to_owned()));
+                return;
+            }
+            let account = CloudAccount {
+                id: id.clone(),
+                name: name.trim().to_owned(),
+                kind: *kind,
+                endpoint,
+                username,
+                secret,
+                bucket,
+                region,
+                identity,
+            };
+            save.emit((account, None))
+        }
+    };
+
+    let ontrust = {
+        let unknown_key = unknown_key.clone();
+        move |_| {
+            if let Some((account, fingerprint)) = (*unknown_key).clone() {
+                unknown_key.set(None);
+                save.emit((account, Some(fingerprint)))
+            }
+        }
+    };
+
+    let ondistrust = {
+        let unknown_key = unknown_key.clone();
+        move |_| unknown_key.set(None)
+    };
+
+    let oncancel = {
+        let oncancel = props.oncancel.clone();
+        move |_| oncancel.emit(())
+    };
+
+    let onremove = {
+        let id = props.account.id.clone();
+        let onremoved = props.onremoved.clone();
+        move |_| {
+            let id = id.clone();
+            let onremoved = onremoved.clone();
+            spawn_local(async move {
+                crate::app::cmds::remove_cloud_account(&id).await.ok();
+                onremoved.emit(())
+            })
+        }
+    };
+
+    let kind_options = CloudKind::ALL.iter().enumerate().map(|(idx, option)| {
+        html! {
+            <option value={idx.to_string()} selected={*option == *kind}>{ option.label() }</option>
+        }
+    });
+
+    let account = &props.account;
+    let (endpoint_hint, username_hint, secret_hint) = match *kind {
+        CloudKind::WebDav => (
+            "https://cloud.example.com/remote.php/dav/files/me/",
+            "user name",
+            "password or app password",
+        ),
+        CloudKind::S3 => (
+            "https://s3.amazonaws.com or http://localhost:9000",
+            "access key",
+            "secret key",
+        ),
+        CloudKind::Sftp => (
+            "a host from ~/.ssh/config, or user@host:port/folder",
+            "user name, if not set elsewhere",
+            "password or key passphrase, if needed",
+        ),
+    };
+    let secret_hint = match is_new {
+        true => secret_hint.to_owned(),
+        false => format!("{secret_hint}, left empty to keep the saved one"),
+    };
+    let kind_fields = match *kind {
+        CloudKind::S3 => html! {
+            <>
+                <input type="text" placeholder="bucket" ref={bucket_ref} value={account.bucket.clone()}/>
+                <input type="text" placeholder="region" ref={region_ref} value={account.region.clone()}/>
+            </>
+        },
+        CloudKind::Sftp => html! {
+            <input type="text" placeholder="private key file, such as ~/.ssh/id_ed25519, if not the usual ones" ref={identity_ref} value={account.identity.clone()}/>
+        },
+        CloudKind::WebDav => html! {},
+    };
+    let host_options = match *kind {
+        CloudKind::Sftp => ssh_hosts
+            .iter()
+            .map(|host| html! { <option value={host.clone()}/> })
+            .collect::<Html>(),
+        _ => html! {},
+    };
+    let host_key = match &*unknown_key {
+        Some((_, fingerprint)) => html! {
+            <div class="cloud-form-host-key">
+                <div>
+                    { "This server hasn't been reached before. The SHA256 fingerprint of its key is:" }
+                </div>
+                <code>{ fingerprint.clone() }</code>
+                <div>{ "Only trust it if it is the one of the server, as told by its administrator." }</div>
+                <div class="cloud-form-actions">
+                    <button type="button" disabled={*busy} onclick={ontrust}>{ "Trust and save" }</button>
+                    <button type="button" onclick={ondistrust}>{ "Don't trust" }</button>
+                </div>
+            </div>
+        },
+        None => html! {},
+    };
+    let remove = match is_new {
+        true => html! {},
+        false => html! { <button type="button" onclick={onremove}>{ "Remove account" }</button> },
+    };
+
+    html! {
+        <form class="cloud-form" {onsubmit}>
+            <div class="cloud-form-title">{ if is_new { "Add an account" } else { "Edit the account" } }</div>
+            <input type="text" placeholder="name" ref={name_ref} value={account.name.clone()}/>
+            <select onchange={onkind}>
+                { for kind_options }
+            </select>
+            <input type="text" placeholder={endpoint_hint} ref={endpoint_ref} value={account.endpoint.clone()} list="cloud-ssh-hosts"/>
+            <datalist id="cloud-ssh-hosts">{ host_options }</datalist>
+            <input type="text" placeholder={username_hint} ref={username_ref} value={account.username.clone()} autocomplete="off"/>
+            <input type="password" placeholder={secret_hint} ref={secret_ref} autocomplete="new-password"/>
+            { kind_fields }
+            <div class="cloud-form-hint">
+                { "Credentials are kept on this computer only, in a file only you can read." }
+            </div>
+            <div class="cloud-form-actions">
+                <button type="submit" disabled={*busy}>{ "Save" }</button>
+                <button type="button" onclick={oncancel}>{ "Cancel" }</button>
+                { remove }
+            </div>
+            { host_key }
+            <div class="cloud-form-message">{ message.as_deref().unwrap_or_default() }</div>
+        </form>
+    }
+}
@@ -0,0 +1,524 @@
+use std::{cell::RefCell, f64::consts::TAU, rc::Rc};
+
+use pitou_core::{
+    frontend::{
+        charts::{sunburst, treemap, Rect},
+        ApplicationContext,
+    },
+    msg::{UsageMsg, UsageProgress},
+    usage::{UsageNode, UsageReport},
+    PitouFile, PitouFilePath, PitouFileSize,
+};
+use tokio_stream::StreamExt;
+use wasm_bindgen_futures::spawn_local;
+use web_sys::HtmlInputElement;
+use yew::{platform::time::interval, prelude::*};
+
+use super::obtain_drives;
+
+/// How many levels below the focused directory are fetched and drawn.
+const CHART_DEPTH: u32 = 2;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Chart {
+    Treemap,
+    Sunburst,
+}
+
+#[derive(Default)]
+struct DiskUsage {
+    progress: UsageProgress,
+    running: bool,
+    report: Option<Rc<UsageReport>>,
+    focus: Option<Rc<UsageNode>>,
+}
+
+async fn focus_on(
+    path: PitouFilePath,
+    state: Rc<RefCell<DiskUsage>>,
+    update: UseForceUpdateHandle,
+) {
+    if let Ok(Some(node)) = crate::app::cmds::usage_tree(&path, CHART_DEPTH).await {
+        state.borrow_mut().focus = Some(Rc::new(node));
+        update.force_update();
+    }
+}
+
+async fn begin_usage_stream(
+    root: PitouFilePath,
+    state: Rc<RefCell<DiskUsage>>,
+    update: UseForceUpdateHandle,
+) {
+    if let Ok(()) = crate::app::cmds::scan_usage(&root).await {
+        *state.borrow_mut() = DiskUsage {
+            running: true,
+            ..Default::default()
+        };
+        update.force_update();
+        let mut interval = Box::pin(interval(std::time::Duration::from_millis(250)));
+        while interval.next().await.is_some() {
+            let Ok(msg) = crate::app::cmds::usage_msg().await else {
+                state.borrow_mut().running = false;
+                break;
+            };
+            let (progress, terminated) = match msg {
+                UsageMsg::Active(progress) => (progress, false),
+                UsageMsg::Terminated(progress) => (progress, true),
+            };
+            {
+                let mut state = state.borrow_mut();
+                state.progress = progress;
+                state.running = !terminated;
+            }
+            update.force_update();
+            if terminated {
+                break;
+            }
+        }
+        // a cancelled scan isn't cached, so there is nothing to show for it
+        if let Ok(Some(report)) = crate::app::cmds::usage_report(&root).await {
+            state.borrow_mut().report = Some(Rc::new(report));
+            focus_on(root, state, update).await;
+        }
+    } else {
+        web_sys::console::log_1(
+            &serde_wasm_bindgen::to_value("couldn't begin scanning disk usage").unwrap(),
+        );
+    }
+}
+
+#[derive(PartialEq, Properties)]
+pub struct DiskUsageViewProps {
+    pub onopen: Callback<Rc<PitouFile>>,
+}
+
+#[function_component]
+pub fn DiskUsageView(props: &DiskUsageViewProps) -> Html {
+    let ctx = use_context::<ApplicationContext>().unwrap();
+    let update = use_force_update();
+    let root_ref = use_node_ref();
+    let state = use_mut_ref(DiskUsage::default);
+    let chart = use_state(|| Chart::Treemap);
+
+    {
+        let data = ctx.static_data.clone();
+        let update = update.clone();
+        use_effect_with((), move |()| {
+            if data.drives.borrow().is_none() {
+                spawn_local(async move { obtain_drives(data, move || update.force_update()).await })
+            }
+        })
+    }
+
+    let onscan = {
+        let state = state.clone();
+        let update = update.clone();
+        let root_ref = root_ref.clone();
+        Callback::from(move |root: Option<String>| {
+            let input = root_ref.cast::<HtmlInputElement>().unwrap();
+            let root = match root {
+                Some(root) => {
+                    input.set_value(&root);
+                    root
+                }
+                None => input.value(),
+            };
+            let root = root.trim();
+            if root.is_empty() {
+                return;
+            }
+            let root = PitouFilePath::from_pathbuf(std::path::PathBuf::from(root));
+            let state = state.clone();
+            let update = update.clone();
+            spawn_local(async move { begin_usage_stream(root, state, update).await })
+        })
+    };
+
+    let oncancel = move |_| {
+        spawn_local(async move {
+            crate::app::cmds::terminate_usage().await.ok();
+        })
+    };
+
+    let onfocus = {
+        let state = state.clone();
+        let update = update.clone();
+        Callback::from(move |path: PitouFilePath| {
+            let state = state.clone();
+            let update = update.clone();
+            spawn_local(async move { focus_on(path, state, update).await })
+        })
+    };
+
+    let onopenfile = {
+        let onopen = props.onopen.clone();
+        Callback::from(move |path: PitouFilePath| {
+            onopen.emit(Rc::new(PitouFile::without_metadata(path)))
+        })
+    };
+
+    let default_root = ctx
+        .active_tab
+        .current_dir()
+        .map(|dir| dir.full_path_str().to_owned())
+        .unwrap_or_default();
+
+    let drives = ctx
+        .static_data
+        .drives
+        .borrow()
+        .iter()
+        .flat_map(|drives| drives.iter())
+        .map(|drive| {
+            let mount_point = drive.mount_point().path.display().to_string();
+            let onclick = {
+                let onscan = onscan.clone();
+                let mount_point = mount_point.clone();
+                move |_| onscan.emit(Some(mount_point.clone()))
+            };
+            let used = drive.total_space.saturating_sub(drive.free_space);
+            let title = format!(
+                "{} of {} used",
+                PitouFileSize::new(used).format(),
+                PitouFileSize::new(drive.total_space).format()
+            );
+            html! {
+                <div class="disk-usage-drive" {title} {onclick}>{ mount_point }</div>
+            }
+        })
+        .collect::<Html>();
+
+    let state_ref = state.borrow();
+
+    let scan_or_cancel_btn = if state_ref.running {
+        html! { <button class="disk-usage-cancel-btn" onclick={oncancel}>{"Cancel"}</button> }
+    } else {
+        let onscan = onscan.clone();
+        html! { <button onclick={move |_| onscan.emit(None)}>{"Scan"}</button> }
+    };
+
+    let summary = match &state_ref.report {
+        Some(report) if !state_ref.running => format!(
+            "{} in {} files and {} folders, scanned {}",
+            PitouFileSize::new(report.size).format(),
+            report.files,
+            report.dirs,
+            report.scanned_at.datetime.format("%Y-%m-%d %H:%M")
+        ),
+        _ => format!(
+            "{} in {} files and {} folders{}",
+            PitouFileSize::new(state_ref.progress.bytes).format(),
+            state_ref.progress.files,
+            state_ref.progress.dirs,
+            if state_ref.running { "..." } else { "" }
+        ),
+    };
+
+    let chart_view = match &state_ref.focus {
+        Some(focus) => {
+            let root = state_ref.report.as_ref().map(|r| r.root.path.clone());
+            let up = focus
+                .path
+                .path
+                .parent()
+                .filter(|_| Some(&focus.path.path) != root.as_ref())
+                .map(|parent| {
+                    let onfocus = onfocus.clone();
+                    let parent = parent.to_path_buf();
+                    let onclick =
+                        move |_| onfocus.emit(PitouFilePath::from_pathbuf(parent.clone()));
+                    html! { <button {onclick}>{"Up"}</button> }
+                });
+            let ontreemap = {
+                let chart = chart.clone();
+                move |_| chart.set(Chart::Treemap)
+            };
+            let onsunburst = {
+                let chart = chart.clone();
+                move |_| chart.set(Chart::Sunburst)
+            };
+            let (treemap_class, sunburst_class) = match *chart {
+                Chart::Treemap => ("selected", "not-selected"),
+                Chart::Sunburst => ("not-selected", "selected"),
+            };
+            let content = match *chart {
+                Chart::Treemap => {
+                    html! { <UsageTreemap node={focus.clone()} onfocus={onfocus.clone()} /> }
+                }
+                Chart::Sunburst => {
+                    html! { <UsageSunburst node={focus.clone()} onfocus={onfocus.clone()} /> }
+                }
+            };
+            html! {
+                <div id="disk-usage-chart-section">
+                    <div id="disk-usage-chart-header">
+                        { up }
+                        <span class="disk-usage-focus" title={focus.path.path.display().to_string()}>
+                            { format!("{} ({})", focus.path.path.display(), PitouFileSize::new(focus.size).format()) }
+                        </span>
+                        <button class={treemap_class} onclick={ontreemap}>{"Treemap"}</button>
+                        <button class={sunburst_class} onclick={onsunburst}>{"Sunburst"}</button>
+                    </div>
+                    { content }
+                </div>
+            }
+        }
+        None => {
+            html! { <div id="disk-usage-chart-section" class="disk-usage-empty">{"Scan a folder or drive to see what takes up its space"}</div> }
+        }
+    };
+
+    let details = match &state_ref.report {
+        Some(report) => html! {
+            <div id="disk-usage-details">
+                <LargestFiles report={report.clone()} onopen={onopenfile} />
+                <TypeBreakdown report={report.clone()} />
+            </div>
+        },
+        None => html! {},
+    };
+
+    html! {
+        <div id="disk-usage-pane" class="fullpane">
+            <div id="disk-usage-options">
+                <input type="text" placeholder="/home/me" value={default_root} ref={root_ref} autocomplete="off"/>
+                { scan_or_cancel_btn }
+                <span class="disk-usage-summary">{ summary }</span>
+            </div>
+            <div id="disk-usage-drives">{ drives }</div>
+            <div id="disk-usage-body">
+                { chart_view }
+                { details }
+            </div>
+        </div>
+    }
+}
+
+fn node_color(idx: usize, ring: u32, is_dir: bool) -> String {
+    if !is_dir {
+        return String::from("hsl(0, 0%, 55%)");
+    }
+    let hue = (idx * 47) % 360;
+    format!("hsl({hue}, 55%, {}%)", 38 + ring * 10)
+}
+
+fn node_title(node: &UsageNode) -> String {
+    let name = if node.is_dir {
+        node.path.path.display().to_string()
+    } else {
+        format!("{} in {}", node.name, node.path.path.display())
+    };
+    format!(
+        "{name}\n{} in {} files",
+        PitouFileSize::new(node.size).format(),
+        node.files
+    )
+}
+
+#[derive(Properties)]
+struct UsageChartProps {
+    node: Rc<UsageNode>,
+    onfocus: Callback<PitouFilePath>,
+}
+
+impl PartialEq for UsageChartProps {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.node, &other.node)
+    }
+}
+
+#[function_component]
+fn UsageTreemap(props: &UsageChartProps) -> Html {
+    let children = &props.node.children;
+    let sizes = children.iter().map(|c| c.size).collect::<Vec<_>>();
+    let tiles = treemap(&sizes, Rect::UNIT)
+        .into_iter()
+        .zip(children)
+        .enumerate()
+        .filter(|(_, (_, child))| child.size > 0)
+        .map(|(idx, (rect, child))| {
+            let style = format!(
+                "left: {}%; top: {}%; width: {}%; height: {}%; background-color: {};",
+                rect.x * 100.0,
+                rect.y * 100.0,
+                rect.w * 100.0,
+                rect.h * 100.0,
+                node_color(idx, 1, child.is_dir)
+            );
+            let onclick = child.is_dir.then(|| {
+                let onfocus = props.onfocus.clone();
+                let path = child.path.path.clone();
+                move |_| onfocus.emit(PitouFilePath::from_pathbuf(path.clone()))
+            });
+            let inner = child
+                .children
+                .iter()
+                .map(|c| c.size)
+                .collect::<Vec<_>>();
+            let inner = treemap(&inner, Rect::UNIT)
+                .into_iter()
+                .zip(&child.children)
+                .filter(|(_, grandchild)| grandchild.size > 0)
+                .map(|(rect, grandchild)| {
+                    let style = format!(
+                        "left: {}%; top: {}%; width: {}%; height: {}%;",
+                        rect.x * 100.0,
+                        rect.y * 100.0,
+                        rect.w * 100.0,
+                        rect.h * 100.0
+                    );
+                    html! { <div class="treemap-subtile" {style} title={node_title(grandchild)}></div> }
+                })
+                .collect::<Html>();
+            html! {
+                <div class="treemap-tile" {style} title={node_title(child)} {onclick}>
+                    <div class="treemap-subtiles">{ inner }</div>
+                    <span class="treemap-label">{ &child.name }</span>
+                </div>
+            }
+        })
+        .collect::<Html>();
+    html! {
+        <div id="disk-usage-treemap">{ tiles }</div>
+    }
+}
+
+/// An SVG path for the ring segment between `start` and `end`, given as fractions of a turn.
+fn ring_segment(start: f64, end: f64, inner: f64, outer: f64) -> String {
+    // a full ring can't be drawn as a single arc
+    let end = if end - start >= 1.0 { end - 1e-4 } else { end };
+    let point = |turn: f64, r: f64| {
+        let angle = turn * TAU;
+        (r * angle.sin(), -r * angle.cos())
+    };
+    let large = if end - start > 0.5 { 1 } else { 0 };
+    let (x0, y0) = point(start, outer);
+    let (x1, y1) = point(end, outer);
+    let (x2, y2) = point(end, inner);
+    let (x3, y3) = point(start, inner);
+    format!(
+        "M {x0} {y0} A {outer} {outer} 0 {large} 1 {x1} {y1} L {x2} {y2} A {inner} {inner} 0 {large} 0 {x3} {y3} Z"
+    )
+}
+
+#[function_component]
+fn UsageSunburst(props: &UsageChartProps) -> Html {
+    const CENTER: f64 = 0.3;
+    const RING: f64 = 0.3;
+    let mut hue = 0;
+    let segments = sunburst(&props.node)
+        .into_iter()
+        .filter(|segment| segment.end - segment.start > 1e-3)
+        .map(|segment| {
+            if segment.ring == 1 {
+                hue += 1;
+            }
+            let inner = CENTER + RING * (segment.ring - 1) as f64;
+            let d = ring_segment(segment.start, segment.end, inner, inner + RING);
+            let fill = node_color(hue, segment.ring, segment.node.is_dir);
+            let onclick = segment.node.is_dir.then(|| {
+                let onfocus = props.onfocus.clone();
+                let path = segment.node.path.path.clone();
+                move |_| onfocus.emit(PitouFilePath::from_pathbuf(path.clone()))
+            });
+            html! {
+                <path class="sunburst-segment" {d} {fill} {onclick}>
+                    <title>{ node_title(segment.node) }</title>
+                </path>
+            }
+        })
+        .collect::<Html>();
+    html! {
+        <svg id="disk-usage-sunburst" viewBox="-1 -1 2 2">
+            <circle class="sunburst-center" r={CENTER.to_string()}>
+                <title>{ node_title(&props.node) }</title>
+            </circle>
+            { segments }
+        </svg>
+    }
+}
+
+#[derive(Properties)]
+struct ReportProps {
+    report: Rc<UsageReport>,
+}
+
+impl PartialEq for ReportProps {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.report, &other.report)
+    }
+}
+
+#[derive(Properties)]
+struct LargestFilesProps {
+    report: Rc<UsageReport>,
+    onopen: Callback<PitouFilePath>,
+}
+
+impl PartialEq for LargestFilesProps {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.report, &other.report)
+    }
+}
+
+#[function_component]
+fn LargestFiles(props: &LargestFilesProps) -> Html {
+    let files = props
+        .report
+        .largest
+        .iter()
+        .map(|file| {
+            let ondblclick = {
+                let onopen = props.onopen.clone();
+                let path = file.path.path.clone();
+                move |_| onopen.emit(PitouFilePath::from_pathbuf(path.clone()))
+            };
+            let path = file.path.path.display().to_string();
+            html! {
+                <div class="disk-usage-row" title={path.clone()} {ondblclick}>
+                    <span class="disk-usage-row-name">{ path }</span>
+                    <span class="disk-usage-row-size">{ PitouFileSize::new(file.size).format() }</span>
+                </div>
+            }
+        })
+        .collect::<Html>();
+    html! {
+        <div id="disk-usage-largest" class="disk-usage-list">
+            <div class="disk-usage-list-title">{ "Largest files" }</div>
+            { files }
+        </div>
+    }
+}
+
+#[function_component]
+fn TypeBreakdown(props: &ReportProps) -> Html {
+    let total = props.report.size.max(1) as f64;
+    let types = props
+        .report
+        .by_type
+        .iter()
+        .map(|usage| {
+            let extension = if usage.extension.is_empty() {
+                String::from("(none)")
+            } else {
+                format!(".{}", usage.extension)
+            };
+            let style = format!("width: {}%;", usage.size as f64 / total * 100.0);
+            html! {
+                <div class="disk-usage-row" title={format!("{} files", usage.count)}>
+                    <span class="disk-usage-row-name">{ extension }</span>
+                    <div class="disk-usage-bar">
+                        <div class="disk-usage-bar-inner" {style}></div>
+                    </div>
+                    <span class="disk-usage-row-size">{ PitouFileSize::new(usage.size).format() }</span>
+                </div>
+            }
+        })
+        .collect::<Html>();
+    html! {
+        <div id="disk-usage-types" class="disk-usage-list">
+            <div class="disk-usage-list-title">{ "By type" }</div>
+            { types }
+        </div>
+    }
+}
@@ -0,0 +1,352 @@
+use std::{cell::RefCell, rc::Rc};
+
+use pitou_core::{
+    duplicates::{DuplicateOptions, KeepRule},
+    frontend::{
+        extra::{DuplicateFinder, DuplicateSet},
+        ApplicationContext,
+    },
+    msg::DuplicateMsg,
+    PitouFile, PitouFilePath, PitouFileSize,
+};
+use tokio_stream::StreamExt;
+use wasm_bindgen_futures::spawn_local;
+use web_sys::{HtmlInputElement, HtmlSelectElement};
+use yew::{platform::time::interval, prelude::*};
+
+async fn begin_duplicate_stream(
+    options: DuplicateOptions,
+    finder: Rc<RefCell<DuplicateFinder>>,
+    update: UseForceUpdateHandle,
+) {
+    if let Ok(()) = crate::app::cmds::find_duplicates(options).await {
+        finder.borrow_mut().running = true;
+        update.force_update();
+        let mut interval = Box::pin(interval(std::time::Duration::from_millis(250)));
+        while interval.next().await.is_some() {
+            let Ok(msg) = crate::app::cmds::duplicates_msg().await else {
+                finder.borrow_mut().running = false;
+                break;
+            };
+            let (progress, groups, terminated) = match msg {
+                DuplicateMsg::Active(progress, groups) => (progress, groups, false),
+                DuplicateMsg::Terminated(progress, groups) => (progress, groups, true),
+            };
+            {
+                let mut finder = finder.borrow_mut();
+                finder.progress = progress;
+                finder.extend(groups.into_iter());
+                finder.running = !terminated;
+            }
+            update.force_update();
+            if terminated {
+                break;
+            }
+        }
+    } else {
+        web_sys::console::log_1(
+            &serde_wasm_bindgen::to_value("couldn't begin looking for duplicates").unwrap(),
+        );
+    }
+}
+
+const MIN_SIZES: [(&str, u64); 4] = [
+    ("Any size", 0),
+    ("At least 1 KB", 1 << 10),
+    ("At least 1 MB", 1 << 20),
+    ("At least 100 MB", 100 << 20),
+];
+
+const KEEP_RULES: [(&str, KeepRule); 3] = [
+    ("Keep newest", KeepRule::Newest),
+    ("Keep oldest", KeepRule::Oldest),
+    ("Keep shortest path", KeepRule::ShortestPath),
+];
+
+#[derive(PartialEq, Properties)]
+pub struct DuplicatesViewProps {
+    pub onopen: Callback<Rc<PitouFile>>,
+}
+
+#[function_component]
+pub fn DuplicatesView(props: &DuplicatesViewProps) -> Html {
+    let ctx = use_context::<ApplicationContext>().unwrap();
+    let update = use_force_update();
+    let roots_ref = use_node_ref();
+    let min_size = use_mut_ref(|| MIN_SIZES[2].1);
+    let keep_rule = use_mut_ref(|| KEEP_RULES[0].1);
+    let finder = ctx.active_tab.duplicate_finder();
+
+    let onfind = {
+        let ctx = ctx.clone();
+        let update = update.clone();
+        let roots_ref = roots_ref.clone();
+        let min_size = min_size.clone();
+        move |_| {
+            let roots = roots_ref
+                .cast::<HtmlInputElement>()
+                .unwrap()
+                .value()
+                .split(';')
+                .map(|v| v.trim())
+                .filter(|v| !v.is_empty())
+                .map(|v| PitouFilePath::from_pathbuf(std::path::PathBuf::from(v)))
+                .collect::<Vec<_>>();
+            if roots.is_empty() {
+                return;
+            }
+            let options = DuplicateOptions {
+                roots,
+                min_size: *min_size.borrow(),
+                hardware_accelerate: ctx.active_tab.search_options.borrow().hardware_accelerate,
+            };
+            let finder = ctx.active_tab.reset_duplicate_finder();
+            let update = update.clone();
+            spawn_local(async move { begin_duplicate_stream(options, finder, update).await })
+        }
+    };
+
+    let oncancel = move |_| {
+        spawn_local(async move {
+            crate::app::cmds::terminate_duplicates().await.ok();
+        })
+    };
+
+    let onchangeminsize = {
+        let min_size = min_size.clone();
+        move |e: Event| {
+            let idx = e
+                .target_dyn_into::<HtmlSelectElement>()
+                .unwrap()
+                .selected_index();
+            if let Some((_, size)) = MIN_SIZES.get(idx as usize) {
+                *min_size.borrow_mut() = *size;
+            }
+        }
+    };
+
+    let onchangekeeprule = {
+        let keep_rule = keep_rule.clone();
+        move |e: Event| {
+            let idx = e
+                .target_dyn_into::<HtmlSelectElement>()
+                .unwrap()
+                .selected_index();
+            if let Some((_, rule)) = KEEP_RULES.get(idx as usize) {
+                *keep_rule.borrow_mut() = *rule;
+            }
+        }
+    };
+
+    let onautoselect = {
+        let finder = finder.clone();
+        let keep_rule = keep_rule.clone();
+        let update = update.clone();
+        move |_| {
+            finder.borrow_mut().auto_select(*keep_rule.borrow());
+            update.force_update();
+        }
+    };
+
+    let ondelete = {
+        let finder = finder.clone();
+        let update = update.clone();
+        move |_| {
+            let items = finder.borrow().selections();
+            if items.is_empty() {
+                return;
+            }
+            let finder = finder.clone();
+            let update = update.clone();
+            spawn_local(async move {
+                if crate::app::cmds::delete(&items).await.is_ok() {
+                    finder.borrow_mut().remove_selected();
+                    update.force_update();
+                }
+            })
+        }
+    };
+
+    let ontoggle = {
+        let finder = finder.clone();
+        let update = update.clone();
+        Callback::from(move |file: Rc<PitouFile>| {
+            finder.borrow_mut().toggle(&file);
+            update.force_update();
+        })
+    };
+
+    let default_roots = ctx
+        .active_tab
+        .current_dir()
+        .map(|dir| dir.full_path_str().to_owned())
+        .unwrap_or_default();
+
+    let state = finder.borrow();
+    let reclaimable = state.groups.iter().map(|g| g.reclaimable()).sum::<u64>();
+    let summary = format!(
+        "Scanned {} files, hashed {} of {} candidates. {} groups, {} reclaimable.",
+        state.progress.scanned,
+        state.progress.hashed,
+        state.progress.candidates,
+        state.groups.len(),
+        PitouFileSize::new(reclaimable).format()
+    );
+    let selection = format!(
+        "Delete {} files ({})",
+        state.selected.len(),
+        PitouFileSize::new(state.selected_size()).format()
+    );
+
+    let find_or_cancel_btn = if state.running {
+        html! { <button class="duplicates-cancel-btn" onclick={oncancel}>{"Cancel"}</button> }
+    } else {
+        html! { <button onclick={onfind}>{"Find Duplicates"}</button> }
+    };
+
+    let current_min_size = *min_size.borrow();
+    let current_keep_rule = *keep_rule.borrow();
+
+    let groups = state
+        .groups
+        .iter()
+        .map(|group| {
+            let files = group
+                .files
+                .iter()
+                .map(|file| {
+                    let selected = state.is_selected(file);
+                    html! { <DuplicateItem item={file.clone()} {selected} onopen={props.onopen.clone()} ontoggle={ontoggle.clone()}/> }
+                })
+                .collect::<Html>();
+            html! {
+                <div class="duplicate-group">
+                    <DuplicateGroupHeader group={group.clone()}/>
+                    { files }
+                </div>
+            }
+        })
+        .collect::<Html>();
+
+    html! {
+        <div id="duplicates-pane" class="fullpane">
+            <div id="duplicates-options">
+                <input type="text" placeholder="/home/me/Downloads; /home/me/Pictures" value={default_roots} ref={roots_ref} autocomplete="off"/>
+                <select class="select-box" onchange={onchangeminsize}>
+                    {
+                        MIN_SIZES.iter().map(|(label, size)| html! {
+                            <option selected={*size == current_min_size}>{ *label }</option>
+                        }).collect::<Html>()
+                    }
+                </select>
+                { find_or_cancel_btn }
+            </div>
+            <div id="duplicates-actions">
+                <span class="duplicates-summary">{ summary }</span>
+                <select class="select-box" onchange={onchangekeeprule}>
+                    {
+                        KEEP_RULES.iter().map(|(label, rule)| html! {
+                            <option selected={*rule == current_keep_rule}>{ *label }</option>
+                        }).collect::<Html>()
+                    }
+                </select>
+                <button onclick={onautoselect}>{"Auto Select"}</button>
+                <button class="duplicates-delete-btn" onclick={ondelete}>{ selection }</button>
+            </div>
+            <div id="duplicates-groups">
+                { groups }
+            </div>
+        </div>
+    }
+}
+
+#[derive(Properties)]
+struct DuplicateGroupHeaderProps {
+    group: Rc<DuplicateSet>,
+}
+
+impl PartialEq for DuplicateGroupHeaderProps {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.group, &other.group)
+    }
+}
+
+#[function_component]
+fn DuplicateGroupHeader(props: &DuplicateGroupHeaderProps) -> Html {
+    let group = &props.group;
+    let label = format!(
+        "{} copies of {}, {} reclaimable",
+        group.files.len(),
+        PitouFileSize::new(group.size).format(),
+        PitouFileSize::new(group.reclaimable()).format()
+    );
+    let hash = group.hash.chars().take(12).collect::<String>();
+    html! {
+        <div class="duplicate-group-header">
+            <span>{ label }</span>
+            <span class="duplicate-group-hash" title={group.hash.clone()}>{ hash }</span>
+        </div>
+    }
+}
+
+#[derive(Properties)]
+struct DuplicateItemProps {
+    item: Rc<PitouFile>,
+    selected: bool,
+    onopen: Callback<Rc<PitouFile>>,
+    ontoggle: Callback<Rc<PitouFile>>,
+}
+
+impl PartialEq for DuplicateItemProps {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.item, &other.item) && self.selected == other.selected
+    }
+}
+
+#[function_component]
+fn DuplicateItem(props: &DuplicateItemProps) -> Html {
+    let onchange = {
+        let item = props.item.clone();
+        let ontoggle = props.ontoggle.clone();
+        move |_| ontoggle.emit(item.clone())
+    };
+
+    let ondblclick = {
+        let item = props.item.clone();
+        let onopen = props.onopen.clone();
+        move |_| onopen.emit(item.clone())
+    };
+
+    let class = if props.selected {
+        "list-item selected"
+    } else {
+        "list-item not-selected"
+    };
+
+    let modified = props
+        .item
+        .metadata
+        .as_ref()
+        .and_then(|v| v.modified)
+        .map(|t| t.datetime.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_default();
+
+    let file_type_icon = crate::app::reusables::list_file_type_icon(&props.item);
+
+    html! {
+        <div {class} {ondblclick}>
+            <div class="list-checkbox-container">
+                <input class="explorer-checkbox" type="checkbox" checked={props.selected} {onchange} />
+            </div>
+            <div class="list-filetypeicon-container">
+                { file_type_icon }
+            </div>
+            <div class="list-filename-container">
+                <div class="list-filename">{ props.item.full_path_str() }</div>
+            </div>
+            <div class="list-modifieddate-container">
+                <div>{ modified }</div>
+            </div>
+        </div>
+    }
+}
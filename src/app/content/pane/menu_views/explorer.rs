@@ -11,7 +11,7 @@ use web_sys::HtmlInputElement;
 use yew::prelude::*;
 use yew_hooks::use_interval;
 
-use crate::app::reusables::{Ancestor, DirChildrenArgs, FindPayload, MainPane};
+use crate::app::reusables::{Ancestor, DirChildrenArgs, FindPayload, MainPane, PreviewPane};
 
 #[derive(PartialEq, Properties)]
 pub struct AncestryProps {
@@ -126,10 +126,13 @@ pub struct ExplorerViewProps {
 
 #[function_component]
 pub fn ExplorerView(props: &ExplorerViewProps) -> Html {
+    let ctx = use_context::<ApplicationContext>().unwrap();
+    let preview = ctx.show_preview().then(|| html! { <PreviewPane /> });
     html! {
         <div id="explorer-pane" class="fullpane">
             <Ancestry onopen={props.onopen.clone()}/>
             <Explorer onopen={props.onopen.clone()} reload={props.reload.clone()} quietreload={props.quietreload.clone()}/>
+            { preview }
         </div>
     }
 }
@@ -146,7 +149,7 @@ pub async fn update_children(ctx: ApplicationContext, after: impl Fn()) {
     if let Some(file) = file {
         let new_children = invoke::<DirChildrenArgs, DirChildren>(
             "children",
-            &DirChildrenArgs::new(&file.path, PitouFileFilter::new(), ctx.items_sort()),
+            &DirChildrenArgs::new(&file.path, ctx.items_filter(), ctx.items_sort()),
         )
         .await
         .ok()
@@ -234,8 +237,10 @@ fn Explorer(props: &ExplorerProps) -> Html {
         html! {}
     };
 
+    let class = ctx.show_preview().then_some("with-preview");
+
     html! {
-        <div id="explorer-main-section">
+        <div id="explorer-main-section" {class}>
         { content }
         </div>
     }
@@ -1 +1,285 @@
+use std::rc::Rc;
 
+use pitou_core::{
+    favorites::{Favorite, FavoriteItem},
+    frontend::ApplicationContext,
+    PitouFile, PitouFilePath,
+};
+use wasm_bindgen_futures::spawn_local;
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+use crate::app::reusables::{list_file_type_icon, MatIcon};
+
+/// A favorite with its target shared, so that it can be opened.
+pub(crate) struct ListedFavorite {
+    pub favorite: Favorite,
+    pub target: Option<Rc<PitouFile>>,
+}
+
+impl ListedFavorite {
+    fn is_missing(&self) -> bool {
+        self.target.is_none()
+    }
+
+    /// The favorite as a file without metadata, which is all the backend needs to find it.
+    fn file(&self) -> Rc<PitouFile> {
+        let path = self.favorite.path.path.clone();
+        Rc::new(PitouFile::without_metadata(PitouFilePath::from_pathbuf(
+            path,
+        )))
+    }
+}
+
+pub(crate) async fn listed_favorites() -> Vec<Rc<ListedFavorite>> {
+    let favorites = crate::app::cmds::favorites().await.unwrap_or_default();
+    favorites
+        .into_iter()
+        .map(|FavoriteItem { favorite, target }| {
+            let target = target.map(Rc::new);
+            Rc::new(ListedFavorite { favorite, target })
+        })
+        .collect()
+}
+
+#[derive(PartialEq, Properties)]
+pub struct FavoritesViewProps {
+    pub onopen: Callback<Rc<PitouFile>>,
+}
+
+#[function_component]
+pub fn FavoritesView(props: &FavoritesViewProps) -> Html {
+    let ctx = use_context::<ApplicationContext>().unwrap();
+    let items = use_state(|| None::<Rc<Vec<Rc<ListedFavorite>>>>);
+    let status = use_state(|| None::<String>);
+
+    let reload = {
+        let items = items.clone();
+        Callback::from(move |()| {
+            let items = items.clone();
+            spawn_local(async move { items.set(Some(Rc::new(listed_favorites().await))) })
+        })
+    };
+
+    {
+        let reload = reload.clone();
+        use_effect_with(ctx.refresher_state(), move |_| reload.emit(()));
+    }
+
+    let onimport = {
+        let status = status.clone();
+        let reload = reload.clone();
+        move |_| {
+            let status = status.clone();
+            let reload = reload.clone();
+            spawn_local(async move {
+                let added = crate::app::cmds::import_gtk_bookmarks()
+                    .await
+                    .ok()
+                    .flatten();
+                status.set(Some(match added {
+                    Some(added) => format!("Imported {added} bookmarks"),
+                    None => "No GTK bookmarks were found".to_owned(),
+                }));
+                reload.emit(())
+            })
+        }
+    };
+
+    let all = items.as_deref().cloned().unwrap_or_default();
+    let missing = all.iter().filter(|item| item.is_missing()).count();
+
+    let onforgetmissing = {
+        let status = status.clone();
+        let reload = reload.clone();
+        move |_| {
+            let status = status.clone();
+            let reload = reload.clone();
+            spawn_local(async move {
+                let removed = crate::app::cmds::forget_missing_favorites()
+                    .await
+                    .unwrap_or(0);
+                status.set(Some(format!("Removed {removed} missing favorites")));
+                reload.emit(())
+            })
+        }
+    };
+
+    let summary = match missing {
+        0 => format!("{} favorites", all.len()),
+        missing => format!("{} favorites, {missing} missing", all.len()),
+    };
+
+    let status_row = match &*status {
+        Some(message) => html! { <div class="favorites-status">{ message.clone() }</div> },
+        None => html! {},
+    };
+
+    let last = all.len().saturating_sub(1);
+    let rows = all.iter().enumerate().map(|(position, item)| {
+        html! {
+            <FavoriteListItem item={item.clone()} {position} {last} onopen={props.onopen.clone()} onchanged={reload.clone()}/>
+        }
+    });
+
+    let empty = if items.is_some() && all.is_empty() {
+        html! {
+            <div class="favorites-empty">
+                { "Pin files and folders from the ribbon to list them here" }
+            </div>
+        }
+    } else {
+        html! {}
+    };
+
+    html! {
+        <div id="favorites-pane" class="fullpane">
+            <div class="favorites-options">
+                <button onclick={onimport}>{ "Import GTK bookmarks" }</button>
+                <button onclick={onforgetmissing} disabled={missing == 0}>{ "Remove missing" }</button>
+                <span class="favorites-summary">{ summary }</span>
+            </div>
+            { status_row }
+            { empty }
+            <div id="favorites-list">
+                { for rows }
+            </div>
+        </div>
+    }
+}
+
+#[derive(Properties)]
+struct FavoriteListItemProps {
+    item: Rc<ListedFavorite>,
+    position: usize,
+    last: usize,
+    onopen: Callback<Rc<PitouFile>>,
+    onchanged: Callback<()>,
+}
+
+impl PartialEq for FavoriteListItemProps {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.item, &other.item)
+            && self.position == other.position
+            && self.last == other.last
+    }
+}
+
+#[function_component]
+fn FavoriteListItem(props: &FavoriteListItemProps) -> Html {
+    let renaming = use_state_eq(|| false);
+    let item = &props.item;
+
+    let ondblclick = {
+        let target = item.target.clone();
+        let onopen = props.onopen.clone();
+        move |_| {
+            if let Some(target) = &target {
+                onopen.emit(target.clone())
+            }
+        }
+    };
+
+    let onmove = |position: usize| {
+        let file = item.file();
+        let onchanged = props.onchanged.clone();
+        move |_| {
+            let file = file.clone();
+            let onchanged = onchanged.clone();
+            spawn_local(async move {
+                crate::app::cmds::move_favorite(file, position).await.ok();
+                onchanged.emit(())
+            })
+        }
+    };
+
+    let onremove = {
+        let file = item.file();
+        let onchanged = props.onchanged.clone();
+        move |_| {
+            let items = vec![file.clone()];
+            let onchanged = onchanged.clone();
+            spawn_local(async move {
+                crate::app::cmds::remove_favorites(&items).await.ok();
+                onchanged.emit(())
+            })
+        }
+    };
+
+    let onrenamestart = {
+        let renaming = renaming.clone();
+        move |_| renaming.set(true)
+    };
+
+    let onrename = {
+        let file = item.file();
+        let renaming = renaming.clone();
+        let onchanged = props.onchanged.clone();
+        move |e: Event| {
+            let name = e.target_unchecked_into::<HtmlInputElement>().value();
+            let file = file.clone();
+            let onchanged = onchanged.clone();
+            renaming.set(false);
+            spawn_local(async move {
+                crate::app::cmds::rename_favorite(file, name).await.ok();
+                onchanged.emit(())
+            })
+        }
+    };
+
+    let icon = match &item.target {
+        Some(target) => list_file_type_icon(target),
+        None => {
+            html! { <MatIcon name="link_off" class="broken-link-icon file-type-icon list-type-icon" /> }
+        }
+    };
+
+    let label = if *renaming {
+        html! {
+            <input type="text" class="favorites-rename" value={item.favorite.label().to_owned()} onchange={onrename} autocomplete="off"/>
+        }
+    } else {
+        html! { <div class="list-filename">{ item.favorite.label().to_owned() }</div> }
+    };
+
+    let missing = if item.is_missing() {
+        html! { <span class="favorites-missing" title="the target has been moved or removed">{ "missing" }</span> }
+    } else {
+        html! {}
+    };
+
+    let class = if item.is_missing() {
+        "list-item favorites-item missing"
+    } else {
+        "list-item favorites-item"
+    };
+
+    html! {
+        <div {class} {ondblclick}>
+            <div class="list-filetypeicon-container">
+                { icon }
+            </div>
+            <div class="list-filename-container">
+                { label }
+            </div>
+            <div class="favorites-path" title={item.favorite.path.path.display().to_string()}>
+                { item.favorite.path.path.display().to_string() }
+            </div>
+            { missing }
+            <div class="favorites-actions">
+                <button title="move up" disabled={props.position == 0} onclick={onmove(props.position.saturating_sub(1))}>
+                    <MatIcon name="arrow_upward"/>
+                </button>
+                <button title="move down" disabled={props.position == props.last} onclick={onmove(props.position + 1)}>
+                    <MatIcon name="arrow_downward"/>
+                </button>
+                <button title="rename" onclick={onrenamestart}>
+                    <MatIcon name="edit"/>
+                </button>
+                <button title="remove from favorites" onclick={onremove}>
+                    <MatIcon name="star_border"/>
+                </button>
+            </div>
+        </div>
+    }
+}
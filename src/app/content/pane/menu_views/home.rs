@@ -5,7 +5,10 @@ use wasm_bindgen_futures::spawn_local;
 use yew::prelude::*;
 use yew_hooks::use_interval;
 
-use crate::app::reusables::{DiskIcon, DriveItems, GenFolderIco, GeneralFolderElems, NoArg};
+use super::{listed_favorites, listed_recents};
+use crate::app::reusables::{
+    list_file_type_icon, DiskIcon, DriveItems, GenFolderIco, GeneralFolderElems, NoArg,
+};
 
 #[derive(PartialEq, Properties)]
 pub struct HomeViewProps {
@@ -14,6 +17,7 @@ pub struct HomeViewProps {
 
 #[function_component]
 pub fn HomeView(props: &HomeViewProps) -> Html {
+    let onopenfile = props.onopen.clone();
     let onopen = {
         let onopen = props.onopen.clone();
         Callback::from(move |pb: PathBuf| {
@@ -28,9 +32,9 @@ pub fn HomeView(props: &HomeViewProps) -> Html {
     html! {
         <div id="home-pane" class="fullpane">
             <DrivesSection onopen={onopen.clone()}/>
-            <FoldersSection onopen={onopen.clone()}/>
-            <FavoritesSection onopen={onopen.clone()}/>
-            <RecentsSection {onopen}/>
+            <FoldersSection {onopen}/>
+            <FavoritesSection onopen={onopenfile.clone()}/>
+            <RecentsSection onopen={onopenfile}/>
         </div>
     }
 }
@@ -276,28 +280,92 @@ fn FoldersSectionItem(props: &FoldersSectionItemProps) -> Html {
 
 #[derive(PartialEq, Properties)]
 struct FavoritesSectionProps {
-    onopen: Callback<PathBuf>,
+    onopen: Callback<Rc<PitouFile>>,
 }
 
+/// The favorites whose targets are still there, in the order they are listed.
 #[function_component]
-fn FavoritesSection(_props: &FavoritesSectionProps) -> Html {
+fn FavoritesSection(props: &FavoritesSectionProps) -> Html {
+    let ctx = use_context::<ApplicationContext>().unwrap();
+    let favorites = use_state(Vec::new);
+    {
+        let favorites = favorites.clone();
+        use_effect_with(ctx.refresher_state(), move |_| {
+            spawn_local(async move { favorites.set(listed_favorites().await) })
+        });
+    }
+
+    let elems = favorites
+        .iter()
+        .filter_map(|favorite| {
+            let target = favorite.target.clone()?;
+            let ondblclick = {
+                let onopen = props.onopen.clone();
+                let target = target.clone();
+                move |_| onopen.emit(target.clone())
+            };
+            let title = favorite.favorite.path.path.display().to_string();
+            Some(html! {
+                <div class="folders-section-elem favorites-section-elem not-selected" {ondblclick} {title}>
+                    { list_file_type_icon(&target) }
+                    <div class="folders-section-elem-name">{ favorite.favorite.label().to_owned() }</div>
+                </div>
+            })
+        })
+        .collect::<Html>();
+
     html! {
         <div id="favorites-section" class="home-section">
             <div class="home-section-dsc-text">{ "Favorites" }</div>
+            { elems }
         </div>
     }
 }
 
 #[derive(PartialEq, Properties)]
 struct RecentsSectionProps {
-    onopen: Callback<PathBuf>,
+    onopen: Callback<Rc<PitouFile>>,
 }
 
+/// The few files and folders used last.
 #[function_component]
-fn RecentsSection(_props: &RecentsSectionProps) -> Html {
+fn RecentsSection(props: &RecentsSectionProps) -> Html {
+    const SHOWN: usize = 8;
+    let ctx = use_context::<ApplicationContext>().unwrap();
+    let recents = use_state(Vec::new);
+    {
+        let recents = recents.clone();
+        use_effect_with(ctx.refresher_state(), move |_| {
+            spawn_local(async move {
+                let mut listed = listed_recents().await;
+                listed.truncate(SHOWN);
+                recents.set(listed)
+            })
+        });
+    }
+
+    let elems = recents
+        .iter()
+        .map(|recent| {
+            let ondblclick = {
+                let onopen = props.onopen.clone();
+                let target = recent.target.clone();
+                move |_| onopen.emit(target.clone())
+            };
+            let title = recent.recent.path.path.display().to_string();
+            html! {
+                <div class="folders-section-elem recents-section-elem not-selected" {ondblclick} {title}>
+                    { list_file_type_icon(&recent.target) }
+                    <div class="folders-section-elem-name">{ recent.recent.path.name().to_owned() }</div>
+                </div>
+            }
+        })
+        .collect::<Html>();
+
     html! {
         <div id="recents-section" class="home-section">
             <div class="home-section-dsc-text">{ "Recents" }</div>
+            { elems }
         </div>
     }
 }
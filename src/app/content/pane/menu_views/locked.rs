@@ -1 +1,443 @@
+use std::{
+    path::{Path, PathBuf},
+    rc::Rc,
+};
 
+use pitou_core::{
+    frontend::ApplicationContext,
+    vault::{
+        vault_children, VaultEntry, VaultError, VaultStatus, AUTO_LOCK_CHOICES, MIN_PASSPHRASE_LEN,
+    },
+    vfs::Location,
+    PitouFile,
+};
+use wasm_bindgen_futures::spawn_local;
+use web_sys::{HtmlInputElement, HtmlSelectElement};
+use yew::prelude::*;
+use yew_hooks::use_interval;
+
+use crate::app::reusables::MainPane;
+
+#[derive(PartialEq, Properties)]
+pub struct LockedViewProps {
+    pub reload: Callback<()>,
+    pub quietreload: Callback<()>,
+}
+
+#[function_component]
+pub fn LockedView(props: &LockedViewProps) -> Html {
+    let ctx = use_context::<ApplicationContext>().unwrap();
+    let status = use_state_eq(|| None::<VaultStatus>);
+
+    let refresh = {
+        let status = status.clone();
+        Callback::from(move |()| {
+            let status = status.clone();
+            spawn_local(async move { status.set(crate::app::cmds::vault_status().await.ok()) })
+        })
+    };
+
+    {
+        let refresh = refresh.clone();
+        use_effect_with(ctx.refresher_state(), move |_| refresh.emit(()));
+    }
+
+    // notices the vault locking itself after going unused
+    {
+        let refresh = refresh.clone();
+        use_interval(move || refresh.emit(()), 2000);
+    }
+
+    let content = match *status {
+        None => html! {},
+        Some(VaultStatus { created: false, .. }) => html! { <CreateVault onchanged={refresh}/> },
+        Some(VaultStatus {
+            unlocked: false, ..
+        }) => html! { <UnlockVault onchanged={refresh}/> },
+        Some(VaultStatus {
+            auto_lock_minutes, ..
+        }) => html! {
+            <VaultContents {auto_lock_minutes} onchanged={refresh} reload={props.reload.clone()} quietreload={props.quietreload.clone()}/>
+        },
+    };
+
+    html! {
+        <div id="locked-pane" class="fullpane">
+            { content }
+        </div>
+    }
+}
+
+fn input_value(node: &NodeRef) -> String {
+    node.cast::<HtmlInputElement>()
+        .map(|input| input.value())
+        .unwrap_or_default()
+}
+
+#[derive(PartialEq, Properties)]
+struct VaultFormProps {
+    onchanged: Callback<()>,
+}
+
+#[function_component]
+fn CreateVault(props: &VaultFormProps) -> Html {
+    let passphrase_ref = use_node_ref();
+    let confirm_ref = use_node_ref();
+    let message = use_state(|| None::<String>);
+    let busy = use_state_eq(|| false);
+
+    let onsubmit = {
+        let passphrase_ref = passphrase_ref.clone();
+        let confirm_ref = confirm_ref.clone();
+        let message = message.clone();
+        let busy = busy.clone();
+        let onchanged = props.onchanged.clone();
+        move |e: SubmitEvent| {
+            e.prevent_default();
+            let passphrase = input_value(&passphrase_ref);
+            if passphrase != input_value(&confirm_ref) {
+                message.set(Some("The passphrases don't match".to_owned()));
+                return;
+            }
+            let message = message.clone();
+            let busy = busy.clone();
+            let onchanged = onchanged.clone();
+            busy.set(true);
+            spawn_local(async move {
+                match crate::app::cmds::create_vault(passphrase).await {
+                    Ok(None) => onchanged.emit(()),
+                    Ok(Some(e)) => message.set(Some(e.message().to_owned())),
+                    Err(_) => message.set(Some(VaultError::Io.message().to_owned())),
+                }
+                busy.set(false);
+            })
+        }
+    };
+
+    html! {
+        <form class="vault-form" {onsubmit}>
+            <div class="vault-form-title">{ "Make a vault" }</div>
+            <div class="vault-form-hint">
+                { format!("Files moved into the vault are encrypted with a key made from a passphrase of at least {MIN_PASSPHRASE_LEN} characters. ") }
+                { "A forgotten passphrase can't be recovered, and neither can the files." }
+            </div>
+            <input type="password" placeholder="passphrase" ref={passphrase_ref} autocomplete="new-password"/>
+            <input type="password" placeholder="passphrase again" ref={confirm_ref} autocomplete="new-password"/>
+            <button type="submit" disabled={*busy}>{ if *busy { "Making the vault..." } else { "Make vault" } }</button>
+            <div class="vault-form-message">{ message.as_deref().unwrap_or_default() }</div>
+        </form>
+    }
+}
+
+#[function_component]
+fn UnlockVault(props: &VaultFormProps) -> Html {
+    let passphrase_ref = use_node_ref();
+    let message = use_state(|| None::<String>);
+    let busy = use_state_eq(|| false);
+
+    let onsubmit = {
+        let passphrase_ref = passphrase_ref.clone();
+        let message = message.clone();
+        let busy = busy.clone();
+        let onchanged = props.onchanged.clone();
+        move |e: SubmitEvent| {
+            e.prevent_default();
+            let passphrase = input_value(&passphrase_ref);
+            if let Some(input) = passphrase_ref.cast::<HtmlInputElement>() {
+                input.set_value("")
+            }
+            let message = message.clone();
+            let busy = busy.clone();
+            let onchanged = onchanged.clone();
+            busy.set(true);
+            spawn_local(async move {
+                match crate::app::cmds::unlock_vault(passphrase).await {
+                    Ok(None) => onchanged.emit(()),
+                    Ok(Some(e)) => message.set(Some(e.message().to_owned())),
+                    Err(_) => message.set(Some(VaultError::Io.message().to_owned())),
+                }
+                busy.set(false);
+            })
+        }
+    };
+
+    html! {
+        <form class="vault-form" {onsubmit}>
+            <div class="vault-form-title">{ "The vault is locked" }</div>
+            <input type="password" placeholder="passphrase" ref={passphrase_ref} autocomplete="current-password"/>
+            <button type="submit" disabled={*busy}>{ if *busy { "Unlocking..." } else { "Unlock" } }</button>
+            <div class="vault-form-message">{ message.as_deref().unwrap_or_default() }</div>
+        </form>
+    }
+}
+
+#[derive(PartialEq, Properties)]
+struct VaultContentsProps {
+    auto_lock_minutes: u32,
+    onchanged: Callback<()>,
+    reload: Callback<()>,
+    quietreload: Callback<()>,
+}
+
+/// The ids of the entries at or below the items selected in the vault.
+fn selected_ids(ctx: &ApplicationContext, entries: &[VaultEntry]) -> Vec<String> {
+    let selected = ctx
+        .static_data
+        .folder_entry_selections()
+        .unwrap_or_default();
+    entries
+        .iter()
+        .filter(|entry| selected.iter().any(|item| entry.is_within(&item.path)))
+        .map(|entry| entry.id.clone())
+        .collect()
+}
+
+/// Lists the vault like a folder, through the explorer's own views, with its folders opened in place.
+#[function_component]
+fn VaultContents(props: &VaultContentsProps) -> Html {
+    let ctx = use_context::<ApplicationContext>().unwrap();
+    let entries = use_state(|| None::<Rc<Vec<VaultEntry>>>);
+    let folder = use_state(PathBuf::new);
+    let message = use_state(|| None::<String>);
+    let confirm_delete = use_state(|| None::<Rc<Vec<String>>>);
+
+    let refetch = {
+        let entries = entries.clone();
+        let onchanged = props.onchanged.clone();
+        Callback::from(move |()| {
+            let entries = entries.clone();
+            let onchanged = onchanged.clone();
+            spawn_local(async move {
+                match crate::app::cmds::vault_entries().await.ok().flatten() {
+                    Some(listed) => entries.set(Some(Rc::new(listed))),
+                    // locked in the meantime
+                    None => onchanged.emit(()),
+                }
+            })
+        })
+    };
+
+    {
+        let refetch = refetch.clone();
+        use_effect_with(ctx.refresher_state(), move |_| refetch.emit(()));
+    }
+
+    let all = (*entries).clone().unwrap_or_default();
+
+    let onlock = {
+        let onchanged = props.onchanged.clone();
+        move |_| {
+            let onchanged = onchanged.clone();
+            spawn_local(async move {
+                crate::app::cmds::lock_vault().await.ok();
+                onchanged.emit(())
+            })
+        }
+    };
+
+    let onautolock = {
+        let onchanged = props.onchanged.clone();
+        move |e: Event| {
+            let minutes = e
+                .target_dyn_into::<HtmlSelectElement>()
+                .and_then(|select| select.value().parse::<u32>().ok());
+            if let Some(minutes) = minutes {
+                let onchanged = onchanged.clone();
+                spawn_local(async move {
+                    crate::app::cmds::set_vault_auto_lock(minutes).await.ok();
+                    onchanged.emit(())
+                })
+            }
+        }
+    };
+
+    let onrestore = {
+        let ctx = ctx.clone();
+        let all = all.clone();
+        let message = message.clone();
+        let reload = props.reload.clone();
+        move |_| {
+            let ids = selected_ids(&ctx, &all);
+            if ids.is_empty() {
+                return message.set(Some("Select the files or folders to restore".to_owned()));
+            }
+            let message = message.clone();
+            let reload = reload.clone();
+            spawn_local(async move {
+                let restored = crate::app::cmds::restore_vault_entries(&ids).await;
+                message.set(Some(match restored.ok().flatten() {
+                    Some(restored) if restored == ids.len() => {
+                        format!("Restored {restored} files to where they came from")
+                    }
+                    Some(restored) => format!("Restored {restored} of {} files", ids.len()),
+                    None => VaultError::Locked.message().to_owned(),
+                }));
+                reload.emit(())
+            })
+        }
+    };
+
+    let ondelete = {
+        let ctx = ctx.clone();
+        let all = all.clone();
+        let message = message.clone();
+        let confirm_delete = confirm_delete.clone();
+        move |_| {
+            let ids = selected_ids(&ctx, &all);
+            if ids.is_empty() {
+                return message.set(Some("Select the files or folders to delete".to_owned()));
+            }
+            confirm_delete.set(Some(Rc::new(ids)))
+        }
+    };
+
+    let oncanceldelete = {
+        let confirm_delete = confirm_delete.clone();
+        move |_| confirm_delete.set(None)
+    };
+
+    let onconfirmdelete = {
+        let message = message.clone();
+        let confirm_delete = confirm_delete.clone();
+        let reload = props.reload.clone();
+        move |_| {
+            let Some(ids) = (*confirm_delete).clone() else {
+                return;
+            };
+            let message = message.clone();
+            let reload = reload.clone();
+            confirm_delete.set(None);
+            spawn_local(async move {
+                if let Ok(Some(e)) = crate::app::cmds::delete_vault_entries(&ids).await {
+                    message.set(Some(e.message().to_owned()))
+                }
+                reload.emit(())
+            })
+        }
+    };
+
+    // folders open in place, files as a decrypted copy
+    let onopen = {
+        let all = all.clone();
+        let folder = folder.clone();
+        let message = message.clone();
+        let reload = props.reload.clone();
+        Callback::from(move |item: Rc<PitouFile>| {
+            if item.is_dir() {
+                if let Location::Mounted { path, .. } = item.path.location() {
+                    folder.set(path);
+                    reload.emit(())
+                }
+                return;
+            }
+            let Some(entry) = all.iter().find(|entry| entry.path() == item.path) else {
+                return;
+            };
+            let id = entry.id.clone();
+            let message = message.clone();
+            spawn_local(async move {
+                match crate::app::cmds::open_vault_entry(id).await {
+                    Ok(Some(e)) => message.set(Some(e.message().to_owned())),
+                    _ => message.set(Some(
+                        "Opened a decrypted copy, which is removed when the vault locks".to_owned(),
+                    )),
+                }
+            })
+        })
+    };
+
+    let total = all.iter().map(|entry| entry.size).sum::<u64>();
+    let summary = format!(
+        "{} files, {}",
+        all.len(),
+        pitou_core::PitouFileSize { bytes: total }.format()
+    );
+
+    let auto_lock_options = AUTO_LOCK_CHOICES.iter().map(|minutes| {
+        let label = match minutes {
+            0 => "Never lock by itself".to_owned(),
+            1 => "Lock after 1 minute unused".to_owned(),
+            minutes => format!("Lock after {minutes} minutes unused"),
+        };
+        html! {
+            <option value={minutes.to_string()} selected={*minutes == props.auto_lock_minutes}>{ label }</option>
+        }
+    });
+
+    let delete_controls = match &*confirm_delete {
+        Some(ids) => html! {
+            <>
+                <span class="vault-confirm">{ format!("Delete {} files for good?", ids.len()) }</span>
+                <button onclick={onconfirmdelete}>{ "Delete" }</button>
+                <button onclick={oncanceldelete}>{ "Cancel" }</button>
+            </>
+        },
+        None => html! { <button onclick={ondelete}>{ "Delete" }</button> },
+    };
+
+    // the vault, then each folder down to the open one
+    let mut ancestors = folder
+        .ancestors()
+        .map(Path::to_path_buf)
+        .collect::<Vec<_>>();
+    ancestors.reverse();
+    let crumbs = ancestors
+        .into_iter()
+        .map(|path| {
+            let name = match path.file_name() {
+                Some(name) => name.to_string_lossy().into_owned(),
+                None => "Vault".to_owned(),
+            };
+            let onclick = {
+                let folder = folder.clone();
+                let reload = props.reload.clone();
+                move |_| {
+                    folder.set(path.clone());
+                    reload.emit(())
+                }
+            };
+            html! { <button class="vault-crumb" {onclick}>{ name }</button> }
+        })
+        .collect::<Html>();
+
+    let items = Rc::new(
+        vault_children(&all, &folder)
+            .into_iter()
+            .map(Rc::new)
+            .collect::<Vec<_>>(),
+    );
+
+    let content = if entries.is_some() && all.is_empty() {
+        html! {
+            <div class="vault-empty">
+                { "Select files or folders in the explorer and press the lock on the ribbon to move them into the vault" }
+            </div>
+        }
+    } else {
+        let view = ctx.items_view();
+        let reload = props.reload.clone();
+        let quietreload = props.quietreload.clone();
+        html! {
+            <div id="vault-list">
+                <MainPane {view} {items} {onopen} {reload} {quietreload}/>
+            </div>
+        }
+    };
+
+    html! {
+        <>
+            <div class="vault-options">
+                <button onclick={onlock}>{ "Lock now" }</button>
+                <select onchange={onautolock}>
+                    { for auto_lock_options }
+                </select>
+                <button onclick={onrestore}>{ "Restore" }</button>
+                { delete_controls }
+                <span class="vault-summary">{ summary }</span>
+            </div>
+            <div class="vault-path">{ crumbs }</div>
+            <div class="vault-status">{ message.as_deref().unwrap_or_default() }</div>
+            { content }
+        </>
+    }
+}
@@ -1,6 +1,8 @@
 #![allow(unused_imports)]
 
 mod cloud;
+mod disk_usage;
+mod duplicates;
 mod explorer;
 mod favorites;
 mod home;
@@ -11,6 +13,8 @@ mod settings;
 mod trash;
 
 pub use cloud::*;
+pub use disk_usage::*;
+pub use duplicates::*;
 pub use explorer::*;
 pub use favorites::*;
 pub use home::*;
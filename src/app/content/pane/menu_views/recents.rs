@@ -1 +1,289 @@
+use std::rc::Rc;
 
+use pitou_core::{
+    frontend::ApplicationContext,
+    recents::{Recent, RecentItem, RecentKind, RecentPeriod},
+    PitouFile,
+};
+use wasm_bindgen_futures::spawn_local;
+use web_sys::{HtmlInputElement, HtmlSelectElement};
+use yew::prelude::*;
+
+use crate::app::reusables::{list_file_type_icon, MatIcon};
+
+/// A recent with its target shared, so that it can be opened.
+pub(crate) struct ListedRecent {
+    pub recent: Recent,
+    pub period: RecentPeriod,
+    pub target: Rc<PitouFile>,
+}
+
+pub(crate) async fn listed_recents() -> Vec<Rc<ListedRecent>> {
+    let recents = crate::app::cmds::recents().await.unwrap_or_default();
+    recents
+        .into_iter()
+        .map(|item: RecentItem| {
+            Rc::new(ListedRecent {
+                recent: item.recent,
+                period: item.period,
+                target: Rc::new(item.target),
+            })
+        })
+        .collect()
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum RecentsFilter {
+    All,
+    Files,
+    Folders,
+}
+
+impl RecentsFilter {
+    const ALL: [Self; 3] = [Self::All, Self::Files, Self::Folders];
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::All => "Files and folders",
+            Self::Files => "Files only",
+            Self::Folders => "Folders only",
+        }
+    }
+
+    fn keeps(self, kind: RecentKind) -> bool {
+        match self {
+            Self::All => true,
+            Self::Files => kind == RecentKind::File,
+            Self::Folders => kind == RecentKind::Folder,
+        }
+    }
+}
+
+#[derive(PartialEq, Properties)]
+pub struct RecentsViewProps {
+    pub onopen: Callback<Rc<PitouFile>>,
+}
+
+#[function_component]
+pub fn RecentsView(props: &RecentsViewProps) -> Html {
+    let ctx = use_context::<ApplicationContext>().unwrap();
+    let items = use_state(|| None::<Rc<Vec<Rc<ListedRecent>>>>);
+    let tracking = use_state(|| None::<bool>);
+    let filter = use_state_eq(|| RecentsFilter::All);
+    let confirm_clear = use_state_eq(|| false);
+
+    let reload = {
+        let items = items.clone();
+        Callback::from(move |()| {
+            let items = items.clone();
+            spawn_local(async move { items.set(Some(Rc::new(listed_recents().await))) })
+        })
+    };
+
+    {
+        let reload = reload.clone();
+        let tracking = tracking.clone();
+        use_effect_with(ctx.refresher_state(), move |_| {
+            reload.emit(());
+            spawn_local(async move {
+                tracking.set(crate::app::cmds::recents_tracking().await.ok());
+            })
+        });
+    }
+
+    let ontoggletracking = {
+        let tracking = tracking.clone();
+        move |e: Event| {
+            let enabled = e.target_unchecked_into::<HtmlInputElement>().checked();
+            let tracking = tracking.clone();
+            spawn_local(async move {
+                if let Ok(true) = crate::app::cmds::set_recents_tracking(enabled).await {
+                    tracking.set(Some(enabled));
+                }
+            })
+        }
+    };
+
+    let onfilter = {
+        let filter = filter.clone();
+        move |e: Event| {
+            let idx = e
+                .target_dyn_into::<HtmlSelectElement>()
+                .and_then(|select| select.value().parse::<usize>().ok());
+            if let Some(picked) = idx.and_then(|idx| RecentsFilter::ALL.get(idx)) {
+                filter.set(*picked)
+            }
+        }
+    };
+
+    let onclear = {
+        let confirm_clear = confirm_clear.clone();
+        move |_| confirm_clear.set(true)
+    };
+
+    let oncancelclear = {
+        let confirm_clear = confirm_clear.clone();
+        move |_| confirm_clear.set(false)
+    };
+
+    let onconfirmclear = {
+        let confirm_clear = confirm_clear.clone();
+        let reload = reload.clone();
+        move |_| {
+            let reload = reload.clone();
+            confirm_clear.set(false);
+            spawn_local(async move {
+                crate::app::cmds::clear_recents().await.ok();
+                reload.emit(())
+            })
+        }
+    };
+
+    let all = items.as_deref().cloned().unwrap_or_default();
+    let shown = all
+        .iter()
+        .filter(|item| filter.keeps(item.recent.kind))
+        .collect::<Vec<_>>();
+
+    let filter_options = RecentsFilter::ALL.iter().enumerate().map(|(idx, option)| {
+        html! {
+            <option value={idx.to_string()} selected={*option == *filter}>{ option.label() }</option>
+        }
+    });
+
+    let clear_controls = if *confirm_clear {
+        html! {
+            <>
+                <span class="recents-confirm">{ "Forget every recent file and folder?" }</span>
+                <button onclick={onconfirmclear}>{ "Clear" }</button>
+                <button onclick={oncancelclear}>{ "Cancel" }</button>
+            </>
+        }
+    } else {
+        html! { <button onclick={onclear} disabled={all.is_empty()}>{ "Clear history" }</button> }
+    };
+
+    let paused = if *tracking == Some(false) {
+        html! {
+            <div class="recents-status">
+                { "Tracking is paused. Files opened and folders visited aren't remembered until it is turned back on." }
+            </div>
+        }
+    } else {
+        html! {}
+    };
+
+    // the recents come sorted by when they were used, so each period is a run of them
+    let mut groups = Vec::<(RecentPeriod, Vec<Html>)>::new();
+    for item in shown.iter() {
+        let row = html! {
+            <RecentListItem item={(*item).clone()} onopen={props.onopen.clone()} onchanged={reload.clone()}/>
+        };
+        match groups.last_mut() {
+            Some((period, rows)) if *period == item.period => rows.push(row),
+            _ => groups.push((item.period, vec![row])),
+        }
+    }
+    let groups = groups.into_iter().map(|(period, rows)| {
+        html! {
+            <div class="recents-group">
+                <div class="recents-group-title">{ period.label() }</div>
+                { for rows }
+            </div>
+        }
+    });
+
+    let empty = if items.is_some() && shown.is_empty() {
+        html! { <div class="recents-empty">{ "Files you open and folders you visit are listed here" }</div> }
+    } else {
+        html! {}
+    };
+
+    html! {
+        <div id="recents-pane" class="fullpane">
+            <div class="recents-options">
+                <label title="remember the files opened and the folders visited">
+                    <input type="checkbox" checked={tracking.unwrap_or(true)} disabled={tracking.is_none()} onchange={ontoggletracking}/>
+                    { "Track recent items" }
+                </label>
+                <select onchange={onfilter}>
+                    { for filter_options }
+                </select>
+                { clear_controls }
+                <span class="recents-summary">{ format!("{} recent items", shown.len()) }</span>
+            </div>
+            { paused }
+            { empty }
+            <div id="recents-list">
+                { for groups }
+            </div>
+        </div>
+    }
+}
+
+#[derive(Properties)]
+struct RecentListItemProps {
+    item: Rc<ListedRecent>,
+    onopen: Callback<Rc<PitouFile>>,
+    onchanged: Callback<()>,
+}
+
+impl PartialEq for RecentListItemProps {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.item, &other.item)
+    }
+}
+
+#[function_component]
+fn RecentListItem(props: &RecentListItemProps) -> Html {
+    let item = &props.item;
+
+    let ondblclick = {
+        let target = item.target.clone();
+        let onopen = props.onopen.clone();
+        move |_| onopen.emit(target.clone())
+    };
+
+    let onforget = {
+        let target = item.target.clone();
+        let onchanged = props.onchanged.clone();
+        move |_| {
+            let items = vec![target.clone()];
+            let onchanged = onchanged.clone();
+            spawn_local(async move {
+                crate::app::cmds::forget_recents(&items).await.ok();
+                onchanged.emit(())
+            })
+        }
+    };
+
+    let path = &item.recent.path.path;
+    let location = path
+        .parent()
+        .map(|dir| dir.display().to_string())
+        .unwrap_or_default();
+    let used = item
+        .recent
+        .used
+        .datetime
+        .format("%Y-%m-%d %H:%M")
+        .to_string();
+
+    html! {
+        <div class="list-item recents-item" {ondblclick} title={path.display().to_string()}>
+            <div class="list-filetypeicon-container">
+                { list_file_type_icon(&item.target) }
+            </div>
+            <div class="list-filename-container">
+                <div class="list-filename">{ item.recent.path.name().to_owned() }</div>
+            </div>
+            <div class="recents-location">{ location }</div>
+            <div class="recents-used">{ used }</div>
+            <div class="recents-actions">
+                <button title="remove from recents" onclick={onforget}>
+                    <MatIcon name="close"/>
+                </button>
+            </div>
+        </div>
+    }
+}
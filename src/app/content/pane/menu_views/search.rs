@@ -1,31 +1,39 @@
 use std::{cell::RefCell, rc::Rc};
 
 use super::Ancestry;
-use pitou_core::{frontend::*, search::SimplifiedSearchOptions, *};
+use pitou_core::{
+    frontend::{extra::SearchResult, *},
+    search::{SavedSearch, SearchError, SimplifiedSearchOptions},
+    *,
+};
 use tokio_stream::StreamExt;
 use wasm_bindgen_futures::spawn_local;
-use web_sys::{HtmlInputElement, HtmlSelectElement};
+use web_sys::{HtmlInputElement, HtmlSelectElement, HtmlTextAreaElement};
 use yew::{platform::time::interval, prelude::*};
 use yew_hooks::{use_effect_update, use_effect_update_with_deps, use_interval};
 
 async fn begin_stream_search(
     options: SimplifiedSearchOptions,
-    bank: Rc<RefCell<Vec<Rc<PitouFile>>>>,
+    bank: Rc<RefCell<Vec<Rc<SearchResult>>>>,
     searching: Rc<RefCell<bool>>,
+    error: Rc<RefCell<Option<SearchError>>>,
     update: UseForceUpdateHandle,
 ) {
-    if let Ok(()) = crate::app::cmds::search(options).await {
+    let res = crate::app::cmds::search(options).await;
+    if let Ok(Some(e)) = res {
+        *error.borrow_mut() = Some(e);
+    } else if let Ok(None) = res {
         *searching.borrow_mut() = true;
         let mut interval = Box::pin(interval(std::time::Duration::from_millis(250)));
         while let Some(_) = interval.next().await {
             if let Ok(msg) = crate::app::cmds::search_msg().await {
                 match msg {
                     msg::SearchMsg::Active(ll) => {
-                        bank.borrow_mut().extend(ll.into_iter().map(|v| Rc::new(v)));
+                        SearchResult::extend_ranked(&mut bank.borrow_mut(), ll.into_iter());
                         update.force_update();
                     }
                     msg::SearchMsg::Terminated(ll) => {
-                        bank.borrow_mut().extend(ll.into_iter().map(|v| Rc::new(v)));
+                        SearchResult::extend_ranked(&mut bank.borrow_mut(), ll.into_iter());
                         update.force_update();
                         *searching.borrow_mut() = false;
                         break;
@@ -53,23 +61,28 @@ pub fn SearchPane(props: &SearchPaneProps) -> Html {
     let ctx = use_context::<ApplicationContext>().unwrap();
     let update = use_force_update();
     let searching = use_mut_ref(|| false);
+    let error = use_mut_ref(|| None::<SearchError>);
 
     let onsearch = {
         let ctx = ctx.clone();
         let update = update.clone();
         let searching = searching.clone();
+        let error = error.clone();
         move |options: FrontendSearchOptions| {
             if !*searching.borrow() {
+                *error.borrow_mut() = None;
                 let ctx = ctx.clone();
                 ctx.static_data.clear_all_selections();
+                ctx.active_tab.update_smart_folder(None);
                 ctx.active_tab.reset_search_results();
                 let bank = ctx.active_tab.get_or_init_search_results();
                 let update = update.clone();
                 let searching = searching.clone();
+                let error = error.clone();
                 if let Some(search_dir) = ctx.active_tab.current_dir() {
                     spawn_local(async move {
                         let options = SimplifiedSearchOptions::build_from(options, search_dir);
-                        begin_stream_search(options, bank, searching, update).await;
+                        begin_stream_search(options, bank, searching, error, update).await;
                     })
                 }
             }
@@ -84,13 +97,45 @@ pub fn SearchPane(props: &SearchPaneProps) -> Html {
         }
     };
 
+    // a saved search opened from the sidebar comes with its results cleared, so that it is run afresh
+    {
+        let ctx = ctx.clone();
+        let update = update.clone();
+        let searching = searching.clone();
+        use_effect(move || {
+            let (Some(name), None) = (
+                ctx.active_tab.smart_folder(),
+                ctx.active_tab.search_results(),
+            ) else {
+                return;
+            };
+            let bank = ctx.active_tab.get_or_init_search_results();
+            let stop = *searching.borrow();
+            spawn_local(async move {
+                if stop {
+                    crate::app::cmds::terminate_search().await.ok();
+                }
+                if let Ok(Some(finds)) = crate::app::cmds::smart_folder(name.to_string()).await {
+                    SearchResult::extend_ranked(&mut bank.borrow_mut(), finds.into_iter());
+                }
+                update.force_update();
+            })
+        });
+    }
+
+    let onsaved = {
+        let quietreload = props.quietreload.clone();
+        move |()| quietreload.emit(())
+    };
+
     let results = ctx.active_tab.search_results();
+    let smart_folder = ctx.active_tab.smart_folder();
 
     html! {
         <div id="search-pane" class="fullpane">
             <Ancestry onopen={props.onopen.clone()}/>
-            <SearchOptionsPane {onsearch} {oncancel} {searching} />
-            <SearchResultsPane {results} onopen={props.onopen.clone()} reload={props.reload.clone()} quietreload={props.quietreload.clone()}/>
+            <SearchOptionsPane {onsearch} {oncancel} {onsaved} {searching} {error} />
+            <SearchResultsPane {results} {smart_folder} onopen={props.onopen.clone()} reload={props.reload.clone()} quietreload={props.quietreload.clone()}/>
         </div>
     }
 }
@@ -99,7 +144,9 @@ pub fn SearchPane(props: &SearchPaneProps) -> Html {
 struct SearchOptionsPaneProps {
     onsearch: Callback<FrontendSearchOptions>,
     oncancel: Callback<()>,
+    onsaved: Callback<()>,
     searching: Rc<RefCell<bool>>,
+    error: Rc<RefCell<Option<SearchError>>>,
 }
 
 #[function_component]
@@ -107,7 +154,9 @@ fn SearchOptionsPane(props: &SearchOptionsPaneProps) -> Html {
     let ctx = use_context::<ApplicationContext>().unwrap();
     let force_update = use_force_update();
     let input_ref = use_node_ref();
+    let save_name_ref = use_node_ref();
     let searching = use_state_eq(|| *props.searching.borrow());
+    let error = use_state_eq(|| props.error.borrow().clone());
 
     {
         let input_ref = input_ref.clone();
@@ -119,7 +168,15 @@ fn SearchOptionsPane(props: &SearchOptionsPaneProps) -> Html {
     {
         let prop_searching = props.searching.clone();
         let searching = searching.clone();
-        use_interval(move || searching.set(*prop_searching.borrow()), 250)
+        let prop_error = props.error.clone();
+        let error = error.clone();
+        use_interval(
+            move || {
+                searching.set(*prop_searching.borrow());
+                error.set(prop_error.borrow().clone());
+            },
+            250,
+        )
     }
 
     let finish = {
@@ -138,6 +195,33 @@ fn SearchOptionsPane(props: &SearchOptionsPaneProps) -> Html {
         move |_| onfinish()
     };
 
+    let onclicksave = {
+        let ctx = ctx.clone();
+        let save_name_ref = save_name_ref.clone();
+        let onsaved = props.onsaved.clone();
+        move |_| {
+            let input = save_name_ref.cast::<HtmlInputElement>().unwrap();
+            let name = input.value().trim().to_owned();
+            if name.is_empty() || ctx.active_tab.search_options.borrow().input.is_empty() {
+                return;
+            }
+            if let Some(current_dir) = ctx.active_tab.current_dir() {
+                let options = SimplifiedSearchOptions::build_from(
+                    ctx.active_tab.search_options(),
+                    current_dir,
+                );
+                let search = SavedSearch { name, options };
+                let onsaved = onsaved.clone();
+                input.set_value("");
+                spawn_local(async move {
+                    if let Ok(true) = crate::app::cmds::save_search(search).await {
+                        onsaved.emit(());
+                    }
+                })
+            }
+        }
+    };
+
     let onclickcancel = {
         let oncancel = props.oncancel.clone();
         move |_| oncancel.emit(())
@@ -151,9 +235,15 @@ fn SearchOptionsPane(props: &SearchOptionsPaneProps) -> Html {
                 .target_dyn_into::<HtmlSelectElement>()
                 .unwrap()
                 .selected_index();
-            if idx == 0 || idx == 1 {
+            let kind = match idx {
+                0 | 1 => Some(idx as u8),
+                2 => Some(4),
+                3 => Some(5),
+                _ => None,
+            };
+            if let Some(kind) = kind {
                 ctx.active_tab
-                    .update_search_options(|so| so.search_kind = idx as u8)
+                    .update_search_options(|so| so.search_kind = kind)
             }
             force_update.force_update();
         }
@@ -195,6 +285,15 @@ fn SearchOptionsPane(props: &SearchOptionsPaneProps) -> Html {
         }
     };
 
+    let onclickfilterhidden = {
+        let ctx = ctx.clone();
+        move |m: Event| {
+            ctx.active_tab.update_search_options(|so| {
+                so.filter.hidden = m.target_dyn_into::<HtmlInputElement>().unwrap().checked();
+            });
+        }
+    };
+
     let onkeypress = {
         let finish = finish.clone();
         move |k: KeyboardEvent| {
@@ -228,11 +327,67 @@ fn SearchOptionsPane(props: &SearchOptionsPaneProps) -> Html {
         move |e: Event| {
             let value = e.target_dyn_into::<HtmlInputElement>().unwrap().value();
             if let Ok(val) = value.parse() {
-                ctx.active_tab.update_search_options(|so| so.depth = val);
+                ctx.active_tab
+                    .update_search_options(|so| so.depth = Some(val));
             }
         }
     };
 
+    let onclickunlimiteddepth = {
+        let ctx = ctx.clone();
+        let force_update = force_update.clone();
+        move |e: Event| {
+            let unlimited = e.target_dyn_into::<HtmlInputElement>().unwrap().checked();
+            ctx.active_tab.update_search_options(|so| {
+                so.depth = if unlimited { None } else { Some(6) };
+            });
+            force_update.force_update();
+        }
+    };
+
+    let onclickfollowlinks = {
+        let ctx = ctx.clone();
+        move |e: Event| {
+            let follow = e.target_dyn_into::<HtmlInputElement>().unwrap().checked();
+            ctx.active_tab
+                .update_search_options(|so| so.follow_links = follow);
+        }
+    };
+
+    let onclicksamefilesystem = {
+        let ctx = ctx.clone();
+        move |e: Event| {
+            let same = e.target_dyn_into::<HtmlInputElement>().unwrap().checked();
+            ctx.active_tab
+                .update_search_options(|so| so.same_filesystem = same);
+        }
+    };
+
+    let onclickignorefiles = {
+        let ctx = ctx.clone();
+        move |e: Event| {
+            let respect = e.target_dyn_into::<HtmlInputElement>().unwrap().checked();
+            ctx.active_tab
+                .update_search_options(|so| so.respect_ignore_files = respect);
+        }
+    };
+
+    // one pattern per line, as globs may hold commas themselves
+    let onchangeexcludes = {
+        let ctx = ctx.clone();
+        move |e: Event| {
+            let value = e.target_dyn_into::<HtmlTextAreaElement>().unwrap().value();
+            let excludes = value
+                .lines()
+                .map(|v| v.trim())
+                .filter(|v| !v.is_empty())
+                .map(|v| v.to_owned())
+                .collect();
+            ctx.active_tab
+                .update_search_options(|so| so.excludes = excludes);
+        }
+    };
+
     let onclickcasesensitive = {
         let ctx = ctx.clone();
         move |e: Event| {
@@ -242,19 +397,79 @@ fn SearchOptionsPane(props: &SearchOptionsPaneProps) -> Html {
         }
     };
 
+    let onchangescope = {
+        let ctx = ctx.clone();
+        let force_update = force_update.clone();
+        move |e: Event| {
+            let idx = e
+                .target_dyn_into::<HtmlSelectElement>()
+                .unwrap()
+                .selected_index();
+            let scope = match idx {
+                1 => FrontendSearchScope::AllDrives,
+                2 => FrontendSearchScope::Folders(Vec::new()),
+                _ => FrontendSearchScope::CurrentFolder,
+            };
+            ctx.active_tab.update_search_options(|so| so.scope = scope);
+            force_update.force_update();
+        }
+    };
+
+    let onchangescopefolders = {
+        let ctx = ctx.clone();
+        move |e: Event| {
+            let value = e.target_dyn_into::<HtmlInputElement>().unwrap().value();
+            let folders = value
+                .split(';')
+                .map(|v| v.trim())
+                .filter(|v| !v.is_empty())
+                .map(|v| {
+                    let path = PitouFilePath::from_pathbuf(std::path::PathBuf::from(v));
+                    Rc::new(PitouFile::without_metadata(path))
+                })
+                .collect();
+            ctx.active_tab
+                .update_search_options(|so| so.scope = FrontendSearchScope::Folders(folders));
+        }
+    };
+
     let placeholder = format! {"Enter search key"};
 
     let so = ctx.active_tab.search_options.borrow();
-    let current_depth = so.depth.to_string();
+    let current_depth = so.depth.map(|v| v.to_string()).unwrap_or_default();
+    let unlimited_depth = so.depth.is_none();
+    let follow_links = so.follow_links;
+    let same_filesystem = so.same_filesystem;
+    let respect_ignore_files = so.respect_ignore_files;
+    let excludes = so.excludes.join("\n");
     let max_finds = so.max_finds.to_string();
     let search_kind = so.search_kind;
     let files_filtered = so.filter.files;
     let dirs_filtered = so.filter.dirs;
     let links_filtered = so.filter.links;
     let sys_filtered = so.filter.sys_items;
+    let hidden_filtered = so.filter.hidden;
     let value = so.input.clone();
+    let scope = so.scope.clone();
     std::mem::drop(so);
 
+    let scope_folders = match &scope {
+        FrontendSearchScope::Folders(folders) => {
+            let value = folders
+                .iter()
+                .map(|f| f.full_path_str())
+                .collect::<Vec<_>>()
+                .join("; ");
+            html! {
+                <label>
+                    {"Folders:"}
+                    <input type="text" placeholder="/home/me/docs; /mnt/backup" {value} onchange={onchangescopefolders} autocomplete="off"/>
+                </label>
+            }
+        }
+        _ => html! {},
+    };
+
     let search_sub_kind = {
         let onchange = {
             let ctx = ctx.clone();
@@ -271,8 +486,7 @@ fn SearchOptionsPane(props: &SearchOptionsPaneProps) -> Html {
         };
 
         match search_kind {
-            0 => html! {},
-            v => {
+            v @ 1..=3 => {
                 html! {
                     <div class="search-type">
                         <label>
@@ -286,6 +500,7 @@ fn SearchOptionsPane(props: &SearchOptionsPaneProps) -> Html {
                     </div>
                 }
             }
+            _ => html! {},
         }
     };
 
@@ -311,13 +526,28 @@ fn SearchOptionsPane(props: &SearchOptionsPaneProps) -> Html {
                 {"Type:"}
                 <select class="select-box" onchange={onchangesearchtype}>
                     <option selected={search_kind==0}>{"Regex"}</option>
-                    <option selected={search_kind>0}>{"Standard"}</option>
+                    <option selected={(1..=3).contains(&search_kind)}>{"Standard"}</option>
+                    <option selected={search_kind==4}>{"Glob"}</option>
+                    <option selected={search_kind==5}>{"Fuzzy"}</option>
                 </select>
             </label>
             {search_sub_kind}
+            <label>
+                {"Scope:"}
+                <select class="select-box" onchange={onchangescope}>
+                    <option selected={scope == FrontendSearchScope::CurrentFolder}>{"This Folder"}</option>
+                    <option selected={scope == FrontendSearchScope::AllDrives}>{"All Drives"}</option>
+                    <option selected={matches!(scope, FrontendSearchScope::Folders(_))}>{"Folders"}</option>
+                </select>
+            </label>
+            {scope_folders}
             <label>
                 {"Depth:"}
-                <input type="number" min={1} max={32} value={current_depth} onchange={onchangedepth}/>
+                <input type="number" min={1} max={1024} value={current_depth} onchange={onchangedepth} disabled={unlimited_depth}/>
+            </label>
+            <label>
+                {"Unlimited Depth:"}
+                <input type="checkbox" onchange={onclickunlimiteddepth} checked={unlimited_depth}/>
             </label>
             <label>
                 {"Case Sensitive:"}
@@ -325,32 +555,59 @@ fn SearchOptionsPane(props: &SearchOptionsPaneProps) -> Html {
             </label>
             <label>
                 {"Files"}
-                <input type="checkbox" ontoggle={onclickfilterfile} checked={files_filtered}/>
+                <input type="checkbox" onchange={onclickfilterfile} checked={files_filtered}/>
             </label>
             <label>
                 {"Folders"}
-                <input type="checkbox" ontoggle={onclickfilterfolder} checked={dirs_filtered}/>
+                <input type="checkbox" onchange={onclickfilterfolder} checked={dirs_filtered}/>
             </label>
             <label>
                 {"Symlinks"}
-                <input type="checkbox" ontoggle={onclickfilterlink} checked={links_filtered}/>
+                <input type="checkbox" onchange={onclickfilterlink} checked={links_filtered}/>
             </label>
             <label>
                 {"System Files"}
-                <input type="checkbox" ontoggle={onclickfiltersys} checked={sys_filtered}/>
+                <input type="checkbox" onchange={onclickfiltersys} checked={sys_filtered}/>
+            </label>
+            <label>
+                {"Hidden Files"}
+                <input type="checkbox" onchange={onclickfilterhidden} checked={hidden_filtered}/>
+            </label>
+            <label>
+                {"Follow Symlinks"}
+                <input type="checkbox" onchange={onclickfollowlinks} checked={follow_links}/>
+            </label>
+            <label>
+                {"Stay On Filesystem"}
+                <input type="checkbox" onchange={onclicksamefilesystem} checked={same_filesystem}/>
+            </label>
+            <label>
+                {"Respect .gitignore"}
+                <input type="checkbox" onchange={onclickignorefiles} checked={respect_ignore_files}/>
+            </label>
+            <label>
+                {"Exclude:"}
+                <textarea id="search-options-excludes" rows="3" placeholder={"node_modules\n*.{log,tmp}"} value={excludes} onchange={onchangeexcludes} {autocomplete}/>
             </label>
             <label>
                 {"Max Finds:"}
                 <input type="number" min={1} max={5000} value={max_finds} onchange={onchangemaxfinds}/>
             </label>
             { search_or_cancel_btn }
+            <div class="search-options-message">{ error.as_ref().map(|e| e.message()).unwrap_or_default() }</div>
+            <label>
+                {"Save As:"}
+                <input type="text" placeholder="Smart folder name" ref={save_name_ref} {autocomplete}/>
+            </label>
+            <button onclick={onclicksave} id="search-options-save-btn">{"Save Search"}</button>
         </div>
     }
 }
 
 #[derive(Properties)]
 struct SearchResultsPaneProps {
-    results: Option<Rc<RefCell<Vec<Rc<PitouFile>>>>>,
+    results: Option<Rc<RefCell<Vec<Rc<SearchResult>>>>>,
+    smart_folder: Option<Rc<String>>,
     onopen: Callback<Rc<PitouFile>>,
     quietreload: Callback<()>,
     reload: Callback<()>,
@@ -381,12 +638,12 @@ fn SearchResultsPane(props: &SearchResultsPaneProps) -> Html {
                     items
                         .borrow()
                         .iter()
-                        .for_each(|item| ctx.static_data.clear_dir_entry_selection(item.clone()))
+                        .for_each(|item| ctx.static_data.clear_search_result(item.file.clone()))
                 } else {
                     items
                         .borrow()
                         .iter()
-                        .for_each(|item| ctx.static_data.select_folder_entry(item.clone()))
+                        .for_each(|item| ctx.static_data.select_search_result(item.file.clone()))
                 }
                 quietreload.emit(())
             }
@@ -394,23 +651,59 @@ fn SearchResultsPane(props: &SearchResultsPaneProps) -> Html {
     };
 
     let contents = match &props.results {
-        Some(items) => items
-            .borrow()
-            .iter()
-            .map(|item| {
-                let item = item.clone();
-                let onopen = props.onopen.clone();
-                let quietreload = { move |()| () };
-                let reload = { move |_| () };
-                html! { <ListItem {item} {onopen} {reload} {quietreload}/>}
-            })
-            .collect::<Html>(),
+        Some(items) => {
+            let items = items.borrow();
+            // results are only tagged with their root when they come from more than one
+            let tagged = items.iter().any(|v| v.root != items[0].root);
+            items
+                .iter()
+                .map(|result| {
+                    let item = result.file.clone();
+                    let matched = result.matched_positions().to_vec();
+                    let root = tagged.then(|| result.root.clone());
+                    let onopen = props.onopen.clone();
+                    let quietreload = { move |()| () };
+                    let reload = { move |_| () };
+                    html! { <ListItem {item} {matched} {root} {onopen} {reload} {quietreload}/>}
+                })
+                .collect::<Html>()
+        }
+        None => html! {},
+    };
+
+    let smart_folder = match &props.smart_folder {
+        Some(name) => {
+            let onclickforget = {
+                let ctx = ctx.clone();
+                let name = name.clone();
+                let quietreload = props.quietreload.clone();
+                move |_| {
+                    let ctx = ctx.clone();
+                    let name = name.clone();
+                    let quietreload = quietreload.clone();
+                    spawn_local(async move {
+                        if let Ok(true) = crate::app::cmds::forget_search(name.to_string()).await {
+                            ctx.active_tab.update_smart_folder(None);
+                            quietreload.emit(())
+                        }
+                    })
+                }
+            };
+            html! {
+                <div id="smart-folder-header">
+                    <span class="title">{ name.as_str() }</span>
+                    <span class="smart-folder-dsc">{"Smart folder, refreshed on open"}</span>
+                    <button id="smart-folder-forget-btn" onclick={onclickforget}>{"Forget"}</button>
+                </div>
+            }
+        }
         None => html! {},
     };
 
     html! {
         <>
             <div id="search-results-pane">
+                { smart_folder }
                 <ListDsc ontoggle={ontoggleselectall}/>
                 <div id="pane-list-view">
                     { contents }
@@ -459,6 +752,8 @@ fn ListDsc(props: &ListDscProps) -> Html {
 struct ListItemProps {
     onopen: Callback<Rc<PitouFile>>,
     item: Rc<PitouFile>,
+    matched: Vec<usize>,
+    root: Option<Rc<PitouFilePath>>,
     reload: Callback<()>,
     quietreload: Callback<()>,
 }
@@ -523,26 +818,42 @@ fn ListItem(props: &ListItemProps) -> Html {
         }
     );
 
-    let name = props.item.full_path_str();
+    let full_path = props.item.full_path_str();
+    let name = props.item.name();
+    let parent = full_path.strip_suffix(name).unwrap_or_default();
+    let name = highlight_matches(name, &props.matched);
+    let root = match &props.root {
+        Some(root) => {
+            let label = match root.name() {
+                "" => root.path.to_str().unwrap_or_default(),
+                name => name,
+            };
+            html! { <span class="search-root">{ label }</span> }
+        }
+        None => html! {},
+    };
     let accessed = props
         .item
         .metadata
         .as_ref()
-        .map(|v| v.accessed.datetime.format("%Y-%m-%d %H:%M").to_string())
+        .and_then(|v| v.accessed)
+        .map(|t| t.datetime.format("%Y-%m-%d %H:%M").to_string())
         .unwrap_or_default();
 
     let modified = props
         .item
         .metadata
         .as_ref()
-        .map(|v| v.modified.datetime.format("%Y-%m-%d %H:%M").to_string())
+        .and_then(|v| v.modified)
+        .map(|t| t.datetime.format("%Y-%m-%d %H:%M").to_string())
         .unwrap_or_default();
 
     let created = props
         .item
         .metadata
         .as_ref()
-        .map(|v| v.created.datetime.format("%Y-%m-%d %H:%M").to_string())
+        .and_then(|v| v.created)
+        .map(|t| t.datetime.format("%Y-%m-%d %H:%M").to_string())
         .unwrap_or_default();
 
     let file_type_icon = crate::app::reusables::list_file_type_icon(&props.item);
@@ -556,7 +867,8 @@ fn ListItem(props: &ListItemProps) -> Html {
                 { file_type_icon }
             </div>
             <div class="list-filename-container">
-                <div class="list-filename search-filename">{ name }</div>
+                { root }
+                <div class="list-filename search-filename">{ parent }{ name }</div>
             </div>
             <div class="list-modifieddate-container">
                 <div>{ modified }</div>
@@ -571,6 +883,35 @@ fn ListItem(props: &ListItemProps) -> Html {
     }
 }
 
+/// Wraps the chars of `name` at the `matched` char indices in highlighted spans.
+fn highlight_matches(name: &str, matched: &[usize]) -> Html {
+    if matched.is_empty() {
+        return html! { name };
+    }
+    let mut segments = Vec::new();
+    let mut current = String::new();
+    let mut current_matched = false;
+    for (idx, c) in name.chars().enumerate() {
+        let is_match = matched.contains(&idx);
+        if is_match != current_matched && !current.is_empty() {
+            segments.push((std::mem::take(&mut current), current_matched));
+        }
+        current_matched = is_match;
+        current.push(c);
+    }
+    segments.push((current, current_matched));
+    segments
+        .into_iter()
+        .map(|(text, is_match)| {
+            if is_match {
+                html! { <span class="search-match">{ text }</span> }
+            } else {
+                html! { text }
+            }
+        })
+        .collect::<Html>()
+}
+
 #[derive(Properties, PartialEq)]
 struct SearchResultProps {
     item: Rc<PitouFile>,
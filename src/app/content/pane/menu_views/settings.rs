@@ -1,11 +1,20 @@
-use pitou_core::{frontend::*, *};
+use pitou_core::{
+    frontend::*,
+    settings::SavedSettings,
+    themes::{NamedTheme, SystemThemes},
+    *,
+};
 use serde_wasm_bindgen::to_value;
+use wasm_bindgen_futures::{spawn_local, JsFuture};
 use web_sys::*;
 use yew::prelude::*;
 
+use crate::app::prefers_dark_scheme;
+
 #[derive(PartialEq, Properties)]
 pub struct SettingsViewProps {
     pub onupdatetheme: Callback<ColorTheme>,
+    pub reload: Callback<()>,
 }
 
 #[function_component]
@@ -13,15 +22,19 @@ pub fn SettingsView(props: &SettingsViewProps) -> Html {
     html! {
         <div id="settings-pane" class="fullpane">
             <Themes onupdatetheme={props.onupdatetheme.clone()}/>
+            <FollowSystemTheme onupdatetheme={props.onupdatetheme.clone()}/>
+            <CustomThemes onupdatetheme={props.onupdatetheme.clone()}/>
             <Extensions />
             <SystemFiles />
+            <HiddenFiles />
             <RefreshRate />
             <Siblings />
             <FilesView />
             <Thumbnails />
             <Zoom />
-            <LoadSettings />
-            <ResetSettings />
+            <LoadSettings reload={props.reload.clone()}/>
+            <ExportSettings />
+            <ResetSettings reload={props.reload.clone()}/>
         </div>
     }
 }
@@ -31,38 +44,390 @@ pub struct ThemesProps {
     onupdatetheme: Callback<ColorTheme>,
 }
 
+/// The index of the option picked in the selector that raised `e`.
+fn selected_index(e: Event) -> Option<usize> {
+    e.target_dyn_into::<HtmlSelectElement>()?
+        .value()
+        .parse()
+        .ok()
+}
+
+/// An option for each of `themes`, with `selected` picked. A theme that isn't listed shows as unsaved.
+fn theme_options(themes: &[NamedTheme], selected: ColorTheme) -> Html {
+    let picked = themes.iter().position(|theme| theme.theme == selected);
+    let unsaved = match picked {
+        Some(_) => html! {},
+        None => html! { <option selected={true} disabled={true}>{ "Unsaved theme" }</option> },
+    };
+    let options = themes.iter().enumerate().map(|(idx, theme)| {
+        html! {
+            <option value={idx.to_string()} selected={picked == Some(idx)}>{ theme.name.clone() }</option>
+        }
+    });
+    html! {
+        <>
+            { unsaved }
+            { for options }
+        </>
+    }
+}
+
 #[function_component]
 pub fn Themes(props: &ThemesProps) -> Html {
     let ctx = use_context::<ApplicationContext>().unwrap();
     let cur_theme = ctx.color_theme();
+    let themes = ctx.theme_settings();
+    let available = themes.available();
+    let following = themes.follow_system.is_some();
+
     let onchange = {
         let onupdatetheme = props.onupdatetheme.clone();
+        let available = available.clone();
         move |e: Event| {
-            let val = e.target_dyn_into::<HtmlSelectElement>().unwrap().value();
-            let theme = match val.parse::<u8>().unwrap() {
-                0 => ColorTheme::DEFAULT_DARK,
-                1 => ColorTheme::DEFAULT_LIGHT,
-                2 => ColorTheme::GEM_DARK,
-                3 => ColorTheme::POLISH_DARK,
-                _ => unimplemented!(),
-            };
-            onupdatetheme.emit(theme)
+            if let Some(theme) = selected_index(e).and_then(|idx| available.get(idx)) {
+                onupdatetheme.emit(theme.theme)
+            }
         }
     };
 
     html! {
         <div class="selectable">
             <label class="label">{ "Themes" }</label>
-            <select class="selector" {onchange} value="1">
-                <option value="0" selected={cur_theme == ColorTheme::DEFAULT_DARK}>{ "Default Dark" }</option>
-                <option value="1" selected={cur_theme == ColorTheme::DEFAULT_LIGHT}>{ "Default Light" }</option>
-                <option value="2" selected={cur_theme == ColorTheme::GEM_DARK}>{ "Gem Dark" }</option>
-                <option value="3" selected={cur_theme == ColorTheme::POLISH_DARK}>{ "Polish Dark" }</option>
+            <select class="selector" {onchange} disabled={following}>
+                { theme_options(&available, cur_theme) }
             </select>
         </div>
     }
 }
 
+#[function_component]
+pub fn FollowSystemTheme(props: &ThemesProps) -> Html {
+    let ctx = use_context::<ApplicationContext>().unwrap();
+    let themes = ctx.theme_settings();
+    let available = themes.available();
+
+    let onchange = {
+        let ctx = ctx.clone();
+        let onupdatetheme = props.onupdatetheme.clone();
+        move |e: Event| {
+            let follow = e.target_dyn_into::<HtmlInputElement>().unwrap().checked();
+            let current = ctx.color_theme();
+            let system = follow.then(|| {
+                // the current theme is kept for the scheme it suits
+                let mut system = SystemThemes::default();
+                if current.is_dark() {
+                    system.dark = current;
+                } else {
+                    system.light = current;
+                }
+                system
+            });
+            ctx.update_theme_settings(|themes| themes.follow_system = system);
+            let theme = system.map_or(current, |system| system.pick(prefers_dark_scheme()));
+            onupdatetheme.emit(theme)
+        }
+    };
+
+    let scheme_selector = |label: &'static str, dark: bool| {
+        let Some(system) = themes.follow_system else {
+            return html! {};
+        };
+        let onchange = {
+            let ctx = ctx.clone();
+            let onupdatetheme = props.onupdatetheme.clone();
+            let available = available.clone();
+            move |e: Event| {
+                let Some(theme) = selected_index(e).and_then(|idx| available.get(idx)) else {
+                    return;
+                };
+                let mut system = system;
+                if dark {
+                    system.dark = theme.theme;
+                } else {
+                    system.light = theme.theme;
+                }
+                ctx.update_theme_settings(|themes| themes.follow_system = Some(system));
+                onupdatetheme.emit(system.pick(prefers_dark_scheme()))
+            }
+        };
+        html! {
+            <div class="selectable">
+                <label class="label">{ label }</label>
+                <select class="selector" {onchange}>
+                    { theme_options(&available, system.pick(dark)) }
+                </select>
+            </div>
+        }
+    };
+
+    html! {
+        <>
+            <div class="selectable">
+                <label class="label">{ "Follow the system's dark or light mode" }</label>
+                <input class="selector" type="checkbox" checked={themes.follow_system.is_some()} {onchange}/>
+            </div>
+            { scheme_selector("Theme in light mode", false) }
+            { scheme_selector("Theme in dark mode", true) }
+        </>
+    }
+}
+
+/// A theme being edited along with the theme to go back to if the edit is cancelled.
+#[derive(Clone, PartialEq)]
+struct ThemeDraft {
+    edited: NamedTheme,
+    previous: ColorTheme,
+}
+
+#[function_component]
+pub fn CustomThemes(props: &ThemesProps) -> Html {
+    let ctx = use_context::<ApplicationContext>().unwrap();
+    let draft = use_state(|| None::<ThemeDraft>);
+    let status = use_state(|| None::<String>);
+
+    let cur_theme = ctx.color_theme();
+    let themes = ctx.theme_settings();
+    // the listed theme that is showing, if any
+    let current = themes
+        .available()
+        .into_iter()
+        .find(|theme| theme.theme == cur_theme);
+
+    if let Some(editing) = &*draft {
+        return html! {
+            <ThemeEditor draft={editing.clone()} state={draft.clone()} onupdatetheme={props.onupdatetheme.clone()}/>
+        };
+    }
+
+    let onedit = {
+        let draft = draft.clone();
+        let name = match &current {
+            Some(theme) if themes.is_custom(&theme.name) => theme.name.clone(),
+            _ => "My theme".to_owned(),
+        };
+        move |_| {
+            draft.set(Some(ThemeDraft {
+                edited: NamedTheme {
+                    name: name.clone(),
+                    theme: cur_theme,
+                },
+                previous: cur_theme,
+            }))
+        }
+    };
+
+    let delete = match &current {
+        Some(theme) if themes.is_custom(&theme.name) => {
+            let onclick = {
+                let ctx = ctx.clone();
+                let onupdatetheme = props.onupdatetheme.clone();
+                let name = theme.name.clone();
+                move |_| {
+                    ctx.update_theme_settings(|themes| themes.remove(&name));
+                    onupdatetheme.emit(cur_theme)
+                }
+            };
+            html! { <button class="selector" {onclick}>{ "Delete" }</button> }
+        }
+        _ => html! {},
+    };
+
+    let onimport = {
+        let ctx = ctx.clone();
+        let status = status.clone();
+        let onupdatetheme = props.onupdatetheme.clone();
+        move |e: Event| {
+            let input = e.target_unchecked_into::<HtmlInputElement>();
+            let Some(file) = input.files().and_then(|files| files.get(0)) else {
+                return;
+            };
+            input.set_value("");
+            let ctx = ctx.clone();
+            let status = status.clone();
+            let onupdatetheme = onupdatetheme.clone();
+            spawn_local(async move {
+                let Some(theme) = read_file(file)
+                    .await
+                    .and_then(|json| NamedTheme::from_json(&json))
+                else {
+                    return status.set(Some("Not a pitou theme file".to_owned()));
+                };
+                let colors = theme.theme;
+                let name = ctx.update_theme_settings(|themes| themes.add(theme));
+                status.set(Some(format!("Added {name}")));
+                if ctx.theme_settings().follow_system.is_none() {
+                    onupdatetheme.emit(colors)
+                } else {
+                    onupdatetheme.emit(ctx.color_theme())
+                }
+            })
+        }
+    };
+
+    let onexport = {
+        let status = status.clone();
+        let theme = NamedTheme {
+            name: current.map_or_else(|| "Unsaved theme".to_owned(), |theme| theme.name),
+            theme: cur_theme,
+        };
+        move |_| {
+            let status = status.clone();
+            let theme = theme.clone();
+            spawn_local(async move {
+                let path = crate::app::cmds::export_theme(&theme).await.ok().flatten();
+                status.set(Some(match path {
+                    Some(path) => format!("Saved to {}", path.path.display()),
+                    None => "Couldn't export the theme".to_owned(),
+                }))
+            })
+        }
+    };
+
+    let status = match &*status {
+        Some(status) => html! { <span class="settings-status">{ status.clone() }</span> },
+        None => html! {},
+    };
+    // edits would be replaced by the theme of the system's scheme while it is followed
+    let following = themes.follow_system.is_some();
+
+    html! {
+        <>
+            <div class="selectable">
+                <label class="label">{ "Customize the theme" }</label>
+                <div class="selector theme-actions">
+                    <button onclick={onedit} disabled={following}>{ "Edit" }</button>
+                    { delete }
+                    <button onclick={onexport}>{ "Export" }</button>
+                </div>
+            </div>
+            <div class="selectable">
+                <label class="label">{ "Import a theme file" }</label>
+                { status }
+                <input class="selector" type="file" accept=".json" onchange={onimport}/>
+            </div>
+        </>
+    }
+}
+
+#[derive(PartialEq, Properties)]
+struct ThemeEditorProps {
+    draft: ThemeDraft,
+    state: UseStateHandle<Option<ThemeDraft>>,
+    onupdatetheme: Callback<ColorTheme>,
+}
+
+/// Edits each slot of a theme, previewing the edits on the whole app as they are made.
+#[function_component]
+fn ThemeEditor(props: &ThemeEditorProps) -> Html {
+    let ctx = use_context::<ApplicationContext>().unwrap();
+
+    let update = {
+        let draft = props.draft.clone();
+        let state = props.state.clone();
+        let onupdatetheme = props.onupdatetheme.clone();
+        move |change: &dyn Fn(&mut NamedTheme)| {
+            let mut draft = draft.clone();
+            change(&mut draft.edited);
+            onupdatetheme.emit(draft.edited.theme);
+            state.set(Some(draft))
+        }
+    };
+
+    let onrename = {
+        let update = update.clone();
+        move |e: Event| {
+            let name = e.target_unchecked_into::<HtmlInputElement>().value();
+            update(&|theme| theme.name = name.trim().to_owned())
+        }
+    };
+
+    let slots = props.draft.edited.theme.slots();
+    let rows = ColorTheme::SLOT_NAMES
+        .iter()
+        .zip(slots)
+        .enumerate()
+        .map(|(idx, (label, color))| {
+            let oncolor = {
+                let update = update.clone();
+                move |e: InputEvent| {
+                    let hex = e.target_unchecked_into::<HtmlInputElement>().value();
+                    if let Some(new) = Color::from_hex(&hex, color.3) {
+                        update(&|theme| {
+                            if let Some(slot) = theme.theme.slot_mut(idx) {
+                                *slot = new
+                            }
+                        })
+                    }
+                }
+            };
+            let onalpha = {
+                let update = update.clone();
+                move |e: InputEvent| {
+                    let value = e.target_unchecked_into::<HtmlInputElement>().value();
+                    if let Ok(alpha) = value.parse() {
+                        update(&|theme| {
+                            if let Some(slot) = theme.theme.slot_mut(idx) {
+                                slot.3 = alpha
+                            }
+                        })
+                    }
+                }
+            };
+            html! {
+                <div class="selectable theme-slot">
+                    <label class="label">{ *label }</label>
+                    <div class="selector">
+                        <input type="color" value={color.to_hex()} oninput={oncolor}/>
+                        <input type="range" min="0" max="255" value={color.3.to_string()} oninput={onalpha} title="Opacity"/>
+                    </div>
+                </div>
+            }
+        });
+
+    let onsave = {
+        let draft = props.draft.clone();
+        let state = props.state.clone();
+        let onupdatetheme = props.onupdatetheme.clone();
+        move |_| {
+            let mut theme = draft.edited.clone();
+            if theme.name.is_empty() {
+                theme.name = "My theme".to_owned();
+            }
+            let colors = theme.theme;
+            ctx.update_theme_settings(|themes| themes.add(theme));
+            state.set(None);
+            onupdatetheme.emit(colors)
+        }
+    };
+
+    let oncancel = {
+        let previous = props.draft.previous;
+        let state = props.state.clone();
+        let onupdatetheme = props.onupdatetheme.clone();
+        move |_| {
+            state.set(None);
+            onupdatetheme.emit(previous)
+        }
+    };
+
+    html! {
+        <div class="theme-editor">
+            <div class="selectable">
+                <label class="label">{ "Theme name" }</label>
+                <input class="selector" type="text" value={props.draft.edited.name.clone()} onchange={onrename}/>
+            </div>
+            { for rows }
+            <div class="selectable">
+                <label class="label">{ "Keep the edited theme" }</label>
+                <div class="selector theme-actions">
+                    <button onclick={onsave}>{ "Save" }</button>
+                    <button onclick={oncancel}>{ "Cancel" }</button>
+                </div>
+            </div>
+        </div>
+    }
+}
+
 #[function_component]
 pub fn Extensions() -> Html {
     let ctx = use_context::<ApplicationContext>().unwrap();
@@ -106,6 +471,27 @@ pub fn SystemFiles() -> Html {
     }
 }
 
+#[function_component]
+pub fn HiddenFiles() -> Html {
+    let ctx = use_context::<ApplicationContext>().unwrap();
+    let onchange = {
+        let ctx = ctx.clone();
+        move |e: Event| {
+            let state = e.target_dyn_into::<HtmlInputElement>().unwrap().checked();
+            ctx.update_hide_hidden_files(state)
+        }
+    };
+
+    let checked = ctx.hide_hidden_files();
+
+    html! {
+        <div class="selectable">
+            <label class="label">{ "Hide Hidden Files" }</label>
+            <input class="selector" type="checkbox" {checked} {onchange}/>
+        </div>
+    }
+}
+
 #[function_component]
 pub fn RefreshRate() -> Html {
     let ctx = use_context::<ApplicationContext>().unwrap();
@@ -134,6 +520,7 @@ pub fn RefreshRate() -> Html {
 #[function_component]
 pub fn Siblings() -> Html {
     let ctx = use_context::<ApplicationContext>().unwrap();
+    let checked = ctx.show_parents();
     let onchange = {
         move |_| {
             let val = ctx.show_parents();
@@ -143,7 +530,7 @@ pub fn Siblings() -> Html {
     html! {
         <div class="selectable">
             <label class="label">{ "Show siblings panel" }</label>
-            <input class="selector" type="checkbox" checked={false} {onchange} />
+            <input class="selector" type="checkbox" {checked} {onchange} />
         </div>
     }
 }
@@ -223,26 +610,120 @@ pub fn FilesView() -> Html {
     }
 }
 
+#[derive(PartialEq, Properties)]
+pub struct ReplaceSettingsProps {
+    reload: Callback<()>,
+}
+
+/// Makes `settings` the current settings, which the backend has already saved.
+fn apply_settings(ctx: &ApplicationContext, settings: SavedSettings, reload: &Callback<()>) {
+    ctx.gen_ctx.borrow_mut().apply_settings(settings);
+    reload.emit(())
+}
+
+async fn read_file(file: File) -> Option<String> {
+    JsFuture::from(file.text()).await.ok()?.as_string()
+}
+
 #[function_component]
-pub fn LoadSettings() -> Html {
-    let oninput = { move |_| () };
+pub fn LoadSettings(props: &ReplaceSettingsProps) -> Html {
+    let ctx = use_context::<ApplicationContext>().unwrap();
+    let failed = use_state_eq(|| false);
+
+    let onchange = {
+        let failed = failed.clone();
+        let reload = props.reload.clone();
+        move |e: Event| {
+            let input = e.target_unchecked_into::<HtmlInputElement>();
+            let Some(file) = input.files().and_then(|files| files.get(0)) else {
+                return;
+            };
+            input.set_value("");
+            let ctx = ctx.clone();
+            let failed = failed.clone();
+            let reload = reload.clone();
+            spawn_local(async move {
+                let imported = match read_file(file).await {
+                    Some(json) => crate::app::cmds::import_settings(json).await.ok().flatten(),
+                    None => None,
+                };
+                failed.set(imported.is_none());
+                if let Some(settings) = imported {
+                    apply_settings(&ctx, settings, &reload)
+                }
+            })
+        }
+    };
+
+    let error = if *failed {
+        html! { <span class="settings-status">{ "Not a pitou settings file" }</span> }
+    } else {
+        html! {}
+    };
 
     html! {
         <div class="selectable">
             <label class="label">{ "Load a saved settings file" }</label>
-            <input class="selector" type="file" accept=".json"{oninput}/>
+            { error }
+            <input class="selector" type="file" accept=".json" {onchange}/>
         </div>
     }
 }
 
 #[function_component]
-pub fn ResetSettings() -> Html {
-    let onclick = { move |_| () };
+pub fn ExportSettings() -> Html {
+    let ctx = use_context::<ApplicationContext>().unwrap();
+    let exported = use_state(|| None::<String>);
+
+    let onclick = {
+        let exported = exported.clone();
+        move |_| {
+            let settings = ctx.gen_ctx.borrow().settings();
+            let exported = exported.clone();
+            spawn_local(async move {
+                crate::app::cmds::save_settings(&settings).await.ok();
+                let path = crate::app::cmds::export_settings().await.ok().flatten();
+                exported.set(Some(match path {
+                    Some(path) => format!("Saved to {}", path.path.display()),
+                    None => "Couldn't export the settings".to_owned(),
+                }))
+            })
+        }
+    };
+
+    let status = match &*exported {
+        Some(status) => html! { <span class="settings-status">{ status.clone() }</span> },
+        None => html! {},
+    };
+
+    html! {
+        <div class="selectable">
+            <label class="label">{ "Export settings to a file" }</label>
+            { status }
+            <button class="selector" {onclick}>{ "Export" }</button>
+        </div>
+    }
+}
+
+#[function_component]
+pub fn ResetSettings(props: &ReplaceSettingsProps) -> Html {
+    let ctx = use_context::<ApplicationContext>().unwrap();
+    let onclick = {
+        let reload = props.reload.clone();
+        move |_| {
+            let ctx = ctx.clone();
+            let reload = reload.clone();
+            spawn_local(async move {
+                let settings = crate::app::cmds::reset_settings().await.ok().flatten();
+                apply_settings(&ctx, settings.unwrap_or_default(), &reload)
+            })
+        }
+    };
 
     html! {
         <div class="selectable">
             <label class="label">{ "Revert to default settings" }</label>
-            <button class="selector" {onclick}/>
+            <button class="selector" {onclick}>{ "Reset" }</button>
         </div>
     }
 }
@@ -1,112 +1,482 @@
-use std::{cell::RefCell, collections::HashSet, rc::Rc};
+use std::{collections::HashSet, path::PathBuf, rc::Rc};
 
-use pitou_core::{frontend::ApplicationContext, PitouFileKind, PitouTrashItem};
+use pitou_core::{
+    frontend::ApplicationContext,
+    trash::{RestoreConflict, RestoreError, TrashRetention, TrashSortKey, TrashSummary},
+    PitouFilePath, PitouFileSize, PitouFileSortOrder, PitouTrashItem,
+};
 use wasm_bindgen_futures::spawn_local;
+use web_sys::{HtmlInputElement, HtmlSelectElement};
 use yew::prelude::*;
-use yew_hooks::use_interval;
 
-use crate::app::reusables::{NoArg, PitouTrashItemsVec, TrashItem};
+/// Restored items whose destination was taken, waiting on what to do about them.
+struct PendingConflicts {
+    errors: Vec<RestoreError>,
+    destination: Option<String>,
+}
+
+/// A permanent deletion waiting to be confirmed.
+#[derive(Clone, Copy, PartialEq)]
+enum Purge {
+    Selected,
+    Everything,
+}
 
-async fn retrieve_trash_items() -> Option<Rc<Vec<Rc<PitouTrashItem>>>> {
-    tauri_sys::tauri::invoke("thrash_items", &NoArg)
-        .await
-        .map(|v: PitouTrashItemsVec| v.items)
-        .ok()
+fn destination_path(destination: &Option<String>) -> Option<PitouFilePath> {
+    destination
+        .as_ref()
+        .map(|dir| PitouFilePath::from_pathbuf(PathBuf::from(dir)))
 }
 
 #[function_component]
 pub fn TrashView() -> Html {
     let ctx = use_context::<ApplicationContext>().unwrap();
-    let force_update = use_force_update();
-    let selections = use_mut_ref(|| HashSet::new());
-    {
+    let update = use_force_update();
+    let selected = use_state(|| Rc::new(HashSet::<String>::new()));
+    let query = use_state(String::new);
+    let sort = use_state(|| (TrashSortKey::Deleted, PitouFileSortOrder::Decreasing));
+    let summary = use_state(|| None::<TrashSummary>);
+    let conflicts = use_state(|| None::<Rc<PendingConflicts>>);
+    let status = use_state(|| None::<String>);
+    let purge = use_state(|| None::<Purge>);
+    let restore_to = use_state(|| false);
+    let restore_to_ref = use_node_ref();
+
+    let reload = {
         let ctx = ctx.clone();
-        let force_update = force_update.clone();
-        use_effect_with(ctx.refresher_state(), move |_| {
-            if ctx.static_data.no_trash_items() {
-                let ctx = ctx.clone();
-                let force_update = force_update.clone();
-                spawn_local(async move {
-                    let trash_items = retrieve_trash_items().await;
-                    ctx.static_data.update_trash_items(trash_items);
-                    force_update.force_update();
-                })
-            }
-        });
-    }
+        let update = update.clone();
+        let summary = summary.clone();
+        let selected = selected.clone();
+        Callback::from(move |()| {
+            let ctx = ctx.clone();
+            let update = update.clone();
+            let summary = summary.clone();
+            let selected = selected.clone();
+            spawn_local(async move {
+                let items = crate::app::cmds::trash_items().await.ok().map(|v| v.items);
+                let remaining = items
+                    .iter()
+                    .flat_map(|items| items.iter())
+                    .map(|item| item.id().to_owned())
+                    .filter(|id| selected.contains(id))
+                    .collect::<HashSet<_>>();
+                selected.set(Rc::new(remaining));
+                ctx.static_data.update_trash_items(items);
+                summary.set(crate::app::cmds::trash_summary().await.ok().flatten());
+                update.force_update();
+            })
+        })
+    };
+
     {
-        let ctx = ctx.clone();
-        let force_update = force_update.clone();
-        use_interval(
-            move || {
-                let ctx = ctx.clone();
-                let force_update = force_update.clone();
+        let reload = reload.clone();
+        use_effect_with(ctx.refresher_state(), move |_| reload.emit(()));
+    }
+
+    let restore = {
+        let reload = reload.clone();
+        let conflicts = conflicts.clone();
+        let status = status.clone();
+        Callback::from(
+            move |(ids, conflict, destination): (Vec<String>, RestoreConflict, Option<String>)| {
+                let reload = reload.clone();
+                let conflicts = conflicts.clone();
+                let status = status.clone();
                 spawn_local(async move {
-                    let trash_items = retrieve_trash_items().await;
-                    ctx.static_data.update_trash_items(trash_items);
-                    force_update.force_update()
+                    let path = destination_path(&destination);
+                    let errors = crate::app::cmds::restore_trash(&ids, conflict, path.as_ref())
+                        .await
+                        .unwrap_or_default();
+                    let (occupied, failed): (Vec<_>, Vec<_>) =
+                        errors.into_iter().partition(|e| e.occupied);
+                    status.set(failed.first().map(|e| {
+                        format!("{} items couldn't be restored: {}", failed.len(), e.message)
+                    }));
+                    conflicts.set((!occupied.is_empty()).then(|| {
+                        Rc::new(PendingConflicts {
+                            errors: occupied,
+                            destination,
+                        })
+                    }));
+                    reload.emit(());
                 })
             },
-            10000,
-        );
-    }
+        )
+    };
+
+    let onrestore = {
+        let restore = restore.clone();
+        let selected = selected.clone();
+        move |_| {
+            let ids = selected.iter().cloned().collect::<Vec<_>>();
+            if !ids.is_empty() {
+                restore.emit((ids, RestoreConflict::Skip, None))
+            }
+        }
+    };
+
+    let onrestoreto = {
+        let restore_to = restore_to.clone();
+        move |_| restore_to.set(!*restore_to)
+    };
+
+    let onrestoretoconfirm = {
+        let restore = restore.clone();
+        let selected = selected.clone();
+        let restore_to = restore_to.clone();
+        let restore_to_ref = restore_to_ref.clone();
+        move |_| {
+            let dir = restore_to_ref.cast::<HtmlInputElement>().unwrap().value();
+            let dir = dir.trim();
+            let ids = selected.iter().cloned().collect::<Vec<_>>();
+            if !dir.is_empty() && !ids.is_empty() {
+                restore.emit((ids, RestoreConflict::Skip, Some(dir.to_owned())));
+                restore_to.set(false);
+            }
+        }
+    };
+
+    let onresolve = {
+        let restore = restore.clone();
+        let conflicts = conflicts.clone();
+        Callback::from(move |choice: RestoreConflict| {
+            if let Some(pending) = &*conflicts {
+                if choice != RestoreConflict::Skip {
+                    let ids = pending.errors.iter().map(|e| e.id.clone()).collect();
+                    restore.emit((ids, choice, pending.destination.clone()));
+                }
+            }
+            conflicts.set(None);
+        })
+    };
+
+    let onpurge = {
+        let purge = purge.clone();
+        let selected = selected.clone();
+        move |_| {
+            if !selected.is_empty() {
+                purge.set(Some(Purge::Selected))
+            }
+        }
+    };
+
+    let onempty = {
+        let purge = purge.clone();
+        move |_| purge.set(Some(Purge::Everything))
+    };
+
+    let onpurgeconfirm = {
+        let purge = purge.clone();
+        let selected = selected.clone();
+        let status = status.clone();
+        let reload = reload.clone();
+        move |_| {
+            let Some(request) = *purge else {
+                return;
+            };
+            purge.set(None);
+            let ids = selected.iter().cloned().collect::<Vec<_>>();
+            let status = status.clone();
+            let reload = reload.clone();
+            spawn_local(async move {
+                let done = match request {
+                    Purge::Selected => crate::app::cmds::purge_trash(&ids).await,
+                    Purge::Everything => crate::app::cmds::empty_trash().await,
+                };
+                if !done.unwrap_or(false) {
+                    status.set(Some("The trash couldn't be cleared".to_owned()))
+                }
+                reload.emit(());
+            })
+        }
+    };
+
+    let onpurgecancel = {
+        let purge = purge.clone();
+        move |_| purge.set(None)
+    };
+
+    let onquery = {
+        let query = query.clone();
+        move |e: InputEvent| query.set(e.target_unchecked_into::<HtmlInputElement>().value())
+    };
+
+    let onsortkey = {
+        let sort = sort.clone();
+        move |e: Event| {
+            let idx = e
+                .target_unchecked_into::<HtmlSelectElement>()
+                .selected_index();
+            if let Some(key) = TrashSortKey::ALL.get(idx as usize) {
+                sort.set((*key, sort.1))
+            }
+        }
+    };
+
+    let onsortorder = {
+        let sort = sort.clone();
+        move |_| {
+            let order = match sort.1 {
+                PitouFileSortOrder::Increasing => PitouFileSortOrder::Decreasing,
+                PitouFileSortOrder::Decreasing => PitouFileSortOrder::Increasing,
+            };
+            sort.set((sort.0, order))
+        }
+    };
+
+    let items = ctx.static_data.trash_items().unwrap_or_default();
+    let mut shown = items
+        .iter()
+        .filter(|item| item.matches(&query))
+        .cloned()
+        .collect::<Vec<_>>();
+    let (key, order) = *sort;
+    shown.sort_by(|a, b| match order {
+        PitouFileSortOrder::Increasing => key.compare(a, b),
+        PitouFileSortOrder::Decreasing => key.compare(b, a),
+    });
+
+    let ontoggle = {
+        let selected = selected.clone();
+        Callback::from(move |id: String| {
+            let mut ids = (**selected).clone();
+            if !ids.remove(&id) {
+                ids.insert(id);
+            }
+            selected.set(Rc::new(ids))
+        })
+    };
 
-    let content = if let Some(val) = ctx.static_data.trash_items() {
-        html! { <TrashListView items= { val.clone() } {selections}/> }
+    let all_selected = !shown.is_empty() && shown.iter().all(|item| selected.contains(item.id()));
+    let ontoggleall = {
+        let selected = selected.clone();
+        let ids = shown
+            .iter()
+            .map(|item| item.id().to_owned())
+            .collect::<Vec<_>>();
+        move |_| {
+            let mut all = (**selected).clone();
+            if all_selected {
+                ids.iter().for_each(|id| {
+                    all.remove(id);
+                });
+            } else {
+                all.extend(ids.iter().cloned());
+            }
+            selected.set(Rc::new(all))
+        }
+    };
+
+    let summary = match &*summary {
+        Some(summary) => format!(
+            "{} items ({} folders), {}",
+            summary.items,
+            summary.folders,
+            PitouFileSize::new(summary.bytes).format()
+        ),
+        None => String::new(),
+    };
+
+    let restore_to_row = if *restore_to {
+        let default_dir = ctx
+            .active_tab
+            .current_dir()
+            .map(|dir| dir.full_path_str().to_owned())
+            .unwrap_or_default();
+        html! {
+            <div class="trash-options">
+                <input type="text" placeholder="restore into this folder" value={default_dir} ref={restore_to_ref} autocomplete="off"/>
+                <button onclick={onrestoretoconfirm}>{ format!("Restore {} items here", selected.len()) }</button>
+            </div>
+        }
+    } else {
+        html! {}
+    };
+
+    let conflict_row = if let Some(pending) = &*conflicts {
+        let choose = |choice| {
+            let onresolve = onresolve.clone();
+            move |_| onresolve.emit(choice)
+        };
+        let names = pending
+            .errors
+            .iter()
+            .map(|e| e.path.name())
+            .collect::<Vec<_>>()
+            .join(", ");
+        html! {
+            <div class="trash-options trash-prompt">
+                <span class="trash-prompt-text" title={names.clone()}>{ format!("Already exists: {names}") }</span>
+                <button onclick={choose(RestoreConflict::Replace)}>{"Replace"}</button>
+                <button onclick={choose(RestoreConflict::KeepBoth)}>{"Keep both"}</button>
+                <button onclick={choose(RestoreConflict::Skip)}>{"Skip"}</button>
+            </div>
+        }
     } else {
         html! {}
     };
 
+    let purge_row = match *purge {
+        Some(request) => {
+            let question = match request {
+                Purge::Selected => {
+                    format!("Permanently delete {} items?", selected.len())
+                }
+                Purge::Everything => format!("Permanently delete all {} items?", items.len()),
+            };
+            html! {
+                <div class="trash-options trash-prompt">
+                    <span class="trash-prompt-text">{ question }</span>
+                    <button class="trash-purge-btn" onclick={onpurgeconfirm}>{"Delete"}</button>
+                    <button onclick={onpurgecancel}>{"Cancel"}</button>
+                </div>
+            }
+        }
+        None => html! {},
+    };
+
+    let status_row = match &*status {
+        Some(message) => html! { <div class="trash-status">{ message.clone() }</div> },
+        None => html! {},
+    };
+
+    let order = match sort.1 {
+        PitouFileSortOrder::Increasing => "Ascending",
+        PitouFileSortOrder::Decreasing => "Descending",
+    };
+
     html! {
         <div id="trash-pane" class="fullpane">
-            { content }
+            <div class="trash-options">
+                <input type="text" placeholder="search the trash" value={(*query).clone()} oninput={onquery} autocomplete="off"/>
+                <select class="select-box" onchange={onsortkey}>
+                    {
+                        TrashSortKey::ALL.iter().map(|k| html! {
+                            <option selected={*k == sort.0}>{ k.name() }</option>
+                        }).collect::<Html>()
+                    }
+                </select>
+                <button onclick={onsortorder}>{ order }</button>
+                <span class="trash-summary">{ summary }</span>
+            </div>
+            <div class="trash-options">
+                <button onclick={onrestore}>{"Restore"}</button>
+                <button onclick={onrestoreto}>{"Restore to..."}</button>
+                <button class="trash-purge-btn" onclick={onpurge}>{"Delete permanently"}</button>
+                <button class="trash-purge-btn" onclick={onempty}>{"Empty trash"}</button>
+                <TrashRetentionEditor onpurged={status.setter()} reload={reload.clone()}/>
+            </div>
+            { restore_to_row }
+            { conflict_row }
+            { purge_row }
+            { status_row }
+            <div id="trash-list-section">
+                <TrashListDsc selected={all_selected} ontoggle={ontoggleall}/>
+                <div id="pane-list-view">
+                    {
+                        shown.iter().map(|item| {
+                            let selected = selected.contains(item.id());
+                            html! { <TrashListItem item={item.clone()} {selected} ontoggle={ontoggle.clone()}/> }
+                        }).collect::<Html>()
+                    }
+                    <div id="pane-list-view-bottom-space">
+                        <div id="pane-list-view-bottom-space-i"></div>
+                    </div>
+                </div>
+            </div>
         </div>
     }
 }
 
-#[derive(Properties)]
-struct TrashPaneProps {
-    items: Rc<Vec<Rc<PitouTrashItem>>>,
-    selections: Rc<RefCell<HashSet<TrashItem>>>,
+#[derive(PartialEq, Properties)]
+struct TrashRetentionEditorProps {
+    onpurged: UseStateSetter<Option<String>>,
+    reload: Callback<()>,
 }
 
-impl PartialEq for TrashPaneProps {
-    fn eq(&self, other: &Self) -> bool {
-        Rc::ptr_eq(&self.items, &other.items)
+/// Blank fields leave the trash unlimited by age or size.
+#[function_component]
+fn TrashRetentionEditor(props: &TrashRetentionEditorProps) -> Html {
+    let retention = use_state(|| None::<TrashRetention>);
+    let days_ref = use_node_ref();
+    let megabytes_ref = use_node_ref();
+    {
+        let retention = retention.clone();
+        use_effect_with((), move |_| {
+            spawn_local(async move {
+                retention.set(crate::app::cmds::trash_retention().await.ok());
+            })
+        });
     }
-}
 
-#[function_component]
-fn TrashListView(props: &TrashPaneProps) -> Html {
-    let free_space = html! {
-        <div id="pane-list-view-bottom-space">
-            <div id="pane-list-view-bottom-space-i"></div>
-        </div>
+    let onsave = {
+        let retention = retention.clone();
+        let days_ref = days_ref.clone();
+        let megabytes_ref = megabytes_ref.clone();
+        let onpurged = props.onpurged.clone();
+        let reload = props.reload.clone();
+        move |_| {
+            let read = |node: &NodeRef| {
+                let value = node.cast::<HtmlInputElement>().unwrap().value();
+                value.trim().parse::<u64>().ok().filter(|v| *v > 0)
+            };
+            let new = TrashRetention {
+                max_age_days: read(&days_ref).map(|days| days.min(u32::MAX as u64) as u32),
+                max_bytes: read(&megabytes_ref).map(|mb| mb.saturating_mul(1 << 20)),
+            };
+            let retention = retention.clone();
+            let onpurged = onpurged.clone();
+            let reload = reload.clone();
+            spawn_local(async move {
+                if let Ok(Some(purged)) = crate::app::cmds::set_trash_retention(new).await {
+                    retention.set(Some(new));
+                    if purged > 0 {
+                        onpurged.set(Some(format!("Purged {purged} items past the limits")));
+                    }
+                    reload.emit(());
+                }
+            })
+        }
     };
 
-    let content = props
-        .items
-        .iter()
-        .map(|v| html! { <TrashListItem item = {v.clone()} selections = {props.selections.clone()} /> })
-        .chain(Some(free_space))
-        .collect::<Html>();
+    let current = (*retention).unwrap_or_default();
+    let days = current
+        .max_age_days
+        .map(|d| d.to_string())
+        .unwrap_or_default();
+    let megabytes = current
+        .max_bytes
+        .map(|b| (b >> 20).to_string())
+        .unwrap_or_default();
 
     html! {
-        <>
-            <TrashListDsc />
-            <div id="pane-list-view">
-                { content }
-            </div>
-        </>
+        <div class="trash-retention" title="items past these limits are deleted permanently">
+            <span>{"Purge after"}</span>
+            <input type="number" min="1" placeholder="never" value={days} ref={days_ref}/>
+            <span>{"days or over"}</span>
+            <input type="number" min="1" placeholder="any" value={megabytes} ref={megabytes_ref}/>
+            <span>{"MB"}</span>
+            <button onclick={onsave}>{"Apply"}</button>
+        </div>
     }
 }
 
+#[derive(PartialEq, Properties)]
+struct TrashListDscProps {
+    selected: bool,
+    ontoggle: Callback<()>,
+}
+
 #[function_component]
-fn TrashListDsc() -> Html {
+fn TrashListDsc(props: &TrashListDscProps) -> Html {
+    let onchange = {
+        let ontoggle = props.ontoggle.clone();
+        move |_| ontoggle.emit(())
+    };
     html! {
         <div id="pane-list-view-dsc">
             <div class="pane-list-view-dsc-checkbox-container">
-                <input class="pane-list-view-dsc-checkbox" type="checkbox" />
+                <input class="pane-list-view-dsc-checkbox" type="checkbox" checked={props.selected} {onchange} />
             </div>
             <div class="pane-list-view-dsc-filetype">
                 { "ico" }
@@ -114,9 +484,15 @@ fn TrashListDsc() -> Html {
             <div class="pane-list-view-dsc-filename">
                 { "filename" }
             </div>
+            <div class="trash-location-container">
+                { "Original location" }
+            </div>
             <div class="list-modifieddate-container">
                 { "Deleted on" }
             </div>
+            <div class="trash-size-container">
+                { "Size" }
+            </div>
         </div>
     }
 }
@@ -124,62 +500,30 @@ fn TrashListDsc() -> Html {
 #[derive(Properties)]
 struct TrashItemProps {
     item: Rc<PitouTrashItem>,
-    selections: Rc<RefCell<HashSet<TrashItem>>>,
+    selected: bool,
+    ontoggle: Callback<String>,
 }
 
 impl PartialEq for TrashItemProps {
     fn eq(&self, other: &Self) -> bool {
-        &self.item.original_path == &other.item.original_path
+        Rc::ptr_eq(&self.item, &other.item) && self.selected == other.selected
     }
 }
 
 #[function_component]
 fn TrashListItem(props: &TrashItemProps) -> Html {
-    let selected = use_state_eq(|| {
-        props
-            .selections
-            .borrow()
-            .contains(&TrashItem::new(props.item.clone()))
-    });
-
-    let toggle = {
-        let item = TrashItem::new(props.item.clone());
-        let selections = props.selections.clone();
-        let selected = selected.clone();
-        move || {
-            let mut selections = selections.borrow_mut();
-            if selections.contains(&item) {
-                selections.remove(&item);
-                selected.set(false);
-            } else {
-                selections.insert(item.clone());
-                selected.set(true);
-            }
-            std::mem::drop(selections);
-        }
-    };
-
     let onclick = {
-        let toggle = toggle.clone();
-        move |_| toggle()
+        let id = props.item.id().to_owned();
+        let ontoggle = props.ontoggle.clone();
+        move |_| ontoggle.emit(id.clone())
     };
 
-    let onchange = { move |_| toggle() };
-
-    let ondblclick = {
-        let _item = props.item.clone();
-        move |_| ()
+    let list_item_class = if props.selected {
+        "list-item selected"
+    } else {
+        "list-item not-selected"
     };
 
-    let list_item_class = format!(
-        "list-item {}",
-        if *selected {
-            "selected"
-        } else {
-            "not-selected"
-        }
-    );
-
     let deleted = props
         .item
         .metadata()
@@ -188,13 +532,25 @@ fn TrashListItem(props: &TrashItemProps) -> Html {
         .format("%Y-%m-%d %H:%M")
         .to_string();
     let name = props.item.name().to_owned();
+    let location = props
+        .item
+        .path()
+        .path
+        .parent()
+        .map(|dir| dir.display().to_string())
+        .unwrap_or_default();
+    let size = if props.item.is_dir() {
+        format!("{} items", props.item.metadata().size.bytes)
+    } else {
+        props.item.metadata().size.format()
+    };
 
     let file_type_icon = crate::app::reusables::list_trash_item_type_icon(&props.item);
 
     html! {
-        <div class={list_item_class} {ondblclick} {onclick}>
+        <div class={list_item_class} {onclick}>
             <div class="list-checkbox-container">
-                <input class="explorer-checkbox" type="checkbox" checked={*selected} {onchange} />
+                <input class="explorer-checkbox" type="checkbox" checked={props.selected} />
             </div>
             <div class="list-filetypeicon-container">
                 { file_type_icon }
@@ -202,9 +558,15 @@ fn TrashListItem(props: &TrashItemProps) -> Html {
             <div class="list-filename-container">
                 <div class="list-filename">{ name }</div>
             </div>
+            <div class="trash-location-container" title={location.clone()}>
+                <div>{ location }</div>
+            </div>
             <div class="list-modifieddate-container">
                 <div>{ deleted }</div>
             </div>
+            <div class="trash-size-container">
+                <div>{ size }</div>
+            </div>
         </div>
     }
 }
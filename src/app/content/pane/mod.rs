@@ -4,12 +4,11 @@ use pitou_core::{frontend::*, *};
 use wasm_bindgen_futures::spawn_local;
 use yew::prelude::*;
 
-use crate::app::{
-    content::pane::menu_views::*,
-    reusables::{NoArg, NotYetImplementedPane},
-};
+use crate::app::{content::pane::menu_views::*, reusables::NoArg};
 mod menu_views;
 
+pub(crate) use menu_views::{download_selections, upload_selections};
+
 #[derive(Properties, PartialEq)]
 pub struct PaneProps {
     pub onupdatedir: Callback<Option<Rc<PitouFile>>>,
@@ -37,16 +36,16 @@ pub fn Pane(props: &PaneProps) -> Html {
     let onopen = {
         let onupdatedir = props.onupdatedir.clone();
         let ctx = ctx.clone();
-        move |pf: Rc<PitouFile>| {
-            if pf.is_file() {
-                spawn_local(async move {
-                    crate::app::cmds::open(pf).await.ok();
-                })
-            } else if pf.is_link() {
-            } else {
+        move |pf: Rc<PitouFile>| match pf.resolved_kind() {
+            Some(PitouFileKind::File) => spawn_local(async move {
+                crate::app::cmds::open(pf).await.ok();
+            }),
+            // broken links and special files have nothing to open
+            Some(PitouFileKind::Directory) | None if !pf.is_broken_link() => {
                 ctx.active_tab.update_cur_menu(AppMenu::Explorer);
                 onupdatedir.emit(Some(pf))
             }
+            _ => (),
         }
     };
 
@@ -57,13 +56,19 @@ pub fn Pane(props: &PaneProps) -> Html {
             html! { <ExplorerView {onopen} reload={props.reload.clone()} quietreload={props.quietreload.clone()}/> }
         }
         AppMenu::Trash => html! { <TrashView /> },
-        AppMenu::Favorites => html! { <NotYetImplementedPane/> },
+        AppMenu::Favorites => html! { <FavoritesView {onopen} /> },
         AppMenu::Search => {
             html! { <SearchPane {onopen} reload={props.reload.clone()} quietreload={props.quietreload.clone()}/> }
         }
-        AppMenu::Locked => html! { <NotYetImplementedPane/> },
-        AppMenu::Recents => html! { <NotYetImplementedPane/> },
-        AppMenu::Cloud => html! { <NotYetImplementedPane/> },
-        AppMenu::Settings => html! { <SettingsView onupdatetheme={props.onupdatetheme.clone()}/> },
+        AppMenu::Locked => {
+            html! { <LockedView reload={props.reload.clone()} quietreload={props.quietreload.clone()}/> }
+        }
+        AppMenu::Recents => html! { <RecentsView {onopen} /> },
+        AppMenu::Cloud => html! { <CloudView {onopen} /> },
+        AppMenu::Duplicates => html! { <DuplicatesView {onopen} /> },
+        AppMenu::DiskUsage => html! { <DiskUsageView {onopen} /> },
+        AppMenu::Settings => {
+            html! { <SettingsView onupdatetheme={props.onupdatetheme.clone()} reload={props.reload.clone()}/> }
+        }
     }
 }
@@ -1,7 +1,12 @@
 use std::rc::Rc;
 
-use crate::app::reusables::{FindPop, ItemsSortPop, NewItemPop};
-use pitou_core::{frontend::ApplicationContext, AppMenu, ItemsView, PitouFile, PitouFilePath};
+use crate::app::{
+    content::pane::{download_selections, upload_selections},
+    reusables::{FindPop, ItemsSortPop, NewItemPop, PropertiesPop},
+};
+use pitou_core::{
+    frontend::ApplicationContext, AppMenu, ItemsView, PitouFile, PitouFileKind, PitouFilePath,
+};
 use wasm_bindgen_futures::spawn_local;
 use web_sys::HtmlSelectElement;
 use yew::prelude::*;
@@ -23,7 +28,7 @@ pub fn Ribbon(props: &RibbonProps) -> Html {
             <RibbonClipboard reload={ props.reload.clone() }/>
             <RibbonCreations reload={ props.reload.clone() }/>
             <RibbonTrash reload={ props.reload.clone() }/>
-            <RibbonActions onupdatedir={ props.onupdatedir.clone() }/>
+            <RibbonActions onupdatedir={ props.onupdatedir.clone() } reload={ props.reload.clone() }/>
             <RibbonRefresh quietreload={ props.quietreload.clone() }/>
             <RibbonProperties />
             <RibbonArrange quietreload={props.quietreload.clone()}/>
@@ -55,6 +60,8 @@ fn RibbonRefresh(props: &RibbonRefreshProps) -> Html {
                     AppMenu::Locked => false,
                     AppMenu::Recents => false,
                     AppMenu::Cloud => false,
+                    AppMenu::Duplicates => false,
+                    AppMenu::DiskUsage => false,
                     AppMenu::Settings => false,
                 };
                 refreshing.set(still_refreshing);
@@ -81,6 +88,8 @@ fn RibbonRefresh(props: &RibbonRefreshProps) -> Html {
                 AppMenu::Locked => (),
                 AppMenu::Recents => (),
                 AppMenu::Cloud => (),
+                AppMenu::Duplicates => (),
+                AppMenu::DiskUsage => (),
                 AppMenu::Settings => (),
             }
             refreshing.set(true);
@@ -107,15 +116,57 @@ fn RibbonRefresh(props: &RibbonRefreshProps) -> Html {
 
 #[function_component]
 fn RibbonProperties() -> Html {
+    let ctx = use_context::<ApplicationContext>().unwrap();
+    let can_show = use_state_eq(|| false);
+    let showing = use_state(|| None);
+    {
+        let ctx = ctx.clone();
+        let can_show = can_show.clone();
+        use_interval(
+            move || can_show.set(properties_selection(&ctx).is_some()),
+            250,
+        )
+    }
+
+    let onclick = {
+        let showing = showing.clone();
+        move |_| {
+            if let Some(items) = properties_selection(&ctx) {
+                showing.set(Some(Rc::new(items)))
+            }
+        }
+    };
+
+    let pop = if let Some(items) = &*showing {
+        let onclose = {
+            let showing = showing.clone();
+            move |()| showing.set(None)
+        };
+        html! { <PropertiesPop items={items.clone()} {onclose} /> }
+    } else {
+        html! {}
+    };
+
+    let class = format! {"ribbon-large {}", if *can_show { "active" } else { "inactive" }};
+
     html! {
         <div id="ribbon-properties" class="ribbon-group">
-            <div class="ribbon-large" title="properties">
+            {pop}
+            <div {class} title="properties" {onclick}>
                 <img src="./public/properties.png"/>
             </div>
         </div>
     }
 }
 
+/// The selected files and folders, wherever they were selected.
+fn properties_selection(ctx: &ApplicationContext) -> Option<Vec<Rc<PitouFile>>> {
+    ctx.static_data
+        .folder_entry_selections()
+        .or_else(|| ctx.static_data.search_result_selections())
+        .filter(|items| !items.is_empty())
+}
+
 #[derive(Properties, PartialEq)]
 struct RibbonTrashProps {
     reload: Callback<()>,
@@ -162,6 +213,7 @@ fn RibbonTrash(props: &RibbonTrashProps) -> Html {
 #[derive(PartialEq, Properties)]
 struct RibbonActionsProps {
     onupdatedir: Callback<Option<Rc<PitouFile>>>,
+    reload: Callback<()>,
 }
 
 #[function_component]
@@ -173,15 +225,16 @@ fn RibbonActions(props: &RibbonActionsProps) -> Html {
         let ctx = ctx.clone();
         move |_| {
             if let Some(pf) = ctx.static_data.openable_selection() {
-                if pf.is_file() {
-                    spawn_local(async move {
+                match pf.resolved_kind() {
+                    Some(PitouFileKind::File) => spawn_local(async move {
                         crate::app::cmds::open(pf).await.ok();
-                    })
-                } else if pf.is_link() {
-                    //TODO
-                } else {
-                    ctx.active_tab.update_cur_menu(AppMenu::Explorer);
-                    onupdatedir.emit(Some(pf))
+                    }),
+                    // broken links and special files have nothing to open
+                    Some(PitouFileKind::Directory) | None if !pf.is_broken_link() => {
+                        ctx.active_tab.update_cur_menu(AppMenu::Explorer);
+                        onupdatedir.emit(Some(pf))
+                    }
+                    _ => (),
                 }
             }
         }
@@ -198,6 +251,80 @@ fn RibbonActions(props: &RibbonActionsProps) -> Html {
         }
     };
 
+    let onpin = {
+        let ctx = ctx.clone();
+        move |_| {
+            // with nothing selected, the folder being explored is pinned
+            let items = ctx
+                .static_data
+                .folder_entry_selections()
+                .or_else(|| ctx.static_data.search_result_selections())
+                .filter(|items| !items.is_empty())
+                .or_else(|| {
+                    let exploring = ctx.current_menu() == AppMenu::Explorer;
+                    let dir = ctx.active_tab.current_dir().filter(|_| exploring)?;
+                    Some(vec![dir])
+                });
+            if let Some(items) = items {
+                spawn_local(async move {
+                    crate::app::cmds::add_favorites(&items).await.ok();
+                })
+            }
+        }
+    };
+
+    let onlock = {
+        let ctx = ctx.clone();
+        let reload = props.reload.clone();
+        move |_| {
+            let Some(items) = ctx.static_data.folder_entry_selections() else {
+                return;
+            };
+            let ctx = ctx.clone();
+            let reload = reload.clone();
+            spawn_local(async move {
+                // a locked vault is shown so that it can be unlocked or made
+                if crate::app::cmds::add_to_vault(&items)
+                    .await
+                    .ok()
+                    .flatten()
+                    .is_none()
+                {
+                    ctx.active_tab.update_cur_menu(AppMenu::Locked);
+                }
+                reload.emit(())
+            })
+        }
+    };
+
+    // with nowhere to upload to or nothing to download, the cloud is shown to pick from
+    let onupload = {
+        let ctx = ctx.clone();
+        move |_| {
+            let ctx = ctx.clone();
+            spawn_local(async move {
+                if !upload_selections(&ctx).await {
+                    ctx.active_tab.update_cur_menu(AppMenu::Cloud);
+                }
+            })
+        }
+    };
+
+    let ondownload = {
+        let ctx = ctx.clone();
+        let reload = props.reload.clone();
+        move |_| {
+            let ctx = ctx.clone();
+            let reload = reload.clone();
+            spawn_local(async move {
+                if !download_selections(&ctx).await {
+                    ctx.active_tab.update_cur_menu(AppMenu::Cloud);
+                }
+                reload.emit(())
+            })
+        }
+    };
+
     html! {
         <div id="ribbon-actions" class="ribbon-group">
             <div class="ribbon-medium-group">
@@ -213,18 +340,18 @@ fn RibbonActions(props: &RibbonActionsProps) -> Html {
                 <div class="ribbon-small" onclick={ onopenwith }>{"open with"}</div>
             </div>
             <div class="ribbon-medium-group">
-                <div class="ribbon-medium" title="pin">
+                <div class="ribbon-medium" title="add to favorites" onclick={ onpin }>
                     <img src="./public/pin.png"/>
                 </div>
-                <div class="ribbon-medium" title="lock">
+                <div class="ribbon-medium" title="move to the vault" onclick={ onlock }>
                     <img src="./public/locked.png" />
                 </div>
             </div>
             <div class="ribbon-medium-group">
-                <div class="ribbon-medium" title="upload">
+                <div class="ribbon-medium" title="upload to the cloud" onclick={ onupload }>
                     <img src="./public/cloud_upload.png"/>
                 </div>
-                <div class="ribbon-medium" title="download">
+                <div class="ribbon-medium" title="download from the cloud" onclick={ ondownload }>
                     <img src="./public/cloud_download.png" />
                 </div>
             </div>
@@ -307,6 +434,15 @@ fn RibbonArrange(props: &RibbonArrangeProps) -> Html {
         html! {}
     };
 
+    let onclickpreview = {
+        let ctx = ctx.clone();
+        let quietreload = props.quietreload.clone();
+        move |_| {
+            ctx.toggle_show_preview(!ctx.show_preview());
+            quietreload.emit(())
+        }
+    };
+
     let sort_class = format! {"ribbon-large {}", if ctx.current_menu() == AppMenu::Explorer { "active" } else { "not-active" }};
     let items_view = ctx.items_view();
 
@@ -326,6 +462,12 @@ fn RibbonArrange(props: &RibbonArrangeProps) -> Html {
                     </select>
                 </div>
             </div>
+            <div class="ribbon-textgroup">
+                <div class="ribbon-small" title="preview pane" onclick={onclickpreview}>
+                    <input type="checkbox" checked={ctx.show_preview()} />
+                    <div>{"preview"}</div>
+                </div>
+            </div>
         </div>
     }
 }
@@ -202,6 +202,22 @@ fn TransferSession(props: &TransferSessionProps) -> Html {
                 let prompt1 = "pasting a cut";
                 format_session_state(state, time_elapsed, prompt1)
             }
+            TransferMsg::Upload {
+                id: _,
+                state,
+                time_elapsed,
+            } => {
+                let prompt1 = "uploading";
+                format_session_state(state, time_elapsed, prompt1)
+            }
+            TransferMsg::Download {
+                id: _,
+                state,
+                time_elapsed,
+            } => {
+                let prompt1 = "downloading";
+                format_session_state(state, time_elapsed, prompt1)
+            }
         },
     };
 
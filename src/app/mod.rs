@@ -1,8 +1,9 @@
 use std::{cell::RefCell, rc::Rc};
 
-use pitou_core::{frontend::*, *};
+use pitou_core::{frontend::*, session::SavedSession, settings::SavedSettings, *};
 use wasm_bindgen_futures::spawn_local;
 use yew::prelude::*;
+use yew_hooks::use_interval;
 mod args;
 mod cmds;
 mod content;
@@ -14,6 +15,15 @@ use title_bar::TitleBar;
 
 pub mod reusables;
 
+/// Whether the desktop prefers a dark color scheme, as reported to the webview.
+pub(crate) fn prefers_dark_scheme() -> bool {
+    web_sys::window()
+        .and_then(|window| window.match_media("(prefers-color-scheme: dark)").ok())
+        .flatten()
+        .map(|query| query.matches())
+        .unwrap_or(true)
+}
+
 #[function_component]
 pub fn App() -> Html {
     let tabs_ctx = use_state(|| Rc::new(AllTabsCtx::default()));
@@ -22,6 +32,116 @@ pub fn App() -> Html {
 
     let static_data = use_state(|| Rc::new(StaticData::new()));
 
+    // what the settings file holds, `None` until it has been read
+    let saved_settings = use_mut_ref(|| None::<SavedSettings>);
+    {
+        let genr_ctx = genr_ctx.clone();
+        let saved_settings = saved_settings.clone();
+        use_effect_with((), move |_| {
+            spawn_local(async move {
+                if let Ok(settings) = cmds::load_settings().await {
+                    let mut gen_ctx = genr_ctx.borrow_mut();
+                    gen_ctx.apply_settings(settings.clone());
+                    gen_ctx.follow_system_theme(prefers_dark_scheme());
+                    std::mem::drop(gen_ctx);
+                    *saved_settings.borrow_mut() = Some(settings);
+                    genr_ctx.set((*genr_ctx).clone());
+                }
+            })
+        });
+    }
+    {
+        // settings are changed in place all over the app, so they are saved whenever they drift from the file
+        let genr_ctx = genr_ctx.clone();
+        let saved_settings = saved_settings.clone();
+        use_interval(
+            move || {
+                let dark = prefers_dark_scheme();
+                if genr_ctx.borrow_mut().follow_system_theme(dark) {
+                    genr_ctx.set((*genr_ctx).clone());
+                }
+                let current = genr_ctx.borrow().settings();
+                let changed = matches!(&*saved_settings.borrow(), Some(saved) if *saved != current);
+                if changed {
+                    *saved_settings.borrow_mut() = Some(current.clone());
+                    spawn_local(async move {
+                        cmds::save_settings(&current).await.ok();
+                    })
+                }
+            },
+            1000,
+        );
+    }
+
+    // the session as last saved, `None` until the previous one has been restored
+    let saved_session = use_mut_ref(|| None::<SavedSession>);
+    {
+        let tabs_ctx = tabs_ctx.clone();
+        let genr_ctx = genr_ctx.clone();
+        let saved_session = saved_session.clone();
+        use_effect_with((), move |_| {
+            spawn_local(async move {
+                let Ok(session) = cmds::load_session().await else {
+                    return;
+                };
+                let current = match session {
+                    Some(session) => {
+                        let mut gen_ctx = genr_ctx.borrow_mut();
+                        gen_ctx.app_width = session.app_width;
+                        gen_ctx.app_height = session.app_height;
+                        std::mem::drop(gen_ctx);
+                        let restored = AllTabsCtx::from_saved(session);
+                        let current = restored.saved(&genr_ctx.borrow());
+                        tabs_ctx.set(Rc::new(restored));
+                        current
+                    }
+                    None => tabs_ctx.saved(&genr_ctx.borrow()),
+                };
+                *saved_session.borrow_mut() = Some(current);
+            })
+        });
+    }
+    {
+        // tabs and folders change all over the app, so the session is saved whenever it drifts from the file
+        let tabs_ctx = tabs_ctx.clone();
+        let genr_ctx = genr_ctx.clone();
+        let saved_session = saved_session.clone();
+        use_interval(
+            move || {
+                if saved_session.borrow().is_none() {
+                    return;
+                }
+                let tabs_ctx = tabs_ctx.clone();
+                let genr_ctx = genr_ctx.clone();
+                let saved_session = saved_session.clone();
+                spawn_local(async move {
+                    let maximized = tauri_sys::window::current_window()
+                        .is_maximized()
+                        .await
+                        .unwrap_or(true);
+                    // a maximized window is restored to the size it had before it was maximized
+                    if let (false, Some(window)) = (maximized, web_sys::window()) {
+                        let width = window.inner_width().ok().and_then(|v| v.as_f64());
+                        let height = window.inner_height().ok().and_then(|v| v.as_f64());
+                        if let (Some(width), Some(height)) = (width, height) {
+                            let mut gen_ctx = genr_ctx.borrow_mut();
+                            gen_ctx.app_width = width as i32;
+                            gen_ctx.app_height = height as i32;
+                        }
+                    }
+                    let current = tabs_ctx.saved(&genr_ctx.borrow());
+                    let changed =
+                        matches!(&*saved_session.borrow(), Some(saved) if *saved != current);
+                    if changed {
+                        cmds::save_session(&current).await.ok();
+                        *saved_session.borrow_mut() = Some(current);
+                    }
+                })
+            },
+            1000,
+        );
+    }
+
     let ctx = {
         let active_tab = tabs_ctx.current_tab();
         ApplicationContext::new((*genr_ctx).clone(), active_tab, (*static_data).clone())
@@ -60,12 +180,17 @@ pub fn App() -> Html {
         let ctx = ctx.clone();
         let tabs_ctx = tabs_ctx.clone();
         let static_data = static_data.clone();
-        move |file| {
+        move |file: Option<Rc<PitouFile>>| {
             let new_tabs = (**tabs_ctx).clone();
             static_data.clear_all_selections();
             let cur_tab = new_tabs.current_tab();
             cur_tab.update_children(None);
             cur_tab.update_siblings(None);
+            if let Some(dir) = file.clone() {
+                spawn_local(async move {
+                    crate::app::cmds::record_visit(dir).await.ok();
+                });
+            }
             cur_tab.update_cur_dir(file);
             ctx.toggle_refresher_state();
             tabs_ctx.set(Rc::new(new_tabs))
@@ -105,9 +230,29 @@ pub fn App() -> Html {
     "};
 
     let onclose = {
-        |()| {
+        let genr_ctx = genr_ctx.clone();
+        let tabs_ctx = tabs_ctx.clone();
+        let saved_settings = saved_settings.clone();
+        let saved_session = saved_session.clone();
+        move |()| {
+            let settings = saved_settings
+                .borrow()
+                .is_some()
+                .then(|| genr_ctx.borrow().settings());
+            let session = saved_session
+                .borrow()
+                .is_some()
+                .then(|| tabs_ctx.saved(&genr_ctx.borrow()));
             let wd = tauri_sys::window::current_window();
-            spawn_local(async move { wd.close().await.unwrap() })
+            spawn_local(async move {
+                if let Some(settings) = settings {
+                    cmds::save_settings(&settings).await.ok();
+                }
+                if let Some(session) = session {
+                    cmds::save_session(&session).await.ok();
+                }
+                wd.close().await.unwrap()
+            })
         }
     };
     let ontogglemaximize = {
@@ -1,6 +1,17 @@
 use std::{marker::PhantomData, rc::Rc};
 
-use pitou_core::{search::SimplifiedSearchOptions, *};
+use pitou_core::{
+    cloud::{CloudAccount, CloudEntry},
+    duplicates::DuplicateOptions,
+    properties::{AttributeChanges, ChecksumKind},
+    search::{SavedSearch, SimplifiedSearchOptions},
+    session::SavedSession,
+    settings::SavedSettings,
+    themes::NamedTheme,
+    thumbnails::ThumbnailSize,
+    trash::{RestoreConflict, TrashRetention},
+    *,
+};
 use serde::{
     de::{SeqAccess, Visitor},
     Deserialize, Deserializer, Serialize,
@@ -16,6 +27,167 @@ pub struct SearchOptionsArg {
     pub options: SimplifiedSearchOptions,
 }
 
+#[derive(Serialize)]
+pub struct SavedSearchArg {
+    pub search: SavedSearch,
+}
+
+#[derive(Serialize)]
+pub struct SettingsArg<'a> {
+    pub settings: &'a SavedSettings,
+}
+
+#[derive(Serialize)]
+pub struct ThemeArg<'a> {
+    pub theme: &'a NamedTheme,
+}
+
+#[derive(Serialize)]
+pub struct SessionArg<'a> {
+    pub session: &'a SavedSession,
+}
+
+#[derive(Serialize)]
+pub struct JsonArg {
+    pub json: String,
+}
+
+#[derive(Serialize)]
+pub struct NameArg {
+    pub name: String,
+}
+
+#[derive(Serialize)]
+pub struct DuplicateOptionsArg {
+    pub options: DuplicateOptions,
+}
+
+#[derive(Serialize)]
+pub struct UsageRootArg<'a> {
+    pub root: &'a PitouFilePath,
+}
+
+#[derive(Serialize)]
+pub struct UsageTreeArg<'a> {
+    pub path: &'a PitouFilePath,
+    pub depth: u32,
+}
+
+#[derive(Serialize)]
+pub struct ThumbnailArg<'a> {
+    pub path: &'a PitouFilePath,
+    pub size: ThumbnailSize,
+}
+
+#[derive(Serialize)]
+pub struct PreviewArg<'a> {
+    pub path: &'a PitouFilePath,
+    pub dark: bool,
+}
+
+#[derive(Serialize)]
+pub struct ChecksumArg<'a> {
+    pub path: &'a PitouFilePath,
+    pub kind: ChecksumKind,
+}
+
+#[derive(Serialize)]
+pub struct AttributeChangesArg<'a> {
+    #[serde(with = "items_serde")]
+    pub items: &'a Vec<Rc<PitouFile>>,
+    pub changes: AttributeChanges,
+}
+
+#[derive(Serialize)]
+pub struct TrashIdsArg<'a> {
+    pub ids: &'a [String],
+}
+
+#[derive(Serialize)]
+pub struct RestoreTrashArg<'a> {
+    pub ids: &'a [String],
+    pub conflict: RestoreConflict,
+    pub destination: Option<&'a PitouFilePath>,
+}
+
+#[derive(Serialize)]
+pub struct TrashRetentionArg {
+    pub retention: TrashRetention,
+}
+
+#[derive(Serialize)]
+pub struct PassphraseArg {
+    pub passphrase: String,
+}
+
+#[derive(Serialize)]
+pub struct AutoLockArg {
+    pub minutes: u32,
+}
+
+#[derive(Serialize)]
+pub struct IdArg {
+    pub id: String,
+}
+
+#[derive(Serialize)]
+pub struct TrackingArg {
+    pub tracking: bool,
+}
+
+#[derive(Serialize)]
+pub struct AccountArg<'a> {
+    pub account: &'a CloudAccount,
+}
+
+#[derive(Serialize)]
+pub struct HostKeyArg<'a> {
+    pub account: &'a CloudAccount,
+    pub fingerprint: &'a str,
+}
+
+#[derive(Serialize)]
+pub struct CloudDirArg<'a> {
+    pub id: &'a str,
+    pub dir: &'a str,
+}
+
+#[derive(Serialize)]
+pub struct CloudFolderArg<'a> {
+    pub id: &'a str,
+    pub dir: &'a str,
+    pub name: &'a str,
+}
+
+#[derive(Serialize)]
+pub struct CloudRenameArg<'a> {
+    pub id: &'a str,
+    pub entry: &'a CloudEntry,
+    pub name: &'a str,
+}
+
+#[derive(Serialize)]
+pub struct CloudEntriesArg<'a> {
+    pub id: &'a str,
+    pub entries: &'a [CloudEntry],
+}
+
+#[derive(Serialize)]
+pub struct CloudUploadArg<'a> {
+    pub id: &'a str,
+    #[serde(with = "items_serde")]
+    pub items: &'a Vec<Rc<PitouFile>>,
+    pub dir: &'a str,
+}
+
+#[derive(Serialize)]
+pub struct CloudDownloadArg<'a> {
+    pub id: &'a str,
+    pub entries: &'a [CloudEntry],
+    #[serde(with = "rc_serde")]
+    pub pitou: Rc<PitouFile>,
+}
+
 #[derive(Serialize)]
 pub struct NoArg;
 
@@ -32,6 +204,13 @@ pub struct RenameArg {
     pub pitou: Rc<PitouFile>,
 }
 
+#[derive(Serialize)]
+pub struct MoveArg {
+    #[serde(with = "rc_serde")]
+    pub pitou: Rc<PitouFile>,
+    pub position: usize,
+}
+
 #[derive(Serialize)]
 pub struct ItemsArg<'a> {
     #[serde(with = "items_serde")]
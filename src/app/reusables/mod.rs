@@ -5,13 +5,15 @@ use yew::prelude::*;
 
 mod labels;
 mod panes;
+mod preview;
+mod properties;
 mod svg;
-mod wrappers;
 
 pub use labels::*;
 pub use panes::*;
+pub use preview::*;
+pub use properties::*;
 pub use svg::*;
-pub use wrappers::*;
 
 /// Material Icon component that uses Google Material Icons font
 #[derive(Properties, PartialEq)]
@@ -253,6 +255,17 @@ pub fn list_trash_item_type_icon(item: &Rc<PitouTrashItem>) -> Html {
     }
 }
 
+/// The material icon standing for a socket, a named pipe or a device.
+pub fn special_file_icon(kind: PitouFileKind) -> &'static str {
+    match kind {
+        PitouFileKind::Socket => "cable",
+        PitouFileKind::Fifo => "swap_horiz",
+        PitouFileKind::BlockDevice => "storage",
+        PitouFileKind::CharDevice => "terminal",
+        _ => "question_mark",
+    }
+}
+
 pub fn list_file_type_icon(file: &Rc<PitouFile>) -> Html {
     match file.kind() {
         Some(filetype) => match filetype {
@@ -260,9 +273,15 @@ pub fn list_file_type_icon(file: &Rc<PitouFile>) -> Html {
                 html! { <FolderIcon id="" class="folder-file-icon file-type-icon list-type-icon" /> }
             }
             PitouFileKind::File => match_extension_list(file.path().extension()),
+            PitouFileKind::Link if file.is_broken_link() => {
+                html! { <MatIcon name="link_off" class="broken-link-icon file-type-icon list-type-icon" /> }
+            }
             PitouFileKind::Link => {
                 html! { <LinkIcon id="" class="link-file-icon file-type-icon list-type-icon" /> }
             }
+            special => {
+                html! { <MatIcon name={special_file_icon(special)} class="special-file-icon file-type-icon list-type-icon" /> }
+            }
         },
         None => {
             html! { <UnknownFileIcon id="" class="unknown-file-icon file-type-icon list-type-icon"/> }
@@ -285,9 +304,15 @@ pub fn tile_file_type_icon(file: &Rc<PitouFile>) -> Html {
             }
             PitouFileKind::File => match_extension_tile(file.path().extension()),
 
+            PitouFileKind::Link if file.is_broken_link() => {
+                html! { <MatIcon name="link_off" class="broken-link-icon tile-type-icon" /> }
+            }
             PitouFileKind::Link => {
                 html! { <img src="./public/link.png" /> }
             }
+            special => {
+                html! { <MatIcon name={special_file_icon(special)} class="special-file-icon tile-type-icon" /> }
+            }
         },
         None => {
             html! { <img src="./public/unknown_file.png" /> }
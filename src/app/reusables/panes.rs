@@ -1,11 +1,15 @@
 use std::rc::Rc;
 
-use pitou_core::{frontend::*, *};
+use pitou_core::{
+    frontend::*,
+    thumbnails::{has_thumbnail, ThumbnailSize},
+    *,
+};
 use wasm_bindgen_futures::spawn_local;
-use web_sys::{HtmlElement, HtmlInputElement};
+use web_sys::{HtmlElement, HtmlInputElement, HtmlSelectElement};
 use yew::prelude::*;
 
-use crate::app::reusables::{list_file_type_icon, tile_file_type_icon};
+use crate::app::reusables::{list_file_type_icon, special_file_icon, tile_file_type_icon, MatIcon};
 
 #[derive(Properties, PartialEq)]
 pub struct FindPopProps {
@@ -70,94 +74,138 @@ pub struct ItemsSortPopProps {
     pub onexit: Callback<()>,
 }
 
+/// The names of the increasing and the decreasing order for `key`.
+fn sort_order_names(key: PitouFileSortKey) -> (&'static str, &'static str) {
+    match key {
+        PitouFileSortKey::DateCreated
+        | PitouFileSortKey::DateModified
+        | PitouFileSortKey::DateAccessed => ("Earliest First", "Latest First"),
+        PitouFileSortKey::Size => ("Smallest First", "Largest First"),
+        _ => ("A to Z", "Z to A"),
+    }
+}
+
+/// Every change is applied right away, the popup stays open until its free area is clicked.
 #[function_component]
 pub fn ItemsSortPop(props: &ItemsSortPopProps) -> Html {
-    let onclickfreearea = {
-        let onexit = props.onexit.clone();
-        move |_| onexit.emit(())
-    };
+    let sort = use_state(|| props.selected);
 
-    let onclicknone = {
+    let update = {
+        let sort = sort.clone();
         let onfinish = props.onfinish.clone();
-        move |m: MouseEvent| {
-            m.stop_propagation();
-            onfinish.emit(None)
+        move |new: Option<PitouFileSort>| {
+            sort.set(new);
+            onfinish.emit(new)
         }
     };
 
-    let onclickcreatedinc = {
-        let onfinish = props.onfinish.clone();
-        move |m: MouseEvent| {
-            m.stop_propagation();
-            onfinish.emit(Some(PitouFileSort::DateCreated(
-                PitouFileSortOrder::Increasing,
-            )))
-        }
+    let onclickfreearea = {
+        let onexit = props.onexit.clone();
+        move |_| onexit.emit(())
     };
 
-    let onclickcreateddec = {
-        let onfinish = props.onfinish.clone();
-        move |m: MouseEvent| {
-            m.stop_propagation();
-            onfinish.emit(Some(PitouFileSort::DateCreated(
-                PitouFileSortOrder::Decreasing,
-            )))
-        }
-    };
+    let stop = |m: MouseEvent| m.stop_propagation();
 
-    let onclickmodifiedinc = {
-        let onfinish = props.onfinish.clone();
+    let onclicknone = {
+        let update = update.clone();
         move |m: MouseEvent| {
             m.stop_propagation();
-            onfinish.emit(Some(PitouFileSort::DateModified(
-                PitouFileSortOrder::Increasing,
-            )))
+            update(None)
         }
     };
 
-    let onclickmodifieddec = {
-        let onfinish = props.onfinish.clone();
-        move |m: MouseEvent| {
-            m.stop_propagation();
-            onfinish.emit(Some(PitouFileSort::DateModified(
-                PitouFileSortOrder::Decreasing,
-            )))
-        }
-    };
+    let keys = PitouFileSortKey::ALL
+        .into_iter()
+        .map(|key| {
+            let onclick = {
+                let update = update.clone();
+                let current = *sort;
+                move |m: MouseEvent| {
+                    m.stop_propagation();
+                    let new = match current {
+                        Some(sort) => PitouFileSort { key, ..sort },
+                        None => PitouFileSort::new(key, PitouFileSortOrder::Increasing),
+                    };
+                    update(Some(new))
+                }
+            };
+            let class = classes!(
+                "sort-popup-item",
+                matches!(*sort, Some(s) if s.key == key).then_some("selected")
+            );
+            html! {
+                <li {class} {onclick}>
+                    <label>{ key.name() }</label>
+                </li>
+            }
+        })
+        .collect::<Html>();
 
-    let onclickaccessedinc = {
-        let onfinish = props.onfinish.clone();
-        move |m: MouseEvent| {
-            m.stop_propagation();
-            onfinish.emit(Some(PitouFileSort::DateAccessed(
-                PitouFileSortOrder::Increasing,
-            )))
-        }
+    let Some(current) = *sort else {
+        return html! {
+            <ul class="sort-popup" onclick={onclickfreearea}>
+                <li class="sort-popup-item selected" onclick={onclicknone}>
+                    <label>{ "None" }</label>
+                </li>
+                { keys }
+            </ul>
+        };
     };
 
-    let onclickaccesseddec = {
-        let onfinish = props.onfinish.clone();
-        move |m: MouseEvent| {
-            m.stop_propagation();
-            onfinish.emit(Some(PitouFileSort::DateAccessed(
-                PitouFileSortOrder::Decreasing,
-            )))
-        }
+    let order = |order: PitouFileSortOrder, name: &'static str| {
+        let onclick = {
+            let update = update.clone();
+            move |m: MouseEvent| {
+                m.stop_propagation();
+                update(Some(PitouFileSort { order, ..current }))
+            }
+        };
+        let class = classes!(
+            "sort-popup-order",
+            (current.order == order).then_some("selected")
+        );
+        html! { <button {class} {onclick}>{ name }</button> }
     };
+    let (increasing, decreasing) = sort_order_names(current.key);
 
-    let onclicknameinc = {
-        let onfinish = props.onfinish.clone();
-        move |m: MouseEvent| {
-            m.stop_propagation();
-            onfinish.emit(Some(PitouFileSort::Name(PitouFileSortOrder::Increasing)))
+    let onchangethen = {
+        let update = update.clone();
+        move |e: Event| {
+            let idx = e
+                .target_dyn_into::<HtmlSelectElement>()
+                .unwrap()
+                .selected_index();
+            // the first option is "None"
+            let then = usize::try_from(idx - 1)
+                .ok()
+                .and_then(|idx| PitouFileSortKey::ALL.get(idx).copied());
+            update(Some(PitouFileSort { then, ..current }))
         }
     };
+    let then_options = PitouFileSortKey::ALL
+        .into_iter()
+        .map(|key| html! { <option selected={current.then == Some(key)}>{ key.name() }</option> })
+        .collect::<Html>();
 
-    let onclicknamedec = {
-        let onfinish = props.onfinish.clone();
-        move |m: MouseEvent| {
-            m.stop_propagation();
-            onfinish.emit(Some(PitouFileSort::Name(PitouFileSortOrder::Decreasing)))
+    let toggle = |label: &'static str, checked: bool, set: fn(&mut PitouFileSort, bool)| {
+        let onchange = {
+            let update = update.clone();
+            move |e: Event| {
+                let mut new = current;
+                set(
+                    &mut new,
+                    e.target_dyn_into::<HtmlInputElement>().unwrap().checked(),
+                );
+                update(Some(new))
+            }
+        };
+        html! {
+            <li class="sort-popup-option" onclick={stop}>
+                <label>
+                    <input type="checkbox" {checked} {onchange} />
+                    { label }
+                </label>
+            </li>
         }
     };
 
@@ -166,50 +214,24 @@ pub fn ItemsSortPop(props: &ItemsSortPopProps) -> Html {
             <li class="sort-popup-item" onclick={onclicknone}>
                 <label>{ "None" }</label>
             </li>
-            <li class="sort-popup-item" onclick={onclickcreateddec.clone()}>
-                <label>{ "Date Created" }</label>
-                <ul class="sort-popup-sub">
-                    <li class="sort-popup-sub-item" onclick={onclickcreatedinc}>
-                        <label>{ "Earliest First" }</label>
-                    </li>
-                    <li class="sort-popup-sub-item" onclick={onclickcreateddec}>
-                        <label>{ "Latest First" }</label>
-                    </li>
-                </ul>
-            </li>
-            <li class="sort-popup-item" onclick={onclickmodifieddec.clone()}>
-                <label>{ "Date Modified" }</label>
-                <ul class="sort-popup-sub">
-                    <li class="sort-popup-sub-item" onclick={onclickmodifiedinc}>
-                        <label>{ "Earliest First" }</label>
-                    </li>
-                    <li class="sort-popup-sub-item" onclick={onclickmodifieddec}>
-                        <label>{ "Latest First" }</label>
-                    </li>
-                </ul>
+            { keys }
+            <li class="sort-popup-separator"></li>
+            <li class="sort-popup-orders" onclick={stop}>
+                { order(PitouFileSortOrder::Increasing, increasing) }
+                { order(PitouFileSortOrder::Decreasing, decreasing) }
             </li>
-            <li class="sort-popup-item" onclick={onclickaccesseddec.clone()}>
-                <label>{ "Date Accessed" }</label>
-                <ul class="sort-popup-sub">
-                    <li class="sort-popup-sub-item" onclick={onclickaccessedinc}>
-                        <label>{ "Earliest First" }</label>
-                    </li>
-                    <li class="sort-popup-sub-item" onclick={onclickaccesseddec}>
-                        <label>{ "Latest First" }</label>
-                    </li>
-                </ul>
-            </li>
-            <li class="sort-popup-item" onclick={onclicknameinc.clone()}>
-                <label>{ "Name" }</label>
-                <ul class="sort-popup-sub">
-                    <li class="sort-popup-sub-item" onclick={onclicknameinc}>
-                        <label>{ "Increasing" }</label>
-                    </li>
-                    <li class="sort-popup-sub-item" onclick={onclicknamedec}>
-                        <label>{ "Decreasing" }</label>
-                    </li>
-                </ul>
+            <li class="sort-popup-option" onclick={stop}>
+                <label>
+                    { "Then by" }
+                    <select onchange={onchangethen}>
+                        <option selected={current.then.is_none()}>{ "None" }</option>
+                        { then_options }
+                    </select>
+                </label>
             </li>
+            { toggle("Folders first", current.folders_first, |s, v| s.folders_first = v) }
+            { toggle("Natural order", current.natural, |s, v| s.natural = v) }
+            { toggle("Case sensitive", current.case_sensitive, |s, v| s.case_sensitive = v) }
         </ul>
     }
 }
@@ -382,6 +404,7 @@ pub struct AncestorProps {
 
 #[function_component]
 pub fn Ancestor(props: &AncestorProps) -> Html {
+    let ctx = use_context::<ApplicationContext>().unwrap();
     let children_dirs = use_state(|| None);
 
     let onclick = {
@@ -399,9 +422,10 @@ pub fn Ancestor(props: &AncestorProps) -> Html {
             let children_dirs = children_dirs.clone();
             e.stop_propagation();
             if children_dirs.is_some() { return children_dirs.set(None) }
+            let hidden = !ctx.hide_hidden_files();
             spawn_local(async move {
                 children_dirs.set(
-                    crate::app::cmds::children_dirs(&item)
+                    crate::app::cmds::children_dirs(&item, hidden)
                         .await
                         .map(|v| v.children)
                         .ok(),
@@ -567,7 +591,7 @@ pub fn ListItem(props: &ItemProps) -> Html {
         }
     );
 
-    let name = if ctx.hide_system_files() {
+    let name = if ctx.show_extensions() {
         props.item.name()
     } else {
         props.item.name_without_extension()
@@ -577,21 +601,24 @@ pub fn ListItem(props: &ItemProps) -> Html {
         .item
         .metadata
         .as_ref()
-        .map(|v| v.accessed.datetime.format("%Y-%m-%d %H:%M").to_string())
+        .and_then(|v| v.accessed)
+        .map(|t| t.datetime.format("%Y-%m-%d %H:%M").to_string())
         .unwrap_or_default();
 
     let modified = props
         .item
         .metadata
         .as_ref()
-        .map(|v| v.modified.datetime.format("%Y-%m-%d %H:%M").to_string())
+        .and_then(|v| v.modified)
+        .map(|t| t.datetime.format("%Y-%m-%d %H:%M").to_string())
         .unwrap_or_default();
 
     let created = props
         .item
         .metadata
         .as_ref()
-        .map(|v| v.created.datetime.format("%Y-%m-%d %H:%M").to_string())
+        .and_then(|v| v.created)
+        .map(|t| t.datetime.format("%Y-%m-%d %H:%M").to_string())
         .unwrap_or_default();
 
     let file_type_icon = list_file_type_icon(&props.item);
@@ -703,12 +730,12 @@ fn TileItem(props: &ItemProps) -> Html {
         }
     });
 
-    let optional = props.item.metadata.as_ref().map(|v| {
-        v.modified
-            .datetime
-            .format("Modified on %Y-%m-%d %H:%M")
-            .to_string()
-    });
+    let optional = props
+        .item
+        .metadata
+        .as_ref()
+        .and_then(|v| v.modified)
+        .map(|t| t.datetime.format("Modified on %Y-%m-%d %H:%M").to_string());
 
     let name = if ctx.show_extensions() {
         props.item.name()
@@ -731,6 +758,11 @@ fn TileItem(props: &ItemProps) -> Html {
     };
 
     let file_type_icon = tile_file_type_icon(&props.item);
+    let file_type_icon = if ctx.show_thumbnails() && has_thumbnail(props.item.path().extension()) {
+        html! { <GridThumbnail item={props.item.clone()} size={ThumbnailSize::Normal} fallback={file_type_icon} /> }
+    } else {
+        file_type_icon
+    };
 
     html! {
         <div class={tile_item_class} {ondblclick} {onclick}>
@@ -762,46 +794,53 @@ struct GridFileTypeIconProps {
 #[function_component]
 fn GridFileTypeIcon(props: &GridFileTypeIconProps) -> Html {
     let ctx = use_context::<ApplicationContext>().unwrap();
-    let cnt = if ctx.show_thumbnails() {
-        html! { <GridThumbnail item={props.item.clone()} /> }
-    } else {
-        if let Some(m) = props.item.metadata() {
-            match m.kind() {
-                PitouFileKind::Directory => {
-                    if m.size.bytes == 0 {
-                        html! {
-                            <svg viewBox="0 0 491.52 491.52">
-                                <path fill="#FFB42D" d="M445.522,88.989h-259.23c-5.832,0-11.24-3.318-14.26-8.749l-13.88-24.957
-                                    c-3.021-5.432-8.427-8.749-14.259-8.749H45.998c-9.208,0-16.671,8.126-16.671,18.15v362.151c0,10.024,7.463,18.15,16.671,18.15
-                                    h399.523c9.207,0,16.671-8.126,16.671-18.15V107.14C462.192,97.116,454.728,88.989,445.522,88.989z"/>
-                                <path fill="#FFD264" d="M474.806,216.429H16.714c-10.557,0-17.956,8.348-16.541,18.538l27.158,195.639
-                                    c1.107,7.974,9.46,14.379,18.667,14.379h399.523c9.207,0,17.56-6.405,18.667-14.379l27.158-195.639
-                                    C492.761,224.777,485.362,216.429,474.806,216.429z"/>
-                            </svg>
-                        }
-                    } else {
-                        html! {
-                            <svg viewBox="0 0 491.52 491.52">
-                                <path fill="#FFB42D" d="M445.522,88.989h-259.23c-5.832,0-11.24-3.318-14.26-8.749l-13.88-24.957
-                                    c-3.021-5.432-8.427-8.749-14.259-8.749H45.998c-9.208,0-16.671,8.126-16.671,18.15v362.151c0,10.024,7.463,18.15,16.671,18.15
-                                    h399.523c9.207,0,16.671-8.126,16.671-18.15V107.14C462.192,97.116,454.728,88.989,445.522,88.989z"/>
-                                <rect x="55.383" y="133.12" style="fill:#EBF0F3;" width="385.536" height="122.092"/>
-                                <rect x="55.383" y="150.17" style="fill:#FFFFFF;" width="385.536" height="122.092"/>
-                                <path fill="#FFD264" d="M474.806,216.429H16.714c-10.557,0-17.956,8.348-16.541,18.538l27.158,195.639
-                                    c1.107,7.974,9.46,14.379,18.667,14.379h399.523c9.207,0,17.56-6.405,18.667-14.379l27.158-195.639
-                                    C492.761,224.777,485.362,216.429,474.806,216.429z"/>
-                            </svg>
-                        }
+    let icon = if let Some(m) = props.item.metadata() {
+        match m.kind() {
+            PitouFileKind::Directory => {
+                if m.size.bytes == 0 {
+                    html! {
+                        <svg viewBox="0 0 491.52 491.52">
+                            <path fill="#FFB42D" d="M445.522,88.989h-259.23c-5.832,0-11.24-3.318-14.26-8.749l-13.88-24.957
+                                c-3.021-5.432-8.427-8.749-14.259-8.749H45.998c-9.208,0-16.671,8.126-16.671,18.15v362.151c0,10.024,7.463,18.15,16.671,18.15
+                                h399.523c9.207,0,16.671-8.126,16.671-18.15V107.14C462.192,97.116,454.728,88.989,445.522,88.989z"/>
+                            <path fill="#FFD264" d="M474.806,216.429H16.714c-10.557,0-17.956,8.348-16.541,18.538l27.158,195.639
+                                c1.107,7.974,9.46,14.379,18.667,14.379h399.523c9.207,0,17.56-6.405,18.667-14.379l27.158-195.639
+                                C492.761,224.777,485.362,216.429,474.806,216.429z"/>
+                        </svg>
+                    }
+                } else {
+                    html! {
+                        <svg viewBox="0 0 491.52 491.52">
+                            <path fill="#FFB42D" d="M445.522,88.989h-259.23c-5.832,0-11.24-3.318-14.26-8.749l-13.88-24.957
+                                c-3.021-5.432-8.427-8.749-14.259-8.749H45.998c-9.208,0-16.671,8.126-16.671,18.15v362.151c0,10.024,7.463,18.15,16.671,18.15
+                                h399.523c9.207,0,16.671-8.126,16.671-18.15V107.14C462.192,97.116,454.728,88.989,445.522,88.989z"/>
+                            <rect x="55.383" y="133.12" style="fill:#EBF0F3;" width="385.536" height="122.092"/>
+                            <rect x="55.383" y="150.17" style="fill:#FFFFFF;" width="385.536" height="122.092"/>
+                            <path fill="#FFD264" d="M474.806,216.429H16.714c-10.557,0-17.956,8.348-16.541,18.538l27.158,195.639
+                                c1.107,7.974,9.46,14.379,18.667,14.379h399.523c9.207,0,17.56-6.405,18.667-14.379l27.158-195.639
+                                C492.761,224.777,485.362,216.429,474.806,216.429z"/>
+                        </svg>
                     }
                 }
-                PitouFileKind::File => {
-                    crate::app::reusables::match_extension_grid(&props.item.path().extension())
-                }
-                PitouFileKind::Link => html! { <img src="./public/file3.png"/> },
             }
-        } else {
-            html! { <img src="./public/unknown_file.png"/> }
+            PitouFileKind::File => {
+                crate::app::reusables::match_extension_grid(&props.item.path().extension())
+            }
+            PitouFileKind::Link if props.item.is_broken_link() => {
+                html! { <MatIcon name="link_off" class="broken-link-icon grid-type-icon" /> }
+            }
+            PitouFileKind::Link => html! { <img src="./public/link.png"/> },
+            special => html! {
+                <MatIcon name={special_file_icon(special)} class="special-file-icon grid-type-icon" />
+            },
         }
+    } else {
+        html! { <img src="./public/unknown_file.png"/> }
+    };
+    let cnt = if ctx.show_thumbnails() && has_thumbnail(props.item.path().extension()) {
+        html! { <GridThumbnail item={props.item.clone()} size={ThumbnailSize::Large} fallback={icon} /> }
+    } else {
+        icon
     };
     html! {
         <div class="grid-filetypeicon-container">
@@ -810,9 +849,50 @@ fn GridFileTypeIcon(props: &GridFileTypeIconProps) -> Html {
     }
 }
 
+#[derive(PartialEq, Properties)]
+struct GridThumbnailProps {
+    item: Rc<PitouFile>,
+    size: ThumbnailSize,
+    /// shown until the thumbnail arrives, and instead of it if the image has none
+    fallback: Html,
+}
+
 #[function_component]
-fn GridThumbnail(_props: &GridFileTypeIconProps) -> Html {
-    html! {}
+fn GridThumbnail(props: &GridThumbnailProps) -> Html {
+    let ctx = use_context::<ApplicationContext>().unwrap();
+    let src = use_state(|| {
+        ctx.static_data
+            .cached_thumbnail(&props.item, props.size)
+            .flatten()
+    });
+
+    {
+        let item = props.item.clone();
+        let size = props.size;
+        let src = src.clone();
+        let modified = item
+            .metadata()
+            .as_ref()
+            .and_then(|m| m.modified)
+            .map(|t| t.datetime);
+        use_effect_with((item.path().path.clone(), modified), move |_| {
+            match ctx.static_data.cached_thumbnail(&item, size) {
+                Some(cached) => src.set(cached),
+                None => spawn_local(async move {
+                    let fetched = crate::app::cmds::thumbnail(item.path(), size)
+                        .await
+                        .ok()
+                        .flatten();
+                    src.set(ctx.static_data.cache_thumbnail(&item, size, fetched));
+                }),
+            }
+        })
+    }
+
+    match &*src {
+        Some(src) => html! { <img class="thumbnail" src={AttrValue::from(src.clone())} /> },
+        None => props.fallback.clone(),
+    }
 }
 
 #[function_component]
@@ -0,0 +1,264 @@
+use std::rc::Rc;
+
+use pitou_core::{
+    frontend::ApplicationContext,
+    preview::{hex_rows, Preview, MAX_TEXT_LEN},
+    PitouFile, PitouFileSize,
+};
+use wasm_bindgen_futures::spawn_local;
+use web_sys::{HtmlInputElement, HtmlSelectElement};
+use yew::prelude::*;
+use yew_hooks::{use_event_with_window, use_interval};
+
+use super::MatIcon;
+
+#[derive(Properties)]
+pub struct FilePreviewProps {
+    pub item: Rc<PitouFile>,
+}
+
+impl PartialEq for FilePreviewProps {
+    fn eq(&self, other: &Self) -> bool {
+        self.item.path == other.item.path
+    }
+}
+
+#[function_component]
+pub fn FilePreview(props: &FilePreviewProps) -> Html {
+    let ctx = use_context::<ApplicationContext>().unwrap();
+    let preview = use_state(|| None::<Rc<Preview>>);
+    let requested = use_mut_ref(|| None);
+    let dark = ctx.color_theme().is_dark();
+
+    {
+        let preview = preview.clone();
+        let item = props.item.clone();
+        use_effect_with((item.path.path.clone(), dark), move |(path, dark)| {
+            let dark = *dark;
+            *requested.borrow_mut() = Some(path.clone());
+            preview.set(None);
+            spawn_local(async move {
+                let res = crate::app::cmds::preview(&item.path, dark).await;
+                // a slower preview of an item that is no longer shown must not replace the current one
+                if requested.borrow().as_ref() != Some(&item.path.path) {
+                    return;
+                }
+                let res = res.unwrap_or_else(|_| Preview::Unavailable("Preview failed".into()));
+                preview.set(Some(Rc::new(res)));
+            })
+        })
+    }
+
+    let body = match preview.as_deref() {
+        None => html! { <div class="preview-message">{ "Loading preview..." }</div> },
+        Some(preview) => preview_body(preview),
+    };
+
+    let size = props.item.metadata.as_ref().map(|m| {
+        if m.is_dir() {
+            String::new()
+        } else {
+            m.size.format()
+        }
+    });
+
+    html! {
+        <div class="file-preview">
+            <div class="file-preview-header">
+                <span class="file-preview-name" title={props.item.full_path_str().to_owned()}>{ props.item.name() }</span>
+                <span class="file-preview-size">{ size }</span>
+            </div>
+            <div class="file-preview-body">
+                { body }
+            </div>
+        </div>
+    }
+}
+
+fn truncation_note(truncated: bool) -> Html {
+    if truncated {
+        let limit = PitouFileSize::new(MAX_TEXT_LEN).format();
+        html! { <div class="preview-note">{ format!("Showing the first {limit}") }</div> }
+    } else {
+        html! {}
+    }
+}
+
+fn preview_body(preview: &Preview) -> Html {
+    match preview {
+        Preview::Code {
+            language,
+            html,
+            truncated,
+        } => html! {
+            <>
+                <div class="preview-note">{ language }</div>
+                <div class="preview-code">{ Html::from_html_unchecked(AttrValue::from(html.clone())) }</div>
+                { truncation_note(*truncated) }
+            </>
+        },
+        Preview::Markdown { html, truncated } => html! {
+            <>
+                <div class="preview-markdown">{ Html::from_html_unchecked(AttrValue::from(html.clone())) }</div>
+                { truncation_note(*truncated) }
+            </>
+        },
+        Preview::Image { src, width, height } => {
+            let dimensions = (*width > 0).then(|| format!("{width} × {height}"));
+            html! {
+                <>
+                    <div class="preview-image">
+                        <img src={src.clone()} />
+                    </div>
+                    <div class="preview-note">{ dimensions }</div>
+                </>
+            }
+        }
+        Preview::Directory {
+            dirs,
+            files,
+            size,
+            entries,
+        } => {
+            let summary = format!(
+                "{dirs} folders, {files} files ({})",
+                PitouFileSize::new(*size).format()
+            );
+            let entries = entries
+                .iter()
+                .map(|entry| {
+                    let icon = if entry.is_dir {
+                        "folder"
+                    } else {
+                        "description"
+                    };
+                    html! {
+                        <div class="preview-entry">
+                            <MatIcon name={icon} class="preview-entry-icon" />
+                            <span>{ &entry.name }</span>
+                        </div>
+                    }
+                })
+                .collect::<Html>();
+            html! {
+                <>
+                    <div class="preview-note">{ summary }</div>
+                    <div class="preview-entries">{ entries }</div>
+                </>
+            }
+        }
+        Preview::Hex { bytes, len } => {
+            let rows = hex_rows(bytes)
+                .map(|row| {
+                    html! {
+                        <div class="preview-hex-row">
+                            <span class="preview-hex-offset">{ format!("{:08x}", row.offset) }</span>
+                            <span>{ row.hex }</span>
+                            <span class="preview-hex-ascii">{ row.ascii }</span>
+                        </div>
+                    }
+                })
+                .collect::<Html>();
+            let note = format!(
+                "Binary file, showing {} of {}",
+                PitouFileSize::new(bytes.len() as u64).format(),
+                PitouFileSize::new(*len).format()
+            );
+            html! {
+                <>
+                    <div class="preview-note">{ note }</div>
+                    <div class="preview-hex">{ rows }</div>
+                </>
+            }
+        }
+        Preview::Empty => html! { <div class="preview-message">{ "This file is empty" }</div> },
+        Preview::Unavailable(reason) => {
+            html! { <div class="preview-message">{ reason }</div> }
+        }
+    }
+}
+
+/// Follows the selection, previewing the first selected item.
+#[function_component]
+pub fn PreviewPane() -> Html {
+    let ctx = use_context::<ApplicationContext>().unwrap();
+    let item = use_state(|| ctx.static_data.openable_selection());
+    {
+        let item = item.clone();
+        use_interval(
+            move || {
+                let selected = ctx.static_data.openable_selection();
+                let changed = match (&*item, &selected) {
+                    (Some(a), Some(b)) => a.path != b.path,
+                    (None, None) => false,
+                    _ => true,
+                };
+                if changed {
+                    item.set(selected);
+                }
+            },
+            250,
+        )
+    }
+
+    let content = match &*item {
+        Some(item) => html! { <FilePreview item={item.clone()} /> },
+        None => html! { <div class="preview-message">{ "Select a file to preview it" }</div> },
+    };
+
+    html! {
+        <div id="preview-pane">
+            { content }
+        </div>
+    }
+}
+
+/// Previews the selected item over everything else while the spacebar is toggled,
+/// escape also closes it. Keys typed into text fields are left alone.
+#[function_component]
+pub fn QuickLook() -> Html {
+    let ctx = use_context::<ApplicationContext>().unwrap();
+    let item = use_state(|| None::<Rc<PitouFile>>);
+
+    {
+        let item = item.clone();
+        use_event_with_window("keydown", move |e: KeyboardEvent| {
+            let typing = e.target_dyn_into::<HtmlInputElement>().is_some()
+                || e.target_dyn_into::<HtmlSelectElement>().is_some();
+            if typing {
+                return;
+            }
+            match e.key().as_str() {
+                " " => {
+                    e.prevent_default();
+                    if item.is_some() {
+                        item.set(None)
+                    } else {
+                        item.set(ctx.static_data.openable_selection())
+                    }
+                }
+                "Escape" if item.is_some() => item.set(None),
+                _ => (),
+            }
+        });
+    }
+
+    let Some(current) = &*item else {
+        return html! {};
+    };
+
+    let onclose = {
+        let item = item.clone();
+        move |_| item.set(None)
+    };
+
+    let onclickcontent = |e: MouseEvent| e.stop_propagation();
+
+    html! {
+        <div id="quick-look" onclick={onclose}>
+            <div id="quick-look-content" onclick={onclickcontent}>
+                <FilePreview item={current.clone()} />
+            </div>
+        </div>
+    }
+}
@@ -0,0 +1,583 @@
+use std::{collections::HashMap, rc::Rc};
+
+use pitou_core::{
+    msg::{ItemTotals, TotalsMsg},
+    properties::{
+        datetime_input, mode_string, parse_datetime_input, AttributeChanges, ChangeError,
+        ChecksumKind, ItemProperties, ModeChange,
+    },
+    PitouDateTime, PitouFile, PitouFileKind, PitouFilePath, PitouFileSize,
+};
+use wasm_bindgen_futures::spawn_local;
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+use yew_hooks::use_interval;
+
+use super::MatIcon;
+
+#[derive(Properties)]
+pub struct PropertiesPopProps {
+    pub items: Rc<Vec<Rc<PitouFile>>>,
+    pub onclose: Callback<()>,
+}
+
+impl PartialEq for PropertiesPopProps {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.items, &other.items) && self.onclose == other.onclose
+    }
+}
+
+/// Shows the properties of the selected items. The recursive totals are counted in the background
+/// while the dialog is open and update as they come in.
+#[function_component]
+pub fn PropertiesPop(props: &PropertiesPopProps) -> Html {
+    let properties = use_state(|| None::<Rc<Vec<ItemProperties>>>);
+    let counting = use_state_eq(|| false);
+    let totals = use_state(|| None::<TotalsMsg>);
+    // bumped after every edit so the properties are read again
+    let generation = use_state_eq(|| 0u32);
+    let errors = use_state(|| None::<Rc<Vec<ChangeError>>>);
+
+    {
+        let counting = counting.clone();
+        let items = props.items.clone();
+        use_effect_with((), move |()| {
+            spawn_local(async move {
+                // polling only starts once the count has begun, or it would read the previous one
+                if crate::app::cmds::count_totals(&items).await.is_ok() {
+                    counting.set(true);
+                }
+            });
+            || {
+                spawn_local(async move {
+                    crate::app::cmds::terminate_totals().await.ok();
+                })
+            }
+        })
+    }
+
+    {
+        let properties = properties.clone();
+        let items = props.items.clone();
+        use_effect_with(*generation, move |_| {
+            spawn_local(async move {
+                if let Ok(res) = crate::app::cmds::properties(&items).await {
+                    properties.set(Some(Rc::new(res)));
+                }
+            });
+        })
+    }
+
+    {
+        let totals = totals.clone();
+        let done = matches!(*totals, Some(TotalsMsg::Terminated(_)));
+        use_interval(
+            move || {
+                let totals = totals.clone();
+                spawn_local(async move {
+                    if let Ok(msg) = crate::app::cmds::totals_msg().await {
+                        totals.set(Some(msg));
+                    }
+                })
+            },
+            if *counting && !done { 250 } else { 0 },
+        )
+    }
+
+    let onclose = {
+        let onclose = props.onclose.clone();
+        move |_| onclose.emit(())
+    };
+
+    let onclickcontent = |e: MouseEvent| e.stop_propagation();
+
+    let (counted, done) = match *totals {
+        Some(TotalsMsg::Active(totals)) => (totals, false),
+        Some(TotalsMsg::Terminated(totals)) => (totals, true),
+        None => (ItemTotals::default(), false),
+    };
+
+    let content = match properties.as_deref().map(Vec::as_slice) {
+        None => html! { <div class="properties-message">{ "Reading properties..." }</div> },
+        Some([single]) => single_properties(single, counted, done),
+        Some(many) => multiple_properties(many, counted, done),
+    };
+
+    let editor = properties.as_ref().map(|properties| {
+        let onapplied = {
+            let generation = generation.clone();
+            let errors = errors.clone();
+            move |res: Rc<Vec<ChangeError>>| {
+                errors.set((!res.is_empty()).then_some(res));
+                generation.set(*generation + 1)
+            }
+        };
+        let errors = (*errors).clone();
+        html! {
+            <AttributesEditor key={*generation} items={props.items.clone()} properties={properties.clone()} {errors} {onapplied} />
+        }
+    });
+
+    html! {
+        <div id="properties-pop" onclick={onclose.clone()}>
+            <div id="properties-pop-content" onclick={onclickcontent}>
+                <div class="properties-header">
+                    <span>{ "Properties" }</span>
+                    <div class="properties-close" onclick={onclose}>
+                        <MatIcon name="close" />
+                    </div>
+                </div>
+                <div class="properties-body">
+                    { content }
+                    { editor }
+                </div>
+            </div>
+        </div>
+    }
+}
+
+fn row(label: &'static str, value: impl Into<Html>) -> Html {
+    html! {
+        <div class="properties-row">
+            <span class="properties-label">{ label }</span>
+            <span class="properties-value">{ value.into() }</span>
+        </div>
+    }
+}
+
+fn format_time(time: Option<PitouDateTime>) -> String {
+    time.map(|t| t.datetime.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_else(|| String::from("Unknown"))
+}
+
+fn format_totals(counted: ItemTotals, done: bool) -> (String, String) {
+    let suffix = if done { "" } else { " (counting...)" };
+    let size = format!(
+        "{} ({} bytes){suffix}",
+        PitouFileSize::new(counted.bytes).format(),
+        counted.bytes
+    );
+    let mut contains = format!("{} files, {} folders", counted.files, counted.dirs);
+    if counted.unreadable > 0 {
+        contains.push_str(&format!(", {} unreadable", counted.unreadable));
+    }
+    (size, contains)
+}
+
+fn parent_of(path: &PitouFilePath) -> String {
+    path.path
+        .parent()
+        .map(|p| p.display().to_string())
+        .unwrap_or_default()
+}
+
+fn single_properties(item: &ItemProperties, counted: ItemTotals, done: bool) -> Html {
+    let Some(kind) = item.kind else {
+        return html! {
+            <div class="properties-message">{ format!("{} can't be read", item.path.path.display()) }</div>
+        };
+    };
+
+    let (size, contains) = format_totals(counted, done);
+    let size = if kind == PitouFileKind::Directory {
+        row("Size", size)
+    } else {
+        row(
+            "Size",
+            format!(
+                "{} ({} bytes)",
+                PitouFileSize::new(item.size).format(),
+                item.size
+            ),
+        )
+    };
+    let contains = (kind == PitouFileKind::Directory).then(|| row("Contains", contains));
+    let link_target = item
+        .link_target
+        .as_ref()
+        .map(|target| row("Link target", target.path.display().to_string()));
+    let permissions = item.mode.map(|mode| {
+        row(
+            "Permissions",
+            format!("{} ({:04o})", mode_string(mode), mode & 0o7777),
+        )
+    });
+    let owner = item
+        .owner
+        .as_ref()
+        .map(|owner| row("Owner", owner.display()));
+    let group = item
+        .group
+        .as_ref()
+        .map(|group| row("Group", group.display()));
+    let inode = item.inode.map(|inode| row("Inode", inode.to_string()));
+    let links = item.links.map(|links| row("Hard links", links.to_string()));
+    let checksums = (kind == PitouFileKind::File).then(|| {
+        let path = Rc::new(PitouFilePath::from_pathbuf(item.path.path.clone()));
+        html! { <Checksums {path} /> }
+    });
+
+    html! {
+        <>
+            { row("Name", item.path.name().to_owned()) }
+            { row("Kind", kind.description()) }
+            { row("Type", item.mime.clone()) }
+            { row("Location", parent_of(&item.path)) }
+            { size }
+            { contains }
+            { link_target }
+            <div class="properties-separator"></div>
+            { row("Modified", format_time(item.modified)) }
+            { row("Accessed", format_time(item.accessed)) }
+            { row("Created", format_time(item.created)) }
+            <div class="properties-separator"></div>
+            { permissions }
+            { owner }
+            { group }
+            { row("Read only", if item.readonly { "Yes" } else { "No" }) }
+            { inode }
+            { links }
+            { checksums }
+        </>
+    }
+}
+
+fn multiple_properties(items: &[ItemProperties], counted: ItemTotals, done: bool) -> Html {
+    let dirs = items
+        .iter()
+        .filter(|item| item.kind == Some(PitouFileKind::Directory))
+        .count();
+    let unreadable = items.iter().filter(|item| item.kind.is_none()).count();
+    let others = items.len() - dirs - unreadable;
+    let mut selection = format!("{} items: {dirs} folders, {others} others", items.len());
+    if unreadable > 0 {
+        selection.push_str(&format!(", {unreadable} unreadable"));
+    }
+
+    let first_parent = items.first().map(|item| parent_of(&item.path));
+    let location = if items
+        .iter()
+        .all(|item| Some(parent_of(&item.path)) == first_parent)
+    {
+        first_parent.unwrap_or_default()
+    } else {
+        String::from("Several folders")
+    };
+
+    let mut mimes = HashMap::<&str, usize>::new();
+    for item in items.iter().filter(|item| item.kind.is_some()) {
+        *mimes.entry(&item.mime).or_default() += 1;
+    }
+    let mut mimes = mimes.into_iter().collect::<Vec<_>>();
+    mimes.sort_unstable_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+    let types = mimes
+        .into_iter()
+        .map(|(mime, count)| format!("{mime} ({count})"))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let (size, contains) = format_totals(counted, done);
+
+    html! {
+        <>
+            { row("Selection", selection) }
+            { row("Types", types) }
+            { row("Location", location) }
+            { row("Total size", size) }
+            { row("In total", contains) }
+        </>
+    }
+}
+
+enum Checksum {
+    Computing,
+    Done(Option<String>),
+}
+
+#[derive(Properties, PartialEq)]
+struct ChecksumsProps {
+    path: Rc<PitouFilePath>,
+}
+
+/// Checksums are only computed on request since hashing a large file takes a while.
+#[function_component]
+fn Checksums(props: &ChecksumsProps) -> Html {
+    let sums = use_mut_ref(HashMap::<ChecksumKind, Checksum>::new);
+    let refresher = use_force_update();
+
+    let entries = ChecksumKind::ALL
+        .into_iter()
+        .map(|kind| {
+            let value = match sums.borrow().get(&kind) {
+                Some(Checksum::Done(Some(sum))) => {
+                    html! { <span class="properties-checksum">{ sum.clone() }</span> }
+                }
+                Some(Checksum::Done(None)) => html! { <span>{ "Failed" }</span> },
+                Some(Checksum::Computing) => html! { <span>{ "Computing..." }</span> },
+                None => {
+                    let onclick = {
+                        let sums = sums.clone();
+                        let refresher = refresher.clone();
+                        let path = props.path.clone();
+                        move |_| {
+                            sums.borrow_mut().insert(kind, Checksum::Computing);
+                            refresher.force_update();
+                            let sums = sums.clone();
+                            let refresher = refresher.clone();
+                            let path = path.clone();
+                            spawn_local(async move {
+                                let sum =
+                                    crate::app::cmds::checksum(&path, kind).await.ok().flatten();
+                                sums.borrow_mut().insert(kind, Checksum::Done(sum));
+                                refresher.force_update();
+                            })
+                        }
+                    };
+                    html! { <button class="properties-button" {onclick}>{ "Compute" }</button> }
+                }
+            };
+            row(kind.name(), value)
+        })
+        .collect::<Html>();
+
+    html! {
+        <>
+            <div class="properties-separator"></div>
+            { entries }
+        </>
+    }
+}
+
+#[derive(Properties)]
+struct AttributesEditorProps {
+    items: Rc<Vec<Rc<PitouFile>>>,
+    properties: Rc<Vec<ItemProperties>>,
+    /// the items the last changes failed on
+    errors: Option<Rc<Vec<ChangeError>>>,
+    onapplied: Callback<Rc<Vec<ChangeError>>>,
+}
+
+impl PartialEq for AttributesEditorProps {
+    fn eq(&self, other: &Self) -> bool {
+        let same_errors = match (&self.errors, &other.errors) {
+            (Some(a), Some(b)) => Rc::ptr_eq(a, b),
+            (a, b) => a.is_none() && b.is_none(),
+        };
+        Rc::ptr_eq(&self.items, &other.items)
+            && Rc::ptr_eq(&self.properties, &other.properties)
+            && same_errors
+    }
+}
+
+/// (label, bit) of the permission checkboxes, a row per class of users.
+const PERMISSIONS: [(&str, [u32; 3]); 3] = [
+    ("Owner", [0o400, 0o200, 0o100]),
+    ("Group", [0o040, 0o020, 0o010]),
+    ("Others", [0o004, 0o002, 0o001]),
+];
+
+const SPECIAL_BITS: [(&str, u32); 3] = [
+    ("Set user ID", 0o4000),
+    ("Set group ID", 0o2000),
+    ("Sticky", 0o1000),
+];
+
+/// Edits the permissions, owners and times of the selected items. Only what was edited is changed,
+/// so that a selection with differing permissions keeps the bits that weren't touched.
+#[function_component]
+fn AttributesEditor(props: &AttributesEditorProps) -> Html {
+    let first = props.properties.iter().find(|item| item.kind.is_some());
+    let single = props.properties.len() == 1;
+    let initial_mode = first.and_then(|item| item.mode).unwrap_or(0) & 0o7777;
+    let mode = use_state_eq(|| initial_mode);
+    let touched = use_state_eq(|| 0u32);
+    let recursive = use_state_eq(|| false);
+    let applying = use_state_eq(|| false);
+    let failed = use_state_eq(|| false);
+    let owner_ref = use_node_ref();
+    let group_ref = use_node_ref();
+    let modified_ref = use_node_ref();
+    let accessed_ref = use_node_ref();
+
+    let initial_time = |time: Option<PitouDateTime>| {
+        single
+            .then_some(time)
+            .flatten()
+            .map(datetime_input)
+            .unwrap_or_default()
+    };
+    let initial_modified = initial_time(first.and_then(|item| item.modified));
+    let initial_accessed = initial_time(first.and_then(|item| item.accessed));
+
+    let has_mode = props.properties.iter().any(|item| item.mode.is_some());
+    let has_dirs = props
+        .properties
+        .iter()
+        .any(|item| item.kind == Some(PitouFileKind::Directory));
+
+    let checkbox = |bit: u32| {
+        let onchange = {
+            let mode = mode.clone();
+            let touched = touched.clone();
+            move |_| {
+                mode.set(*mode ^ bit);
+                touched.set(*touched | bit);
+            }
+        };
+        let checked = *mode & bit != 0;
+        html! { <input type="checkbox" {checked} {onchange} /> }
+    };
+
+    let permissions = has_mode.then(|| {
+        let rows = PERMISSIONS
+            .iter()
+            .map(|(label, bits)| {
+                html! {
+                    <tr>
+                        <td>{ label }</td>
+                        { bits.iter().map(|&bit| html! { <td>{ checkbox(bit) }</td> }).collect::<Html>() }
+                    </tr>
+                }
+            })
+            .collect::<Html>();
+        let special = SPECIAL_BITS
+            .iter()
+            .map(|&(label, bit)| {
+                html! {
+                    <label class="properties-special">{ checkbox(bit) }{ label }</label>
+                }
+            })
+            .collect::<Html>();
+        html! {
+            <>
+                <table class="properties-permissions">
+                    <tr><th></th><th>{ "Read" }</th><th>{ "Write" }</th><th>{ "Execute" }</th></tr>
+                    { rows }
+                </table>
+                <div class="properties-row">{ special }</div>
+                <div class="properties-row">
+                    <span class="properties-label">{ "Owner" }</span>
+                    <input type="text" class="properties-input" ref={owner_ref.clone()} placeholder={first.and_then(|item| item.owner.as_ref()).map(|owner| owner.display())} />
+                </div>
+                <div class="properties-row">
+                    <span class="properties-label">{ "Group" }</span>
+                    <input type="text" class="properties-input" ref={group_ref.clone()} placeholder={first.and_then(|item| item.group.as_ref()).map(|group| group.display())} />
+                </div>
+            </>
+        }
+    });
+
+    let onchangerecursive = {
+        let recursive = recursive.clone();
+        move |_| recursive.set(!*recursive)
+    };
+
+    let recursive_box = has_dirs.then(|| {
+        html! {
+            <label class="properties-row">
+                <input type="checkbox" checked={*recursive} onchange={onchangerecursive} />
+                { "Apply to everything inside the selected folders" }
+            </label>
+        }
+    });
+
+    let onapply = {
+        let items = props.items.clone();
+        let onapplied = props.onapplied.clone();
+        let mode = mode.clone();
+        let touched = touched.clone();
+        let recursive = recursive.clone();
+        let applying = applying.clone();
+        let failed = failed.clone();
+        let owner_ref = owner_ref.clone();
+        let group_ref = group_ref.clone();
+        let modified_ref = modified_ref.clone();
+        let accessed_ref = accessed_ref.clone();
+        let initial_modified = initial_modified.clone();
+        let initial_accessed = initial_accessed.clone();
+        move |_| {
+            let text = |node: &NodeRef| {
+                node.cast::<HtmlInputElement>()
+                    .map(|input| input.value().trim().to_owned())
+                    .filter(|value| !value.is_empty())
+            };
+            let time = |node: &NodeRef, initial: &str| {
+                text(node)
+                    .filter(|value| value != initial)
+                    .and_then(|value| parse_datetime_input(&value))
+            };
+            let change = ModeChange {
+                set: *mode & *touched,
+                clear: !*mode & *touched,
+            };
+            let changes = AttributeChanges {
+                mode: (!change.is_empty()).then_some(change),
+                owner: text(&owner_ref),
+                group: text(&group_ref),
+                modified: time(&modified_ref, &initial_modified),
+                accessed: time(&accessed_ref, &initial_accessed),
+                recursive: *recursive,
+            };
+            let items = items.clone();
+            let onapplied = onapplied.clone();
+            let applying = applying.clone();
+            let failed = failed.clone();
+            applying.set(true);
+            spawn_local(async move {
+                let res = crate::app::cmds::change_attributes(&items, changes).await;
+                applying.set(false);
+                match res {
+                    Ok(res) => onapplied.emit(Rc::new(res)),
+                    Err(_) => failed.set(true),
+                }
+            })
+        }
+    };
+
+    let errors = props.errors.as_ref().map(|errors| {
+        let entries = errors
+            .iter()
+            .map(|error| {
+                html! {
+                    <div class="properties-error">
+                        <span class="properties-error-path">{ error.path.path.display().to_string() }</span>
+                        <span>{ &error.message }</span>
+                    </div>
+                }
+            })
+            .collect::<Html>();
+        html! {
+            <>
+                <div class="properties-note">{ format!("{} items couldn't be changed", errors.len()) }</div>
+                <div class="properties-errors">{ entries }</div>
+            </>
+        }
+    });
+
+    let failed = failed.then(|| {
+        html! { <div class="properties-error">{ "The changes couldn't be applied" }</div> }
+    });
+
+    let apply_label = if *applying { "Applying..." } else { "Apply" };
+
+    html! {
+        <div class="properties-editor">
+            <div class="properties-separator"></div>
+            { permissions }
+            <div class="properties-row">
+                <span class="properties-label">{ "Modified" }</span>
+                <input type="datetime-local" step="1" class="properties-input" ref={modified_ref} value={initial_modified} />
+            </div>
+            <div class="properties-row">
+                <span class="properties-label">{ "Accessed" }</span>
+                <input type="datetime-local" step="1" class="properties-input" ref={accessed_ref} value={initial_accessed} />
+            </div>
+            { recursive_box }
+            <div class="properties-row properties-actions">
+                <button class="properties-button" onclick={onapply} disabled={*applying}>{ apply_label }</button>
+            </div>
+            { failed }
+            { errors }
+        </div>
+    }
+}
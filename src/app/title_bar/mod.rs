@@ -280,6 +280,8 @@ pub fn TabLogo(prop: &TabLogoProp) -> Html {
         AppMenu::Favorites => "star",
         AppMenu::Recents => "history",
         AppMenu::Cloud => "cloud",
+        AppMenu::Duplicates => "file_copy",
+        AppMenu::DiskUsage => "pie_chart",
         AppMenu::Settings => "settings",
         AppMenu::Locked => "lock",
         AppMenu::Search => "search",